@@ -0,0 +1,72 @@
+//! スタイル計算のベンチマーク（`cargo bench --bench style`）
+//!
+//! 1 万個の要素を持つ文書に、id / クラス / 型 / 子孫セレクタが混ざった数百個のルールを当てて、
+//! レイアウトツリーを作る（カスケード → デフォルティング → レイアウト）までの時間を測ります。
+//! 同じクラスの `<p>` が 10 個ずつ続くので、兄弟のスタイル共有も効く形にしています。
+//! まとまりの先頭の `<p>` は id を持つので、id のルールも実際に当たります。
+
+#![feature(test)]
+
+extern crate test;
+
+use saba_core::renderer::css::cssom::CssParser;
+use saba_core::renderer::css::token::CssTokenizer;
+use saba_core::renderer::css::user_agent::user_agent_stylesheet;
+use saba_core::renderer::html::parser::HtmlParser;
+use saba_core::renderer::html::token::HtmlTokenizer;
+use saba_core::renderer::layout::layout_view::LayoutView;
+use test::Bencher;
+
+// 要素の数（<p> とその中の <a> で 2 個ずつ）
+const ELEMENTS: usize = 10_000;
+// クラスの種類（ルールもクラスごとに作る）と、同じクラスが続く数
+const CLASSES: usize = 50;
+const RUN: usize = 10;
+
+fn document() -> String {
+    let mut html = String::from("<html><head></head><body>");
+    for i in 0..ELEMENTS / 2 {
+        // まとまりの先頭の <p> だけ id を持つ（`#pN` のルールは最初の CLASSES 個に当たる）
+        let id = if i % RUN == 0 {
+            format!(" id=\"p{}\"", i / RUN)
+        } else {
+            String::new()
+        };
+        html.push_str(&format!(
+            "<p{} class=\"c{} item\"><a href=\"/{}\">link</a></p>",
+            id,
+            i / RUN % CLASSES,
+            i
+        ));
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+fn stylesheet() -> String {
+    let mut css = String::new();
+    for i in 0..CLASSES {
+        css.push_str(&format!(".c{} {{ color: rgb({}, 0, 0); }}\n", i, i));
+        css.push_str(&format!("#p{} {{ margin-top: {}px; }}\n", i, i));
+        css.push_str(&format!("p.c{} a {{ font-size: {}px; }}\n", i, 10 + i));
+        css.push_str(&format!("h1 .c{} a {{ color: blue; }}\n", i));
+        css.push_str(&format!(
+            "body > p.c{}:first-child {{ color: green; }}\n",
+            i
+        ));
+    }
+    css.push_str("* { letter-spacing: normal; }\n");
+    css.push_str(".item a { text-decoration: none; }\n");
+    css
+}
+
+#[bench]
+fn bench_style_10k_elements(b: &mut Bencher) {
+    let html = document();
+    let ua = user_agent_stylesheet();
+    let cssom = CssParser::new(CssTokenizer::new(stylesheet())).parse_stylesheet();
+    let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+    let dom = window.borrow().document();
+
+    b.iter(|| LayoutView::new(dom.clone(), &ua, &cssom));
+}
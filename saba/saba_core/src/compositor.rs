@@ -0,0 +1,307 @@
+//! compositor — DisplayItem を画素の面に重ねて描く参照実装（半透明の合成）
+//!
+//! 役割
+//! - 描画命令（DisplayItem）を、後に描くものが上になる順で 1 枚の画素の面（`Canvas`）に重ねます。
+//! - 色の alpha は「下の色と混ぜる」source-over 合成で扱います（上書きではない）。
+//!   https://www.w3.org/TR/compositing-1/#porterduffcompositingoperators_srcover
+//! - `Group`（opacity を持つ要素）は、いったん透明な別の面に中身を描いてから、その面全体を
+//!   opacity の濃さで重ねます。子を 1 つずつ薄くすると、重なった部分で下の子が透けてしまうためです。
+//!
+//! 文字について
+//! - 文字の形はフォントを持つ描画側でしか描けないので、Canvas は文字の画素を塗りません。
+//!   代わりに、文字の色をその下の色と合成して不透明な色にし、`texts()` として返します。
+//!   描画側は面を描いたあと、その上に `texts()` の文字を描きます。
+//!
+//! 例（イメージ）
+//! - 白い面に `Rect(bg=rgba(255, 0, 0, 0.5))` → その範囲の画素は rgb(255, 128, 128)
+//! - `Group { opacity: 0.5, [Rect(bg=red), Rect(bg=blue)] }` で 2 つが重なる部分
+//!   → 上の blue だけが半分の濃さで見える（red は透けない）
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `pixels: Vec<Color>` は幅 × 高さの 1 次元配列で、(x, y) の画素は `y * width + x` 番目。
+//!   TS の Canvas API の `ImageData.data` に近いものです。
+use crate::display_item::DisplayItem;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::Font;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 合成済みの色で描く文字（`Canvas::texts` の要素）
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompositedText {
+    text: String,
+    // 下の色と合成した色（`Canvas::texts` では不透明）
+    color: Color,
+    style: ComputedStyle,
+    font: Font,
+    layout_point: LayoutPoint,
+}
+
+impl CompositedText {
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn style(&self) -> ComputedStyle {
+        self.style.clone()
+    }
+
+    pub fn font(&self) -> Font {
+        self.font.clone()
+    }
+
+    pub fn layout_point(&self) -> LayoutPoint {
+        self.layout_point
+    }
+}
+
+/// 描画命令を重ねていく画素の面
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    width: i64,
+    height: i64,
+    pixels: Vec<Color>,
+    texts: Vec<CompositedText>,
+    // 文字の色を下の画素と合成するか（Group 用の途中の面では、重ねる先が決まるまで待つ）
+    resolves_texts: bool,
+}
+
+impl Canvas {
+    /// `background` で塗り潰した幅 `width`・高さ `height` の面を作る
+    pub fn new(width: i64, height: i64, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; (width.max(0) * height.max(0)) as usize],
+            texts: Vec::new(),
+            resolves_texts: true,
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    /// (x, y) の画素の色（面の外なら None）
+    pub fn pixel(&self, x: i64, y: i64) -> Option<Color> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.pixels[(y * self.width + x) as usize])
+    }
+
+    /// 描いた文字（描いた順。色は下の画素と合成済み）
+    pub fn texts(&self) -> Vec<CompositedText> {
+        self.texts.clone()
+    }
+
+    /// 描画命令を順に重ねる
+    pub fn paint(&mut self, items: &[DisplayItem]) {
+        for item in items {
+            match item {
+                DisplayItem::Rect {
+                    style,
+                    layout_point,
+                    layout_size,
+                } => self.fill_rect(style.background_color(), *layout_point, *layout_size),
+                DisplayItem::Border {
+                    color,
+                    layout_point,
+                    layout_size,
+                } => self.fill_rect(*color, *layout_point, *layout_size),
+                DisplayItem::Text {
+                    text,
+                    style,
+                    font,
+                    layout_point,
+                } => self.push_text(CompositedText {
+                    text: text.clone(),
+                    color: style.color(),
+                    style: style.clone(),
+                    font: font.clone(),
+                    layout_point: *layout_point,
+                }),
+                DisplayItem::Group { opacity, items } => self.paint_group(*opacity, items),
+            }
+        }
+    }
+
+    // 矩形を塗る（面からはみ出た部分は切り捨てる）
+    fn fill_rect(&mut self, color: Color, point: LayoutPoint, size: LayoutSize) {
+        if color.is_transparent() {
+            return;
+        }
+        let left = point.x().max(0);
+        let top = point.y().max(0);
+        let right = (point.x() + size.width()).min(self.width);
+        let bottom = (point.y() + size.height()).min(self.height);
+        for y in top..bottom {
+            for x in left..right {
+                let i = (y * self.width + x) as usize;
+                self.pixels[i] = source_over(color, self.pixels[i]);
+            }
+        }
+    }
+
+    // 中身を透明な面に描き、その面全体を opacity の濃さで重ねる
+    fn paint_group(&mut self, opacity: f64, items: &[DisplayItem]) {
+        if opacity <= 0.0 {
+            return;
+        }
+        let mut layer = Canvas::new(self.width, self.height, Color::transparent());
+        layer.resolves_texts = false;
+        layer.paint(items);
+
+        for (pixel, src) in self.pixels.iter_mut().zip(layer.pixels) {
+            if !src.is_transparent() {
+                *pixel = source_over(with_alpha(src, src.alpha() * opacity), *pixel);
+            }
+        }
+        for mut text in layer.texts {
+            text.color = with_alpha(text.color, text.color.alpha() * opacity);
+            self.push_text(text);
+        }
+    }
+
+    // 文字を記録する。重ねる先が決まっていれば、文字の左上の画素と色を合成しておく
+    fn push_text(&mut self, mut text: CompositedText) {
+        if self.resolves_texts {
+            if let Some(backdrop) = self.pixel(text.layout_point.x(), text.layout_point.y()) {
+                text.color = source_over(text.color, backdrop);
+            }
+            text.color = with_alpha(text.color, 1.0);
+        }
+        self.texts.push(text);
+    }
+}
+
+fn with_alpha(color: Color, alpha: f64) -> Color {
+    Color::rgba(color.red(), color.green(), color.blue(), alpha)
+}
+
+/// `src` を `dst` の上に重ねた色（source-over 合成）
+///
+/// - 重ねた後の alpha: `a = as + ad × (1 - as)`
+/// - 重ねた後の色: `(cs × as + cd × ad × (1 - as)) / a`（チャンネルごと）
+///
+/// 例: source_over(rgba(255, 0, 0, 0.5), rgb(255, 255, 255)) → rgb(255, 128, 128)
+pub fn source_over(src: Color, dst: Color) -> Color {
+    let (sa, da) = (src.alpha(), dst.alpha());
+    let alpha = sa + da * (1.0 - sa);
+    if alpha <= 0.0 {
+        return Color::transparent();
+    }
+    let mix = |s: u8, d: u8| {
+        let c = (s as f64 * sa + d as f64 * da * (1.0 - sa)) / alpha;
+        (c + 0.5) as u8
+    };
+    Color::rgba(
+        mix(src.red(), dst.red()),
+        mix(src.green(), dst.green()),
+        mix(src.blue(), dst.blue()),
+        alpha,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::css::user_agent::user_agent_stylesheet;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::layout::layout_view::LayoutView;
+    use crate::renderer::layout::property::PropertyId;
+    use crate::renderer::layout::property::PropertyValue;
+    use alloc::string::ToString;
+
+    fn rect(color: Color, x: i64, y: i64, width: i64, height: i64) -> DisplayItem {
+        let mut style = ComputedStyle::new();
+        style.defaulting(None);
+        style.set(PropertyId::BackgroundColor, PropertyValue::Color(color));
+        DisplayItem::Rect {
+            style,
+            layout_point: LayoutPoint::new(x, y),
+            layout_size: LayoutSize::new(width, height),
+        }
+    }
+
+    #[test]
+    fn test_source_over() {
+        let white = Color::white();
+        assert_eq!(
+            source_over(Color::rgba(255, 0, 0, 0.5), white),
+            Color::rgb(255, 128, 128)
+        );
+        assert_eq!(source_over(Color::black(), white), Color::black());
+        assert_eq!(source_over(Color::transparent(), white), white);
+        // 透明な面どうしを重ねると、alpha も足し合わされる
+        assert_eq!(
+            source_over(Color::rgba(0, 0, 255, 0.5), Color::rgba(0, 0, 255, 0.5)),
+            Color::rgba(0, 0, 255, 0.75)
+        );
+    }
+
+    #[test]
+    fn test_paint_translucent_rect() {
+        let mut canvas = Canvas::new(4, 4, Color::white());
+        canvas.paint(&[rect(Color::rgba(0, 0, 0, 0.5), 1, 1, 10, 10)]);
+        assert_eq!(canvas.pixel(0, 0), Some(Color::white()));
+        assert_eq!(canvas.pixel(3, 3), Some(Color::rgb(128, 128, 128)));
+        assert_eq!(canvas.pixel(4, 4), None);
+    }
+
+    #[test]
+    fn test_paint_group() {
+        // 重なった部分は上の blue だけが半分の濃さで見え、下の red は透けない
+        let mut canvas = Canvas::new(3, 1, Color::white());
+        canvas.paint(&[DisplayItem::Group {
+            opacity: 0.5,
+            items: vec![
+                rect(Color::rgb(255, 0, 0), 0, 0, 2, 1),
+                rect(Color::rgb(0, 0, 255), 1, 0, 2, 1),
+            ],
+        }]);
+        assert_eq!(canvas.pixel(0, 0), Some(Color::rgb(255, 128, 128)));
+        assert_eq!(canvas.pixel(1, 0), Some(Color::rgb(128, 128, 255)));
+        assert_eq!(canvas.pixel(2, 0), Some(Color::rgb(128, 128, 255)));
+    }
+
+    #[test]
+    fn test_paint_translucent_parent_background() {
+        // background-color は継承しないので、<p> の中でも body の半透明の背景は 1 回だけ重なる
+        let html = "<html><body><p>text</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let css = "body { background-color: rgba(0, 0, 0, 0.5); }".to_string();
+        let cssom = CssParser::new(CssTokenizer::new(css)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom, &user_agent_stylesheet(), &cssom);
+        let p = layout_view
+            .root()
+            .expect("body should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        let point = p.borrow().point();
+
+        let mut canvas = Canvas::new(100, 100, Color::white());
+        canvas.paint(&layout_view.paint());
+        assert_eq!(
+            canvas.pixel(point.x() + 1, point.y() + 1),
+            Some(Color::rgb(128, 128, 128))
+        );
+    }
+}
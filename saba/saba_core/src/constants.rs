@@ -40,3 +40,6 @@ pub static WINDOW_INIT_X_POS: i64 = 30;
 pub static WINDOW_INIT_Y_POS: i64 = 50;
 
 pub static ADDRESSBAR_HEIGHT: i64 = 20;
+
+// テキスト選択のハイライト色（CSS の色名。`Color::from_name` で解決できるもの）
+pub static SELECTION_COLOR: &str = "lightgray";
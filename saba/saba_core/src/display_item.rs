@@ -8,6 +8,10 @@
 //! 現在の種類（学習用）
 //! - `Rect` … 矩形の塗り潰しや背景など。スタイル一式 + 位置 + サイズを持ちます。
 //! - `Text` … 文字列の描画。テキスト内容 + スタイル + 位置を持ちます。
+//! - `Border` … 枠線の 1 辺。色 + 位置 + サイズを持つ塗り潰し矩形です（辺ごとに 1 つ）。
+//! - `Group` … `opacity` を持つ要素とその子孫の描画命令のまとまり。中身を一度別の面に描いてから、
+//!   まとめて `opacity` の濃さで重ねます（`compositor` を参照）。
+//! - 色は alpha（不透明度）込みで届きます（`Color::alpha`）。下の色と混ぜて描きます。
 //!
 //! 例（イメージ）
 //! - <p style="background-color:yellow">Hi</p>
@@ -15,10 +19,13 @@
 //!        Rect { style(bg=yellow), point=(x,y), size=(w,h) },
 //!        Text { text="Hi", style(color=...), point=(x_text,y_text) }
 //!      ]
+use crate::renderer::layout::computed_style::Color;
 use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::Font;
 use crate::renderer::layout::layout_object::LayoutPoint;
 use crate::renderer::layout::layout_object::LayoutSize;
 use alloc::string::String;
+use alloc::vec::Vec;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DisplayItem {
@@ -35,7 +42,22 @@ pub enum DisplayItem {
         text: String,
         // 文字色などのスタイル。
         style: ComputedStyle,
+        // フォント（大きさ・太さ・傾き・ファミリ）。描画側は描ける範囲で最も近い書体を選ぶ
+        font: Font,
         // 左上座標（ベースラインではなく簡易に左上で扱う）
         layout_point: LayoutPoint,
     },
+    Border {
+        // 枠線の色（currentColor は解決済み）
+        color: Color,
+        // 辺の左上座標と幅・高さ（上下の辺は横長、左右の辺は縦長の矩形）
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+    Group {
+        // 中身全体にかける不透明度（0.0〜1.0。1.0 未満のときだけ作られる）
+        opacity: f64,
+        // 要素自身と子孫の描画命令（描く順）
+        items: Vec<DisplayItem>,
+    },
 }
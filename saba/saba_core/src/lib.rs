@@ -41,6 +41,7 @@
 extern crate alloc; // no_std でも `String`/`Vec` を使うために必要
 
 pub mod browser;
+pub mod compositor; // DisplayItem の半透明合成（参照実装）
 pub mod constants;
 pub mod display_item;
 pub mod error; // 共有エラー型（Result<T, Error> 用）
@@ -0,0 +1,613 @@
+//! アクセシビリティツリー（Accessibility Tree）の最小実装
+//!
+//! 役割（実ブラウザでの位置づけ）
+//! - スクリーンリーダーなどの支援技術は、ピクセルではなく「役割（role）」「名前（name）」「状態（state）」
+//!   で画面を理解します。ブラウザは DOM とレイアウト結果からこの情報をまとめたツリーを作って公開します。
+//! - テストでも「座標 (x,y) をクリック」ではなく「名前が "次へ" のリンクを探してクリック」のように
+//!   役割と名前で要素を指定できると、レイアウト変更に強いテストになります。
+//!
+//! 作り方（この実装の方針）
+//! - 入力は `LayoutView`（描画される要素だけのツリー）。`display:none` の要素は最初から含まれません。
+//! - 役割: 要素の意味（`<h1>` → heading、`<a href>` → link など）と、ARIA の `role` 属性から決めます。
+//! - 名前: `aria-label` → `alt`（role が img のとき）→ 子孫のテキスト（heading/link/button など）→ `title` の順。
+//! - 状態: フォーカス中（focused）、訪問済みリンク（visited）、フォーカス可能（focusable）。
+//! - `aria-hidden="true"` の要素は子孫ごと除外。役割を持たない generic 要素と `role="none"` は
+//!   ツリーに出さず、子だけを親へ繰り上げます（Chromium の "ignored" ノードに相当）。
+//!
+//! 仕様
+//! - ARIA: https://www.w3.org/TR/wai-aria-1.2/
+//! - 名前の計算: https://www.w3.org/TR/accname-1.2/
+//! - HTML 要素と役割の対応: https://www.w3.org/TR/html-aam-1.0/
+//!
+//! 制約（学習用の簡易化）
+//! - `aria-labelledby` や `<label>` による名前付けは未対応です。
+//! - `<img>` 要素はまだパースできないため、`alt` は `role="img"` を付けた要素でのみ使います。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `AxNode` は TS の `{ role: string; name: string; children: AxNode[] }` のような単純な木です。
+//! - `dump()` は Playwright の ARIA スナップショットのような「インデント付きテキスト」を返します。
+//!
+//! 例（概念）
+//! ```text
+//! <body><h1>Title</h1><p>hello <a href="http://example.com/">link</a></p></body>
+//! → document
+//!     heading "Title" level=1
+//!       text "Title"
+//!     paragraph
+//!       text "hello"
+//!       link "link" focusable
+//!         text "link"
+//! ```
+
+use crate::renderer::dom::node::Element;
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use crate::renderer::layout::layout_object::LayoutObject;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::layout_view::LayoutView;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+/// アクセシビリティ上の役割（ARIA role のうち、このブラウザで扱うもの）
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    /// ツリーのルート（文書全体）
+    Document,
+    /// 意味を持たない入れ物（`<body>` など）。名前が無ければツリーには出ない
+    Generic,
+    Heading,
+    Paragraph,
+    Link,
+    Image,
+    Button,
+    Navigation,
+    Main,
+    List,
+    ListItem,
+    /// テキストノード
+    StaticText,
+    /// `role="none"` / `role="presentation"`。要素自身はツリーに出さない
+    None,
+}
+
+impl Role {
+    // `role` 属性の 1 トークンを Role に変換する（未対応の値は None を返す）
+    fn from_aria(value: &str) -> Option<Self> {
+        match value {
+            "generic" => Some(Role::Generic),
+            "heading" => Some(Role::Heading),
+            "paragraph" => Some(Role::Paragraph),
+            "link" => Some(Role::Link),
+            "img" | "image" => Some(Role::Image),
+            "button" => Some(Role::Button),
+            "navigation" => Some(Role::Navigation),
+            "main" => Some(Role::Main),
+            "list" => Some(Role::List),
+            "listitem" => Some(Role::ListItem),
+            "none" | "presentation" => Some(Role::None),
+            _ => None,
+        }
+    }
+
+    // 子孫のテキストから名前を計算してよい役割か（accname の "name from content"）
+    fn allows_name_from_content(&self) -> bool {
+        matches!(
+            self,
+            Role::Heading | Role::Link | Role::Button | Role::ListItem | Role::StaticText
+        )
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let s = match self {
+            Role::Document => "document",
+            Role::Generic => "generic",
+            Role::Heading => "heading",
+            Role::Paragraph => "paragraph",
+            Role::Link => "link",
+            Role::Image => "img",
+            Role::Button => "button",
+            Role::Navigation => "navigation",
+            Role::Main => "main",
+            Role::List => "list",
+            Role::ListItem => "listitem",
+            Role::StaticText => "text",
+            Role::None => "none",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// ノードの状態
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct AxStates {
+    /// キーボードやクリックでフォーカスできる（`href` を持つリンク）
+    pub focusable: bool,
+    /// 現在フォーカスされている
+    pub focused: bool,
+    /// 訪問済みのリンク
+    pub visited: bool,
+}
+
+/// アクセシビリティツリーの 1 ノード
+#[derive(Debug, Clone)]
+pub struct AxNode {
+    role: Role,
+    name: String,
+    // 見出しレベル（heading のときだけ Some）
+    level: Option<u8>,
+    states: AxStates,
+    // 対応する DOM ノード（ルートの document は None）
+    node: Option<Rc<RefCell<Node>>>,
+    // レイアウト結果の矩形（コンテンツ座標）
+    point: LayoutPoint,
+    size: LayoutSize,
+    children: Vec<AxNode>,
+}
+
+impl AxNode {
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn level(&self) -> Option<u8> {
+        self.level
+    }
+
+    pub fn states(&self) -> AxStates {
+        self.states
+    }
+
+    pub fn node(&self) -> Option<Rc<RefCell<Node>>> {
+        self.node.clone()
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        self.size
+    }
+
+    pub fn children(&self) -> &Vec<AxNode> {
+        &self.children
+    }
+
+    /// 矩形の中心座標。テストで「このリンクをクリック」するときに `Page::clicked` へ渡せます
+    pub fn center(&self) -> (i64, i64) {
+        (
+            self.point.x() + self.size.width() / 2,
+            self.point.y() + self.size.height() / 2,
+        )
+    }
+}
+
+// ツリー構築中に参照するページ側の状態
+struct Context<'a> {
+    focused: Option<Rc<RefCell<Node>>>,
+    visited_urls: &'a [String],
+}
+
+/// アクセシビリティツリー
+#[derive(Debug, Clone)]
+pub struct AccessibilityTree {
+    root: AxNode,
+}
+
+impl AccessibilityTree {
+    /// レイアウトツリーからアクセシビリティツリーを作る
+    ///
+    /// 入力
+    /// - `layout_view`: レイアウト済みのツリー（描画される要素だけを持つ）
+    /// - `focused`: 現在フォーカスされている DOM ノード（無ければ None）
+    /// - `visited_urls`: 訪問済みの URL（リンクの visited 状態の判定に使う）
+    pub fn new(
+        layout_view: &LayoutView,
+        focused: Option<Rc<RefCell<Node>>>,
+        visited_urls: &[String],
+    ) -> Self {
+        let context = Context {
+            focused,
+            visited_urls,
+        };
+
+        let mut children = Vec::new();
+        build_siblings(&layout_view.root(), &context, &mut children);
+
+        let size = match layout_view.root() {
+            Some(root) => root.borrow().size(),
+            None => LayoutSize::new(0, 0),
+        };
+
+        Self {
+            root: AxNode {
+                role: Role::Document,
+                name: String::new(),
+                level: None,
+                states: AxStates::default(),
+                node: None,
+                point: LayoutPoint::new(0, 0),
+                size,
+                children,
+            },
+        }
+    }
+
+    pub fn root(&self) -> &AxNode {
+        &self.root
+    }
+
+    /// 役割と名前が一致する最初のノード（ツリー順）を返す
+    ///
+    /// 例: `tree.find(Role::Link, "next")` → 名前が "next" のリンク
+    pub fn find(&self, role: Role, name: &str) -> Option<&AxNode> {
+        find_node(&self.root, role, name)
+    }
+
+    /// スナップショットテスト用のテキスト表現を返す
+    ///
+    /// 書式
+    /// - 1 行 1 ノード。深さごとに空白 2 つでインデント。
+    /// - `役割 "名前" 属性...` の順。名前が空なら省略。属性は `level=N` と状態（focusable/focused/visited）。
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        dump_node(&self.root, 0, &mut out);
+        out
+    }
+}
+
+fn find_node<'a>(node: &'a AxNode, role: Role, name: &str) -> Option<&'a AxNode> {
+    if node.role == role && node.name == name {
+        return Some(node);
+    }
+    for child in &node.children {
+        if let Some(found) = find_node(child, role, name) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn dump_node(node: &AxNode, depth: usize, out: &mut String) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+    out.push_str(&node.role.to_string());
+    if !node.name.is_empty() {
+        out.push_str(&format!(" \"{}\"", node.name.replace('"', "\\\"")));
+    }
+    if let Some(level) = node.level {
+        out.push_str(&format!(" level={}", level));
+    }
+    if node.states.focusable {
+        out.push_str(" focusable");
+    }
+    if node.states.focused {
+        out.push_str(" focused");
+    }
+    if node.states.visited {
+        out.push_str(" visited");
+    }
+    out.push('\n');
+
+    for child in &node.children {
+        dump_node(child, depth + 1, out);
+    }
+}
+
+// 連続する空白を 1 つにまとめ、前後の空白を取り除く（テキストの見た目に合わせる）
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// 空白だけの属性値は「無い」ものとして扱う
+fn non_empty_attribute(element: &Element, name: &str) -> Option<String> {
+    match element.get_attribute(name) {
+        Some(value) => {
+            let value = collapse_whitespace(&value);
+            if value.is_empty() {
+                None
+            } else {
+                Some(value)
+            }
+        }
+        None => None,
+    }
+}
+
+// `aria-hidden="true"` なら子孫ごとツリーから除外する
+fn is_aria_hidden(element: &Element) -> bool {
+    element.get_attribute("aria-hidden") == Some("true".to_string())
+}
+
+// 要素の役割を決める
+// - `role` 属性は空白区切りのリストで、先頭から見て最初に対応しているものを使う（フォールバック）
+// - 無ければ要素の種類から決まる暗黙の役割（implicit role）を使う
+fn role_of(element: &Element) -> Role {
+    if let Some(value) = element.get_attribute("role") {
+        for token in value.split_whitespace() {
+            if let Some(role) = Role::from_aria(token) {
+                return role;
+            }
+        }
+    }
+
+    match element.kind() {
+        ElementKind::P => Role::Paragraph,
+        ElementKind::H1 | ElementKind::H2 => Role::Heading,
+        // href の無い <a> はリンクではない（プレースホルダ扱い）
+        ElementKind::A if element.get_attribute("href").is_some() => Role::Link,
+        _ => Role::Generic,
+    }
+}
+
+// 見出しレベル: `aria-level` があればそれを優先、なければ <h1>/<h2> から決める
+fn level_of(element: &Element, role: Role) -> Option<u8> {
+    if role != Role::Heading {
+        return None;
+    }
+    if let Some(level) = element.get_attribute("aria-level") {
+        if let Ok(level) = level.trim().parse::<u8>() {
+            if level > 0 {
+                return Some(level);
+            }
+        }
+    }
+    match element.kind() {
+        ElementKind::H1 => Some(1),
+        ElementKind::H2 => Some(2),
+        // role="heading" だけが指定された場合の既定値（ARIA 仕様）
+        _ => Some(2),
+    }
+}
+
+// 子孫のテキストを連結する（aria-hidden は飛ばし、aria-label を持つ子孫はそのラベルを使う）
+fn text_from_content(first_child: &Option<Rc<RefCell<LayoutObject>>>, out: &mut String) {
+    let mut current = first_child.clone();
+    while let Some(obj) = current {
+        match obj.borrow().node_kind() {
+            NodeKind::Text(t) => {
+                out.push_str(&t);
+                text_from_content(&obj.borrow().first_child(), out);
+            }
+            NodeKind::Element(e) => {
+                if !is_aria_hidden(&e) {
+                    match non_empty_attribute(&e, "aria-label") {
+                        Some(label) => {
+                            out.push(' ');
+                            out.push_str(&label);
+                            out.push(' ');
+                        }
+                        None => text_from_content(&obj.borrow().first_child(), out),
+                    }
+                }
+            }
+            NodeKind::Document => {}
+        }
+        current = obj.borrow().next_sibling();
+    }
+}
+
+// 名前の計算（accname の簡易版）
+fn name_of(obj: &Rc<RefCell<LayoutObject>>, element: &Element, role: Role) -> String {
+    if let Some(label) = non_empty_attribute(element, "aria-label") {
+        return label;
+    }
+    if role == Role::Image {
+        if let Some(alt) = non_empty_attribute(element, "alt") {
+            return alt;
+        }
+    }
+    if role.allows_name_from_content() {
+        let mut text = String::new();
+        text_from_content(&obj.borrow().first_child(), &mut text);
+        let text = collapse_whitespace(&text);
+        if !text.is_empty() {
+            return text;
+        }
+    }
+    non_empty_attribute(element, "title").unwrap_or_default()
+}
+
+// レイアウトオブジェクトと、その弟たちを順に変換して `out` へ追加する
+fn build_siblings(
+    first: &Option<Rc<RefCell<LayoutObject>>>,
+    context: &Context,
+    out: &mut Vec<AxNode>,
+) {
+    let mut current = first.clone();
+    while let Some(obj) = current {
+        build_node(&obj, context, out);
+        current = obj.borrow().next_sibling();
+    }
+}
+
+// 1 つのレイアウトオブジェクトを AxNode に変換して `out` へ追加する
+// - ツリーに出さない要素（generic/none）は、自分の代わりに子を `out` へ追加する
+fn build_node(obj: &Rc<RefCell<LayoutObject>>, context: &Context, out: &mut Vec<AxNode>) {
+    let node = obj.borrow().node();
+    let point = obj.borrow().point();
+    let size = obj.borrow().size();
+
+    let element = match obj.borrow().node_kind() {
+        NodeKind::Text(t) => {
+            let name = collapse_whitespace(&t);
+            if !name.is_empty() {
+                out.push(AxNode {
+                    role: Role::StaticText,
+                    name,
+                    level: None,
+                    states: AxStates::default(),
+                    node: Some(node),
+                    point,
+                    size,
+                    children: Vec::new(),
+                });
+            }
+            // パーサの簡易実装では、テキストの直後に開始した要素がテキストノードの子になる
+            // （例: `<p>hello <a>..</a></p>`）。文書の見た目どおり、テキストの後ろに並べる。
+            build_siblings(&obj.borrow().first_child(), context, out);
+            return;
+        }
+        NodeKind::Element(e) => e,
+        NodeKind::Document => return,
+    };
+
+    if is_aria_hidden(&element) {
+        return;
+    }
+
+    let role = role_of(&element);
+    let mut children = Vec::new();
+    build_siblings(&obj.borrow().first_child(), context, &mut children);
+
+    let name = match role {
+        Role::None => String::new(),
+        _ => name_of(obj, &element, role),
+    };
+
+    if role == Role::None || (role == Role::Generic && name.is_empty()) {
+        out.extend(children);
+        return;
+    }
+
+    let href = match element.kind() {
+        ElementKind::A => element.get_attribute("href"),
+        _ => None,
+    };
+    let focused = match &context.focused {
+        Some(f) => Rc::ptr_eq(f, &node),
+        None => false,
+    };
+    let visited = match &href {
+        Some(url) => context.visited_urls.iter().any(|v| v == url),
+        None => false,
+    };
+
+    out.push(AxNode {
+        role,
+        name,
+        level: level_of(&element, role),
+        states: AxStates {
+            focusable: href.is_some(),
+            focused,
+            visited,
+        },
+        node: Some(node),
+        point,
+        size,
+        children,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpResponse;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::css::user_agent::user_agent_stylesheet;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::dom::api::get_style_content;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::page::Page;
+
+    fn create_layout_view(html: &str) -> (Rc<RefCell<Node>>, LayoutView) {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &user_agent_stylesheet(), &cssom);
+        (dom, layout_view)
+    }
+
+    #[test]
+    fn test_roles_and_names() {
+        let (_, view) = create_layout_view(
+            r#"<html><head></head><body><h1>Title</h1><p>hello <a href="http://example.com/">link</a></p></body></html>"#,
+        );
+        let tree = AccessibilityTree::new(&view, None, &[]);
+        let expected = r#"document
+  heading "Title" level=1
+    text "Title"
+  paragraph
+    text "hello"
+    link "link" focusable
+      text "link"
+"#;
+        assert_eq!(expected, tree.dump());
+    }
+
+    #[test]
+    fn test_aria_attributes() {
+        // role/aria-label/alt/aria-hidden/role="none" と、display:none による除外
+        let (_, view) = create_layout_view(
+            r#"<html><head><style>h1{display:none;}</style></head><body><p role="button" aria-label="Close">x</p><p role="img" alt="A cat"></p><h2 aria-hidden="true">secret</h2><h1>gone</h1><p role="presentation">plain</p><a>no href</a><h2 role="heading" aria-level="3">Sub</h2></body></html>"#,
+        );
+        let tree = AccessibilityTree::new(&view, None, &[]);
+        let expected = r#"document
+  button "Close"
+    text "x"
+  img "A cat"
+  text "plain"
+  text "no href"
+  heading "Sub" level=3
+    text "Sub"
+"#;
+        assert_eq!(expected, tree.dump());
+    }
+
+    #[test]
+    fn test_focused_and_visited() {
+        let (dom, view) = create_layout_view(
+            r#"<html><head></head><body><a id="a" href="http://a.test/">a</a><a href="http://b.test/">b</a></body></html>"#,
+        );
+        let a = get_element_by_id(Some(dom), &"a".to_string());
+        let visited = ["http://b.test/".to_string()];
+        let tree = AccessibilityTree::new(&view, a, &visited);
+
+        let a = tree.find(Role::Link, "a").expect("link a should exist");
+        assert!(a.states().focused);
+        assert!(!a.states().visited);
+        let b = tree.find(Role::Link, "b").expect("link b should exist");
+        assert!(!b.states().focused);
+        assert!(b.states().visited);
+    }
+
+    #[test]
+    fn test_click_link_by_role_and_name() {
+        // 座標を直接書かず、「名前が next のリンク」を探してその中心をクリックする
+        let mut page = Page::new();
+        let raw = "HTTP/1.1 200 OK\n\n<html><head></head><body><p>text</p><a href=\"http://next.test/\">next</a></body></html>";
+        page.receive_response(HttpResponse::new(raw.to_string()).expect("valid response"));
+
+        let tree = page.accessibility_tree().expect("tree should exist");
+        let link = tree.find(Role::Link, "next").expect("link should exist");
+        assert_eq!(
+            Some("http://next.test/".to_string()),
+            page.clicked(link.center())
+        );
+
+        // クリック後はフォーカス中かつ訪問済みになる
+        let tree = page.accessibility_tree().expect("tree should exist");
+        let link = tree.find(Role::Link, "next").expect("link should exist");
+        assert!(link.states().focused);
+        assert!(link.states().visited);
+    }
+}
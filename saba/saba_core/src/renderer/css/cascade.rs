@@ -0,0 +1,265 @@
+//! カスケード（初心者向け）
+//!
+//! 役割
+//! - 1 つの要素に一致するルールの宣言をすべて集め、「どの宣言が勝つか」の順に並べ替えます。
+//!   並べた結果を先頭から順に適用すれば（後勝ち）、最後に残るのが勝者です。
+//!
+//! 優先順位（強い順に比べる）
+//! 1) 出どころと重要度（origin + `!important`）
+//!    通常: UserAgent < User < Author、`!important`: Author < User < UserAgent
+//!    （どの `!important` も通常の宣言より強い）
+//! 2) インラインスタイル（`style` 属性）かどうか。同じ出どころ・重要度ならどのセレクタよりも強い
+//! 3) セレクタの詳細度（`#id` > `.class` > `p`）
+//! 4) 出現順（後に書かれたものが強い。スタイルシートが複数あれば後のシートが強い）
+//!
+//! 例
+//! ```text
+//! #title { color: blue; }   ← (1,0,0) なので、後ろにある p より強い
+//! p { color: red; }
+//! p { color: green !important; }  ← !important は詳細度に関係なく最優先
+//! <p id="title" style="color: gray">  ← 通常の宣言同士なら #title より強いが、!important には負ける
+//! ```
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `sort_by_key` は安定ソートなので、キーが同じ宣言は元の順序（出現順）を保ちます
+//!   （TS の `Array.prototype.sort` も現在は安定）。ここではさらに出現順もキーに含めています。
+//!
+//! 仕様: https://www.w3.org/TR/css-cascade-4/#cascading
+
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::rule_set::AncestorFilter;
+use crate::renderer::css::rule_set::RuleSet;
+use crate::renderer::css::selector::MatchingContext;
+use crate::renderer::css::selector::Specificity;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::node::Node;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// `node` に一致する宣言を、弱いものから強いものの順に並べて返す
+///
+/// - `stylesheets` は読み込んだ順に並べて渡します（後ろのシートほど同じ条件では強い）。
+/// - 要素の `style` 属性はページ作者（Author）の宣言として、同じ重要度のどのセレクタよりも強く扱います。
+/// - 戻り値をそのまま `LayoutObject::cascading_style` に渡すと、最も強い宣言が最後に適用されます。
+/// - 呼ぶたびにルールの索引を作るので、多くの要素を続けて計算するときは
+///   `RuleSet` を 1 回だけ作って `cascaded_declarations_with` を使ってください。
+pub fn cascaded_declarations(
+    node: &Rc<RefCell<Node>>,
+    stylesheets: &[StyleSheet],
+    context: &MatchingContext,
+) -> Vec<Declaration> {
+    cascaded_declarations_with(node, stylesheets, &RuleSet::new(stylesheets), context, None)
+}
+
+/// `cascaded_declarations` と同じ。一致するルールは、`stylesheets` から作った索引 `rules` で探す
+///
+/// - `ancestors` には `node` の祖先を入れたフィルタを渡せます（祖先の名前で先に候補を絞る）。
+pub fn cascaded_declarations_with(
+    node: &Rc<RefCell<Node>>,
+    stylesheets: &[StyleSheet],
+    rules: &RuleSet,
+    context: &MatchingContext,
+    ancestors: Option<&AncestorFilter>,
+) -> Vec<Declaration> {
+    // (優先順位, インラインか, 詳細度, 出現順, 宣言)
+    let mut matched: Vec<(u8, bool, Specificity, usize, Declaration)> = Vec::new();
+
+    // 索引はシート順・ルール順に返すので、出現順は全ルールを順に試したときと変わらない
+    for m in rules.matching_rules(node, context, ancestors) {
+        let sheet = &stylesheets[m.sheet];
+        let rule = &sheet.rules[m.rule];
+        // `@media` の中のルールは、今の環境（ビューポートの大きさなど）に一致するときだけ使う
+        if !rule.media.iter().all(|q| q.matches(context.media())) {
+            continue;
+        }
+        for declaration in &rule.declarations {
+            let order = matched.len();
+            matched.push((
+                precedence(sheet.origin, declaration.important),
+                false,
+                m.specificity,
+                order,
+                declaration.clone(),
+            ));
+        }
+    }
+
+    for declaration in inline_declarations(node) {
+        let order = matched.len();
+        matched.push((
+            precedence(CascadeOrigin::Author, declaration.important),
+            true,
+            Specificity::default(),
+            order,
+            declaration,
+        ));
+    }
+
+    matched.sort_by_key(|(precedence, inline, specificity, order, _)| {
+        (*precedence, *inline, *specificity, *order)
+    });
+    matched.into_iter().map(|(_, _, _, _, d)| d).collect()
+}
+
+/// 要素の `style` 属性を宣言の並びとして解釈する（属性がなければ空）
+/// https://drafts.csswg.org/css-style-attr/#syntax
+fn inline_declarations(node: &Rc<RefCell<Node>>) -> Vec<Declaration> {
+    let style = match node.borrow().get_element() {
+        Some(element) => element.get_attribute("style"),
+        None => None,
+    };
+    match style {
+        Some(style) => CssParser::new(CssTokenizer::new(style)).parse_declaration_list(),
+        None => Vec::new(),
+    }
+}
+
+/// 出どころと重要度を 1 つの数値にする（大きいほど強い）
+/// https://www.w3.org/TR/css-cascade-4/#cascade-origin
+fn precedence(origin: CascadeOrigin, important: bool) -> u8 {
+    match (important, origin) {
+        (false, CascadeOrigin::UserAgent) => 0,
+        (false, CascadeOrigin::User) => 1,
+        (false, CascadeOrigin::Author) => 2,
+        (true, CascadeOrigin::Author) => 3,
+        (true, CascadeOrigin::User) => 4,
+        (true, CascadeOrigin::UserAgent) => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::ComponentValue;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn stylesheet(css: &str, origin: CascadeOrigin) -> StyleSheet {
+        let mut sheet = CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet();
+        sheet.set_origin(origin);
+        sheet
+    }
+
+    // <p id=title class=note> に対してカスケードし、最後に残った color の値を返す
+    fn winning_color(stylesheets: &[StyleSheet]) -> Option<ComponentValue> {
+        winning_color_of("<p id=title class=note>x</p>", stylesheets)
+    }
+
+    // body に `p` を置いたページで id=title の要素をカスケードし、最後に残った color の値を返す
+    fn winning_color_of(p: &str, stylesheets: &[StyleSheet]) -> Option<ComponentValue> {
+        let html = "<html><body>".to_string() + p + "</body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let p = get_element_by_id(Some(document), &"title".to_string()).expect("p should exist");
+
+        cascaded_declarations(&p, stylesheets, &MatchingContext::new())
+            .into_iter()
+            .filter(|d| d.property == "color")
+            .last()
+            .map(|d| d.value)
+    }
+
+    fn ident(s: &str) -> Option<ComponentValue> {
+        Some(ComponentValue::Ident(String::from(s)))
+    }
+
+    #[test]
+    fn test_specificity_beats_source_order() {
+        // #title (1,0,0) は後ろの .note (0,1,0) や p (0,0,1) より強い
+        let sheet = stylesheet(
+            "#title { color: blue; } .note { color: green; } p { color: red; }",
+            CascadeOrigin::Author,
+        );
+        assert_eq!(winning_color(&[sheet]), ident("blue"));
+    }
+
+    #[test]
+    fn test_source_order_breaks_ties() {
+        // 詳細度が同じ（.note と [id] はどちらも (0,1,0)）なら後に書いたものが勝つ
+        let sheet = stylesheet(
+            ".note { color: blue; } [id] { color: green; } p { color: red; }",
+            CascadeOrigin::Author,
+        );
+        assert_eq!(winning_color(&[sheet]), ident("green"));
+
+        // 同じ出どころのシートが複数あれば、後のシートが勝つ
+        let first = stylesheet(".note { color: blue; }", CascadeOrigin::Author);
+        let second = stylesheet(".note { color: gray; }", CascadeOrigin::Author);
+        assert_eq!(winning_color(&[first, second]), ident("gray"));
+    }
+
+    #[test]
+    fn test_important() {
+        // !important は詳細度より優先される。!important 同士なら詳細度で比べる
+        let sheet = stylesheet(
+            "p { color: red !important; } #title { color: blue; }",
+            CascadeOrigin::Author,
+        );
+        assert_eq!(winning_color(&[sheet]), ident("red"));
+
+        let sheet = stylesheet(
+            "#title { color: blue !important; } p { color: red !important; }",
+            CascadeOrigin::Author,
+        );
+        assert_eq!(winning_color(&[sheet]), ident("blue"));
+    }
+
+    #[test]
+    fn test_origins() {
+        let user_agent = stylesheet(
+            "#title { color: black; } p { display: block !important; }",
+            CascadeOrigin::UserAgent,
+        );
+        let user = stylesheet("p { color: green; }", CascadeOrigin::User);
+        let author = stylesheet("p { color: red; }", CascadeOrigin::Author);
+
+        // 通常の宣言は Author > User > UserAgent（詳細度より出どころが先）
+        assert_eq!(
+            winning_color(&[user_agent.clone(), user.clone(), author.clone()]),
+            ident("red")
+        );
+        assert_eq!(
+            winning_color(&[user_agent.clone(), user.clone()]),
+            ident("green")
+        );
+
+        // !important では逆転し、User の !important は Author の !important より強い
+        let user = stylesheet("p { color: green !important; }", CascadeOrigin::User);
+        let author = stylesheet("#title { color: red !important; }", CascadeOrigin::Author);
+        assert_eq!(winning_color(&[user_agent, user, author]), ident("green"));
+
+        // 並び順を入れ替えても結果は変わらない
+        let sheets = vec![
+            stylesheet("p { color: red !important; }", CascadeOrigin::Author),
+            stylesheet("p { color: green !important; }", CascadeOrigin::UserAgent),
+        ];
+        assert_eq!(winning_color(&sheets), ident("green"));
+    }
+
+    #[test]
+    fn test_inline_style() {
+        let p = "<p id=title style=\"color: gray\">x</p>";
+
+        // インラインスタイルは #id より強い
+        let sheet = stylesheet("#title { color: blue; }", CascadeOrigin::Author);
+        assert_eq!(winning_color_of(p, &[sheet]), ident("gray"));
+
+        // ただしシート側の !important には負ける
+        let sheet = stylesheet("p { color: red !important; }", CascadeOrigin::Author);
+        assert_eq!(winning_color_of(p, &[sheet.clone()]), ident("red"));
+
+        // インラインの !important は Author の !important にも勝つ
+        let important = "<p id=title style=\"color: gray !important\">x</p>";
+        assert_eq!(winning_color_of(important, &[sheet]), ident("gray"));
+    }
+}
@@ -9,9 +9,28 @@
 //!   例: `p { color: red; } h1 { font-size: 40; }`
 //! - `QualifiedRule` … 1つのルール。セレクタ + 宣言ブロックの組。
 //!   例: `p { color: red; }` が1ルール。
-//! - `Selector` … セレクタ。`p`, `.class`, `#id` など（ここでは3種を簡易対応）。
+//! - `Selector` … セレクタ。`p`, `.class`, `#id`, `[type=text]` と、それらを組み合わせた
+//!   複合セレクタ（`a.button#go`）・結合子（`body > p`）・セレクタリスト（`h1, h2`）。
+//!   パースとマッチングは `selector` モジュールにあります。
 //! - `Declaration` … 宣言1つ。プロパティ名と値のペア。
 //!   例: `color: red` は `property="color"`, `value=Ident("red")`。
+//! - `@import "a.css" print;` … 読み込む外部シートの URL と、後ろのメディアクエリを
+//!   `StyleSheet.imports` に記録します（`ImportRule`）。
+//!   取得して展開するのはネットワークを持つ `Page` の役目です。
+//! - `@media (max-width: 600px) { ... }` … 中のルールを取り出し、それぞれにメディアクエリ
+//!   （`QualifiedRule.media`）を付けて `rules` に並べます。一致するかはカスケードで判定します。
+//! - `@keyframes fade { from { ... } 50% { ... } to { ... } }` … アニメーションの各時点の宣言を
+//!   `StyleSheet.keyframes` に記録します（`KeyframesRule`）。使うのは `layout::animation` です。
+//!   `@media` の中に書いたものも、条件を見ずに記録します（簡易版）。
+//!   その他の at-rule は読み飛ばします。
+//!
+//! 壊れた CSS の扱い（CSS Syntax Level 3 のエラー回復）
+//! - 読めない宣言は、その宣言だけを次の `;` まで捨てます（`p { color red; margin: 0 }` の margin は有効）。
+//! - セレクタが読めないルールは、宣言ブロックごと捨てます。余分な `}` は次のルールのセレクタの一部になり、
+//!   そのルールが捨てられます（ブラウザと同じ）。
+//! - 括弧やブロックは対応する閉じ括弧まで 1 つの塊として読むので、中の `;` や `}` で区切られません。
+//! - 閉じていないブロックは入力の終わりで閉じたものとします。
+//! - 捨てた場所と理由は `ParseWarning`（行・列つき）として `CssParser::warnings()` に残ります。
 //!
 //! 言語ブリッジ（TS / Python / Go）
 //! - `StyleSheet.rules: Vec<QualifiedRule>` … 配列にルールが並ぶ（TS: QualifiedRule[]、Python: list）。
@@ -35,157 +54,184 @@
 //! ```
 
 use crate::alloc::string::ToString;
+use crate::renderer::css::media::parse_media_query_list;
+use crate::renderer::css::media::MediaQueryList;
+use crate::renderer::css::selector::parse_selector_list;
 use crate::renderer::css::token::CssToken;
 use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::css::token::SourcePosition;
+use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::iter::Peekable;
+use core::fmt;
+use core::fmt::Display;
+
+/// パース中に見つけた書き間違い（読み飛ばした部分）
+///
+/// CSS は壊れた部分だけを捨てて続きを読むので、書き間違いに気づきにくいです。
+/// 自分たちのスタイルシートを lint するために、捨てた場所と理由を記録します。
+///
+/// 例: `p { color red; }` → `1:5: color の後ろに `:` がないので、この宣言を無視します`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub position: SourcePosition, // 読み飛ばした部分の先頭
+    pub message: String,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.position.line, self.position.column, self.message
+        )
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct CssParser {
-    t: Peekable<CssTokenizer>, // トークナイザを先読み可能に包む（lookahead が必要になるため）
+    tokens: Vec<CssToken>, // トークナイザから読んだすべてのトークン（先読みと切り出しのため先に読む）
+    positions: Vec<SourcePosition>, // tokens[i] のソース上の位置（警告に使う）
+    end: SourcePosition,   // 入力の終わりの位置
+    pos: usize,            // 次に読むトークン
+    imports: Vec<ImportRule>, // 読み取った `@import`（出現順）
+    keyframes: Vec<KeyframesRule>, // 読み取った `@keyframes`（出現順）
+    warnings: Vec<ParseWarning>, // 見つけた書き間違い（出現順）
 }
 
 impl CssParser {
-    pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+    pub fn new(mut t: CssTokenizer) -> Self {
+        let mut tokens = Vec::new();
+        let mut positions = Vec::new();
+        while let Some(token) = t.next() {
+            tokens.push(token);
+            positions.push(t.position());
+        }
+        Self {
+            tokens,
+            positions,
+            end: t.position(),
+            pos: 0,
+            imports: Vec::new(),
+            keyframes: Vec::new(),
+            warnings: Vec::new(),
+        }
     }
 
-    // 次のトークンを消費し、ComponentValueとして返す
-    /// 次のトークンを“そのまま”値として受け取る（ComponentValue = CssToken の簡易方針）
-    /// 例: `color: red;` の直後に `Ident("red")` が現れる想定。
-    /// 本格実装ではここで `CssToken` → `Value`（Color/Length/String 等）に解釈します。
-    /// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-component-value
-    fn consume_component_value(&mut self) -> ComponentValue {
-        self.t
-            .next()
-            .expect("should have a token in consume_component_value")
-    }
-
-    // 識別子トークンを消費し、文字列を取得する
-    /// 入力例: `color: red` の `color` 部分や、`font-size` のようなプロパティ名。
-    /// 不正（Ident 以外）なら明確なメッセージで失敗させ、解析の不整合に気づけるようにします。
-    fn consume_ident(&mut self) -> String {
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
+    /// これまでに見つけた書き間違い（`parse_stylesheet` などのあとに読む）
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
 
-        match token {
-            CssToken::Ident(ref ident) => ident.to_string(),
-            _ => {
-                panic!("Parse error: {:?} is an unexpected token.", token);
-            }
-        }
+    fn peek(&self) -> Option<&CssToken> {
+        self.tokens.get(self.pos)
     }
 
-    // ひとつの宣言を解釈する
-    /// 入力例: `color: red;` → Declaration { property: "color", value: Ident("red") }
-    /// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-a-declaration
-    fn consume_declaration(&mut self) -> Option<Declaration> {
-        if self.t.peek().is_none() {
-            return None;
+    fn next(&mut self) -> Option<CssToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
         }
+        token
+    }
 
-        // 1) Declaration 構造体を初期化する
-        let mut declaration = Declaration::new();
-        // 2) Declaration構造体のプロパティに識別子を設定する
-        declaration.set_property(self.consume_ident());
+    // `index` 番目のトークンの位置で警告を記録する（入力の終わりなら終わりの位置）
+    fn warn(&mut self, index: usize, message: String) {
+        let position = self.positions.get(index).copied().unwrap_or(self.end);
+        self.warnings.push(ParseWarning { position, message });
+    }
 
-        // 3) もし次のトークンがコロンでない場合、パースエラーなので、Noneを返す
-        match self.t.next() {
-            Some(token) => match token {
-                CssToken::Colon => {}
-                _ => return None,
-            },
-            None => return None,
+    /// コンポーネント値を 1 つ読み進める
+    /// 関数（`rgb(`）・括弧（`(` `[`）・ブロック（`{`）なら、対応する閉じ括弧まで中身ごと読みます。
+    /// そのため、括弧の中の `;` や `}` で宣言やルールが終わることはありません。
+    ///
+    /// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-component-value
+    fn consume_component_value(&mut self) {
+        let start = self.pos;
+        let mut closers = Vec::new();
+        while let Some(token) = self.next() {
+            match token {
+                CssToken::Function(_) | CssToken::OpenParenthesis => {
+                    closers.push(CssToken::CloseParenthesis)
+                }
+                CssToken::OpenSquareBracket => closers.push(CssToken::CloseSquareBracket),
+                CssToken::OpenCurly => closers.push(CssToken::CloseCurly),
+                ref t if closers.last() == Some(t) => {
+                    closers.pop();
+                }
+                _ => {}
+            }
+            if closers.is_empty() {
+                return;
+            }
         }
+        if !closers.is_empty() {
+            self.warn(start, "括弧が閉じないまま入力が終わりました".to_string());
+        }
+    }
 
-        // 4) Declaration構造体の値にコンポーネント値を設定する
-        declaration.set_value(self.consume_component_value());
-
-        Some(declaration)
+    // 宣言の終わり（`;`・ブロックの `}`・入力の終わり）の手前まで読み進める
+    fn skip_to_declaration_end(&mut self, nested: bool) {
+        loop {
+            match self.peek() {
+                None | Some(CssToken::SemiColon) => return,
+                Some(CssToken::CloseCurly) if nested => return,
+                Some(_) => self.consume_component_value(),
+            }
+        }
     }
 
-    // consume_list_of_declarationsでは、複数の宣言を解釈する
+    /// 宣言の並びを解釈する
+    ///
+    /// 役割
+    /// - 識別子で始まる部分を `;` まで集めて、1 つの宣言として解釈します（`parse_declaration`）。
+    ///   解釈できない宣言はその宣言だけを捨て、次の `;` から読み続けます。
+    /// - 識別子以外で始まる部分（`{ color: red; 123; }` の `123`）も、次の `;` まで読み捨てます。
+    /// - 宣言ブロックの中の at-rule は未対応なので読み捨てます。
+    /// - `nested` が true（ルールの `{ ... }` の中）のときは、閉じる `}` で終わります。
+    ///
     /// 入力例: `{ color: red; font-size: 40; }` → [Declaration("color", Ident("red")), Declaration("font-size", Number(40))]
     /// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-declarations
-    fn consume_list_of_declarations(&mut self) -> Vec<Declaration> {
-        let mut declarations = Vec::new(); // 宣言ベクタを初期化する
-
+    fn consume_list_of_declarations(&mut self, nested: bool) -> Vec<Declaration> {
+        let mut declarations = Vec::new();
         loop {
-            let token = match self.t.peek() {
-                Some(t) => t,
-                None => return declarations,
-            };
-
-            match token {
-                // 閉じ波括弧が現れるまで宣言を作成しベクタに追加する
-                CssToken::CloseCurly => {
-                    assert_eq!(self.t.next(), Some(CssToken::CloseCurly));
+            let start = self.pos;
+            match self.peek() {
+                None => {
+                    if nested {
+                        self.warn(start, "宣言ブロックの `}` がありません".to_string());
+                    }
                     return declarations;
                 }
-                // セミコロンのとき一つの宣言が終了したことを表す
-                CssToken::SemiColon => {
-                    assert_eq!(self.t.next(), Some(CssToken::SemiColon));
-                    // 一つの宣言が終了。何もしない（次のプロパティへ）
+                Some(CssToken::Whitespace | CssToken::SemiColon) => {
+                    self.next();
                 }
-                // 識別子トークンの時、一つの宣言を解釈し、ベクタに追加する
-                CssToken::Ident(ref _ident) => {
-                    if let Some(declaration) = self.consume_declaration() {
-                        declarations.push(declaration);
-                    }
+                Some(CssToken::CloseCurly) if nested => {
+                    self.next();
+                    return declarations;
                 }
-                _ => {
-                    self.t.next();
+                Some(CssToken::AtKeyword(name)) => {
+                    let message = format!("宣言ブロックの中の @{} は未対応なので無視します", name);
+                    self.warn(start, message);
+                    self.consume_at_rule(nested);
                 }
-            }
-        }
-    }
-
-    /// セレクタを 1 つ解釈する（Type / Class / ID の簡易版）
-    /// 入力例:
-    /// - `#main {` → IdSelector("main")
-    /// - `.note {` → ClassSelector("note")
-    /// - `a:hover {` → TypeSelector("a") として扱い、`:` 以降は `{` まで読み飛ばす（簡易）
-    fn consume_selector(&mut self) -> Selector {
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
-
-        match token {
-            CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()), // ハッシュトークンの時、IDセレクタを作成して返す（先頭の # を落とす）
-            CssToken::Delim(delim) => {
-                if delim == '.' {
-                    // ピリオドの時、クラスセレクタを作成して返す
-                    return Selector::ClassSelector(self.consume_ident());
-                }
-                panic!("Parse error: {:?} is an unexpected token.", token);
-            }
-            CssToken::Ident(ident) => {
-                // 識別子の時、タイプセレクタを作成して返す
-                // a:hoverのようなセレクタはタグ名のセレクタとして扱うため、
-                // もしコロン（:）が出てきた場合は宣言ブロックの開始直前まで
-                // トークンを進める
-                if self.t.peek() == Some(&CssToken::Colon) {
-                    while self.t.peek() != Some(&CssToken::OpenCurly) {
-                        self.t.next();
+                Some(CssToken::Ident(_)) => {
+                    self.skip_to_declaration_end(nested);
+                    match parse_declaration(&self.tokens[start..self.pos]) {
+                        Ok(declaration) => declarations.push(declaration),
+                        Err(message) => self.warn(start, message),
                     }
                 }
-                Selector::TypeSelector(ident.to_string())
-            }
-            CssToken::AtKeyword(_keyword) => {
-                // @から始まるルールを無視するために、宣言ブロックの開始直前まで
-                // トークンを進める
-                while self.t.peek() != Some(&CssToken::OpenCurly) {
-                    self.t.next();
+                Some(token) => {
+                    let message = format!(
+                        "宣言はプロパティ名で始まる必要があるので、{:?} から次の `;` までを無視します",
+                        token
+                    );
+                    self.warn(start, message);
+                    self.skip_to_declaration_end(nested);
                 }
-                Selector::UnknownSelector
-            }
-            _ => {
-                self.t.next();
-                Selector::UnknownSelector
             }
         }
     }
@@ -193,75 +239,290 @@ impl CssParser {
     /// Qualified Rule（セレクタ + 宣言ブロック）を 1 つ解釈する
     ///
     /// 役割
-    /// - セレクタ部分を読み（例: `p`, `.note`, `#main`）、`{` が来たら宣言ブロックを `}` まで解釈します。
-    /// - 読み終えたら `Some(QualifiedRule)` を返し、入力が尽きたら `None` を返します。
+    /// - `{` までのトークン（prelude）を集めてセレクタリストとして解釈し（例: `p`, `.note`, `body > p`）、
+    ///   `{` が来たら宣言ブロックを `}` まで解釈します。
+    /// - セレクタに解釈できない部分があれば、宣言ブロックごとルールを捨てます（`None`）。
+    /// - `{` が来ないまま入力（`@media` の中なら `}`）が終わったときも `None` です。
     ///
     /// 入力例 → 出力イメージ
     /// - `p { color: red; }` → selector=TypeSelector("p"), declarations=[ Declaration("color", Ident("red")) ]
     /// - `.note { font-size: 40; }` → selector=ClassSelector("note"), declarations=[ Declaration("font-size", Number(40.0)) ]
+    /// - `} p { color: red; }` → 余分な `}` も prelude に入るので、セレクタが読めずルールごと捨てる（ブラウザと同じ）
     ///
     /// 関連仕様
     /// - consume-qualified-rule: https://www.w3.org/TR/css-syntax-3/#consume-qualified-rule
     /// - qualified-rule:         https://www.w3.org/TR/css-syntax-3/#qualified-rule
     /// - style rules:            https://www.w3.org/TR/css-syntax-3/#style-rules
-    fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
+    fn consume_qualified_rule(&mut self, nested: bool) -> Option<QualifiedRule> {
+        // `{` より前のトークン列（prelude）。結合子の判定に空白が必要なので Whitespace も含めて切り出す
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None => {
+                    self.warn(
+                        start,
+                        "`{` が無いまま入力が終わったので、このルールを無視します".to_string(),
+                    );
+                    return None;
+                }
+                Some(CssToken::CloseCurly) if nested => {
+                    self.warn(
+                        start,
+                        "`{` が無いまま @media ブロックが終わったので、このルールを無視します"
+                            .to_string(),
+                    );
+                    return None;
+                }
+                Some(CssToken::OpenCurly) => break,
+                Some(_) => self.consume_component_value(),
+            }
+        }
+        let prelude = self.tokens[start..self.pos].to_vec();
+        // `{` に到達 → 宣言ブロック開始。中身（declarations）を読み切る。
+        self.next();
+        let declarations = self.consume_list_of_declarations(true);
+
+        let selector = parse_selector_list(&prelude);
+        if selector == Selector::UnknownSelector {
+            self.warn(
+                start,
+                "セレクタを解釈できないので、このルールを無視します".to_string(),
+            );
+            return None;
+        }
         let mut rule = QualifiedRule::new();
+        rule.set_selector(selector);
+        rule.set_declarations(declarations);
+        Some(rule)
+    }
 
+    // at-keyword と prelude を読む（`;` `{`、`nested` なら `}` の手前、または入力の終わりまで）
+    fn consume_at_rule_prelude(&mut self, nested: bool) -> (String, Vec<CssToken>) {
+        let name = match self.next() {
+            Some(CssToken::AtKeyword(name)) => name,
+            _ => String::new(),
+        };
+        let start = self.pos;
         loop {
-            let token = match self.t.peek() {
-                Some(t) => t,
-                None => return None,
-            };
+            match self.peek() {
+                None | Some(CssToken::SemiColon | CssToken::OpenCurly) => break,
+                Some(CssToken::CloseCurly) if nested => break,
+                Some(_) => self.consume_component_value(),
+            }
+        }
+        (name, self.tokens[start..self.pos].to_vec())
+    }
 
-            match token {
-                CssToken::OpenCurly => {
-                    // `{` に到達 → 宣言ブロック開始。中身（declarations）を読み切って返す。
-                    assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
-                    rule.set_declarations(self.consume_list_of_declarations());
-                    return Some(rule);
+    /// at-rule（`@import ...;` や `@font-face { ... }`）を 1 つ読み、名前と prelude を返す
+    ///
+    /// - prelude は `;` か `{` までのトークン列です。
+    /// - `{ ... }` のブロックがあれば、対応する `}` まで読み飛ばします。
+    ///
+    /// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-an-at-rule
+    fn consume_at_rule(&mut self, nested: bool) -> (String, Vec<CssToken>) {
+        let (name, prelude) = self.consume_at_rule_prelude(nested);
+        match self.peek() {
+            Some(CssToken::SemiColon) => {
+                self.next();
+            }
+            Some(CssToken::OpenCurly) => self.consume_component_value(),
+            _ => {}
+        }
+        (name, prelude)
+    }
+
+    /// `@media` を 1 つ読み、中のルールにメディアクエリを付けて返す
+    ///
+    /// - prelude（`{` の前）をメディアクエリのリストとして解釈し、ブロックの中身を
+    ///   `consume_list_of_rules` で `}` まで読みます。
+    /// - 入れ子の `@media` の中のルールは、外側と内側のクエリの両方を持ちます（両方に一致したときだけ有効）。
+    /// - `{` の無い `@media ...;` は中身が無いので何も返しません。
+    ///
+    /// 例: `@media print { p { color: red; } }` → [QualifiedRule(p, media=[print])]
+    ///
+    /// 仕様: https://www.w3.org/TR/css-conditional-3/#at-media
+    fn consume_media_rule(&mut self, nested: bool) -> Vec<QualifiedRule> {
+        let start = self.pos;
+        let (_, prelude) = self.consume_at_rule_prelude(nested);
+        if self.peek() != Some(&CssToken::OpenCurly) {
+            if self.peek() == Some(&CssToken::SemiColon) {
+                self.next();
+            }
+            self.warn(start, "@media にブロックがありません".to_string());
+            return Vec::new();
+        }
+        self.next();
+
+        let media = parse_media_query_list(&prelude);
+        let mut rules = self.consume_list_of_rules(true);
+        for rule in &mut rules {
+            rule.media.insert(0, media.clone());
+        }
+        rules
+    }
+
+    /// `@keyframes` を 1 つ読む
+    ///
+    /// - prelude はアニメーションの名前（識別子か文字列）1 つです。読めなければブロックごと捨てます。
+    /// - ブロックの中は「キーフレームセレクタ + 宣言ブロック」の並びです。
+    ///   セレクタは `from`（0%）/ `to`（100%）/ `0%`〜`100%` のカンマ区切りで、読めないものはそのキーフレームだけを捨てます。
+    /// - キーフレームの中の `!important` の宣言は無視します（仕様）。
+    ///
+    /// 例: `@keyframes fade { from { opacity: 0 } 50%, 75% { opacity: 0.5 } }`
+    ///     → KeyframesRule("fade", [Keyframe([0], opacity: 0), Keyframe([0.5, 0.75], opacity: 0.5)])
+    ///
+    /// 仕様: https://drafts.csswg.org/css-animations-1/#keyframes
+    fn consume_keyframes_rule(&mut self, nested: bool) -> Option<KeyframesRule> {
+        let start = self.pos;
+        let (_, prelude) = self.consume_at_rule_prelude(nested);
+        if self.peek() != Some(&CssToken::OpenCurly) {
+            if self.peek() == Some(&CssToken::SemiColon) {
+                self.next();
+            }
+            self.warn(start, "@keyframes にブロックがありません".to_string());
+            return None;
+        }
+        let name = keyframes_name(&prelude);
+        if name.is_none() {
+            self.warn(
+                start,
+                "@keyframes の名前を読めないので、このルールを無視します".to_string(),
+            );
+            self.consume_component_value();
+            return None;
+        }
+        self.next();
+
+        let mut keyframes = Vec::new();
+        loop {
+            let keyframe_start = self.pos;
+            match self.peek() {
+                None => {
+                    self.warn(
+                        keyframe_start,
+                        "@keyframes ブロックの `}` がありません".to_string(),
+                    );
+                    break;
+                }
+                Some(CssToken::Whitespace | CssToken::SemiColon) => {
+                    self.next();
                 }
-                _ => {
-                    // それ以外の時、ルールのセレクタとして扱うためセレクタを解釈し、
-                    // ルールの selector フィールドに設定する。
-                    rule.set_selector(self.consume_selector());
+                Some(CssToken::CloseCurly) => {
+                    self.next();
+                    break;
+                }
+                Some(_) => {
+                    // `{` までをキーフレームセレクタとして読む
+                    loop {
+                        match self.peek() {
+                            None | Some(CssToken::OpenCurly | CssToken::CloseCurly) => break,
+                            Some(_) => self.consume_component_value(),
+                        }
+                    }
+                    if self.peek() != Some(&CssToken::OpenCurly) {
+                        self.warn(
+                            keyframe_start,
+                            "キーフレームに宣言ブロックがないので無視します".to_string(),
+                        );
+                        continue;
+                    }
+                    let selector = self.tokens[keyframe_start..self.pos].to_vec();
+                    self.next();
+                    let declarations = self.consume_list_of_declarations(true);
+                    match keyframe_offsets(&selector) {
+                        Some(offsets) => keyframes.push(Keyframe {
+                            offsets,
+                            declarations: declarations
+                                .into_iter()
+                                .filter(|declaration| !declaration.important)
+                                .collect(),
+                        }),
+                        None => self.warn(
+                            keyframe_start,
+                            "キーフレームセレクタを解釈できないので、このキーフレームを無視します"
+                                .to_string(),
+                        ),
+                    }
                 }
             }
         }
+
+        name.map(|name| KeyframesRule { name, keyframes })
     }
 
     /// スタイルルールの並びを EOF まで解釈する
     ///
     /// 役割
     /// - 通常の style rule を次々に `consume_qualified_rule` で読み取り、ベクタに集めます。
-    /// - `@` で始まる at-rule（@import/@media 等）は本書の簡易実装では無視（読み飛ばし）の方針です。
+    ///   読めないルールはそのルールだけを捨てて、続きを読みます。
+    /// - `@media` は中のルールを取り出して並べ、`@import` は URL を、`@keyframes` は中身を記録します。
+    ///   その他の at-rule は本書の簡易実装では無視（読み飛ばし）の方針です。
+    /// - `nested` が true（`@media { ... }` の中）のときは、閉じる `}` で終わります。
     ///
     /// 入力例 → 出力イメージ
     /// - `p{...} h1{...}` → vec![ QualifiedRule(p, ...), QualifiedRule(h1, ...) ]
     ///
     /// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-a-list-of-rules
-    fn consume_list_of_rules(&mut self) -> Vec<QualifiedRule> {
+    fn consume_list_of_rules(&mut self, nested: bool) -> Vec<QualifiedRule> {
         // 空のベクタを作成する
         let mut rules = Vec::new();
 
         loop {
-            let token = match self.t.peek() {
-                Some(t) => t,
-                None => return rules,
-            };
-            match token {
+            let start = self.pos;
+            match self.peek() {
+                None => {
+                    if nested {
+                        self.warn(start, "@media ブロックの `}` がありません".to_string());
+                    }
+                    return rules;
+                }
+                // ルールとルールの間の空白は読み飛ばす
+                Some(CssToken::Whitespace) => {
+                    self.next();
+                }
+                // `<style><!-- p { ... } --></style>` のように HTML コメントで囲んだ名残。最上位では読み飛ばす
+                Some(CssToken::Cdo | CssToken::Cdc) if !nested => {
+                    self.next();
+                }
+                // `@media { ... }` の終わり
+                Some(CssToken::CloseCurly) if nested => {
+                    self.next();
+                    return rules;
+                }
+                Some(CssToken::AtKeyword(keyword)) if keyword.eq_ignore_ascii_case("media") => {
+                    rules.extend(self.consume_media_rule(nested));
+                }
+                Some(CssToken::AtKeyword(keyword)) if keyword.eq_ignore_ascii_case("keyframes") => {
+                    if let Some(keyframes) = self.consume_keyframes_rule(nested) {
+                        self.keyframes.push(keyframes);
+                    }
+                }
                 // AtKeywordトークンが出てきた場合、他のCSSをインポートする@import、
                 // メディアクエリを表す@mediaなどのルールが始まることを表す
-                CssToken::AtKeyword(_keyword) => {
-                    let _rule = self.consume_qualified_rule();
-                    // しかし、本書のブラウザでは@から始まるルールはサポート
-                    // しないので、無視をする
+                Some(CssToken::AtKeyword(_)) => {
+                    let (name, prelude) = self.consume_at_rule(nested);
+                    if !name.eq_ignore_ascii_case("import") {
+                        // それ以外の@から始まるルールはサポートしないので、無視をする
+                        self.warn(start, format!("@{} は未対応なので無視します", name));
+                    } else if nested || !rules.is_empty() {
+                        // @import はスタイルルールより前に書かれたものだけが有効
+                        // https://www.w3.org/TR/css-cascade-4/#at-import
+                        self.warn(
+                            start,
+                            "@import はほかのルールより前に書く必要があるので無視します"
+                                .to_string(),
+                        );
+                    } else {
+                        match import_rule(&prelude) {
+                            Some(import) => self.imports.push(import),
+                            None => self.warn(start, "@import の URL を読めません".to_string()),
+                        }
+                    }
                 }
-                _ => {
+                Some(_) => {
                     // 1つの style rule を解釈し、成功したらベクタに追加する
-                    let rule = self.consume_qualified_rule();
-                    match rule {
-                        Some(r) => rules.push(r),
-                        None => return rules,
+                    if let Some(rule) = self.consume_qualified_rule(nested) {
+                        rules.push(rule);
                     }
                 }
             }
@@ -273,6 +534,7 @@ impl CssParser {
     /// 役割
     /// - 見出し（ルールの列）を `consume_list_of_rules` で構築し、`StyleSheet.rules` に設定します。
     /// - この戻り値（CSSOM）は、後工程の「スタイル計算」（DOMとマッチングして計算）で用います。
+    /// - 読み捨てた部分は `warnings()` で確認できます。
     ///
     /// 仕様: https://www.w3.org/TR/css-syntax-3/#parse-stylesheet
     pub fn parse_stylesheet(&mut self) -> StyleSheet {
@@ -280,9 +542,94 @@ impl CssParser {
         let mut sheet = StyleSheet::new();
 
         // トークン列からルールのリストを作成し、StyleSheetのフィールドに設定する
-        sheet.set_rules(self.consume_list_of_rules());
+        sheet.set_rules(self.consume_list_of_rules(false));
+        sheet.set_imports(core::mem::take(&mut self.imports));
+        sheet.set_keyframes(core::mem::take(&mut self.keyframes));
         sheet
     }
+
+    /// 宣言の並び（`color: red; margin: 0`）だけを解釈する
+    ///
+    /// 役割
+    /// - セレクタも `{}` もない、`style` 属性（インラインスタイル）の中身を読むために使います。
+    /// - 例: `<p style="color: red; display: none">` → [color: red, display: none]
+    ///
+    /// 仕様: https://www.w3.org/TR/css-syntax-3/#parse-list-of-declarations
+    pub fn parse_declaration_list(&mut self) -> Vec<Declaration> {
+        self.consume_list_of_declarations(false)
+    }
+}
+
+/// 宣言 1 つ分のトークン列（`color : red !important`）を Declaration にする
+///
+/// - 値の中の空白は除く（`Times New Roman` は Ident が 3 つ並んだものとして残る）
+/// - 末尾の `!important`（`!` と `important` の間の空白も可）は取り除いて `important` にする
+/// - 次の場合は宣言にならず、理由（警告の文）を返す
+///   - `:` が無い、値が空（`color: ;`）
+///   - 値に閉じていない文字列・壊れた `url()`・対応しない閉じ括弧がある
+///     （どのプロパティの値にもなれないため。カスタムプロパティでも無効）
+///
+/// 仕様: https://www.w3.org/TR/css-syntax-3/#consume-declaration
+fn parse_declaration(tokens: &[CssToken]) -> Result<Declaration, String> {
+    let mut tokens = tokens.iter().filter(|t| **t != CssToken::Whitespace);
+    let property = match tokens.next() {
+        Some(CssToken::Ident(name)) => name.clone(),
+        _ => return Err("宣言がプロパティ名で始まっていません".to_string()),
+    };
+    if tokens.next() != Some(&CssToken::Colon) {
+        return Err(format!(
+            "{} の後ろに `:` がないので、この宣言を無視します",
+            property
+        ));
+    }
+
+    let mut values: Vec<ComponentValue> = tokens.cloned().collect();
+    let important = matches!(
+        values.as_slice(),
+        [.., CssToken::Delim('!'), CssToken::Ident(i)] if i.eq_ignore_ascii_case("important")
+    );
+    if important {
+        values.truncate(values.len() - 2);
+    }
+    if values.is_empty() {
+        return Err(format!("{} の値がないので、この宣言を無視します", property));
+    }
+    if let Some(problem) = invalid_value_token(&values) {
+        return Err(format!(
+            "{} の値に{}があるので、この宣言を無視します",
+            property, problem
+        ));
+    }
+
+    let mut declaration = Declaration::new();
+    declaration.set_property(property);
+    declaration.set_values(values);
+    declaration.set_important(important);
+    Ok(declaration)
+}
+
+// 宣言の値に含められないトークンの説明（閉じていない文字列・壊れた url()・対応しない閉じ括弧）
+// https://www.w3.org/TR/css-syntax-3/#typedef-declaration-value
+fn invalid_value_token(values: &[ComponentValue]) -> Option<&'static str> {
+    let mut closers = Vec::new();
+    for value in values {
+        match value {
+            CssToken::BadString => return Some("閉じていない文字列"),
+            CssToken::BadUrl => return Some("壊れた url()"),
+            CssToken::Function(_) | CssToken::OpenParenthesis => {
+                closers.push(CssToken::CloseParenthesis)
+            }
+            CssToken::OpenSquareBracket => closers.push(CssToken::CloseSquareBracket),
+            CssToken::OpenCurly => closers.push(CssToken::CloseCurly),
+            CssToken::CloseParenthesis | CssToken::CloseSquareBracket | CssToken::CloseCurly => {
+                if closers.pop().as_ref() != Some(value) {
+                    return Some("対応しない閉じ括弧");
+                }
+            }
+            _ => {}
+        }
+    }
+    None
 }
 
 /// https://www.w3.org/TR/cssom-1/#cssstylesheet
@@ -290,16 +637,129 @@ impl CssParser {
 pub struct StyleSheet {
     /// https://drafts.csswg.org/cssom/#dom-cssstylesheet-cssrules
     pub rules: Vec<QualifiedRule>, // スタイルシートに含まれるルール列（読み順）
+    /// https://www.w3.org/TR/css-cascade-4/#cascading-origins
+    pub origin: CascadeOrigin, // 誰が書いたスタイルか（ページの <style> は Author）
+    /// https://drafts.csswg.org/cssom/#cssimportrule
+    pub imports: Vec<ImportRule>, // `@import` で読み込むシート（出現順）
+    /// https://drafts.csswg.org/css-animations-1/#interface-csskeyframesrule
+    pub keyframes: Vec<KeyframesRule>, // `@keyframes`（出現順。同じ名前なら後ろのものが使われる）
 }
 
 impl StyleSheet {
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            origin: CascadeOrigin::Author,
+            imports: Vec::new(),
+            keyframes: Vec::new(),
+        }
+    }
+
+    pub fn set_imports(&mut self, imports: Vec<ImportRule>) {
+        self.imports = imports;
     }
 
     pub fn set_rules(&mut self, rules: Vec<QualifiedRule>) {
         self.rules = rules;
     }
+
+    pub fn set_origin(&mut self, origin: CascadeOrigin) {
+        self.origin = origin;
+    }
+
+    pub fn set_keyframes(&mut self, keyframes: Vec<KeyframesRule>) {
+        self.keyframes = keyframes;
+    }
+}
+
+/// `@keyframes name { ... }` 1 つ分
+/// https://drafts.csswg.org/css-animations-1/#keyframes
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyframesRule {
+    pub name: String, // `animation-name` で指す名前（大文字小文字を区別する）
+    pub keyframes: Vec<Keyframe>, // 書かれた順（時点の順に並べ替えるのは使う側）
+}
+
+/// キーフレーム 1 つ（`from, 50% { opacity: 0 }`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    pub offsets: Vec<f64>, // 時点（0.0 = from 〜 1.0 = to）。カンマ区切りで書いた分だけある
+    pub declarations: Vec<Declaration>, // この時点の宣言（`!important` のものは除いてある）
+}
+
+/// `@import url(a.css) screen;` 1 つ分
+/// https://www.w3.org/TR/css-cascade-4/#at-import
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRule {
+    pub url: String,           // 書かれたままの（相対）URL
+    pub media: MediaQueryList, // URL の後ろのメディアクエリ（無ければ空 = 常に一致）
+}
+
+/// `@import` の prelude から URL とメディアクエリを取り出す
+/// 受け付ける形: `"a.css"` / `url(a.css)` / `url("a.css")` の後ろに、省略できるメディアクエリのリスト
+/// （`layer()` / `supports()` は未対応で、メディアクエリとして読めずにどの環境にも一致しなくなる）
+fn import_rule(prelude: &[CssToken]) -> Option<ImportRule> {
+    let start = prelude.iter().position(|t| *t != CssToken::Whitespace)?;
+    let (url, rest) = match &prelude[start..] {
+        [CssToken::StringToken(url) | CssToken::Url(url), rest @ ..] => (url.clone(), rest),
+        [CssToken::Function(name), rest @ ..] if name.eq_ignore_ascii_case("url") => {
+            let close = rest.iter().position(|t| *t == CssToken::CloseParenthesis)?;
+            let mut arguments = rest[..close].iter().filter(|t| **t != CssToken::Whitespace);
+            match (arguments.next(), arguments.next()) {
+                (Some(CssToken::StringToken(url)), None) => (url.clone(), &rest[close + 1..]),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(ImportRule {
+        url,
+        media: parse_media_query_list(rest),
+    })
+}
+
+/// `@keyframes` の prelude から名前を取り出す（識別子か文字列 1 つ。`none` は名前にできない）
+fn keyframes_name(prelude: &[CssToken]) -> Option<String> {
+    let tokens: Vec<&CssToken> = prelude
+        .iter()
+        .filter(|t| **t != CssToken::Whitespace)
+        .collect();
+    match tokens[..] {
+        [CssToken::Ident(name)] if !name.eq_ignore_ascii_case("none") => Some(name.clone()),
+        [CssToken::StringToken(name)] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// キーフレームセレクタ（`from, 50%`）を時点（0.0〜1.0）の並びにする。読めなければ None
+/// https://drafts.csswg.org/css-animations-1/#typedef-keyframe-selector
+fn keyframe_offsets(selector: &[CssToken]) -> Option<Vec<f64>> {
+    let tokens: Vec<&CssToken> = selector
+        .iter()
+        .filter(|t| **t != CssToken::Whitespace)
+        .collect();
+    let mut offsets = Vec::new();
+    for part in tokens.split(|t| **t == CssToken::Comma) {
+        let offset = match part {
+            [CssToken::Ident(s)] if s.eq_ignore_ascii_case("from") => 0.0,
+            [CssToken::Ident(s)] if s.eq_ignore_ascii_case("to") => 1.0,
+            [CssToken::Percentage(n)] if (0.0..=100.0).contains(n) => n / 100.0,
+            _ => return None,
+        };
+        offsets.push(offset);
+    }
+    Some(offsets)
+}
+
+/// https://www.w3.org/TR/css-cascade-4/#cascading-origins
+/// スタイルの出どころ。宣言がぶつかったときの優先順位に使います。
+/// - 通常の宣言: UserAgent < User < Author（ページの作者が一番強い）
+/// - `!important`: Author < User < UserAgent（順番が逆転し、利用者やブラウザの設定を守れる）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CascadeOrigin {
+    UserAgent, // ブラウザ組み込みの既定スタイル
+    User,      // 利用者が設定したスタイル
+    Author,    // ページ（<style> など）のスタイル
 }
 
 /// https://www.w3.org/TR/css-syntax-3/#qualified-rule
@@ -307,10 +767,12 @@ impl StyleSheet {
 pub struct QualifiedRule {
     /// https://www.w3.org/TR/selectors-4/#typedef-selector-list
     /// The prelude of the qualified rule is parsed as a <selector-list>.
-    pub selector: Selector, // セレクタ（簡易: 1つだけを保持。複合/コンビネータは未対応）
+    pub selector: Selector, // セレクタ（`h1, h2` のようなリストは SelectorList として 1 つにまとめて保持）
     /// https://www.w3.org/TR/css-syntax-3/#parse-a-list-of-declarations
     /// The content of the qualified rule’s block is parsed as a list of declarations.
     pub declarations: Vec<Declaration>, // ブロック `{ ... }` 内の宣言一覧
+    /// https://www.w3.org/TR/css-conditional-3/#at-media
+    pub media: Vec<MediaQueryList>, // 囲んでいる `@media` のクエリ（外側から順。すべてに一致したときだけ有効）
 }
 
 impl QualifiedRule {
@@ -318,6 +780,7 @@ impl QualifiedRule {
         Self {
             selector: Selector::TypeSelector("".to_string()),
             declarations: Vec::new(),
+            media: Vec::new(),
         }
     }
 
@@ -339,15 +802,91 @@ pub enum Selector {
     ClassSelector(String), // 例: ".note" → ClassSelector("note") として保持
     /// https://www.w3.org/TR/selectors-4/#id-selectors
     IdSelector(String), // 例: "#main" → IdSelector("main") として保持
+    /// https://www.w3.org/TR/selectors-4/#the-universal-selector
+    UniversalSelector, // 例: "*"（すべての要素に一致）
+    /// https://www.w3.org/TR/selectors-4/#attribute-selectors
+    /// 例: `[type=text]` → name="type", operator=Equals, value="text"
+    ///     `[lang|="en" i]` → operator=DashMatch, case_insensitive=true
+    AttributeSelector {
+        name: String,
+        operator: AttributeOperator,
+        value: String,
+        case_insensitive: bool,
+    },
+    /// https://www.w3.org/TR/selectors-4/#pseudo-classes
+    /// 例: `:first-child`, `:nth-child(2n+1)`, `:hover`（`a:hover` は Compound[Type("a"), PseudoClass(Hover)]）
+    PseudoClassSelector(PseudoClass),
+    /// https://www.w3.org/TR/selectors-4/#compound
+    /// 空白を挟まずに並べた単純セレクタの組。例: `a.button#go` → [Type("a"), Class("button"), Id("go")]
+    /// すべての要素が一致したときだけ一致する（AND）。
+    CompoundSelector(Vec<Selector>),
+    /// https://www.w3.org/TR/selectors-4/#complex
+    /// 結合子でつないだセレクタ。`body > p` → { left: Type("body"), combinator: Child, right: Type("p") }
+    /// `a b c` は `(a b) c` のように左結合で入れ子にします（右端の `c` が“対象要素”）。
+    ComplexSelector {
+        left: Box<Selector>,
+        combinator: Combinator,
+        right: Box<Selector>,
+    },
+    /// https://www.w3.org/TR/selectors-4/#selector-list
+    /// カンマ区切りのリスト。例: `h1, h2` → どれか 1 つに一致すれば一致（OR）。
+    SelectorList(Vec<Selector>),
     /// パース中にエラーが起こったときに使用されるセレクタ
     UnknownSelector,
 }
 
+/// https://www.w3.org/TR/selectors-4/#attribute-representation
+/// 属性セレクタの演算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeOperator {
+    Exists,    // [attr]        属性があれば一致
+    Equals,    // [attr=v]      完全一致
+    Includes,  // [attr~=v]     空白区切りのどれかが v
+    DashMatch, // [attr|=v]     v そのもの、または `v-` で始まる（lang="en-US" など）
+    Prefix,    // [attr^=v]     v で始まる
+    Suffix,    // [attr$=v]     v で終わる
+    Substring, // [attr*=v]     v を含む
+}
+
+/// https://www.w3.org/TR/selectors-4/#pseudo-classes
+/// 対応している疑似クラス。
+/// `Hover`/`Focus`/`Active`/`Visited` はユーザー操作や履歴で変わるため、
+/// マッチング時に `MatchingContext`（`selector` モジュール）から状態を受け取ります。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PseudoClass {
+    FirstChild,                  // :first-child
+    LastChild,                   // :last-child
+    NthChild { a: i64, b: i64 }, // :nth-child(an+b)。`odd` は a=2,b=1、`3` は a=0,b=3
+    Not(Box<Selector>),          // :not(S)  S に一致しない
+    Is(Box<Selector>),           // :is(S)   S のどれかに一致する
+    Root,                        // :root    文書のルート要素（<html>）
+    Empty,                       // :empty   子ノードを持たない
+    Link,                        // :link    未訪問のリンク
+    Visited,                     // :visited 訪問済みのリンク
+    Hover,                       // :hover   マウスカーソルが乗っている（その祖先も含む）
+    Focus,                       // :focus   フォーカスを持っている
+    Active,                      // :active  マウスボタンで押されている（その祖先も含む）
+}
+
+/// https://www.w3.org/TR/selectors-4/#combinators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    Descendant,        // `A B`  B は A の子孫
+    Child,             // `A > B` B は A の子
+    NextSibling,       // `A + B` B は A の直後の兄弟
+    SubsequentSibling, // `A ~ B` B は A より後ろの兄弟
+}
+
 /// https://www.w3.org/TR/css-syntax-3/#declaration
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
     pub property: String,      // 例: "color", "font-size"
-    pub value: ComponentValue, // 例: Ident("red"), Number(40.0)
+    pub value: ComponentValue, // 例: Ident("red"), Number(40.0)（`values` の先頭）
+    /// 値を構成するすべてのコンポーネント値（空白は除く）
+    /// 例: `font-family: "A", serif` → [StringToken("A"), Comma, Ident("serif")]
+    pub values: Vec<ComponentValue>,
+    /// https://www.w3.org/TR/css-cascade-4/#importance
+    pub important: bool, // 例: `color: red !important` → true
 }
 
 impl Declaration {
@@ -355,16 +894,32 @@ impl Declaration {
         Self {
             property: String::new(),
             value: ComponentValue::Ident(String::new()),
+            values: Vec::new(),
+            important: false,
         }
     }
 
+    pub fn set_important(&mut self, important: bool) {
+        self.important = important;
+    }
+
     pub fn set_property(&mut self, property: String) {
         self.property = property;
     }
 
+    /// 値が 1 つだけの宣言にする
     pub fn set_value(&mut self, value: ComponentValue) {
+        self.values = vec![value.clone()];
         self.value = value;
     }
+
+    /// 値を設定する（`value` には先頭の値が入る）
+    pub fn set_values(&mut self, values: Vec<ComponentValue>) {
+        if let Some(first) = values.first() {
+            self.value = first.clone();
+        }
+        self.values = values;
+    }
 }
 
 // 値（component value）を、まずは「CSSトークンそのもの」で持つ簡易版。
@@ -376,6 +931,9 @@ pub type ComponentValue = CssToken;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::css::media::MediaEnvironment;
+    use crate::renderer::css::media::MediaType;
+    use crate::renderer::css::token::NumericType;
     use alloc::vec;
 
     #[test]
@@ -480,7 +1038,7 @@ mod tests {
         rule2.set_selector(Selector::TypeSelector("h1".to_string()));
         let mut declaration2 = Declaration::new();
         declaration2.set_property("font-size".to_string());
-        declaration2.set_value(ComponentValue::Number(40.0));
+        declaration2.set_value(ComponentValue::Number(40.0, NumericType::Integer));
         let mut declaration3 = Declaration::new();
         declaration3.set_property("color".to_string());
         declaration3.set_value(ComponentValue::Ident("blue".to_string()));
@@ -492,4 +1050,222 @@ mod tests {
             assert_eq!(exp, got);
         }
     }
+
+    #[test]
+    fn test_multiple_component_values() {
+        let style =
+            "p { font-family: \"A B\", Times New Roman, serif !important; color: ; }".to_string();
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        let declarations = &cssom.rules[0].declarations;
+
+        // 値の無い `color: ;` は宣言にならない
+        assert_eq!(1, declarations.len());
+        assert!(declarations[0].important);
+        assert_eq!(
+            vec![
+                ComponentValue::StringToken("A B".to_string()),
+                ComponentValue::Comma,
+                ComponentValue::Ident("Times".to_string()),
+                ComponentValue::Ident("New".to_string()),
+                ComponentValue::Ident("Roman".to_string()),
+                ComponentValue::Comma,
+                ComponentValue::Ident("serif".to_string()),
+            ],
+            declarations[0].values
+        );
+        assert_eq!(
+            ComponentValue::StringToken("A B".to_string()),
+            declarations[0].value
+        );
+    }
+
+    #[test]
+    fn test_important() {
+        // 入力: p { color: red !important; display : block; }
+        // 期待: 1 つ目だけ important=true。コロン前後の空白も許す
+        let style = "p { color: red !important; display : block; }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let declarations = &cssom.rules[0].declarations;
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[0].property, "color");
+        assert_eq!(
+            declarations[0].value,
+            ComponentValue::Ident("red".to_string())
+        );
+        assert!(declarations[0].important);
+        assert_eq!(declarations[1].property, "display");
+        assert!(!declarations[1].important);
+    }
+
+    #[test]
+    fn test_import() {
+        // @import は URL とメディアクエリを記録し、後ろのルールを飲み込まない
+        // ルールの後ろに書かれた @import と、その他の at-rule は無視する
+        let style = "@import \"a.css\"; @import url(css/b.css) screen; @font-face { src: x; } \
+                     p { color: red; } @import \"late.css\"; h1 { color: blue; }"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let urls: Vec<&str> = cssom.imports.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(urls, ["a.css", "css/b.css"]);
+        // URL の後ろのメディアクエリも記録する（無ければ空 = 常に一致）
+        let screen = MediaEnvironment::new(800, 600);
+        let mut print = MediaEnvironment::new(800, 600);
+        print.set_media_type(MediaType::Print);
+        assert!(cssom.imports[0].media.matches(&print));
+        assert!(cssom.imports[1].media.matches(&screen));
+        assert!(!cssom.imports[1].media.matches(&print));
+        assert_eq!(cssom.rules.len(), 2);
+        assert_eq!(
+            cssom.rules[1].selector,
+            Selector::TypeSelector("h1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_media() {
+        // @media の中のルールにはクエリが付き、外のルールには付かない。入れ子なら両方が付く
+        let style =
+            "@media (max-width: 600px) { p { color: red; } @media print { a { color: blue; } } } \
+                     h1 { color: green; }"
+                .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        assert_eq!(cssom.rules.len(), 3);
+        assert_eq!(cssom.rules[0].media.len(), 1);
+        assert_eq!(cssom.rules[1].media.len(), 2);
+        assert_eq!(
+            cssom.rules[2].selector,
+            Selector::TypeSelector("h1".to_string())
+        );
+        assert!(cssom.rules[2].media.is_empty());
+
+        let narrow = MediaEnvironment::new(500, 400);
+        let wide = MediaEnvironment::new(800, 400);
+        assert!(cssom.rules[0].media[0].matches(&narrow));
+        assert!(!cssom.rules[0].media[0].matches(&wide));
+        assert!(!cssom.rules[1].media[1].matches(&narrow));
+    }
+
+    #[test]
+    fn test_keyframes() {
+        // キーフレームはセレクタの時点と宣言を持つ。読めないセレクタのキーフレームと !important の宣言は捨てる
+        let style = "@keyframes fade { from { opacity: 0 } 50%, 75% { opacity: 0.5 !important; color: red } \
+                     120% { opacity: 1 } to { opacity: 1 } } \
+                     @keyframes { } p { animation-name: fade; }"
+            .to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let cssom = parser.parse_stylesheet();
+
+        assert_eq!(cssom.rules.len(), 1);
+        assert_eq!(cssom.keyframes.len(), 1);
+        let fade = &cssom.keyframes[0];
+        assert_eq!(fade.name, "fade");
+        let offsets: Vec<Vec<f64>> = fade.keyframes.iter().map(|k| k.offsets.clone()).collect();
+        assert_eq!(offsets, vec![vec![0.0], vec![0.5, 0.75], vec![1.0]]);
+        let properties: Vec<&str> = fade.keyframes[1]
+            .declarations
+            .iter()
+            .map(|d| d.property.as_str())
+            .collect();
+        assert_eq!(properties, vec!["color"]);
+        // 120% のキーフレームと、名前の無い @keyframes
+        assert_eq!(parser.warnings().len(), 2);
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        // 読めない宣言はその宣言だけ、読めないセレクタはそのルールだけを捨てる
+        let style = "p { color red; display: none; width: 10px; }\n\
+                     } h1 { color: blue; }\n\
+                     h2 { color: \"bad\n; margin: 0; font-size: 12px }\n\
+                     h3 { 123; float: left; @page x { y: z } height: 1px }\n\
+                     a { background: url(x y); color: rgb(0, 128, 0"
+            .to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let cssom = parser.parse_stylesheet();
+
+        let summary: Vec<(Selector, Vec<&str>)> = cssom
+            .rules
+            .iter()
+            .map(|rule| {
+                let properties = rule
+                    .declarations
+                    .iter()
+                    .map(|d| d.property.as_str())
+                    .collect();
+                (rule.selector.clone(), properties)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Selector::TypeSelector("p".to_string()),
+                    vec!["display", "width"]
+                ),
+                (
+                    Selector::TypeSelector("h2".to_string()),
+                    vec!["margin", "font-size"]
+                ),
+                (
+                    Selector::TypeSelector("h3".to_string()),
+                    vec!["float", "height"]
+                ),
+                (Selector::TypeSelector("a".to_string()), vec!["color"]),
+            ]
+        );
+        // 入力の終わりで閉じていない関数とブロックは閉じたものとする
+        assert_eq!(
+            cssom.rules[3].declarations[0].values.last(),
+            Some(&ComponentValue::Number(0.0, NumericType::Integer))
+        );
+        assert_eq!(parser.warnings().len(), 8);
+    }
+
+    #[test]
+    fn test_warnings() {
+        // 警告には読み捨てた部分の先頭の行・列（1 始まり）が付く
+        let style = "p {\n  color red;\n}\n@font-face { src: x }\n} a { }".to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let cssom = parser.parse_stylesheet();
+        assert!(cssom.rules.len() == 1 && cssom.rules[0].declarations.is_empty());
+
+        let warnings: Vec<String> = parser.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "2:3: color の後ろに `:` がないので、この宣言を無視します".to_string(),
+                "4:1: @font-face は未対応なので無視します".to_string(),
+                "5:1: セレクタを解釈できないので、このルールを無視します".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recovery_in_blocks() {
+        // `@media` の中で `{` の無いルールは、ブロックの `}` までで捨てる（外のルールは残る）
+        let style = "@media print { p { color: red } a } h1 { color: blue }".to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let cssom = parser.parse_stylesheet();
+        assert_eq!(cssom.rules.len(), 2);
+        assert_eq!(cssom.rules[0].media.len(), 1);
+        assert_eq!(
+            cssom.rules[1].selector,
+            Selector::TypeSelector("h1".to_string())
+        );
+        assert_eq!(parser.warnings().len(), 1);
+
+        // インラインスタイルでは `}` もただの読めない部分。次の `;` まで捨てる
+        let style = "color: red; } width: 1px; height: 2px".to_string();
+        let mut parser = CssParser::new(CssTokenizer::new(style));
+        let declarations = parser.parse_declaration_list();
+        let properties: Vec<&str> = declarations.iter().map(|d| d.property.as_str()).collect();
+        assert_eq!(properties, vec!["color", "height"]);
+        assert_eq!(parser.warnings().len(), 1);
+    }
 }
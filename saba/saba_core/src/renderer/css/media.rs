@@ -0,0 +1,426 @@
+//! メディアクエリ（`@media screen and (max-width: 600px) { ... }`）
+//!
+//! 役割
+//! - `@media` の prelude（`{` の前の部分）を `MediaQueryList` に解釈します。
+//! - 表示する環境（`MediaEnvironment` = ビューポートの大きさ・画面か印刷か・ライト/ダーク）に
+//!   一致するかを判定します。一致しない `@media` の中のルールはカスケードで使われません。
+//!
+//! 対応している書き方
+//! - メディアタイプ: `all` / `screen` / `print`（`not` / `only` を前に付けられる）
+//! - 特性（`and` でつなぐ）:
+//!   - `(width: 600px)` / `(min-width: 600px)` / `(max-width: 600px)`（height も同じ）
+//!   - 範囲の書き方: `(width >= 600px)` / `(400px <= width < 700px)`
+//!   - `(orientation: portrait | landscape)`
+//!   - `(prefers-color-scheme: light | dark)`
+//! - カンマ区切りのリスト（どれか 1 つに一致すればよい）
+//! - 長さの単位は px / em / rem / pt（em と rem は初期値の font-size = 16px が基準）
+//!
+//! 読めない部分があるクエリは「どの環境にも一致しない」（`not all` と同じ）として扱います。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `MediaQueryList::matches` は JS の `window.matchMedia(query).matches` に相当します。
+//!
+//! 仕様: https://www.w3.org/TR/mediaqueries-4/
+use crate::constants::CONTENT_AREA_HEIGHT;
+use crate::constants::CONTENT_AREA_WIDTH;
+use crate::renderer::css::token::CssToken;
+use alloc::vec::Vec;
+
+/// メディアタイプ（表示先の種類）
+/// https://www.w3.org/TR/mediaqueries-4/#media-types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+}
+
+/// 利用者が好む配色（`prefers-color-scheme`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// メディアクエリを判定する環境
+///
+/// - `width` / `height`: ビューポート（`LayoutView` がレイアウトする領域）の大きさ（px）
+/// - `media_type`: 画面に表示しているなら `Screen`、印刷なら `Print`
+/// - `color_scheme`: 利用者が好む配色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaEnvironment {
+    width: i64,
+    height: i64,
+    media_type: MediaType,
+    color_scheme: ColorScheme,
+}
+
+impl MediaEnvironment {
+    /// 大きさ `width` x `height` の画面（ライトモード）
+    pub fn new(width: i64, height: i64) -> Self {
+        Self {
+            width,
+            height,
+            media_type: MediaType::Screen,
+            color_scheme: ColorScheme::Light,
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        self.media_type
+    }
+
+    pub fn color_scheme(&self) -> ColorScheme {
+        self.color_scheme
+    }
+
+    pub fn set_media_type(&mut self, media_type: MediaType) {
+        self.media_type = media_type;
+    }
+
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.color_scheme = color_scheme;
+    }
+}
+
+/// 既定はコンテンツ領域（CONTENT_AREA_WIDTH x CONTENT_AREA_HEIGHT）の画面
+impl Default for MediaEnvironment {
+    fn default() -> Self {
+        Self::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT)
+    }
+}
+
+/// 大小の比べ方（`min-width` は `>=`、`max-width` は `<=`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, actual: f64, expected: f64) -> bool {
+        match self {
+            Self::Equal => actual == expected,
+            Self::Less => actual < expected,
+            Self::LessOrEqual => actual <= expected,
+            Self::Greater => actual > expected,
+            Self::GreaterOrEqual => actual >= expected,
+        }
+    }
+
+    // `a < width` を `width > a` に読み替えるときの向きの反転
+    fn flip(self) -> Self {
+        match self {
+            Self::Equal => Self::Equal,
+            Self::Less => Self::Greater,
+            Self::LessOrEqual => Self::GreaterOrEqual,
+            Self::Greater => Self::Less,
+            Self::GreaterOrEqual => Self::LessOrEqual,
+        }
+    }
+}
+
+/// `(...)` 1 つ分の条件
+#[derive(Debug, Clone, PartialEq)]
+enum MediaFeature {
+    /// ビューポートの幅が (比べ方, px) を満たす
+    Width(Comparison, f64),
+    Height(Comparison, f64),
+    /// 縦長（高さ >= 幅）なら portrait
+    Portrait(bool),
+    PrefersColorScheme(ColorScheme),
+}
+
+impl MediaFeature {
+    fn matches(&self, env: &MediaEnvironment) -> bool {
+        match self {
+            Self::Width(cmp, px) => cmp.holds(env.width as f64, *px),
+            Self::Height(cmp, px) => cmp.holds(env.height as f64, *px),
+            Self::Portrait(portrait) => (env.height >= env.width) == *portrait,
+            Self::PrefersColorScheme(scheme) => env.color_scheme == *scheme,
+        }
+    }
+}
+
+/// カンマで区切られたクエリの 1 つ（例: `not print and (min-width: 600px)`）
+#[derive(Debug, Clone, PartialEq)]
+struct MediaQuery {
+    not: bool,
+    media_type: MediaType,
+    features: Vec<MediaFeature>,
+}
+
+impl MediaQuery {
+    fn matches(&self, env: &MediaEnvironment) -> bool {
+        let type_matches = match self.media_type {
+            MediaType::All => true,
+            t => t == env.media_type,
+        };
+        let matches = type_matches && self.features.iter().all(|f| f.matches(env));
+        matches != self.not
+    }
+}
+
+/// メディアクエリのリスト（`@media` の prelude）
+///
+/// 例: `screen and (max-width: 600px), print` → どちらかに一致すれば有効
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQueryList {
+    // None は読めなかったクエリ（どの環境にも一致しない）
+    queries: Vec<Option<MediaQuery>>,
+}
+
+impl MediaQueryList {
+    /// 環境 `env` に一致するか（空のリストは常に一致）
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        self.queries.is_empty()
+            || self
+                .queries
+                .iter()
+                .any(|q| q.as_ref().is_some_and(|q| q.matches(env)))
+    }
+}
+
+/// `@media` の prelude（空白を含むトークン列）を読む
+///
+/// 例: `[Ident("screen"), Whitespace, Ident("and"), Whitespace, OpenParenthesis, ...]`
+/// → screen かつ (max-width: 600px)
+pub fn parse_media_query_list(prelude: &[CssToken]) -> MediaQueryList {
+    let tokens: Vec<CssToken> = prelude
+        .iter()
+        .filter(|t| **t != CssToken::Whitespace)
+        .cloned()
+        .collect();
+    if tokens.is_empty() {
+        return MediaQueryList {
+            queries: Vec::new(),
+        };
+    }
+    MediaQueryList {
+        queries: tokens
+            .split(|t| *t == CssToken::Comma)
+            .map(parse_media_query)
+            .collect(),
+    }
+}
+
+// クエリ 1 つ: `[not|only] <タイプ> [and (<特性>)]*` か `(<特性>) [and (<特性>)]*`
+fn parse_media_query(tokens: &[CssToken]) -> Option<MediaQuery> {
+    let mut query = MediaQuery {
+        not: false,
+        media_type: MediaType::All,
+        features: Vec::new(),
+    };
+    let mut rest = tokens;
+
+    if let [CssToken::Ident(word), tail @ ..] = rest {
+        let mut word = word;
+        let mut tail = tail;
+        if word.eq_ignore_ascii_case("not") || word.eq_ignore_ascii_case("only") {
+            query.not = word.eq_ignore_ascii_case("not");
+            match tail {
+                [CssToken::Ident(t), after @ ..] => {
+                    word = t;
+                    tail = after;
+                }
+                _ => return None,
+            }
+        }
+        query.media_type = match word.to_ascii_lowercase().as_str() {
+            "all" => MediaType::All,
+            "screen" => MediaType::Screen,
+            "print" => MediaType::Print,
+            _ => return None,
+        };
+        rest = match tail {
+            [] => return Some(query),
+            [CssToken::Ident(and), after @ ..] if and.eq_ignore_ascii_case("and") => after,
+            _ => return None,
+        };
+    }
+
+    // (<特性>) を `and` でつないだ並び
+    loop {
+        let close = rest.iter().position(|t| *t == CssToken::CloseParenthesis)?;
+        match &rest[..close] {
+            [CssToken::OpenParenthesis, inner @ ..] => {
+                query.features.extend(parse_media_feature(inner)?)
+            }
+            _ => return None,
+        }
+        rest = match &rest[close + 1..] {
+            [] => return Some(query),
+            [CssToken::Ident(and), after @ ..] if and.eq_ignore_ascii_case("and") => after,
+            _ => return None,
+        };
+    }
+}
+
+// 括弧の中身。`name: value` の形か範囲の形（`width >= 600px` / `400px <= width < 700px`）
+fn parse_media_feature(tokens: &[CssToken]) -> Option<Vec<MediaFeature>> {
+    if let [CssToken::Ident(name), CssToken::Colon, value] = tokens {
+        let name = name.to_ascii_lowercase();
+        let (prefix, name) = match name.split_once('-') {
+            Some((prefix @ ("min" | "max"), name)) => (Some(prefix), name),
+            _ => (None, name.as_str()),
+        };
+        let cmp = match prefix {
+            Some("min") => Comparison::GreaterOrEqual,
+            Some(_) => Comparison::LessOrEqual,
+            None => Comparison::Equal,
+        };
+        let feature = match (name, value) {
+            ("width", v) => MediaFeature::Width(cmp, length_px(v)?),
+            ("height", v) => MediaFeature::Height(cmp, length_px(v)?),
+            ("orientation", CssToken::Ident(v)) if prefix.is_none() => {
+                match v.to_ascii_lowercase().as_str() {
+                    "portrait" => MediaFeature::Portrait(true),
+                    "landscape" => MediaFeature::Portrait(false),
+                    _ => return None,
+                }
+            }
+            ("prefers-color-scheme", CssToken::Ident(v)) if prefix.is_none() => {
+                match v.to_ascii_lowercase().as_str() {
+                    "light" => MediaFeature::PrefersColorScheme(ColorScheme::Light),
+                    "dark" => MediaFeature::PrefersColorScheme(ColorScheme::Dark),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        return Some(alloc::vec![feature]);
+    }
+
+    // 範囲の形: 値と名前の間に比較演算子が挟まる
+    let mut features = Vec::new();
+    let mut rest = tokens;
+    let mut left: Option<(f64, Comparison)> = None;
+    let name = loop {
+        match rest {
+            [CssToken::Ident(name), tail @ ..] => {
+                rest = tail;
+                break name.to_ascii_lowercase();
+            }
+            [value, tail @ ..] if left.is_none() => {
+                let px = length_px(value)?;
+                let (cmp, tail) = comparison(tail)?;
+                left = Some((px, cmp));
+                rest = tail;
+            }
+            _ => return None,
+        }
+    };
+    let make = |cmp: Comparison, px: f64| match name.as_str() {
+        "width" => Some(MediaFeature::Width(cmp, px)),
+        "height" => Some(MediaFeature::Height(cmp, px)),
+        _ => None,
+    };
+    // `400px <= width` は `width >= 400px`
+    if let Some((px, cmp)) = left {
+        features.push(make(cmp.flip(), px)?);
+    }
+    if !rest.is_empty() {
+        let (cmp, tail) = comparison(rest)?;
+        match tail {
+            [value] => features.push(make(cmp, length_px(value)?)?),
+            _ => return None,
+        }
+    }
+    if features.is_empty() {
+        return None;
+    }
+    Some(features)
+}
+
+// 比較演算子（`=` / `<` / `<=` / `>` / `>=`）を読み、残りを返す
+fn comparison(tokens: &[CssToken]) -> Option<(Comparison, &[CssToken])> {
+    match tokens {
+        [CssToken::Delim('<'), CssToken::Delim('='), rest @ ..] => {
+            Some((Comparison::LessOrEqual, rest))
+        }
+        [CssToken::Delim('>'), CssToken::Delim('='), rest @ ..] => {
+            Some((Comparison::GreaterOrEqual, rest))
+        }
+        [CssToken::Delim('<'), rest @ ..] => Some((Comparison::Less, rest)),
+        [CssToken::Delim('>'), rest @ ..] => Some((Comparison::Greater, rest)),
+        [CssToken::Delim('='), rest @ ..] => Some((Comparison::Equal, rest)),
+        _ => None,
+    }
+}
+
+// 長さを px にする（em / rem は初期値の font-size = 16px が基準。単位の無い 0 も可）
+fn length_px(token: &CssToken) -> Option<f64> {
+    match token {
+        CssToken::Number(n, _) if *n == 0.0 => Some(0.0),
+        CssToken::Dimension(n, _, unit) => match unit.to_ascii_lowercase().as_str() {
+            "px" => Some(*n),
+            "em" | "rem" => Some(n * 16.0),
+            "pt" => Some(n * 4.0 / 3.0),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssTokenizer;
+    use alloc::string::String;
+
+    fn matches(query: &str, env: &MediaEnvironment) -> bool {
+        let tokens: Vec<CssToken> = CssTokenizer::new(String::from(query)).collect();
+        parse_media_query_list(&tokens).matches(env)
+    }
+
+    #[test]
+    fn test_width_and_height() {
+        let env = MediaEnvironment::new(500, 300);
+        assert!(matches("(max-width: 600px)", &env));
+        assert!(!matches("(min-width: 600px)", &env));
+        assert!(matches("(width: 500px)", &env));
+        assert!(matches("(min-width: 30em) and (max-height: 300px)", &env));
+        assert!(matches("(width >= 500px)", &env));
+        assert!(!matches("(width > 500px)", &env));
+        assert!(matches("(400px < width <= 500px)", &env));
+        assert!(!matches("(200px <= height < 300px)", &env));
+    }
+
+    #[test]
+    fn test_media_type_and_other_features() {
+        let mut env = MediaEnvironment::new(500, 300);
+        assert!(matches("screen", &env));
+        assert!(!matches("print", &env));
+        assert!(matches("not print", &env));
+        assert!(matches("only screen and (orientation: landscape)", &env));
+        assert!(!matches("(orientation: portrait)", &env));
+        assert!(matches("print, (prefers-color-scheme: light)", &env));
+
+        env.set_media_type(MediaType::Print);
+        env.set_color_scheme(ColorScheme::Dark);
+        assert!(matches("print and (prefers-color-scheme: dark)", &env));
+        assert!(!matches("screen", &env));
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        let env = MediaEnvironment::new(500, 300);
+        // 空のリストは常に一致、読めないクエリは一致しない（リストの他のクエリは有効なまま）
+        assert!(matches("", &env));
+        assert!(!matches("tv", &env));
+        assert!(!matches("(max-width: 50%)", &env));
+        assert!(!matches("(hover: hover)", &env));
+        assert!(matches("(unknown), all", &env));
+    }
+}
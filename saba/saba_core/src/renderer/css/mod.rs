@@ -1,2 +1,10 @@
+pub mod cascade;
 pub mod cssom;
+pub mod media;
+#[cfg(test)]
+mod parsing_tests;
+pub mod rule_set;
+pub mod selector;
+pub mod serialize;
 pub mod token;
+pub mod user_agent;
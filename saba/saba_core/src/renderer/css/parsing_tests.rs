@@ -0,0 +1,391 @@
+//! css-parsing-tests 形式の JSON フィクスチャでトークナイザとパーサを確かめる
+//!
+//! フィクスチャ（`testdata/`）は `[入力, 期待値, 入力, 期待値, ...]` の配列で、
+//! 書き方は css-parsing-tests（https://github.com/SimonSapin/css-parsing-tests）と同じです。
+//! - `component_value_list.json`: 入力全体をコンポーネント値の並びにしたもの
+//! - `one_component_value.json`: 前後の空白を除いてちょうど 1 つのコンポーネント値。
+//!   無ければ `["error", "empty"]`、2 つ以上あれば `["error", "extra-input"]`
+//! - `declaration_list.json`: `style` 属性の中身のような宣言の並び。
+//!   `["declaration", 名前, 値, !important か]` / `["at-rule", ...]` / `["error", "invalid"]`
+//!
+//! コンポーネント値（関数と括弧のブロックは入れ子の配列）の書き方
+//! - `" "` は空白、`":"` `";"` `","` `"<!--"` `"-->"` はそれぞれのトークン、そのほかの 1 文字は Delim
+//! - `["ident", 名前]` / `["at-keyword", 名前]` / `["hash", 名前, "id" | "unrestricted"]`
+//! - `["string", 値]` / `["url", 値]`
+//! - `["number", 表記, 値, "integer" | "number"]` / `["percentage", 表記, 値, 種類]` /
+//!   `["dimension", 表記, 値, 種類, 単位]`
+//! - `["function", 名前, 引数...]` / `["()", ...]` / `["[]", ...]` / `["{}", ...]`
+//! - `["error", "bad-string" | "bad-url" | ")" | "]" | "}"]`
+//!
+//! 比べる前に、期待値から今の CSS Syntax の仕様のトークンに無い情報だけを落とします。
+//! - 数値の書かれたままの表記（`"+.5"` など。仕様から無くなった）
+//! - percentage の型フラグ（仕様では number と dimension だけが持つ）
+//!
+//! hash の種類（id / unrestricted）と数値の型フラグ（integer / number）はそのまま比べます。
+//! 宣言の並びでは、このパーサが持つもの（残った宣言と、読み捨てた回数）に合わせて比べます。
+//! - 宣言の値は空白を除いたトークン列として持つので、期待値の値からも空白を除く
+//! - at-rule と `["error", "invalid"]` は、どちらも読み捨てて警告を 1 つ出したものとして数だけ比べる
+//!
+//! フィクスチャのケースは上流のカテゴリ（識別子・エスケープ・文字列・url・数値・unicode-range・
+//! コメントと CDO/CDC・ブロックの対応）に沿って仕様から書いたもので、上流のファイルの写しではありません。
+//! 上流の `component_value_list.json` / `one_component_value.json` / `declaration_list.json` も
+//! 同じ形式なので、そのまま `testdata/` に置けばこのテストで読めます。
+//! 依存クレートを使わないため、JSON はこのファイルの小さな読み取り器で読みます。
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::token::CssToken;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::css::token::HashType;
+use crate::renderer::css::token::NumericType;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use core::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+}
+
+fn s(value: &str) -> Json {
+    Json::String(value.to_string())
+}
+
+struct JsonReader<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl JsonReader<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+    }
+
+    fn value(&mut self) -> Json {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('[') => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&']').is_some() {
+                    return Json::Array(items);
+                }
+                loop {
+                    items.push(self.value());
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Json::Array(items),
+                        c => panic!("unexpected {:?} in array", c),
+                    }
+                }
+            }
+            Some('"') => {
+                self.chars.next();
+                Json::String(self.string())
+            }
+            Some('t' | 'f' | 'n') => {
+                let mut word = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_lowercase()) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Json::Bool(true),
+                    "false" => Json::Bool(false),
+                    "null" => Json::Null,
+                    _ => panic!("unexpected literal {:?}", word),
+                }
+            }
+            _ => {
+                let mut repr = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    repr.push(c);
+                }
+                Json::Number(repr.parse().expect("should be a number"))
+            }
+        }
+    }
+
+    // 開き `"` の直後から閉じ `"` までを読む
+    fn string(&mut self) -> String {
+        let mut result = String::new();
+        loop {
+            match self.chars.next().expect("unterminated string") {
+                '"' => return result,
+                '\\' => {
+                    let c = match self.chars.next().expect("unterminated escape") {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let mut code = self.hex4();
+                            // サロゲートペア（`😀`）は 2 つでひとつの文字
+                            if (0xd800..0xdc00).contains(&code) {
+                                assert_eq!(self.chars.next(), Some('\\'));
+                                assert_eq!(self.chars.next(), Some('u'));
+                                code = 0x10000 + ((code - 0xd800) << 10) + (self.hex4() - 0xdc00);
+                            }
+                            char::from_u32(code).expect("should be a scalar value")
+                        }
+                        c => c,
+                    };
+                    result.push(c);
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> u32 {
+        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+        u32::from_str_radix(&hex, 16).expect("should be 4 hex digits")
+    }
+}
+
+// トークン列をコンポーネント値に組み立てる（`ending` のトークンか入力の終わりまで）
+// https://www.w3.org/TR/css-syntax-3/#consume-component-value
+fn component_values(
+    tokens: &mut dyn Iterator<Item = CssToken>,
+    ending: Option<&CssToken>,
+) -> Vec<Json> {
+    let mut result = Vec::new();
+    while let Some(token) = tokens.next() {
+        if Some(&token) == ending {
+            break;
+        }
+        result.push(component_value(token, tokens));
+    }
+    result
+}
+
+// 読んだトークン `token` から始まるコンポーネント値を 1 つ組み立てる（関数とブロックは閉じるまで読む）
+fn component_value(token: CssToken, tokens: &mut dyn Iterator<Item = CssToken>) -> Json {
+    match token {
+        CssToken::Function(name) => {
+            let mut function = vec![s("function"), Json::String(name)];
+            function.extend(component_values(tokens, Some(&CssToken::CloseParenthesis)));
+            Json::Array(function)
+        }
+        CssToken::OpenParenthesis => block("()", tokens, CssToken::CloseParenthesis),
+        CssToken::OpenSquareBracket => block("[]", tokens, CssToken::CloseSquareBracket),
+        CssToken::OpenCurly => block("{}", tokens, CssToken::CloseCurly),
+        CssToken::CloseParenthesis => Json::Array(vec![s("error"), s(")")]),
+        CssToken::CloseSquareBracket => Json::Array(vec![s("error"), s("]")]),
+        CssToken::CloseCurly => Json::Array(vec![s("error"), s("}")]),
+        CssToken::Whitespace => s(" "),
+        CssToken::Colon => s(":"),
+        CssToken::SemiColon => s(";"),
+        CssToken::Comma => s(","),
+        CssToken::Cdo => s("<!--"),
+        CssToken::Cdc => s("-->"),
+        CssToken::Delim(c) => Json::String(c.to_string()),
+        CssToken::Ident(v) => Json::Array(vec![s("ident"), Json::String(v)]),
+        CssToken::AtKeyword(v) => Json::Array(vec![s("at-keyword"), Json::String(v)]),
+        CssToken::HashToken(v, hash_type) => {
+            let hash_type = match hash_type {
+                HashType::Id => "id",
+                HashType::Unrestricted => "unrestricted",
+            };
+            Json::Array(vec![s("hash"), Json::String(v), s(hash_type)])
+        }
+        CssToken::StringToken(v) => Json::Array(vec![s("string"), Json::String(v)]),
+        CssToken::Url(v) => Json::Array(vec![s("url"), Json::String(v)]),
+        CssToken::BadString => Json::Array(vec![s("error"), s("bad-string")]),
+        CssToken::BadUrl => Json::Array(vec![s("error"), s("bad-url")]),
+        CssToken::Number(n, numeric_type) => Json::Array(vec![
+            s("number"),
+            Json::Number(n),
+            numeric_type_name(numeric_type),
+        ]),
+        CssToken::Percentage(n) => Json::Array(vec![s("percentage"), Json::Number(n)]),
+        CssToken::Dimension(n, numeric_type, unit) => Json::Array(vec![
+            s("dimension"),
+            Json::Number(n),
+            numeric_type_name(numeric_type),
+            Json::String(unit),
+        ]),
+    }
+}
+
+fn numeric_type_name(numeric_type: NumericType) -> Json {
+    match numeric_type {
+        NumericType::Integer => s("integer"),
+        NumericType::Number => s("number"),
+    }
+}
+
+fn block(name: &str, tokens: &mut dyn Iterator<Item = CssToken>, ending: CssToken) -> Json {
+    let mut block = vec![s(name)];
+    block.extend(component_values(tokens, Some(&ending)));
+    Json::Array(block)
+}
+
+// 前後の空白を除いて、ちょうど 1 つのコンポーネント値を読む
+// https://www.w3.org/TR/css-syntax-3/#parse-component-value
+fn one_component_value(input: &str) -> Json {
+    let mut tokens = CssTokenizer::new(input.to_string());
+    let value = match tokens.find(|t| *t != CssToken::Whitespace) {
+        Some(token) => component_value(token, &mut tokens),
+        None => return Json::Array(vec![s("error"), s("empty")]),
+    };
+    match tokens.find(|t| *t != CssToken::Whitespace) {
+        Some(_) => Json::Array(vec![s("error"), s("extra-input")]),
+        None => value,
+    }
+}
+
+// 宣言の並びを読み、残った宣言と、読み捨てて警告を出した回数を返す
+fn declaration_list(input: &str) -> (Json, usize) {
+    let mut parser = CssParser::new(CssTokenizer::new(input.to_string()));
+    let declarations = parser
+        .parse_declaration_list()
+        .into_iter()
+        .map(|d| declaration(&d))
+        .collect();
+    (Json::Array(declarations), parser.warnings().len())
+}
+
+fn declaration(d: &Declaration) -> Json {
+    let mut values = d.values.iter().cloned();
+    Json::Array(vec![
+        s("declaration"),
+        Json::String(d.property.clone()),
+        Json::Array(component_values(&mut values, None)),
+        Json::Bool(d.important),
+    ])
+}
+
+// 期待値から、仕様のトークンに無い情報（数値の表記と、percentage の型フラグ）を落とす
+fn normalize(expected: &Json) -> Json {
+    let items = match expected {
+        Json::Array(items) => items,
+        other => return other.clone(),
+    };
+    let pick = |indices: &[usize]| Json::Array(indices.iter().map(|&i| items[i].clone()).collect());
+    match items.first() {
+        Some(Json::String(kind)) if kind == "number" => pick(&[0, 2, 3]),
+        Some(Json::String(kind)) if kind == "percentage" => pick(&[0, 2]),
+        Some(Json::String(kind)) if kind == "dimension" => pick(&[0, 2, 3, 4]),
+        _ => Json::Array(items.iter().map(normalize).collect()),
+    }
+}
+
+// 宣言の並びの期待値を、残る宣言（値から空白を除く）と、読み捨てるものの数に分ける
+fn normalize_declaration_list(expected: &Json) -> (Json, usize) {
+    let items = match normalize(expected) {
+        Json::Array(items) => items,
+        other => panic!("declaration list should be an array: {:?}", other),
+    };
+    let mut declarations = Vec::new();
+    let mut dropped = 0;
+    for item in items {
+        match item {
+            Json::Array(mut parts) if parts.first() == Some(&s("declaration")) => {
+                parts[2] = without_whitespace(&parts[2]);
+                declarations.push(Json::Array(parts));
+            }
+            _ => dropped += 1,
+        }
+    }
+    (Json::Array(declarations), dropped)
+}
+
+fn without_whitespace(value: &Json) -> Json {
+    match value {
+        Json::Array(items) => Json::Array(
+            items
+                .iter()
+                .filter(|item| **item != s(" "))
+                .map(without_whitespace)
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+// フィクスチャを読み、ケースごとに `check`（一致しなければ説明を返す）を呼ぶ。一致しなかったケースをまとめて報告する
+fn run_fixture(fixture: &str, check: impl Fn(&str, &Json) -> Option<String>) {
+    let cases = match (JsonReader {
+        chars: fixture.chars().peekable(),
+    })
+    .value()
+    {
+        Json::Array(items) => items,
+        _ => panic!("fixture should be an array"),
+    };
+    assert!(!cases.is_empty() && cases.len() % 2 == 0);
+    let failures: Vec<String> = cases
+        .chunks(2)
+        .filter_map(|case| match case {
+            [Json::String(input), expected] => check(input, expected),
+            _ => panic!("fixture should be pairs of input and expected output"),
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases failed:\n{}",
+        failures.len(),
+        cases.len() / 2,
+        failures.join("\n")
+    );
+}
+
+fn mismatch<T: PartialEq + core::fmt::Debug>(
+    input: &str,
+    expected: T,
+    actual: T,
+) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    Some(format!(
+        "input: {:?}\n  expected: {:?}\n  actual:   {:?}",
+        input, expected, actual
+    ))
+}
+
+#[test]
+fn test_component_value_list() {
+    run_fixture(
+        include_str!("testdata/component_value_list.json"),
+        |input, expected| {
+            let mut tokenizer = CssTokenizer::new(input.to_string());
+            let actual = Json::Array(component_values(&mut tokenizer, None));
+            mismatch(input, normalize(expected), actual)
+        },
+    );
+}
+
+#[test]
+fn test_one_component_value() {
+    run_fixture(
+        include_str!("testdata/one_component_value.json"),
+        |input, expected| mismatch(input, normalize(expected), one_component_value(input)),
+    );
+}
+
+#[test]
+fn test_declaration_list() {
+    run_fixture(
+        include_str!("testdata/declaration_list.json"),
+        |input, expected| {
+            mismatch(
+                input,
+                normalize_declaration_list(expected),
+                declaration_list(input),
+            )
+        },
+    );
+}
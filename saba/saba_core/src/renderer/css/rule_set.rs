@@ -0,0 +1,586 @@
+//! ルールの索引と祖先の Bloom フィルタ（セレクタマッチングの高速化）
+//!
+//! 役割
+//! - 要素ごとに「すべてのルールを順に試す」と、ルール数 × 要素数の照合が必要になります。
+//!   `RuleSet` はルールを右端の複合セレクタ（対象要素に当たる部分）の id / クラス / 型名で
+//!   バケツに分けておき、要素が持つ id・クラス・型名のバケツ（と全称バケツ）だけを試します。
+//!   例: `.note a` は型名 `a` のバケツに入るので、`<p>` の照合では一度も見ない。
+//! - `AncestorFilter` は、いま照合している要素の祖先が持つ id / クラス / 型名を入れた Bloom フィルタです。
+//!   `.sidebar a` の `.sidebar` のように祖先に必要な名前がフィルタに無ければ、
+//!   祖先を辿らずに「一致しない」と分かります（Bloom フィルタは「無い」と答えたときだけ確実）。
+//!
+//! バケツの選び方（右端の複合セレクタから 1 つ）
+//! - id があれば id（`p#main.note` → id `main`）、無ければ最初のクラス、無ければ型名。
+//! - どれも無いもの（`*`、`[type=text]`、`:hover` だけなど）は全称バケツに入れ、すべての要素で試す。
+//! - `h1, .note` のようなリストは、カンマで分けたセレクタごとに別のバケツへ入れます
+//!   （1 つのルールに複数が一致したら、最も高い詳細度を使う）。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - バケツは `BTreeMap<String, Vec<RuleEntry>>`（TS の `Map<string, RuleEntry[]>`、Go の `map[string][]RuleEntry`）。
+//!   no_std では `HashMap` が使えないので、順序付きの `BTreeMap` を使っています。
+//!
+//! 参考（Blink の実装）:
+//! - https://chromium.googlesource.com/chromium/src/+/HEAD/third_party/blink/renderer/core/css/rule_set.h
+//! - https://chromium.googlesource.com/chromium/src/+/HEAD/third_party/blink/renderer/core/css/selector_filter.h
+//!
+//! 仕様（Bloom フィルタ）: https://en.wikipedia.org/wiki/Bloom_filter
+
+use crate::renderer::css::cssom::Combinator;
+use crate::renderer::css::cssom::PseudoClass;
+use crate::renderer::css::cssom::Selector;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::selector::matches;
+use crate::renderer::css::selector::specificity;
+use crate::renderer::css::selector::MatchingContext;
+use crate::renderer::css::selector::Specificity;
+use crate::renderer::dom::node::Element;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::Reverse;
+
+/// 一致したルール（スタイルシートの何番目の、何番目のルールか）と、一致したときの詳細度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchedRule {
+    pub sheet: usize,
+    pub rule: usize,
+    pub specificity: Specificity,
+}
+
+/// 索引に入れたセレクタ 1 つ分（カンマで分けた後の 1 つ）
+#[derive(Debug, Clone)]
+struct RuleEntry {
+    selector: Selector,
+    sheet: usize,
+    rule: usize,
+    specificity: Specificity,
+    // 祖先に必要な id / クラス / 型名のハッシュ（`AncestorFilter` で先に確かめる）
+    ancestor_hashes: Vec<u32>,
+    // 兄弟の並び・子の有無・ユーザー操作の状態で一致が変わるか（スタイル共有を止める目印）
+    affects_sharing: bool,
+}
+
+/// ルールの索引
+///
+/// - `new` でスタイルシートの列から作ります（シートを書き換えたら作り直す）。
+/// - `matching_rules` は `selector::matching_specificity` で全ルールを試したときと同じ結果を、
+///   同じ順序（シート順・ルール順）で返します。
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    ids: BTreeMap<String, Vec<RuleEntry>>,
+    classes: BTreeMap<String, Vec<RuleEntry>>,
+    types: BTreeMap<String, Vec<RuleEntry>>,
+    universal: Vec<RuleEntry>,
+}
+
+/// バケツの鍵（右端の複合セレクタから選ぶ）
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BucketKey {
+    Id(String),
+    Class(String),
+    Type(String),
+    Universal,
+}
+
+impl RuleSet {
+    pub fn new(stylesheets: &[StyleSheet]) -> Self {
+        let mut rule_set = Self::default();
+        for (sheet_index, sheet) in stylesheets.iter().enumerate() {
+            for (rule_index, rule) in sheet.rules.iter().enumerate() {
+                let selectors = match &rule.selector {
+                    Selector::SelectorList(selectors) => selectors.clone(),
+                    selector => vec![selector.clone()],
+                };
+                for selector in selectors {
+                    // 不正なセレクタはどこにも一致しないので、索引に入れない
+                    if selector == Selector::UnknownSelector {
+                        continue;
+                    }
+                    rule_set.insert(RuleEntry {
+                        specificity: specificity(&selector),
+                        ancestor_hashes: ancestor_hashes(&selector),
+                        affects_sharing: affects_sharing(&selector),
+                        selector,
+                        sheet: sheet_index,
+                        rule: rule_index,
+                    });
+                }
+            }
+        }
+        rule_set
+    }
+
+    fn insert(&mut self, entry: RuleEntry) {
+        match bucket_key(&entry.selector) {
+            BucketKey::Id(id) => self.ids.entry(id).or_default().push(entry),
+            BucketKey::Class(class) => self.classes.entry(class).or_default().push(entry),
+            BucketKey::Type(name) => self.types.entry(name).or_default().push(entry),
+            BucketKey::Universal => self.universal.push(entry),
+        }
+    }
+
+    /// 要素 `element` に一致する可能性があるセレクタ（id / クラス / 型名 / 全称のバケツ）
+    fn candidates(&self, element: &Element) -> Vec<&RuleEntry> {
+        let mut candidates = Vec::new();
+        if let Some(id) = element.get_attribute("id") {
+            candidates.extend(self.ids.get(&id).into_iter().flatten());
+        }
+        if let Some(classes) = element.get_attribute("class") {
+            for class in classes.split_ascii_whitespace() {
+                candidates.extend(self.classes.get(class).into_iter().flatten());
+            }
+        }
+        candidates.extend(
+            self.types
+                .get(&element.kind().to_string())
+                .into_iter()
+                .flatten(),
+        );
+        candidates.extend(self.universal.iter());
+        candidates
+    }
+
+    /// `node` に一致するルールを、シート順・ルール順に並べて返す（要素以外は空）
+    ///
+    /// - `ancestors` を渡すと、祖先に必要な名前が無いセレクタを照合せずに飛ばします。
+    ///   渡すフィルタには `node` の祖先がすべて入っていなければなりません。
+    /// - 同じルールのセレクタが複数一致したら（`h1, #title`）、最も高い詳細度を使います。
+    pub fn matching_rules(
+        &self,
+        node: &Rc<RefCell<Node>>,
+        context: &MatchingContext,
+        ancestors: Option<&AncestorFilter>,
+    ) -> Vec<MatchedRule> {
+        let element = match node.borrow().kind() {
+            NodeKind::Element(element) => element,
+            _ => return Vec::new(),
+        };
+
+        let mut matched: Vec<MatchedRule> = self
+            .candidates(&element)
+            .into_iter()
+            .filter(|entry| entry.may_match(ancestors) && matches(&entry.selector, node, context))
+            .map(|entry| MatchedRule {
+                sheet: entry.sheet,
+                rule: entry.rule,
+                specificity: entry.specificity,
+            })
+            .collect();
+        matched.sort_by_key(|m| (m.sheet, m.rule, Reverse(m.specificity)));
+        matched.dedup_by_key(|m| (m.sheet, m.rule));
+        matched
+    }
+
+    /// 兄弟の並びや状態で一致が変わるセレクタ（`:first-child`、`+`、`:hover` など）に `node` が一致するか
+    ///
+    /// 一致すると、同じ属性の兄弟でもスタイルが同じとは限らないので、スタイル共有に使えません。
+    pub fn matches_sharing_sensitive_rule(
+        &self,
+        node: &Rc<RefCell<Node>>,
+        context: &MatchingContext,
+        ancestors: Option<&AncestorFilter>,
+    ) -> bool {
+        let element = match node.borrow().kind() {
+            NodeKind::Element(element) => element,
+            _ => return false,
+        };
+        self.candidates(&element).into_iter().any(|entry| {
+            entry.affects_sharing
+                && entry.may_match(ancestors)
+                && matches(&entry.selector, node, context)
+        })
+    }
+}
+
+impl RuleEntry {
+    /// 祖先に必要な名前がフィルタに全部あるか（無ければ確実に一致しない）
+    fn may_match(&self, ancestors: Option<&AncestorFilter>) -> bool {
+        match ancestors {
+            Some(filter) => self
+                .ancestor_hashes
+                .iter()
+                .all(|h| filter.might_contain(*h)),
+            None => true,
+        }
+    }
+}
+
+/// 右端の複合セレクタ（対象要素に当たる部分）から、バケツの鍵を選ぶ
+///
+/// 例: `body > p.note` → Class("note") / `#main` → Id("main") / `a:hover` → Type("a") / `*` → Universal
+fn bucket_key(selector: &Selector) -> BucketKey {
+    let subject = match selector {
+        Selector::ComplexSelector { right, .. } => right,
+        selector => selector,
+    };
+    let parts = match subject {
+        Selector::CompoundSelector(parts) => parts.as_slice(),
+        selector => core::slice::from_ref(selector),
+    };
+
+    let find = |f: fn(&Selector) -> Option<BucketKey>| parts.iter().find_map(f);
+    find(|s| match s {
+        Selector::IdSelector(id) => Some(BucketKey::Id(id.clone())),
+        _ => None,
+    })
+    .or_else(|| {
+        find(|s| match s {
+            Selector::ClassSelector(class) => Some(BucketKey::Class(class.clone())),
+            _ => None,
+        })
+    })
+    .or_else(|| {
+        find(|s| match s {
+            Selector::TypeSelector(name) => Some(BucketKey::Type(name.clone())),
+            _ => None,
+        })
+    })
+    .unwrap_or(BucketKey::Universal)
+}
+
+/// 祖先に一致しなければならない id / クラス / 型名のハッシュを集める
+///
+/// - 右から見て最初の子孫結合子（空白）か子結合子（`>`）より左の複合セレクタは、すべて祖先に当たる。
+/// - それより右で兄弟結合子（`+` `~`）の左にある複合セレクタは兄弟なので、フィルタでは確かめない。
+///
+/// 例: `.sidebar ul > li + a` → `.sidebar` と `ul`（`li` は `a` の兄弟）
+fn ancestor_hashes(selector: &Selector) -> Vec<u32> {
+    let mut hashes = Vec::new();
+    let mut current = selector;
+    let mut is_ancestor = false;
+    while let Selector::ComplexSelector {
+        left,
+        combinator,
+        right,
+    } = current
+    {
+        if is_ancestor {
+            compound_hashes(right, &mut hashes);
+        }
+        if matches!(combinator, Combinator::Descendant | Combinator::Child) {
+            is_ancestor = true;
+        }
+        current = left;
+    }
+    if is_ancestor {
+        compound_hashes(current, &mut hashes);
+    }
+    hashes
+}
+
+/// 複合セレクタの id / クラス / 型名のハッシュ（属性や疑似クラスは確かめない）
+fn compound_hashes(selector: &Selector, hashes: &mut Vec<u32>) {
+    match selector {
+        Selector::IdSelector(id) => hashes.push(name_hash(NameKind::Id, id)),
+        Selector::ClassSelector(class) => hashes.push(name_hash(NameKind::Class, class)),
+        Selector::TypeSelector(name) => hashes.push(name_hash(NameKind::Type, name)),
+        Selector::CompoundSelector(parts) => {
+            for part in parts {
+                compound_hashes(part, hashes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 兄弟の並び・子の有無・ユーザー操作の状態で一致が変わるセレクタか
+///
+/// 同じ親・同じ属性の兄弟でも、`:first-child` や `p + p`、`:hover` は片方にしか一致しないことがある。
+fn affects_sharing(selector: &Selector) -> bool {
+    match selector {
+        Selector::PseudoClassSelector(pseudo) => match pseudo {
+            PseudoClass::FirstChild
+            | PseudoClass::LastChild
+            | PseudoClass::NthChild { .. }
+            | PseudoClass::Empty
+            | PseudoClass::Hover
+            | PseudoClass::Active
+            | PseudoClass::Focus => true,
+            PseudoClass::Not(inner) | PseudoClass::Is(inner) => affects_sharing(inner),
+            PseudoClass::Root | PseudoClass::Link | PseudoClass::Visited => false,
+        },
+        Selector::CompoundSelector(selectors) | Selector::SelectorList(selectors) => {
+            selectors.iter().any(affects_sharing)
+        }
+        Selector::ComplexSelector {
+            left,
+            combinator,
+            right,
+        } => {
+            matches!(
+                combinator,
+                Combinator::NextSibling | Combinator::SubsequentSibling
+            ) || affects_sharing(left)
+                || affects_sharing(right)
+        }
+        _ => false,
+    }
+}
+
+/// ハッシュを取る名前の種類（同じ文字列の id とクラスを区別する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NameKind {
+    Id,
+    Class,
+    Type,
+}
+
+/// 名前の 32 ビットハッシュ（FNV-1a。先頭に種類の 1 バイトを混ぜる）
+/// https://datatracker.ietf.org/doc/html/draft-eastlake-fnv
+fn name_hash(kind: NameKind, name: &str) -> u32 {
+    let prefix = match kind {
+        NameKind::Id => b'#',
+        NameKind::Class => b'.',
+        NameKind::Type => b't',
+    };
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in core::iter::once(prefix).chain(name.bytes()) {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// 要素が持つ id / クラス / 型名のハッシュ
+fn element_hashes(element: &Element) -> Vec<u32> {
+    let mut hashes = vec![name_hash(NameKind::Type, &element.kind().to_string())];
+    if let Some(id) = element.get_attribute("id") {
+        hashes.push(name_hash(NameKind::Id, &id));
+    }
+    if let Some(classes) = element.get_attribute("class") {
+        for class in classes.split_ascii_whitespace() {
+            hashes.push(name_hash(NameKind::Class, class));
+        }
+    }
+    hashes
+}
+
+// フィルタの大きさ（2^12 = 4096 個のカウンタ。ハッシュの下位 12 ビットと次の 12 ビットで 2 か所を使う）
+const FILTER_BITS: u32 = 12;
+const FILTER_SIZE: usize = 1 << FILTER_BITS;
+const FILTER_MASK: u32 = (1 << FILTER_BITS) - 1;
+
+/// 祖先の id / クラス / 型名を入れる Bloom フィルタ（数を数える版）
+///
+/// - 木を下りるときに `push`、上がるときに `pop` するので、取り除ける「カウンタ」を持ちます。
+/// - `might_contain` が false なら、その名前を持つ祖先は確実にいない。true は「いるかもしれない」。
+/// - カウンタが上限（255）に達したら、以後は減らさない（取り除けなくなるが、誤って「無い」とは答えない）。
+///
+/// 例: `<body class=page><p>` の `<p>` を照合するときは `body` と `.page` が入っている
+#[derive(Debug, Clone)]
+pub struct AncestorFilter {
+    counts: Vec<u8>,
+    // push した要素ごとのハッシュ（pop で同じものを取り除く）
+    stack: Vec<Vec<u32>>,
+}
+
+impl Default for AncestorFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AncestorFilter {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; FILTER_SIZE],
+            stack: Vec::new(),
+        }
+    }
+
+    /// `node` の祖先（要素）をすべて入れたフィルタを作る（`node` の照合にそのまま使える）
+    pub fn for_ancestors_of(node: &Rc<RefCell<Node>>) -> Self {
+        let mut ancestors = Vec::new();
+        let mut current = node.borrow().parent().upgrade();
+        while let Some(n) = current {
+            current = n.borrow().parent().upgrade();
+            ancestors.push(n);
+        }
+
+        let mut filter = Self::new();
+        for ancestor in ancestors.iter().rev() {
+            filter.push(ancestor);
+        }
+        filter
+    }
+
+    /// `node` の子孫を照合する前に呼ぶ（要素以外は何も入れないが、`pop` と対にするため積む）
+    pub fn push(&mut self, node: &Rc<RefCell<Node>>) {
+        let hashes = match node.borrow().kind() {
+            NodeKind::Element(element) => element_hashes(&element),
+            _ => Vec::new(),
+        };
+        for hash in &hashes {
+            for index in Self::indexes(*hash) {
+                self.counts[index] = self.counts[index].saturating_add(1);
+            }
+        }
+        self.stack.push(hashes);
+    }
+
+    /// 最後に `push` した要素を取り除く
+    pub fn pop(&mut self) {
+        for hash in self.stack.pop().unwrap_or_default() {
+            for index in Self::indexes(hash) {
+                if self.counts[index] != u8::MAX {
+                    self.counts[index] -= 1;
+                }
+            }
+        }
+    }
+
+    fn might_contain(&self, hash: u32) -> bool {
+        Self::indexes(hash)
+            .iter()
+            .all(|index| self.counts[*index] > 0)
+    }
+
+    fn indexes(hash: u32) -> [usize; 2] {
+        [
+            (hash & FILTER_MASK) as usize,
+            ((hash >> FILTER_BITS) & FILTER_MASK) as usize,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::selector::matching_specificity;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn stylesheet(css: &str) -> StyleSheet {
+        CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet()
+    }
+
+    fn selector(css: &str) -> Selector {
+        stylesheet(&(css.to_string() + " {}")).rules[0]
+            .selector
+            .clone()
+    }
+
+    fn document(html: &str) -> Rc<RefCell<Node>> {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    fn element(document: &Rc<RefCell<Node>>, id: &str) -> Rc<RefCell<Node>> {
+        get_element_by_id(Some(document.clone()), &id.to_string()).expect("element should exist")
+    }
+
+    #[test]
+    fn test_bucket_key() {
+        assert_eq!(
+            bucket_key(&selector("p#main.note")),
+            BucketKey::Id("main".to_string())
+        );
+        assert_eq!(
+            bucket_key(&selector("#main body > p.note")),
+            BucketKey::Class("note".to_string())
+        );
+        assert_eq!(
+            bucket_key(&selector(".note a:hover")),
+            BucketKey::Type("a".to_string())
+        );
+        assert_eq!(bucket_key(&selector("p [href]")), BucketKey::Universal);
+        assert_eq!(bucket_key(&selector("*")), BucketKey::Universal);
+    }
+
+    #[test]
+    fn test_ancestor_hashes() {
+        // `li` は `a` の兄弟なので入らない
+        assert_eq!(
+            ancestor_hashes(&selector(".sidebar p > h1 + a")),
+            vec![
+                name_hash(NameKind::Type, "p"),
+                name_hash(NameKind::Class, "sidebar")
+            ]
+        );
+        assert!(ancestor_hashes(&selector("p.note")).is_empty());
+    }
+
+    #[test]
+    fn test_same_result_as_trying_every_rule() {
+        let sheets = [
+            stylesheet(
+                "p { color: red; } .note { color: blue; } #title { color: gray; } \
+                 body .note { color: green; } h1 p { color: black; } * { margin: 0; } \
+                 h1, #title { font-size: 1px; } p:first-child + p { color: white; } \
+                 [href] { color: navy; } .missing a { color: olive; } body > p.note.big { color: teal; }",
+            ),
+            stylesheet("#title.note { color: red; } p { color: red; }"),
+        ];
+        let document = document(
+            "<html><body><p id=first class=note>x</p><p id=title class=\"note big\">y</p>\
+             <a id=link href=\"/\">z</a></body></html>",
+        );
+        let rule_set = RuleSet::new(&sheets);
+        let context = MatchingContext::new();
+
+        for id in ["first", "title", "link"] {
+            let node = element(&document, id);
+            let mut expected = Vec::new();
+            for (sheet_index, sheet) in sheets.iter().enumerate() {
+                for (rule_index, rule) in sheet.rules.iter().enumerate() {
+                    if let Some(specificity) = matching_specificity(&rule.selector, &node, &context)
+                    {
+                        expected.push(MatchedRule {
+                            sheet: sheet_index,
+                            rule: rule_index,
+                            specificity,
+                        });
+                    }
+                }
+            }
+            let filter = AncestorFilter::for_ancestors_of(&node);
+            assert_eq!(
+                rule_set.matching_rules(&node, &context, Some(&filter)),
+                expected
+            );
+            assert_eq!(rule_set.matching_rules(&node, &context, None), expected);
+        }
+    }
+
+    #[test]
+    fn test_ancestor_filter() {
+        let document = document("<html><body class=page><p id=a>x</p></body></html>");
+        let p = element(&document, "a");
+        let mut filter = AncestorFilter::for_ancestors_of(&p);
+
+        assert!(filter.might_contain(name_hash(NameKind::Type, "body")));
+        assert!(filter.might_contain(name_hash(NameKind::Class, "page")));
+        // id の "page" とクラスの "page" は別の名前
+        assert!(!filter.might_contain(name_hash(NameKind::Id, "page")));
+        assert!(!filter.might_contain(name_hash(NameKind::Type, "p")));
+
+        // p の子孫へ下りて戻ると、元に戻る
+        filter.push(&p);
+        assert!(filter.might_contain(name_hash(NameKind::Id, "a")));
+        filter.pop();
+        assert!(!filter.might_contain(name_hash(NameKind::Id, "a")));
+
+        // 祖先に `.sidebar` が無いので、照合せずに飛ばす
+        let rule_set = RuleSet::new(&[stylesheet(".sidebar p { color: red; }")]);
+        let entry = &rule_set.types["p"][0];
+        assert!(!entry.may_match(Some(&filter)));
+    }
+
+    #[test]
+    fn test_affects_sharing() {
+        assert!(affects_sharing(&selector("p:first-child")));
+        assert!(affects_sharing(&selector("h1 + p")));
+        assert!(affects_sharing(&selector("p:not(:hover)")));
+        assert!(!affects_sharing(&selector("body > p.note a:visited")));
+    }
+}
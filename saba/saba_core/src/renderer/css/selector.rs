@@ -0,0 +1,1138 @@
+//! セレクタのパースとマッチング（初心者向け）
+//!
+//! 役割
+//! - パース: ルールの `{` より前のトークン列（prelude）を `Selector` の木に組み立てます。
+//!   例: `body > p.note, h1` → SelectorList([ Complex(body > Compound[p, .note]), Type(h1) ])
+//! - マッチング: ある DOM 要素がセレクタに一致するかを判定します。
+//!
+//! 右から左へのマッチング
+//! - `body p a` を「body を探す → その子孫の p を探す → …」と左から辿ると、候補が爆発します。
+//! - ブラウザは逆に、判定したい要素（右端の `a`）から始めて、親方向へ `p` → `body` を探します。
+//!   要素 1 つにつき辿るのは祖先/兄弟だけなので、ツリー全体を何度も走査せずに済みます。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `matches(&selector, &node)` は DOM の `element.matches("body > p")` に相当します。
+//! - パースに失敗したら `UnknownSelector`（どこにもマッチしない）を返します。
+//!   仕様でも「セレクタリストの一部でも不正ならルール全体を無視する」決まりです。
+//!
+//! 疑似クラス
+//! - 構造（`:first-child`, `:nth-child(2n+1)`, `:root`, `:empty`）は DOM の形だけで決まります。
+//! - `:hover`/`:focus`/`:active`/`:visited` はユーザー操作や履歴で変わるので、
+//!   `MatchingContext` に「今どの要素に乗っているか」などを入れて `matches` に渡します。
+//!   状態が変わったら `Page` がスタイルを計算し直します。
+//!
+//! 詳細度（specificity）
+//! - 同じプロパティを複数のルールが指定したとき、どれが勝つかを決める“強さ”です。
+//!   `(ID の数, クラス/属性/疑似クラスの数, 型セレクタの数)` を左から比べます。
+//!   例: `#main` (1,0,0) > `.note.big` (0,2,0) > `body p` (0,0,2)
+//!
+//! 簡易化している点
+//! - 疑似要素（`::before`）と未対応の疑似クラスは、仕様どおりセレクタ全体を不正として扱います。
+//! - 名前空間（`svg|rect`）は未対応です。
+//!
+//! 仕様: https://www.w3.org/TR/selectors-4/
+
+use crate::renderer::css::cssom::AttributeOperator;
+use crate::renderer::css::cssom::Combinator;
+use crate::renderer::css::cssom::PseudoClass;
+use crate::renderer::css::cssom::Selector;
+use crate::renderer::css::media::MediaEnvironment;
+use crate::renderer::css::token::CssToken;
+use crate::renderer::css::token::HashType;
+use crate::renderer::dom::node::Element;
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// prelude のトークン列をセレクタリストとして解釈する
+///
+/// 戻り値
+/// - 要素が 1 つだけなら中身をそのまま返す（`p` → `TypeSelector("p")`）。
+/// - カンマ区切りで複数あれば `SelectorList`。
+/// - どこかが不正なら `UnknownSelector`。
+///
+/// 仕様: https://www.w3.org/TR/selectors-4/#parse-selector
+pub fn parse_selector_list(tokens: &[CssToken]) -> Selector {
+    let mut parser = SelectorParser { tokens, pos: 0 };
+    match parser.selector_list() {
+        Some(selector) => selector,
+        None => Selector::UnknownSelector,
+    }
+}
+
+/// トークン列の上を 1 つずつ進むだけの小さなパーサ
+struct SelectorParser<'a> {
+    tokens: &'a [CssToken],
+    pos: usize,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn peek(&self) -> Option<&CssToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<CssToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// 空白を読み飛ばし、1 つでも読み飛ばしたら true
+    fn skip_whitespace(&mut self) -> bool {
+        let mut skipped = false;
+        while self.peek() == Some(&CssToken::Whitespace) {
+            self.pos += 1;
+            skipped = true;
+        }
+        skipped
+    }
+
+    /// <selector-list> = <complex-selector> [ , <complex-selector> ]*
+    fn selector_list(&mut self) -> Option<Selector> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.complex_selector()?);
+            match self.next() {
+                Some(CssToken::Comma) => continue,
+                None => break,
+                Some(_) => return None,
+            }
+        }
+
+        if selectors.len() == 1 {
+            return selectors.pop();
+        }
+        Some(Selector::SelectorList(selectors))
+    }
+
+    /// <complex-selector> = <compound-selector> [ <combinator>? <compound-selector> ]*
+    /// `,` か入力の終わりで止まる（`,` は消費しない）。
+    fn complex_selector(&mut self) -> Option<Selector> {
+        let mut selector = self.compound_selector()?;
+        loop {
+            // 空白だけなら子孫結合子、記号があればそちらを優先（`a > b` の空白は無視）
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(CssToken::Comma) => return Some(selector),
+                Some(CssToken::Delim('>')) => Combinator::Child,
+                Some(CssToken::Delim('+')) => Combinator::NextSibling,
+                Some(CssToken::Delim('~')) => Combinator::SubsequentSibling,
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(_) => return None,
+            };
+            if combinator != Combinator::Descendant {
+                self.next();
+                self.skip_whitespace();
+            }
+
+            let right = self.compound_selector()?;
+            selector = Selector::ComplexSelector {
+                left: Box::new(selector),
+                combinator,
+                right: Box::new(right),
+            };
+        }
+    }
+
+    /// <compound-selector> = [ <type-selector>? <subclass-selector>* ]!
+    /// 空白や結合子が来たところで止まる。
+    fn compound_selector(&mut self) -> Option<Selector> {
+        let mut parts = Vec::new();
+
+        loop {
+            match self.peek() {
+                // 型セレクタ/全称セレクタは先頭にしか書けない
+                Some(CssToken::Ident(name)) if parts.is_empty() => {
+                    let name = name.to_ascii_lowercase();
+                    self.next();
+                    parts.push(Selector::TypeSelector(name));
+                }
+                Some(CssToken::Delim('*')) if parts.is_empty() => {
+                    self.next();
+                    parts.push(Selector::UniversalSelector);
+                }
+                Some(CssToken::HashToken(value, HashType::Id)) => {
+                    let id = value.clone();
+                    self.next();
+                    parts.push(Selector::IdSelector(id));
+                }
+                // `#0red` のように識別子として読めない名前は ID セレクタにならない（ルールごと捨てる）
+                // https://www.w3.org/TR/selectors-4/#id-selectors
+                Some(CssToken::HashToken(_, HashType::Unrestricted)) => return None,
+                Some(CssToken::Delim('.')) => {
+                    self.next();
+                    match self.next() {
+                        Some(CssToken::Ident(class)) => parts.push(Selector::ClassSelector(class)),
+                        _ => return None,
+                    }
+                }
+                Some(CssToken::OpenSquareBracket) => {
+                    self.next();
+                    parts.push(self.attribute_selector()?);
+                }
+                Some(CssToken::Colon) => {
+                    self.next();
+                    parts.push(self.pseudo_class()?);
+                }
+                _ => break,
+            }
+        }
+
+        match parts.len() {
+            0 => None,
+            1 => parts.pop(),
+            _ => Some(Selector::CompoundSelector(parts)),
+        }
+    }
+
+    /// `[` の直後から `]` までを読む
+    /// 例: `type]` / `type=text]` / `lang|="en" i]`
+    fn attribute_selector(&mut self) -> Option<Selector> {
+        self.skip_whitespace();
+        let name = match self.next() {
+            Some(CssToken::Ident(name)) => name.to_ascii_lowercase(),
+            _ => return None,
+        };
+        self.skip_whitespace();
+
+        let operator = match self.next() {
+            Some(CssToken::CloseSquareBracket) => {
+                return Some(Selector::AttributeSelector {
+                    name,
+                    operator: AttributeOperator::Exists,
+                    value: String::new(),
+                    case_insensitive: false,
+                });
+            }
+            Some(CssToken::Delim('=')) => AttributeOperator::Equals,
+            Some(CssToken::Delim(c)) => {
+                let operator = match c {
+                    '~' => AttributeOperator::Includes,
+                    '|' => AttributeOperator::DashMatch,
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    '*' => AttributeOperator::Substring,
+                    _ => return None,
+                };
+                // 2 文字の演算子（`~=` など）は後ろに `=` が続く
+                if self.next() != Some(CssToken::Delim('=')) {
+                    return None;
+                }
+                operator
+            }
+            _ => return None,
+        };
+        self.skip_whitespace();
+
+        // 値は識別子（`text`）でも文字列（`"text"`）でもよい
+        let value = match self.next() {
+            Some(CssToken::Ident(value)) | Some(CssToken::StringToken(value)) => value,
+            Some(CssToken::Number(n, _)) => format!("{}", n),
+            Some(CssToken::Dimension(n, _, unit)) => format!("{}{}", n, unit),
+            _ => return None,
+        };
+        self.skip_whitespace();
+
+        // `i` / `s` フラグ（大文字小文字を区別するか）
+        let mut case_insensitive = false;
+        if let Some(CssToken::Ident(flag)) = self.peek() {
+            case_insensitive = match flag.to_ascii_lowercase().as_str() {
+                "i" => true,
+                "s" => false,
+                _ => return None,
+            };
+            self.next();
+            self.skip_whitespace();
+        }
+
+        if self.next() != Some(CssToken::CloseSquareBracket) {
+            return None;
+        }
+        Some(Selector::AttributeSelector {
+            name,
+            operator,
+            value,
+            case_insensitive,
+        })
+    }
+
+    /// `:` の直後から疑似クラスを読む
+    /// 例: `hover` / `first-child` / `nth-child(2n + 1)` / `not(.note)`
+    fn pseudo_class(&mut self) -> Option<Selector> {
+        // `::before` のような疑似要素は未対応（要素そのものではないので、一致させない）
+        let (name, function) = match self.next() {
+            Some(CssToken::Ident(name)) => (name.to_ascii_lowercase(), false),
+            Some(CssToken::Function(name)) => (name.to_ascii_lowercase(), true),
+            _ => return None,
+        };
+
+        let pseudo = if function {
+            // 関数形式: 対応する `)` までの中身を取り出して、疑似クラスごとに解釈する
+            let arguments = self.function_arguments()?;
+            match name.as_str() {
+                "nth-child" => {
+                    let (a, b) = parse_an_plus_b(arguments)?;
+                    PseudoClass::NthChild { a, b }
+                }
+                "not" => PseudoClass::Not(Box::new(
+                    SelectorParser {
+                        tokens: arguments,
+                        pos: 0,
+                    }
+                    .selector_list()?,
+                )),
+                "is" => PseudoClass::Is(Box::new(
+                    SelectorParser {
+                        tokens: arguments,
+                        pos: 0,
+                    }
+                    .selector_list()?,
+                )),
+                _ => return None,
+            }
+        } else {
+            match name.as_str() {
+                "first-child" => PseudoClass::FirstChild,
+                "last-child" => PseudoClass::LastChild,
+                "root" => PseudoClass::Root,
+                "empty" => PseudoClass::Empty,
+                "link" => PseudoClass::Link,
+                "visited" => PseudoClass::Visited,
+                "hover" => PseudoClass::Hover,
+                "focus" => PseudoClass::Focus,
+                "active" => PseudoClass::Active,
+                _ => return None,
+            }
+        };
+        Some(Selector::PseudoClassSelector(pseudo))
+    }
+
+    /// 関数トークン（`nth-child(`）の直後から対応する `)` までのトークン列を返す（`)` は消費するが結果には含めない）
+    fn function_arguments(&mut self) -> Option<&'a [CssToken]> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                CssToken::Function(_) | CssToken::OpenParenthesis => depth += 1,
+                CssToken::CloseParenthesis if depth == 0 => break,
+                CssToken::CloseParenthesis => depth -= 1,
+                _ => {}
+            }
+        }
+        Some(&self.tokens[start..self.pos - 1])
+    }
+}
+
+/// `an+b` 記法を (a, b) にする
+/// 例: `odd` → (2, 1), `even` → (2, 0), `3` → (0, 3), `-n+3` → (-1, 3), `2n - 1` → (2, -1)
+///
+/// トークナイザは `2n-1` を `Dimension(2, "n-1")`、`2n+1` を `Dimension(2, "n")` と `Number(1)`
+/// のように読むので、いったん文字列に戻してから読みます。
+/// 数値トークンは符号が書かれていたかを持たないため、`n` の直後の負でない数値には `+` を補います
+/// （そのため `2n 1` も `2n+1` として受け付けます）。
+///
+/// 仕様: https://www.w3.org/TR/css-syntax-3/#anb-microsyntax
+fn parse_an_plus_b(tokens: &[CssToken]) -> Option<(i64, i64)> {
+    let mut s = String::new();
+    for token in tokens {
+        match token {
+            CssToken::Number(n, _) if *n >= 0.0 && s.ends_with('n') => {
+                s.push_str(&format!("+{}", n))
+            }
+            CssToken::Number(n, _) => s.push_str(&format!("{}", n)),
+            CssToken::Dimension(n, _, unit) => {
+                s.push_str(&format!("{}{}", n, unit.to_ascii_lowercase()))
+            }
+            CssToken::Ident(ident) => s.push_str(&ident.to_ascii_lowercase()),
+            CssToken::Delim(c @ ('+' | '-')) => s.push(*c),
+            CssToken::Whitespace => {}
+            _ => return None,
+        }
+    }
+
+    match s.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    match s.split_once('n') {
+        Some((a, b)) => {
+            let a = match a {
+                "" | "+" => 1,
+                "-" => -1,
+                _ => a.parse::<i64>().ok()?,
+            };
+            // `n` の後ろは空か、符号付きの整数
+            let b = if b.is_empty() {
+                0
+            } else if b.starts_with('+') || b.starts_with('-') {
+                b.parse::<i64>().ok()?
+            } else {
+                return None;
+            };
+            Some((a, b))
+        }
+        None => Some((0, s.parse::<i64>().ok()?)),
+    }
+}
+
+/// マッチングに必要な“文書の外側”の状態
+///
+/// - `hovered`: マウスカーソルの下にある要素（`:hover`）
+/// - `active`: マウスボタンで押されている要素（`:active`）
+/// - `focused`: フォーカスを持っている要素（`:focus`）
+/// - `visited_urls`: 訪問済みの URL（`:visited` / `:link`）
+/// - `media`: 表示する環境（`@media` の判定とレイアウトのビューポート）
+///
+/// TS でいえば `{ hovered?: Element, active?: Element, focused?: Element, visitedUrls: string[], media: MediaEnvironment }` です。
+#[derive(Debug, Clone, Default)]
+pub struct MatchingContext {
+    hovered: Option<Rc<RefCell<Node>>>,
+    active: Option<Rc<RefCell<Node>>>,
+    focused: Option<Rc<RefCell<Node>>>,
+    visited_urls: Vec<String>,
+    media: MediaEnvironment,
+}
+
+impl MatchingContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_hovered(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        self.hovered = node;
+    }
+
+    pub fn set_active(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        self.active = node;
+    }
+
+    pub fn set_focused(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        self.focused = node;
+    }
+
+    pub fn set_visited_urls(&mut self, urls: Vec<String>) {
+        self.visited_urls = urls;
+    }
+
+    pub fn set_media(&mut self, media: MediaEnvironment) {
+        self.media = media;
+    }
+
+    pub fn media(&self) -> &MediaEnvironment {
+        &self.media
+    }
+}
+
+/// https://www.w3.org/TR/selectors-4/#specificity-rules
+/// セレクタの詳細度 (a, b, c)
+/// - `ids`: ID セレクタの数
+/// - `classes`: クラス・属性・疑似クラスの数
+/// - `types`: 型セレクタの数
+///
+/// フィールドの並び順どおりに比較されるので、`Ord` の derive で大小比較がそのまま仕様になります
+/// （TS なら `[a, b, c]` の辞書式比較）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity {
+    pub ids: u32,
+    pub classes: u32,
+    pub types: u32,
+}
+
+impl Specificity {
+    pub fn new(ids: u32, classes: u32, types: u32) -> Self {
+        Self {
+            ids,
+            classes,
+            types,
+        }
+    }
+
+    fn add(self, other: Specificity) -> Self {
+        Self::new(
+            self.ids + other.ids,
+            self.classes + other.classes,
+            self.types + other.types,
+        )
+    }
+}
+
+/// セレクタの詳細度を計算する
+///
+/// - 全称セレクタ `*` は 0。
+/// - `:is()` / `:not()` は引数の中で最も詳細度の高いセレクタの値になる。
+/// - `SelectorList` はどれか 1 つが一致したときに使う値なので、ここでは最大値を返す
+///   （一致したものだけで比べたいときは `matching_specificity`）。
+pub fn specificity(selector: &Selector) -> Specificity {
+    match selector {
+        Selector::IdSelector(_) => Specificity::new(1, 0, 0),
+        Selector::ClassSelector(_) | Selector::AttributeSelector { .. } => {
+            Specificity::new(0, 1, 0)
+        }
+        Selector::PseudoClassSelector(PseudoClass::Not(inner))
+        | Selector::PseudoClassSelector(PseudoClass::Is(inner)) => specificity(inner),
+        Selector::PseudoClassSelector(_) => Specificity::new(0, 1, 0),
+        Selector::TypeSelector(_) => Specificity::new(0, 0, 1),
+        Selector::UniversalSelector | Selector::UnknownSelector => Specificity::default(),
+        Selector::CompoundSelector(selectors) => selectors
+            .iter()
+            .fold(Specificity::default(), |acc, s| acc.add(specificity(s))),
+        Selector::ComplexSelector { left, right, .. } => specificity(left).add(specificity(right)),
+        Selector::SelectorList(selectors) => {
+            selectors.iter().map(specificity).max().unwrap_or_default()
+        }
+    }
+}
+
+/// `node` に一致したときの詳細度（一致しなければ None）
+///
+/// `h1, #title` のようなリストでは、実際に一致したセレクタのうち最も高い詳細度を使います。
+/// 例: `<h1>` には (0,0,1)、`<p id=title>` には (1,0,0)。
+pub fn matching_specificity(
+    selector: &Selector,
+    node: &Rc<RefCell<Node>>,
+    context: &MatchingContext,
+) -> Option<Specificity> {
+    match selector {
+        Selector::SelectorList(selectors) => selectors
+            .iter()
+            .filter(|s| matches(s, node, context))
+            .map(specificity)
+            .max(),
+        _ if matches(selector, node, context) => Some(specificity(selector)),
+        _ => None,
+    }
+}
+
+/// `node` が `selector` に一致するかを判定する（右から左へのマッチング）
+///
+/// - 要素ノード以外（テキスト/ドキュメント）は常に不一致です。
+/// - `:hover` などの状態は `context` から読みます（状態を使わないなら `MatchingContext::new()` でよい）。
+/// - `ComplexSelector` は右側をこの要素で確かめてから、結合子に従って親や兄弟へ進み、
+///   左側に一致する要素を探します。
+///
+/// 仕様: https://www.w3.org/TR/selectors-4/#match-a-selector-against-an-element
+pub fn matches(selector: &Selector, node: &Rc<RefCell<Node>>, context: &MatchingContext) -> bool {
+    let element = match node.borrow().kind() {
+        NodeKind::Element(e) => e,
+        _ => return false,
+    };
+
+    match selector {
+        Selector::TypeSelector(type_name) => {
+            // ElementKind → 文字列（"p" など）に直して比較（HTML のタグ名は大文字小文字を区別しない）
+            element.kind().to_string().eq_ignore_ascii_case(type_name)
+        }
+        Selector::UniversalSelector => true,
+        Selector::ClassSelector(class_name) => match element.get_attribute("class") {
+            // `class="note warning"` のように空白区切りで複数書ける
+            Some(classes) => classes.split_ascii_whitespace().any(|c| c == class_name),
+            None => false,
+        },
+        Selector::IdSelector(id_name) => element.get_attribute("id").as_ref() == Some(id_name),
+        Selector::AttributeSelector {
+            name,
+            operator,
+            value,
+            case_insensitive,
+        } => match attribute_value(&element, name) {
+            Some(actual) => {
+                if *case_insensitive {
+                    match_attribute(
+                        *operator,
+                        &actual.to_ascii_lowercase(),
+                        &value.to_ascii_lowercase(),
+                    )
+                } else {
+                    match_attribute(*operator, &actual, value)
+                }
+            }
+            None => false,
+        },
+        Selector::PseudoClassSelector(pseudo) => {
+            matches_pseudo_class(pseudo, node, &element, context)
+        }
+        Selector::CompoundSelector(selectors) => {
+            selectors.iter().all(|s| matches(s, node, context))
+        }
+        Selector::ComplexSelector {
+            left,
+            combinator,
+            right,
+        } => {
+            // 1) まず右側（対象要素）を確認。ここで外れれば祖先を辿る必要はない
+            if !matches(right, node, context) {
+                return false;
+            }
+            // 2) 結合子に従って左側に一致する要素を探す
+            match combinator {
+                Combinator::Descendant => {
+                    let mut ancestor = parent_element(node);
+                    while let Some(a) = ancestor {
+                        if matches(left, &a, context) {
+                            return true;
+                        }
+                        ancestor = parent_element(&a);
+                    }
+                    false
+                }
+                Combinator::Child => match parent_element(node) {
+                    Some(parent) => matches(left, &parent, context),
+                    None => false,
+                },
+                Combinator::NextSibling => match previous_element_sibling(node) {
+                    Some(sibling) => matches(left, &sibling, context),
+                    None => false,
+                },
+                Combinator::SubsequentSibling => {
+                    let mut sibling = previous_element_sibling(node);
+                    while let Some(s) = sibling {
+                        if matches(left, &s, context) {
+                            return true;
+                        }
+                        sibling = previous_element_sibling(&s);
+                    }
+                    false
+                }
+            }
+        }
+        Selector::SelectorList(selectors) => selectors.iter().any(|s| matches(s, node, context)),
+        Selector::UnknownSelector => false,
+    }
+}
+
+/// https://www.w3.org/TR/selectors-4/#pseudo-classes
+fn matches_pseudo_class(
+    pseudo: &PseudoClass,
+    node: &Rc<RefCell<Node>>,
+    element: &Element,
+    context: &MatchingContext,
+) -> bool {
+    match pseudo {
+        PseudoClass::FirstChild => previous_element_sibling(node).is_none(),
+        PseudoClass::LastChild => next_element_sibling(node).is_none(),
+        PseudoClass::NthChild { a, b } => {
+            // 兄弟要素の中での 1 始まりの位置
+            let mut index = 1;
+            let mut sibling = previous_element_sibling(node);
+            while let Some(s) = sibling {
+                index += 1;
+                sibling = previous_element_sibling(&s);
+            }
+            // index = a*n + b となる 0 以上の整数 n があるか
+            if *a == 0 {
+                index == *b
+            } else {
+                (index - b) % a == 0 && (index - b) / a >= 0
+            }
+        }
+        PseudoClass::Not(selector) => !matches(selector, node, context),
+        PseudoClass::Is(selector) => matches(selector, node, context),
+        PseudoClass::Root => match node.borrow().parent().upgrade() {
+            Some(parent) => matches!(parent.borrow().kind(), NodeKind::Document),
+            None => false,
+        },
+        PseudoClass::Empty => node.borrow().first_child().is_none(),
+        PseudoClass::Link => match link_href(element) {
+            Some(href) => !context.visited_urls.contains(&href),
+            None => false,
+        },
+        PseudoClass::Visited => match link_href(element) {
+            Some(href) => context.visited_urls.contains(&href),
+            None => false,
+        },
+        // カーソルが子要素に乗っていれば、その祖先も :hover（:active も同様）
+        PseudoClass::Hover => is_inclusive_ancestor(node, &context.hovered),
+        PseudoClass::Active => is_inclusive_ancestor(node, &context.active),
+        PseudoClass::Focus => match &context.focused {
+            Some(focused) => Rc::ptr_eq(focused, node),
+            None => false,
+        },
+    }
+}
+
+/// `:link`/`:visited` の対象になるのは href を持つ `<a>` だけ
+fn link_href(element: &Element) -> Option<String> {
+    match element.kind() {
+        ElementKind::A => element.get_attribute("href"),
+        _ => None,
+    }
+}
+
+/// `node` が `target` 自身かその祖先か
+fn is_inclusive_ancestor(node: &Rc<RefCell<Node>>, target: &Option<Rc<RefCell<Node>>>) -> bool {
+    let mut current = target.clone();
+    while let Some(n) = current {
+        if Rc::ptr_eq(&n, node) {
+            return true;
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    false
+}
+
+/// 属性名は大文字小文字を区別しないので、小文字にそろえて探す
+fn attribute_value(element: &Element, name: &str) -> Option<String> {
+    element
+        .attributes()
+        .iter()
+        .find(|attr| attr.name().eq_ignore_ascii_case(name))
+        .map(|attr| attr.value())
+}
+
+/// https://www.w3.org/TR/selectors-4/#attribute-representation
+fn match_attribute(operator: AttributeOperator, actual: &str, expected: &str) -> bool {
+    match operator {
+        AttributeOperator::Exists => true,
+        AttributeOperator::Equals => actual == expected,
+        AttributeOperator::Includes => {
+            // 空文字や空白を含む値はどの単語とも一致しない
+            !expected.is_empty()
+                && !expected.contains(char::is_whitespace)
+                && actual.split_ascii_whitespace().any(|w| w == expected)
+        }
+        AttributeOperator::DashMatch => {
+            actual == expected
+                || (actual.starts_with(expected) && actual[expected.len()..].starts_with('-'))
+        }
+        // `^=` `$=` `*=` は値が空だと何にも一致しない
+        AttributeOperator::Prefix => !expected.is_empty() && actual.starts_with(expected),
+        AttributeOperator::Suffix => !expected.is_empty() && actual.ends_with(expected),
+        AttributeOperator::Substring => !expected.is_empty() && actual.contains(expected),
+    }
+}
+
+/// 最も近い祖先の要素ノード
+/// 本書のパーサではテキストノードの下に要素がぶら下がることがあるため、テキストは飛ばして辿る。
+fn parent_element(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if let NodeKind::Element(_) = n.borrow().kind() {
+            return Some(n.clone());
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+/// 直前の兄弟要素（テキストノードは飛ばす）
+/// テキストノードの子になっている要素は、そのテキストの後ろに並んでいる兄弟として扱う。
+fn previous_element_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.clone();
+    loop {
+        let previous = current.borrow().previous_sibling().upgrade();
+        match previous {
+            Some(p) => {
+                if let NodeKind::Element(_) = p.borrow().kind() {
+                    return Some(p.clone());
+                }
+                current = p;
+            }
+            None => {
+                let parent = current.borrow().parent().upgrade()?;
+                if let NodeKind::Text(_) = parent.borrow().kind() {
+                    current = parent.clone();
+                    continue;
+                }
+                return None;
+            }
+        }
+    }
+}
+
+/// 直後の兄弟要素（テキストノードは飛ばす）
+/// `previous_element_sibling` と同じく、テキストノードの子は兄弟として扱う。
+fn next_element_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.clone();
+    loop {
+        // テキストノードの子（本書のパーサの癖）は、そのテキストのすぐ後ろに並ぶ兄弟とみなす
+        let child_of_text = match current.borrow().kind() {
+            NodeKind::Text(_) if !Rc::ptr_eq(&current, node) => current.borrow().first_child(),
+            _ => None,
+        };
+        let next = match child_of_text {
+            Some(child) => Some(child),
+            None => following_sibling(&current),
+        };
+        match next {
+            Some(n) => {
+                if let NodeKind::Element(_) = n.borrow().kind() {
+                    return Some(n.clone());
+                }
+                current = n;
+            }
+            None => return None,
+        }
+    }
+}
+
+/// 次の兄弟。無ければ、親がテキストノードのときに限りその親の次の兄弟へ進む
+fn following_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.clone();
+    loop {
+        if let Some(next) = current.borrow().next_sibling() {
+            return Some(next);
+        }
+        let parent = current.borrow().parent().upgrade()?;
+        match parent.borrow().kind() {
+            NodeKind::Text(_) => {}
+            _ => return None,
+        }
+        current = parent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::vec;
+
+    fn parse(selector: &str) -> Selector {
+        let tokens: Vec<CssToken> = CssTokenizer::new(selector.to_string()).collect();
+        parse_selector_list(&tokens)
+    }
+
+    fn type_selector(name: &str) -> Box<Selector> {
+        Box::new(Selector::TypeSelector(name.to_string()))
+    }
+
+    fn create_document(html: &str) -> Rc<RefCell<Node>> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    fn element(document: &Rc<RefCell<Node>>, id: &str) -> Rc<RefCell<Node>> {
+        get_element_by_id(Some(document.clone()), &id.to_string()).expect("element should exist")
+    }
+
+    #[test]
+    fn test_parse_compound_and_list() {
+        assert_eq!(
+            parse("a.button#go"),
+            Selector::CompoundSelector(vec![
+                Selector::TypeSelector("a".to_string()),
+                Selector::ClassSelector("button".to_string()),
+                Selector::IdSelector("go".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse("h1, h2"),
+            Selector::SelectorList(vec![
+                Selector::TypeSelector("h1".to_string()),
+                Selector::TypeSelector("h2".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse("a:hover"),
+            Selector::CompoundSelector(vec![
+                Selector::TypeSelector("a".to_string()),
+                Selector::PseudoClassSelector(PseudoClass::Hover),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_pseudo_class() {
+        let nth = |s: &str| match parse(s) {
+            Selector::PseudoClassSelector(PseudoClass::NthChild { a, b }) => Some((a, b)),
+            _ => None,
+        };
+        assert_eq!(nth(":nth-child(odd)"), Some((2, 1)));
+        assert_eq!(nth(":nth-child(even)"), Some((2, 0)));
+        assert_eq!(nth(":nth-child(3)"), Some((0, 3)));
+        assert_eq!(nth(":nth-child(2n+1)"), Some((2, 1)));
+        assert_eq!(nth(":nth-child(2n - 1)"), Some((2, -1)));
+        assert_eq!(nth(":nth-child(-n+3)"), Some((-1, 3)));
+        assert_eq!(nth(":nth-child(n)"), Some((1, 0)));
+
+        assert_eq!(
+            parse(":not(.note, #main)"),
+            Selector::PseudoClassSelector(PseudoClass::Not(Box::new(Selector::SelectorList(
+                vec![
+                    Selector::ClassSelector("note".to_string()),
+                    Selector::IdSelector("main".to_string()),
+                ]
+            ))))
+        );
+        // 疑似要素と未知の疑似クラスは不正
+        assert_eq!(parse("p::before"), Selector::UnknownSelector);
+        assert_eq!(parse("p:unknown"), Selector::UnknownSelector);
+        assert_eq!(parse(":nth-child(x)"), Selector::UnknownSelector);
+    }
+
+    #[test]
+    fn test_parse_combinators() {
+        // `body p > a` は `(body p) > a` と左結合になる
+        assert_eq!(
+            parse("body p > a"),
+            Selector::ComplexSelector {
+                left: Box::new(Selector::ComplexSelector {
+                    left: type_selector("body"),
+                    combinator: Combinator::Descendant,
+                    right: type_selector("p"),
+                }),
+                combinator: Combinator::Child,
+                right: type_selector("a"),
+            }
+        );
+        assert_eq!(
+            parse("h1+p"),
+            Selector::ComplexSelector {
+                left: type_selector("h1"),
+                combinator: Combinator::NextSibling,
+                right: type_selector("p"),
+            }
+        );
+        assert_eq!(
+            parse("h1 ~ p"),
+            Selector::ComplexSelector {
+                left: type_selector("h1"),
+                combinator: Combinator::SubsequentSibling,
+                right: type_selector("p"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_attribute() {
+        assert_eq!(
+            parse("[type=text]"),
+            Selector::AttributeSelector {
+                name: "type".to_string(),
+                operator: AttributeOperator::Equals,
+                value: "text".to_string(),
+                case_insensitive: false,
+            }
+        );
+        assert_eq!(
+            parse("a[lang |= \"en\" i]"),
+            Selector::CompoundSelector(vec![
+                Selector::TypeSelector("a".to_string()),
+                Selector::AttributeSelector {
+                    name: "lang".to_string(),
+                    operator: AttributeOperator::DashMatch,
+                    value: "en".to_string(),
+                    case_insensitive: true,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        // どこか 1 か所でも不正ならリスト全体が UnknownSelector
+        assert_eq!(parse("h1, "), Selector::UnknownSelector);
+        assert_eq!(parse("p > > a"), Selector::UnknownSelector);
+        assert_eq!(parse("[type=text"), Selector::UnknownSelector);
+        assert_eq!(parse("a, [=x]"), Selector::UnknownSelector);
+        // 識別子として読めない名前の hash（unrestricted）は ID セレクタにならない
+        assert_eq!(parse("#0red"), Selector::UnknownSelector);
+        assert_eq!(parse("p#-0red"), Selector::UnknownSelector);
+        assert_eq!(parse("#red0"), Selector::IdSelector("red0".to_string()));
+    }
+
+    #[test]
+    fn test_match_combinators() {
+        let document = create_document(
+            "<html><body><h1 id=title>t</h1><p id=first>a<a id=link>x</a></p><p id=second>b</p></body></html>",
+        );
+        let title = element(&document, "title");
+        let first = element(&document, "first");
+        let second = element(&document, "second");
+        let link = element(&document, "link");
+
+        assert!(matches(&parse("body p"), &first, &MatchingContext::new()));
+        assert!(matches(&parse("body a"), &link, &MatchingContext::new()));
+        assert!(matches(&parse("p > a"), &link, &MatchingContext::new()));
+        assert!(!matches(&parse("body > a"), &link, &MatchingContext::new()));
+        assert!(matches(&parse("h1 + p"), &first, &MatchingContext::new()));
+        assert!(!matches(&parse("h1 + p"), &second, &MatchingContext::new()));
+        assert!(matches(&parse("h1 ~ p"), &second, &MatchingContext::new()));
+        assert!(!matches(&parse("p ~ h1"), &title, &MatchingContext::new()));
+        assert!(matches(&parse("h2, h1"), &title, &MatchingContext::new()));
+        assert!(matches(&parse("*"), &title, &MatchingContext::new()));
+    }
+
+    #[test]
+    fn test_match_compound_and_attribute() {
+        let document = create_document(
+            "<html><body><a id=go class=\"big button\" href=\"https://example.com/page.html\" lang=en-US>go</a></body></html>",
+        );
+        let link = element(&document, "go");
+
+        assert!(matches(
+            &parse("a.button#go"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(matches(
+            &parse(".big.button"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(!matches(
+            &parse("a.button#stop"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(!matches(&parse("p.button"), &link, &MatchingContext::new()));
+
+        assert!(matches(&parse("[href]"), &link, &MatchingContext::new()));
+        assert!(!matches(&parse("[title]"), &link, &MatchingContext::new()));
+        assert!(matches(
+            &parse("[class~=big]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(!matches(
+            &parse("[class=big]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(matches(
+            &parse("[lang|=en]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(!matches(
+            &parse("[lang|=e]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(matches(
+            &parse("[href^=\"https://\"]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(matches(
+            &parse("[href$=\".html\"]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(matches(
+            &parse("[href*=example]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(!matches(
+            &parse("[href*=\"\"]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(matches(
+            &parse("[lang=\"EN-us\" i]"),
+            &link,
+            &MatchingContext::new()
+        ));
+        assert!(!matches(
+            &parse("[lang=\"EN-us\"]"),
+            &link,
+            &MatchingContext::new()
+        ));
+    }
+
+    #[test]
+    fn test_match_structural_pseudo_classes() {
+        let document = create_document(
+            "<html><body><p id=a>1</p><p id=b>2</p><p id=c>3</p><h1 id=d></h1></body></html>",
+        );
+        let ctx = MatchingContext::new();
+        let a = element(&document, "a");
+        let b = element(&document, "b");
+        let c = element(&document, "c");
+        let d = element(&document, "d");
+        let html = parent_element(&parent_element(&a).unwrap()).unwrap();
+
+        assert!(matches(&parse("p:first-child"), &a, &ctx));
+        assert!(!matches(&parse("p:first-child"), &b, &ctx));
+        assert!(matches(&parse(":last-child"), &d, &ctx));
+        assert!(!matches(&parse(":last-child"), &c, &ctx));
+        assert!(matches(&parse(":nth-child(2)"), &b, &ctx));
+        assert!(matches(&parse(":nth-child(odd)"), &c, &ctx));
+        assert!(!matches(&parse(":nth-child(odd)"), &b, &ctx));
+        assert!(matches(&parse(":nth-child(-n+2)"), &b, &ctx));
+        assert!(!matches(&parse(":nth-child(-n+2)"), &c, &ctx));
+        assert!(matches(&parse("p:not(:first-child)"), &b, &ctx));
+        assert!(!matches(&parse("p:not(:first-child)"), &a, &ctx));
+        assert!(matches(&parse(":is(h1, h2)"), &d, &ctx));
+        assert!(matches(&parse(":empty"), &d, &ctx));
+        assert!(!matches(&parse(":empty"), &a, &ctx));
+        assert!(matches(&parse(":root"), &html, &ctx));
+        assert!(!matches(&parse(":root"), &a, &ctx));
+    }
+
+    #[test]
+    fn test_match_user_action_pseudo_classes() {
+        let document = create_document(
+            "<html><body><p id=para><a id=home href=\"/\">home</a></p><a id=next href=\"/next\">next</a></body></html>",
+        );
+        let para = element(&document, "para");
+        let home = element(&document, "home");
+        let next = element(&document, "next");
+
+        let mut ctx = MatchingContext::new();
+        ctx.set_hovered(Some(home.clone()));
+        ctx.set_active(Some(next.clone()));
+        ctx.set_focused(Some(next.clone()));
+        ctx.set_visited_urls(vec!["/next".to_string()]);
+
+        // :hover はカーソルが乗っている要素とその祖先に一致する
+        assert!(matches(&parse("a:hover"), &home, &ctx));
+        assert!(matches(&parse("p:hover"), &para, &ctx));
+        assert!(!matches(&parse("a:hover"), &next, &ctx));
+        assert!(matches(&parse(":active"), &next, &ctx));
+        assert!(matches(&parse(":focus"), &next, &ctx));
+        assert!(!matches(&parse(":focus"), &home, &ctx));
+        assert!(matches(&parse(":link"), &home, &ctx));
+        assert!(!matches(&parse(":link"), &next, &ctx));
+        assert!(matches(&parse(":visited"), &next, &ctx));
+        assert!(!matches(&parse(":visited"), &para, &ctx));
+    }
+
+    #[test]
+    fn test_specificity() {
+        assert_eq!(specificity(&parse("*")), Specificity::new(0, 0, 0));
+        assert_eq!(specificity(&parse("p")), Specificity::new(0, 0, 1));
+        assert_eq!(specificity(&parse("body p > a")), Specificity::new(0, 0, 3));
+        assert_eq!(
+            specificity(&parse("a.button[href]:hover")),
+            Specificity::new(0, 3, 1)
+        );
+        assert_eq!(specificity(&parse("#go.button")), Specificity::new(1, 1, 0));
+        // :not() は引数の中で最も高い詳細度
+        assert_eq!(
+            specificity(&parse("p:not(.a, #b)")),
+            Specificity::new(1, 0, 1)
+        );
+        assert!(specificity(&parse("#a")) > specificity(&parse(".a.b.c.d")));
+
+        // リストは実際に一致したセレクタの詳細度を使う
+        let document = create_document("<html><body><h1 id=title>t</h1></body></html>");
+        let title = element(&document, "title");
+        let ctx = MatchingContext::new();
+        assert_eq!(
+            matching_specificity(&parse("h1, #title, p#x"), &title, &ctx),
+            Some(Specificity::new(1, 0, 0))
+        );
+        assert_eq!(
+            matching_specificity(&parse("h1, #other"), &title, &ctx),
+            Some(Specificity::new(0, 0, 1))
+        );
+        assert_eq!(matching_specificity(&parse("p"), &title, &ctx), None);
+    }
+}
@@ -0,0 +1,289 @@
+//! CSSOM の直列化（CSSOM → CSS の文字列）（初心者向け）
+//!
+//! 役割
+//! - スクリプトから読む `rule.cssText` / `rule.selectorText` / `rule.style.color` の文字列を作ります。
+//! - 元の CSS の文字列は残していないので、読み取った結果（セレクタ・コンポーネント値）から書き直します。
+//!   そのため空白や書き方は元と変わることがあります。
+//!   例: `p>a{color:red}` → `p > a { color: red; }`、`#FFF` → `#FFF`（色の値そのものは解釈しない）
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - TS の `toString()`、Python の `__str__` を型ごとに手で書いたものです。
+//!
+//! 仕様: https://drafts.csswg.org/cssom/#serializing-css-values
+use crate::renderer::css::cssom::AttributeOperator;
+use crate::renderer::css::cssom::Combinator;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::cssom::PseudoClass;
+use crate::renderer::css::cssom::QualifiedRule;
+use crate::renderer::css::cssom::Selector;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// ルールを `p { color: red; margin-top: 0px; }` の形にする
+///
+/// - 宣言が無ければ `p { }`
+/// - `@media` の中のルールも、中身のスタイルルールだけを書く（`@media` の条件は含めない）
+///
+/// 仕様: https://drafts.csswg.org/cssom/#serialize-a-css-rule
+pub fn serialize_rule(rule: &QualifiedRule) -> String {
+    let mut s = serialize_selector(&rule.selector);
+    s.push_str(" {");
+    for declaration in &rule.declarations {
+        s.push(' ');
+        s.push_str(&serialize_declaration(declaration));
+        s.push(';');
+    }
+    s.push_str(" }");
+    s
+}
+
+/// 宣言を `color: red` / `color: red !important` の形にする（末尾の `;` は付けない）
+pub fn serialize_declaration(declaration: &Declaration) -> String {
+    let mut s = format!(
+        "{}: {}",
+        declaration.property,
+        serialize_component_values(&declaration.values)
+    );
+    if declaration.important {
+        s.push_str(" !important");
+    }
+    s
+}
+
+/// コンポーネント値の並び（空白を除いたもの）を、空白で区切って 1 つの文字列にする
+///
+/// - 関数と括弧の内側には空白を入れず、`,` の後ろにだけ空白を入れる
+///
+/// 例: [Dimension(1, px), Ident(solid), Function(rgb), Number(0), Comma, ...] → `1px solid rgb(0, 0, 0)`
+pub fn serialize_component_values(values: &[ComponentValue]) -> String {
+    let mut s = String::new();
+    let mut previous: Option<&ComponentValue> = None;
+    for value in values {
+        let needs_space = !matches!(
+            (previous, value),
+            (None, _)
+                | (_, ComponentValue::Comma)
+                | (_, ComponentValue::CloseParenthesis)
+                | (_, ComponentValue::CloseSquareBracket)
+                | (Some(ComponentValue::Function(_)), _)
+                | (Some(ComponentValue::OpenParenthesis), _)
+                | (Some(ComponentValue::OpenSquareBracket), _)
+        );
+        if needs_space {
+            s.push(' ');
+        }
+        s.push_str(&serialize_token(value));
+        previous = Some(value);
+    }
+    s
+}
+
+// トークン 1 つを CSS の書き方に戻す
+fn serialize_token(token: &ComponentValue) -> String {
+    match token {
+        ComponentValue::Ident(name) => name.clone(),
+        ComponentValue::Function(name) => format!("{}(", name),
+        ComponentValue::AtKeyword(name) => format!("@{}", name),
+        ComponentValue::HashToken(name, _) => format!("#{}", name),
+        ComponentValue::StringToken(value) => serialize_string(value),
+        ComponentValue::Url(url) => format!("url({})", serialize_string(url)),
+        ComponentValue::Number(value, _) => format!("{}", value),
+        ComponentValue::Percentage(value) => format!("{}%", value),
+        ComponentValue::Dimension(value, _, unit) => format!("{}{}", value, unit),
+        ComponentValue::Delim(c) => c.to_string(),
+        ComponentValue::Colon => ":".to_string(),
+        ComponentValue::SemiColon => ";".to_string(),
+        ComponentValue::Comma => ",".to_string(),
+        ComponentValue::OpenParenthesis => "(".to_string(),
+        ComponentValue::CloseParenthesis => ")".to_string(),
+        ComponentValue::OpenSquareBracket => "[".to_string(),
+        ComponentValue::CloseSquareBracket => "]".to_string(),
+        ComponentValue::OpenCurly => "{".to_string(),
+        ComponentValue::CloseCurly => "}".to_string(),
+        ComponentValue::Cdo => "<!--".to_string(),
+        ComponentValue::Cdc => "-->".to_string(),
+        ComponentValue::Whitespace => " ".to_string(),
+        // 宣言の値には入らない（`parse_declaration` が捨てる）
+        ComponentValue::BadString | ComponentValue::BadUrl => String::new(),
+    }
+}
+
+/// 文字列を `"..."` で囲む（中の `"` と `\` はエスケープする）
+/// https://drafts.csswg.org/cssom/#serialize-a-string
+fn serialize_string(value: &str) -> String {
+    let mut s = String::from("\"");
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s.push('"');
+    s
+}
+
+/// セレクタを文字列にする（`rule.selectorText`）
+///
+/// 例: ComplexSelector(Type(body), Child, Compound[Type(a), Class(go)]) → `body > a.go`
+///
+/// 仕様: https://drafts.csswg.org/cssom/#serialize-a-selector
+pub fn serialize_selector(selector: &Selector) -> String {
+    match selector {
+        Selector::TypeSelector(name) => name.clone(),
+        Selector::ClassSelector(name) => format!(".{}", name),
+        Selector::IdSelector(name) => format!("#{}", name),
+        Selector::UniversalSelector => "*".to_string(),
+        Selector::AttributeSelector {
+            name,
+            operator,
+            value,
+            case_insensitive,
+        } => {
+            let operator = match operator {
+                AttributeOperator::Exists => return format!("[{}]", name),
+                AttributeOperator::Equals => "=",
+                AttributeOperator::Includes => "~=",
+                AttributeOperator::DashMatch => "|=",
+                AttributeOperator::Prefix => "^=",
+                AttributeOperator::Suffix => "$=",
+                AttributeOperator::Substring => "*=",
+            };
+            let flag = if *case_insensitive { " i" } else { "" };
+            format!("[{}{}{}{}]", name, operator, serialize_string(value), flag)
+        }
+        Selector::PseudoClassSelector(pseudo_class) => serialize_pseudo_class(pseudo_class),
+        Selector::CompoundSelector(selectors) => selectors.iter().map(serialize_selector).collect(),
+        Selector::ComplexSelector {
+            left,
+            combinator,
+            right,
+        } => {
+            let combinator = match combinator {
+                Combinator::Descendant => " ",
+                Combinator::Child => " > ",
+                Combinator::NextSibling => " + ",
+                Combinator::SubsequentSibling => " ~ ",
+            };
+            format!(
+                "{}{}{}",
+                serialize_selector(left),
+                combinator,
+                serialize_selector(right)
+            )
+        }
+        Selector::SelectorList(selectors) => selectors
+            .iter()
+            .map(serialize_selector)
+            .collect::<Vec<String>>()
+            .join(", "),
+        Selector::UnknownSelector => String::new(),
+    }
+}
+
+fn serialize_pseudo_class(pseudo_class: &PseudoClass) -> String {
+    match pseudo_class {
+        PseudoClass::FirstChild => ":first-child".to_string(),
+        PseudoClass::LastChild => ":last-child".to_string(),
+        PseudoClass::NthChild { a, b } => format!(":nth-child({})", serialize_an_plus_b(*a, *b)),
+        PseudoClass::Not(selector) => format!(":not({})", serialize_selector(selector)),
+        PseudoClass::Is(selector) => format!(":is({})", serialize_selector(selector)),
+        PseudoClass::Root => ":root".to_string(),
+        PseudoClass::Empty => ":empty".to_string(),
+        PseudoClass::Link => ":link".to_string(),
+        PseudoClass::Visited => ":visited".to_string(),
+        PseudoClass::Hover => ":hover".to_string(),
+        PseudoClass::Focus => ":focus".to_string(),
+        PseudoClass::Active => ":active".to_string(),
+    }
+}
+
+/// `an+b` を書き戻す（`odd` は `2n+1`、`3` は `3`、`-n+2` は `-n+2`）
+/// https://www.w3.org/TR/css-syntax-3/#serializing-anb
+fn serialize_an_plus_b(a: i64, b: i64) -> String {
+    if a == 0 {
+        return format!("{}", b);
+    }
+    let mut s = match a {
+        1 => "n".to_string(),
+        -1 => "-n".to_string(),
+        _ => format!("{}n", a),
+    };
+    match b {
+        0 => {}
+        1.. => s.push_str(&format!("+{}", b)),
+        _ => s.push_str(&format!("{}", b)),
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+
+    fn parse(css: &str) -> Vec<QualifiedRule> {
+        CssParser::new(CssTokenizer::new(css.to_string()))
+            .parse_stylesheet()
+            .rules
+    }
+
+    #[test]
+    fn test_serialize_rule() {
+        let rules = parse(
+            "p>a.go{color:red;border:1px solid rgb(0,0,0)} \
+             h1 , h2{font-family:\"A \\\"B\\\"\",serif !important}\
+             div{}",
+        );
+        assert_eq!(
+            serialize_rule(&rules[0]),
+            "p > a.go { color: red; border: 1px solid rgb(0, 0, 0); }"
+        );
+        assert_eq!(
+            serialize_rule(&rules[1]),
+            "h1, h2 { font-family: \"A \\\"B\\\"\", serif !important; }"
+        );
+        assert_eq!(serialize_rule(&rules[2]), "div { }");
+    }
+
+    #[test]
+    fn test_serialize_selector() {
+        let cases = [
+            ("*", "*"),
+            ("#main .note", "#main .note"),
+            ("a + b ~ c", "a + b ~ c"),
+            ("[lang|=en i]", "[lang|=\"en\" i]"),
+            ("[hidden]", "[hidden]"),
+            ("li:nth-child(odd)", "li:nth-child(2n+1)"),
+            ("li:nth-child(-n + 3)", "li:nth-child(-n+3)"),
+            ("li:nth-child(4)", "li:nth-child(4)"),
+            ("p:not(.a):first-child", "p:not(.a):first-child"),
+            ("a:is(:hover, :focus)", "a:is(:hover, :focus)"),
+        ];
+        for (input, expected) in cases {
+            let css = format!("{} {{}}", input);
+            assert_eq!(
+                serialize_selector(&parse(&css)[0].selector),
+                expected,
+                "selector: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialize_component_values() {
+        let rules = parse("p { width: calc(100% - 2.5em); list-style-image: url(dot.png); }");
+        assert_eq!(
+            serialize_component_values(&rules[0].declarations[0].values),
+            "calc(100% - 2.5em)"
+        );
+        assert_eq!(
+            serialize_component_values(&rules[0].declarations[1].values),
+            "url(\"dot.png\")"
+        );
+    }
+}
@@ -2,51 +2,85 @@
 //!
 //! 目的
 //! - CSS の文字列を“トークン”（部品）に分解します。例: `color: #fff;` → `Ident("color")`, `Colon`,
-//!   `HashToken("fff")`, `SemiColon`。
+//!   `Whitespace`, `HashToken("fff", Unrestricted)`, `SemiColon`。
 //! - ここで得たトークン列は、このあとセレクタ/宣言ブロックなどのパーサに渡されます。
 //!
 //! 言語ブリッジ（TS / Python / Go）
 //! - `Iterator` 実装により、`next()` で1トークンずつ取り出せます（TS の `for..of`、Python の `for x in it`）。
 //! - `String`/`Vec<char>` は `alloc` のヒープ型（no_std なので明示利用）。
-//! - 仕様リンク（W3C）を各トークン定義に添えていますが、実装は学習用に単純化しています。
 //!
-//! トークナイズの流れ（ざっくり）
-//! 1) 1文字読む → 種別を判定（記号/数字/識別子/文字列/特殊）
-//! 2) 必要なら `consume_xxx_token` で連続した塊を読み切る
-//! 3) 1トークンを返す（`Some(token)`）
-//! 4) 呼び出し側が `next()` を再度呼ぶ
+//! トークナイズの流れ（CSS Syntax Level 3 の「トークンを読む」手順のとおり）
+//! 0) 入力の前処理: 改行 `\r\n` / `\r` / `\u{c}` を `\n` に、`\0` を `U+FFFD` にそろえる
+//! 1) コメント `/* ... */` を読み飛ばす（トークンは作らない）
+//! 2) 1文字読む → 種別を判定（記号/数字/識別子/文字列/特殊）。
+//!    `-` `+` `.` `<` `@` `\` は後ろの数文字を先読みして、数値・識別子・`-->`・`<!--` などの始まりかを決める
+//! 3) 必要なら `consume_xxx` で連続した塊を読み切り、1トークンを返す（`Some(token)`）
 //!
-//! 簡易化している点（制約）
-//! - コメント `/* ... */` のスキップやバックスラッシュエスケープは未対応。
-//! - 数値の指数表記（1e3）や単位（px, em）は別フェーズで扱う前提。
-//! - `.` 単体は Delim とし、`.5` のような先頭ドット数値は未対応。
-//! - `-` 先頭の負数は未対応（`-` は識別子として扱う）。
+//! エラーの扱い
+//! - トークナイズは失敗しません。閉じていない文字列やコメントは入力の終わりで閉じたものとし、
+//!   文字列の途中の改行は `BadString`、壊れた `url(...)` は `BadUrl` にします（パーサがその宣言を捨てる）。
+//! - 知らない記号は `Delim` にします。
+//!
+//! 仕様との違い
+//! - `unicode-range`（`U+0-7F`）は、最新の仕様ではトークナイザではなく `@font-face` の
+//!   `unicode-range` の値を読むときに扱うことになったため、ここでは `Ident("U")` と数値に分かれます
+//!   （ブラウザと同じ）。
+//!
+//! 仕様: https://www.w3.org/TR/css-syntax-3/#tokenization
 
 use alloc::string::String;
 use alloc::vec::Vec;
 
+/// hash トークンの型フラグ
+///
+/// - `Id`: `#` の後ろが識別子として読める（`#main` / `#-x`）。ID セレクタに使えるのはこちらだけ
+/// - `Unrestricted`: 数字などで始まる（`#0red` / `#fff` は Unrestricted、`#abc` は Id）
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HashType {
+    Id,
+    Unrestricted,
+}
+
+/// 数値トークンの型フラグ（小数点も指数も無く書かれていれば `Integer`）
+///
+/// 例: `12` / `+3` → Integer、`1.0` / `.5` / `1e3` → Number
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NumericType {
+    Integer,
+    Number,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssToken {
     /// https://www.w3.org/TR/css-syntax-3/#typedef-hash-token
-    /// 例: `#fff` → `HashToken("fff")`（本実装では常に ID/カラー風の文字列として扱う）
-    HashToken(String),
+    /// `#` に続く名前（`#` は含まない）と型フラグ。例: `#main` → `HashToken("main", Id)`、`#0f0` → `HashToken("0f0", Unrestricted)`
+    HashToken(String, HashType),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-delim-token
-    /// 単独記号。例: `,` や `.` など。
+    /// ほかのトークンにならない単独の記号。例: `.` `>` `*` `!` など。
     Delim(char),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-number-token
-    /// 数値。例: `12`, `0.5`。
-    Number(f64),
+    /// 数値と型フラグ。例: `12` → `Number(12.0, Integer)`、`-0.5` / `1e3` → `Number(_, Number)`
+    Number(f64, NumericType),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-dimension-token
+    /// 単位付きの数値。例: `10px` → `Dimension(10.0, Integer, "px")`（単位は書かれたまま。大文字小文字は区別しない）
+    Dimension(f64, NumericType, String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-percentage-token
+    /// パーセント。例: `50%` → `Percentage(50.0)`
+    Percentage(f64),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-colon-token
     /// `:`（プロパティ名と値の区切り）
     Colon,
     /// https://www.w3.org/TR/css-syntax-3/#typedef-semicolon-token
     /// `;`（宣言の終端）
     SemiColon,
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-comma-token
+    /// `,`（セレクタリストや関数の引数の区切り）
+    Comma,
     /// https://www.w3.org/TR/css-syntax-3/#tokendef-open-paren
-    /// `(`（関数呼び出しや `rgb(` など）
+    /// `(`（関数ではない括弧。メディア特性 `(min-width: 600px)` や calc() の中の入れ子など）
     OpenParenthesis,
     /// https://www.w3.org/TR/css-syntax-3/#tokendef-close-paren
-    /// `)`
+    /// `)`（関数の終わりも、括弧の終わりもこのトークン）
     CloseParenthesis,
     /// https://www.w3.org/TR/css-syntax-3/#tokendef-open-curly
     /// `{`（宣言ブロック開始）
@@ -55,125 +89,350 @@ pub enum CssToken {
     /// `}`（宣言ブロック終了）
     CloseCurly,
     /// https://www.w3.org/TR/css-syntax-3/#typedef-ident-token
-    /// 識別子。例: `color`, `background`, `--var`（本実装は英数字/`-`/`_` 程度に限定）
+    /// 識別子。例: `color`, `background`, `--var`, `\31 23`（エスケープは解いた値 `"123"` を持つ）
     Ident(String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-function-token
+    /// 関数の始まり（名前と `(`）。例: `rgb(` → `Function("rgb")`。引数が続き、`)` で終わる
+    Function(String),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-string-token
     /// 引用符で囲まれた文字列。例: `"Helvetica"` → `StringToken("Helvetica")`
     StringToken(String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-bad-string-token
+    /// 途中で改行した文字列（閉じ引用符が無い）
+    BadString,
     /// https://www.w3.org/TR/css-syntax-3/#typedef-at-keyword-token
     /// `@xxx`。例: `@media`, `@import` → `AtKeyword("media")` など
     AtKeyword(String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-whitespace-token
+    /// 連続する空白・改行・タブ（まとめて 1 トークン）。
+    /// セレクタの子孫結合子 `div p` の区切りとして意味を持つため、読み飛ばさずに返します。
+    Whitespace,
+    /// https://www.w3.org/TR/css-syntax-3/#tokendef-open-square
+    /// `[`（属性セレクタ `[type=text]` の開始）
+    OpenSquareBracket,
+    /// https://www.w3.org/TR/css-syntax-3/#tokendef-close-square
+    /// `]`
+    CloseSquareBracket,
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-url-token
+    /// 引用符なしの `url(...)`。例: `url(a.css)` → `Url("a.css")`
+    /// （`url("a.css")` は `Function("url")` `StringToken("a.css")` `)` になる）
+    Url(String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-bad-url-token
+    /// 引用符なしの `url(...)` の中に、空白や引用符など使えない文字があった
+    BadUrl,
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-cdo-token
+    /// `<!--`（HTML の `<style>` を古いブラウザから隠すための名残。スタイルシートの最上位では読み飛ばす）
+    Cdo,
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-cdc-token
+    /// `-->`
+    Cdc,
+}
+
+/// ソース上の位置（行・列とも 1 始まり。列は文字単位で数える）
+///
+/// 例: `p {\n  color red;\n}` の `color` は line 2, column 3
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CssTokenizer {
-    pos: usize,       // 次に読む位置（0..len）
-    input: Vec<char>, // 入力を1文字ずつに分割した配列（単純化のため事前に chars().collect()）
+    pos: usize,         // 次に読む位置（0..len）
+    input: Vec<char>,   // 前処理した入力を1文字ずつに分割した配列
+    token_start: usize, // 直前に返したトークンの先頭
+    line: usize,        // token_start の行（1 始まり）
+    line_start: usize,  // その行の先頭の位置
 }
 
 impl CssTokenizer {
     /// 文字列からトークナイザを作成します。
-    /// - 内部では `chars().collect()` で `Vec<char>` を作り、`pos` を 0 から進めていきます。
+    /// - 改行をそろえ（`\r\n` / `\r` / `\u{c}` → `\n`）、`\0` を `U+FFFD` に置き換えてから
+    ///   `Vec<char>` にします。
+    ///
+    /// https://www.w3.org/TR/css-syntax-3/#input-preprocessing
     pub fn new(css: String) -> Self {
+        let mut input = Vec::with_capacity(css.len());
+        let mut chars = css.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' => {
+                    if chars.peek() == Some(&'\n') {
+                        chars.next();
+                    }
+                    input.push('\n');
+                }
+                '\u{c}' => input.push('\n'),
+                '\0' => input.push('\u{FFFD}'),
+                _ => input.push(c),
+            }
+        }
         Self {
             pos: 0,
-            input: css.chars().collect(),
+            input,
+            token_start: 0,
+            line: 1,
+            line_start: 0,
         }
     }
 
-    // 再びダブルクオーテーションまたはシングルクォーテーションが現れるまで入力を文字として解釈する
-    /// https://www.w3.org/TR/css-syntax-3/#consume-a-string-token
-    /// 例: `"Helvetica"` → `"Helvetica"`（両端の引用は除いた内容を返す）
-    /// ポインタの動き（概念図）
-    /// - 先頭の引用 `"` 上で呼ばれる → ループ先頭で `pos += 1` して中身を 1 文字ずつ読む
-    /// - 終端の引用 `"` に当たったら break → 呼び出し側の `next()` で最後に `pos += 1` して
-    ///   “閉じ引用の次” に進む
-    /// 注意: この簡易実装では `\"` などのエスケープや改行・EOF の扱いを省略しています。
-    fn consume_string_token(&mut self) -> String {
-        let mut s = String::new();
-
-        loop {
-            if self.pos >= self.input.len() {
-                return s;
-            }
+    /// 直前に `next()` で返したトークンの先頭の位置（`None` を返したあとは入力の終わり）
+    /// パーサが書き間違いを報告するときに使います。
+    pub fn position(&self) -> SourcePosition {
+        SourcePosition {
+            line: self.line,
+            column: self.token_start - self.line_start + 1,
+        }
+    }
 
-            // 現在の引用符の次の文字から走査を開始する想定
-            self.pos += 1;
-            let c = self.input[self.pos];
-            match c {
-                '"' | '\'' => break,
-                _ => s.push(c),
+    // 読み位置をトークンの先頭として記録する（前回の先頭からここまでの改行を数えて行を進める）
+    fn mark_token_start(&mut self) {
+        for i in self.token_start..self.pos {
+            if self.input[i] == '\n' {
+                self.line += 1;
+                self.line_start = i + 1;
             }
         }
+        self.token_start = self.pos;
+    }
 
-        s
+    // 読み位置から n 文字先の文字（読み位置は進めない）
+    fn peek(&self, n: usize) -> Option<char> {
+        self.input.get(self.pos + n).copied()
     }
 
-    // 数字またはピリオドが出続けている間、数字として解釈する
-    /// https://www.w3.org/TR/css-syntax-3/#consume-number
-    /// https://www.w3.org/TR/css-syntax-3/#consume-a-numeric-token
-    /// 例: `12.34` → 12.34
-    /// 実装メモ
-    /// - `floating` が true になると、小数点以下の重み `floating_digit` を 1/10, 1/100, ... と下げながら
-    ///   桁を加算していきます。
-    /// - 読み進めはこの関数内で行い、呼び出し側（`next()`）では帳尻合わせの `self.pos -= 1` を行います
-    ///   （`next()` の末尾で一律 `pos += 1` する設計のため）。
-    /// 注意: `.5` のような先頭ドット数値や指数表記 `1e3` は未対応です。
-    fn consume_numeric_token(&mut self) -> f64 {
-        let mut num = 0f64;
-        let mut floating = false;
-        let mut floating_digit = 1f64;
+    // 1文字読んで読み位置を進める
+    fn consume(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    // 直前に読んだ1文字を読み直す（仕様の reconsume）
+    fn reconsume(&mut self) {
+        self.pos -= 1;
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#consume-comments
+    /// `/*` から `*/` までを読み飛ばす（続けて書かれたコメントもまとめて）。閉じていなければ入力の終わりまで
+    fn consume_comments(&mut self) {
+        while self.peek(0) == Some('/') && self.peek(1) == Some('*') {
+            self.pos += 2;
+            loop {
+                match self.consume() {
+                    None => return,
+                    Some('*') if self.peek(0) == Some('/') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
 
+    /// https://www.w3.org/TR/css-syntax-3/#consume-a-string-token
+    /// 開き引用符を読んだあとに呼ぶ。`ending`（同じ引用符）までを文字列として読む
+    /// - `\` + 改行は行の継続（何も足さない）、`\` + その他はエスケープ
+    /// - エスケープされていない改行に当たったら `BadString`（改行は読まずに残す）
+    ///
+    /// 例: `"a\"b"` → `StringToken("a\"b")`
+    fn consume_string_token(&mut self, ending: char) -> CssToken {
+        let mut s = String::new();
         loop {
-            if self.pos >= self.input.len() {
-                return num;
+            match self.consume() {
+                None => return CssToken::StringToken(s),
+                Some(c) if c == ending => return CssToken::StringToken(s),
+                Some('\n') => {
+                    self.reconsume();
+                    return CssToken::BadString;
+                }
+                Some('\\') => match self.peek(0) {
+                    None => {}
+                    Some('\n') => self.pos += 1,
+                    Some(_) => s.push(self.consume_escape()),
+                },
+                Some(c) => s.push(c),
             }
+        }
+    }
 
-            let c = self.input[self.pos];
+    /// https://www.w3.org/TR/css-syntax-3/#consume-escaped-code-point
+    /// `\` を読んだあとに呼ぶ。16 進数（最大 6 桁、後ろの空白 1 つまで読む）か、その1文字そのもの
+    ///
+    /// 例: `\31 ` → `'1'`、`\"` → `'"'`。0・サロゲート・範囲外は `U+FFFD`
+    fn consume_escape(&mut self) -> char {
+        match self.consume() {
+            Some(c) if c.is_ascii_hexdigit() => {
+                let mut hex = String::from(c);
+                while hex.len() < 6 && self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+                    hex.push(self.consume().expect("peeked"));
+                }
+                if self.peek(0).is_some_and(is_whitespace) {
+                    self.pos += 1;
+                }
+                match u32::from_str_radix(&hex, 16) {
+                    Ok(0) => '\u{FFFD}',
+                    Ok(n) => char::from_u32(n).unwrap_or('\u{FFFD}'),
+                    Err(_) => '\u{FFFD}',
+                }
+            }
+            Some(c) => c,
+            None => '\u{FFFD}',
+        }
+    }
 
-            match c {
-                '0'..='9' => {
-                    if floating {
-                        floating_digit *= 1f64 / 10f64;
-                        num += (c.to_digit(10).unwrap() as f64) * floating_digit
-                    } else {
-                        num = num * 10.0 + (c.to_digit(10).unwrap() as f64);
-                    }
+    /// https://www.w3.org/TR/css-syntax-3/#consume-name
+    /// 名前に使える文字とエスケープが続く間、読み続ける
+    /// 例: `background-color` → `"background-color"`、`a\.b` → `"a.b"`
+    fn consume_name(&mut self) -> String {
+        let mut s = String::new();
+        loop {
+            match self.peek(0) {
+                Some(c) if is_ident_char(c) => {
+                    s.push(c);
                     self.pos += 1;
                 }
-                '.' => {
-                    floating = true;
+                Some('\\') if is_valid_escape(Some('\\'), self.peek(1)) => {
                     self.pos += 1;
+                    s.push(self.consume_escape());
+                }
+                _ => return s,
+            }
+        }
+    }
+
+    /// https://www.w3.org/TR/css-syntax-3/#consume-number
+    /// 符号・整数部・小数部・指数部を読んで値と型フラグにする（小数部か指数部があれば Number）
+    /// 例: `-1.5e2` → (-150.0, Number)、`.5` → (0.5, Number)、`+3` → (3.0, Integer)
+    fn consume_number(&mut self) -> (f64, NumericType) {
+        let mut repr = String::new();
+        let mut numeric_type = NumericType::Integer;
+        if let Some(c @ ('+' | '-')) = self.peek(0) {
+            repr.push(c);
+            self.pos += 1;
+        }
+        self.consume_digits(&mut repr);
+        if self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit()) {
+            repr.push('.');
+            self.pos += 1;
+            self.consume_digits(&mut repr);
+            numeric_type = NumericType::Number;
+        }
+        // `1e3` / `1e-3`。`1em` の `e` は単位なので、e の後が数字（符号 + 数字）のときだけ指数とみなす
+        if let Some(e @ ('e' | 'E')) = self.peek(0) {
+            let sign = matches!(self.peek(1), Some('+' | '-'));
+            let digit_at = if sign { 2 } else { 1 };
+            if self.peek(digit_at).is_some_and(|c| c.is_ascii_digit()) {
+                repr.push(e);
+                if sign {
+                    repr.push(self.peek(1).expect("peeked"));
                 }
-                _ => break,
+                self.pos += digit_at;
+                self.consume_digits(&mut repr);
+                numeric_type = NumericType::Number;
             }
         }
+        (repr.parse().unwrap_or(0.0), numeric_type)
+    }
+
+    fn consume_digits(&mut self, repr: &mut String) {
+        while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+            repr.push(c);
+            self.pos += 1;
+        }
+    }
 
-        num
+    /// https://www.w3.org/TR/css-syntax-3/#consume-a-numeric-token
+    /// 数値の後ろに名前が続けば `Dimension`、`%` なら `Percentage`、どちらでもなければ `Number`
+    /// 例: `40` → Number(40.0, Integer) / `50%` → Percentage(50.0) / `1.5em` → Dimension(1.5, Number, "em")
+    /// （percentage-token は型フラグを持たない）
+    fn consume_numeric_token(&mut self) -> CssToken {
+        let (num, numeric_type) = self.consume_number();
+        if would_start_ident(self.peek(0), self.peek(1), self.peek(2)) {
+            CssToken::Dimension(num, numeric_type, self.consume_name())
+        } else if self.peek(0) == Some('%') {
+            self.pos += 1;
+            CssToken::Percentage(num)
+        } else {
+            CssToken::Number(num, numeric_type)
+        }
     }
 
-    // 文字、数字、ハイフン、アンダースコアが出続けている間、識別子として扱う
-    // それ以外の入力が出てきたら、今までの文字を返してメソッドを終了する
     /// https://www.w3.org/TR/css-syntax-3/#consume-ident-like-token
-    /// https://www.w3.org/TR/css-syntax-3/#consume-name
-    /// 例: `color` や `background-color` → `"color"`, `"background-color"`
-    /// 注意: CSS の厳密な name-start / name-char 規則（非ASCII, escape など）は簡略化しています。
-    fn consume_ident_token(&mut self) -> String {
-        let mut s = String::new();
-        s.push(self.input[self.pos]);
+    /// 名前を読み、直後が `(` なら関数（`url(` で中身が引用符で始まらなければ `Url`）、そうでなければ識別子
+    fn consume_ident_like_token(&mut self) -> CssToken {
+        let name = self.consume_name();
+        if self.peek(0) != Some('(') {
+            return CssToken::Ident(name);
+        }
+        self.pos += 1;
+        if !name.eq_ignore_ascii_case("url") {
+            return CssToken::Function(name);
+        }
+        // 引用符の前の空白は、関数の引数の Whitespace トークンとして1つ残す
+        while self.peek(0).is_some_and(is_whitespace) && self.peek(1).is_some_and(is_whitespace) {
+            self.pos += 1;
+        }
+        let quote = |c: Option<char>| matches!(c, Some('"' | '\''));
+        if quote(self.peek(0)) || (self.peek(0).is_some_and(is_whitespace) && quote(self.peek(1))) {
+            CssToken::Function(name)
+        } else {
+            self.consume_url_token()
+        }
+    }
 
-        loop {
+    /// https://www.w3.org/TR/css-syntax-3/#consume-url-token
+    /// `url(` を読んだあとに呼ぶ。`)` までを URL として読む（前後の空白は除く）
+    /// 例: `url( a.css )` → `Url("a.css")`、`url(a b)` → `BadUrl`
+    fn consume_url_token(&mut self) -> CssToken {
+        let mut s = String::new();
+        while self.peek(0).is_some_and(is_whitespace) {
             self.pos += 1;
-            let c = self.input[self.pos];
-            match c {
-                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
-                    s.push(c);
+        }
+        loop {
+            match self.consume() {
+                None | Some(')') => return CssToken::Url(s),
+                Some(c) if is_whitespace(c) => {
+                    while self.peek(0).is_some_and(is_whitespace) {
+                        self.pos += 1;
+                    }
+                    return match self.peek(0) {
+                        None => CssToken::Url(s),
+                        Some(')') => {
+                            self.pos += 1;
+                            CssToken::Url(s)
+                        }
+                        Some(_) => self.consume_bad_url_remnants(),
+                    };
                 }
-                _ => break,
+                Some('"' | '\'' | '(') => return self.consume_bad_url_remnants(),
+                Some(c) if is_non_printable(c) => return self.consume_bad_url_remnants(),
+                Some('\\') => {
+                    if is_valid_escape(Some('\\'), self.peek(0)) {
+                        s.push(self.consume_escape());
+                    } else {
+                        return self.consume_bad_url_remnants();
+                    }
+                }
+                Some(c) => s.push(c),
             }
         }
+    }
 
-        s
+    /// https://www.w3.org/TR/css-syntax-3/#consume-remnants-of-bad-url
+    /// 壊れた URL の残りを `)` まで読み捨てる（エスケープされた `)` では止まらない）
+    fn consume_bad_url_remnants(&mut self) -> CssToken {
+        loop {
+            match self.consume() {
+                None | Some(')') => return CssToken::BadUrl,
+                Some('\\') if is_valid_escape(Some('\\'), self.peek(0)) => {
+                    self.consume_escape();
+                }
+                Some(_) => {}
+            }
+        }
     }
 }
 
@@ -182,103 +441,165 @@ impl Iterator for CssTokenizer {
 
     /// https://www.w3.org/TR/css-syntax-3/#consume-token
     /// 実装のポイント
-    /// - 空白・改行は“トークン化せず”に読み飛ばします。
-    /// - `consume_*` を呼んだ場合は内部で `pos` を進めるため、末尾で一律 `pos += 1` する前に
-    ///   1 文字ぶん戻す（`self.pos -= 1`）箇所があります。
+    /// - 空白・改行は 1 つの `Whitespace` トークンにまとめます（宣言の中では呼び出し側が読み飛ばす）。
+    /// - 先頭の 1 文字を読んでから種別を決め、数値や識別子は `reconsume` で戻して `consume_*` に任せます。
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            // 0) 入力末尾ならイテレータ終了（None）。
-            if self.pos >= self.input.len() {
-                return None;
-            }
-
-            // 1) まだ pos は進めず、現在位置の1文字を見て種別を判定。
-            let c = self.input[self.pos];
-
-            // 2) 先頭文字に応じて分岐。必要なら consume_* で“塊”を読み切る。
-            let token = match c {
-                // 記号類: コロン、セミコロン、丸括弧、波括弧など
-                // CSS では宣言の区切りや関数呼び出しの括弧に使われます。
-                '(' => CssToken::OpenParenthesis,
-                ')' => CssToken::CloseParenthesis,
-                ',' => CssToken::Delim(','),
-                '.' => {
-                    // 簡易: 直後が数字でも `.5` を Number とせず Delim('.') とする。
-                    // 対応したい場合はここで先読みして consume_numeric_token を呼ぶ分岐を追加します。
-                    CssToken::Delim('.')
-                }
-                ':' => CssToken::Colon,
-                ';' => CssToken::SemiColon,
-                '{' => CssToken::OpenCurly,
-                '}' => CssToken::CloseCurly,
-                ' ' | '\n' => {
-                    // 空白・改行は“トークン化せず”読み飛ばす。
+        self.consume_comments();
+        self.mark_token_start();
+        // 入力末尾ならイテレータ終了（None）。
+        let c = self.consume()?;
+        let token = match c {
+            c if is_whitespace(c) => {
+                // 連続する空白・改行は 1 つの Whitespace トークンにまとめる。
+                while self.peek(0).is_some_and(is_whitespace) {
                     self.pos += 1;
-                    continue;
-                }
-                // 文字列: ダブル/シングルクォートで囲まれたもの
-                '"' | '\'' => {
-                    let value = self.consume_string_token();
-                    CssToken::StringToken(value)
                 }
-                // 数値
-                '0'..='9' => {
-                    let t = CssToken::Number(self.consume_numeric_token());
-                    // consume_* 内で pos を進めた分、末尾の pos += 1 と釣り合うように 1 戻す（帳尻合わせ）。
-                    self.pos -= 1;
-                    t
+                CssToken::Whitespace
+            }
+            // 文字列: ダブル/シングルクォートで囲まれたもの
+            '"' | '\'' => self.consume_string_token(c),
+            // `#` に名前が続けば HashToken（`#fff` / `#main`）、そうでなければ単独の記号
+            // 名前が識別子として読めるなら型フラグは Id
+            '#' => {
+                if self.peek(0).is_some_and(is_ident_char)
+                    || is_valid_escape(self.peek(0), self.peek(1))
+                {
+                    let hash_type = if would_start_ident(self.peek(0), self.peek(1), self.peek(2)) {
+                        HashType::Id
+                    } else {
+                        HashType::Unrestricted
+                    };
+                    CssToken::HashToken(self.consume_name(), hash_type)
+                } else {
+                    CssToken::Delim('#')
                 }
-                // #ID or 色コード風（本実装では単純化して識別子の連結）
-                '#' => {
-                    // 簡易版: 常に `#` に続く識別子を HashToken とする。
-                    // 注意: 厳密には `#` 後ろは name ルールに従うべきですが、ここでは `#id` や
-                    // `#fff` のようなケースを広く受けるため、`#` を含めて 1 つの識別子として読みます。
-                    let value = self.consume_ident_token();
-                    self.pos -= 1;
-                    CssToken::HashToken(value)
+            }
+            '(' => CssToken::OpenParenthesis,
+            ')' => CssToken::CloseParenthesis,
+            ',' => CssToken::Comma,
+            ':' => CssToken::Colon,
+            ';' => CssToken::SemiColon,
+            '[' => CssToken::OpenSquareBracket,
+            ']' => CssToken::CloseSquareBracket,
+            '{' => CssToken::OpenCurly,
+            '}' => CssToken::CloseCurly,
+            // `+1` / `+.5` は数値、それ以外（隣接結合子 `a + b` など）は記号
+            '+' | '.' => {
+                if would_start_number(Some(c), self.peek(0), self.peek(1)) {
+                    self.reconsume();
+                    self.consume_numeric_token()
+                } else {
+                    CssToken::Delim(c)
                 }
-                // ハイフン始まりは識別子とみなす（負の数は扱わない前提）
-                '-' => {
-                    // 例: `--var` や `border-left` の先頭 `-`
-                    let t = CssToken::Ident(self.consume_ident_token());
-                    self.pos -= 1;
-                    t
+            }
+            // `-1px` は数値、`-->` は CDC、`-webkit-box` / `--var` は識別子、それ以外は記号
+            '-' => {
+                if would_start_number(Some(c), self.peek(0), self.peek(1)) {
+                    self.reconsume();
+                    self.consume_numeric_token()
+                } else if self.peek(0) == Some('-') && self.peek(1) == Some('>') {
+                    self.pos += 2;
+                    CssToken::Cdc
+                } else if would_start_ident(Some(c), self.peek(0), self.peek(1)) {
+                    self.reconsume();
+                    self.consume_ident_like_token()
+                } else {
+                    CssToken::Delim('-')
                 }
-                '@' => {
-                    // `@media` / `@import` のような at-keyword を簡易判定。
-                    // 3文字先までが英数字のとき AtKeyword とみなし、そうでなければ単独記号扱い。
-                    // 本来は「@ の後に ident を読む」処理で十分ですが、
-                    // 短絡評価の例として3文字先までの簡易チェックを入れています。
-                    if self.input[self.pos + 1].is_ascii_alphabetic()
-                        && self.input[self.pos + 2].is_alphanumeric()
-                        && self.input[self.pos + 3].is_alphanumeric()
-                    {
-                        // skip '@'
-                        self.pos += 1;
-                        let t = CssToken::AtKeyword(self.consume_ident_token());
-                        self.pos -= 1;
-                        t
-                    } else {
-                        CssToken::Delim('@')
-                    }
+            }
+            '<' => {
+                if self.peek(0) == Some('!')
+                    && self.peek(1) == Some('-')
+                    && self.peek(2) == Some('-')
+                {
+                    self.pos += 3;
+                    CssToken::Cdo
+                } else {
+                    CssToken::Delim('<')
                 }
-                // 識別子（プロパティ名やキーワード）
-                'a'..='z' | 'A'..='Z' | '_' => {
-                    let t = CssToken::Ident(self.consume_ident_token());
-                    self.pos -= 1;
-                    t
+            }
+            // `@media` / `@import` のような at-keyword。`@` の後が名前の始まりでなければ単独の記号
+            '@' => {
+                if would_start_ident(self.peek(0), self.peek(1), self.peek(2)) {
+                    CssToken::AtKeyword(self.consume_name())
+                } else {
+                    CssToken::Delim('@')
                 }
-                _ => {
-                    // 未対応の文字は学習用に unimplemented! で明示
-                    unimplemented!("char {} is not supported yet", c);
+            }
+            // エスケープで始まる識別子（`\31 0` など）。改行が続く `\` は不正なので記号
+            '\\' => {
+                if is_valid_escape(Some(c), self.peek(0)) {
+                    self.reconsume();
+                    self.consume_ident_like_token()
+                } else {
+                    CssToken::Delim('\\')
                 }
-            };
+            }
+            '0'..='9' => {
+                self.reconsume();
+                self.consume_numeric_token()
+            }
+            // 識別子（プロパティ名やキーワード）
+            c if is_ident_start(c) => {
+                self.reconsume();
+                self.consume_ident_like_token()
+            }
+            // セレクタの結合子（`>` `~`）、全称セレクタ `*`、属性セレクタの演算子（`=` `|` `^` `$`）、
+            // `!important` の `!`、色関数の alpha の区切り（`/`）など
+            _ => CssToken::Delim(c),
+        };
+        Some(token)
+    }
+}
 
-            // 3) 1 トークン確定。読み位置を 1 進めて返す。
-            self.pos += 1;
-            // 呼び出し側は再度 next() を呼んで次トークンを取得する。
-            return Some(token);
+/// https://www.w3.org/TR/css-syntax-3/#whitespace
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\n' | '\t')
+}
+
+/// https://www.w3.org/TR/css-syntax-3/#ident-start-code-point
+/// 英字・`_`・ASCII 以外の文字
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+/// https://www.w3.org/TR/css-syntax-3/#ident-code-point
+fn is_ident_char(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+/// https://www.w3.org/TR/css-syntax-3/#non-printable-code-point
+fn is_non_printable(c: char) -> bool {
+    matches!(c, '\0'..='\u{8}' | '\u{b}' | '\u{e}'..='\u{1f}' | '\u{7f}')
+}
+
+/// https://www.w3.org/TR/css-syntax-3/#starts-with-a-valid-escape
+/// `\` の後が改行でない（入力の終わりでもよい。そのときのエスケープは `U+FFFD` になる）
+fn is_valid_escape(first: Option<char>, second: Option<char>) -> bool {
+    first == Some('\\') && second != Some('\n')
+}
+
+/// https://www.w3.org/TR/css-syntax-3/#would-start-an-identifier
+/// 例: `a` / `-a` / `--` / `\31` は識別子の始まり、`-1` / `- ` は違う
+fn would_start_ident(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    match first {
+        Some('-') => {
+            second.is_some_and(|c| is_ident_start(c) || c == '-') || is_valid_escape(second, third)
         }
+        Some('\\') => is_valid_escape(first, second),
+        Some(c) => is_ident_start(c),
+        None => false,
+    }
+}
+
+/// https://www.w3.org/TR/css-syntax-3/#starts-with-a-number
+/// 例: `1` / `+1` / `-.5` / `.5` は数値の始まり、`+a` / `-` / `.` は違う
+fn would_start_number(first: Option<char>, second: Option<char>, third: Option<char>) -> bool {
+    let digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+    match first {
+        Some('+' | '-') => digit(second) || (second == Some('.') && digit(third)),
+        Some('.') => digit(second),
+        c => digit(c),
     }
 }
 
@@ -303,11 +624,15 @@ mod tests {
         let mut t = CssTokenizer::new(style);
         let expected = [
             CssToken::Ident("p".to_string()), // セレクタ（要素名）
+            CssToken::Whitespace,             // 空白もトークンになる
             CssToken::OpenCurly,              // '{'
+            CssToken::Whitespace,
             CssToken::Ident("color".to_string()),
             CssToken::Colon, // ':'
+            CssToken::Whitespace,
             CssToken::Ident("red".to_string()),
-            CssToken::SemiColon,  // ';'
+            CssToken::SemiColon, // ';'
+            CssToken::Whitespace,
             CssToken::CloseCurly, // '}'
         ];
         for e in expected {
@@ -320,16 +645,20 @@ mod tests {
     #[test]
     fn test_id_selector() {
         // ID セレクタ: #id { color: red; }
-        // 簡易実装では HashToken("#id") として“#を含んだまま”読ませています。
+        // HashToken は `#` を除いた名前を持つ。識別子として読める名前なので型フラグは Id
         let style = "#id { color: red; }".to_string();
         let mut t = CssTokenizer::new(style);
         let expected = [
-            CssToken::HashToken("#id".to_string()),
+            CssToken::HashToken("id".to_string(), HashType::Id),
+            CssToken::Whitespace,
             CssToken::OpenCurly,
+            CssToken::Whitespace,
             CssToken::Ident("color".to_string()),
             CssToken::Colon,
+            CssToken::Whitespace,
             CssToken::Ident("red".to_string()),
             CssToken::SemiColon,
+            CssToken::Whitespace,
             CssToken::CloseCurly,
         ];
         for e in expected {
@@ -341,17 +670,21 @@ mod tests {
     #[test]
     fn test_class_selector() {
         // クラスセレクタ: .class { color: red; }
-        // 現状の簡易実装では先頭 '.' を Delim('.') として返し、続く "class" を Ident として返します。
+        // 先頭 '.' は（数字が続かないので）Delim('.')、続く "class" は Ident。
         let style = ".class { color: red; }".to_string();
         let mut t = CssTokenizer::new(style);
         let expected = [
             CssToken::Delim('.'),
             CssToken::Ident("class".to_string()),
+            CssToken::Whitespace,
             CssToken::OpenCurly,
+            CssToken::Whitespace,
             CssToken::Ident("color".to_string()),
             CssToken::Colon,
+            CssToken::Whitespace,
             CssToken::Ident("red".to_string()),
             CssToken::SemiColon,
+            CssToken::Whitespace,
             CssToken::CloseCurly,
         ];
         for e in expected {
@@ -360,32 +693,81 @@ mod tests {
         assert!(t.next().is_none());
     }
 
+    #[test]
+    fn test_dimension_and_percentage() {
+        let style = "10px 1.5em 50% 40".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Dimension(10.0, NumericType::Integer, "px".to_string()),
+            CssToken::Whitespace,
+            CssToken::Dimension(1.5, NumericType::Number, "em".to_string()),
+            CssToken::Whitespace,
+            CssToken::Percentage(50.0),
+            CssToken::Whitespace,
+            CssToken::Number(40.0, NumericType::Integer),
+        ];
+        assert_eq!(expected.to_vec(), t.collect::<Vec<CssToken>>());
+    }
+
+    #[test]
+    fn test_numbers() {
+        // 符号・先頭ドット・指数。`1em` の e は単位、`2e3px` の e3 は指数
+        let t = CssTokenizer::new("-20px +.5 1e3 -1.5E-2% 1em 2e3px a-1".to_string());
+        let expected = [
+            CssToken::Dimension(-20.0, NumericType::Integer, "px".to_string()),
+            CssToken::Whitespace,
+            CssToken::Number(0.5, NumericType::Number),
+            CssToken::Whitespace,
+            CssToken::Number(1000.0, NumericType::Number),
+            CssToken::Whitespace,
+            CssToken::Percentage(-0.015),
+            CssToken::Whitespace,
+            CssToken::Dimension(1.0, NumericType::Integer, "em".to_string()),
+            CssToken::Whitespace,
+            CssToken::Dimension(2000.0, NumericType::Number, "px".to_string()),
+            CssToken::Whitespace,
+            CssToken::Ident("a-1".to_string()),
+        ];
+        assert_eq!(expected.to_vec(), t.collect::<Vec<CssToken>>());
+    }
+
     #[test]
     fn test_multiple_rules() {
         // 複数ルールと混在する値の検証。
         // 入力: p { content: "Hey"; } h1 { font-size: 40; color: blue; }
         // - StringToken("Hey") は引用符付き文字列
-        // - Number(40.0) は数値トークンとして読まれる（単位は未対応）
+        // - Number(40.0, Integer) は単位の無い数値トークン
         let style = "p { content: \"Hey\"; } h1 { font-size: 40; color: blue; }".to_string();
         let mut t = CssTokenizer::new(style);
         let expected = [
             CssToken::Ident("p".to_string()),
+            CssToken::Whitespace,
             CssToken::OpenCurly,
+            CssToken::Whitespace,
             CssToken::Ident("content".to_string()),
             CssToken::Colon,
+            CssToken::Whitespace,
             CssToken::StringToken("Hey".to_string()),
             CssToken::SemiColon,
+            CssToken::Whitespace,
             CssToken::CloseCurly,
+            CssToken::Whitespace,
             CssToken::Ident("h1".to_string()),
+            CssToken::Whitespace,
             CssToken::OpenCurly,
+            CssToken::Whitespace,
             CssToken::Ident("font-size".to_string()),
             CssToken::Colon,
-            CssToken::Number(40.0),
+            CssToken::Whitespace,
+            CssToken::Number(40.0, NumericType::Integer),
             CssToken::SemiColon,
+            CssToken::Whitespace,
             CssToken::Ident("color".to_string()),
             CssToken::Colon,
+            CssToken::Whitespace,
             CssToken::Ident("blue".to_string()),
             CssToken::SemiColon,
+            CssToken::Whitespace,
             CssToken::CloseCurly,
         ];
         for e in expected {
@@ -393,4 +775,99 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
+
+    #[test]
+    fn test_combinators_and_attribute() {
+        // 結合子と属性セレクタに使う記号のトークン化
+        let style = "body > p[type^=\"a\"]".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("body".to_string()),
+            CssToken::Whitespace,
+            CssToken::Delim('>'),
+            CssToken::Whitespace,
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenSquareBracket,
+            CssToken::Ident("type".to_string()),
+            CssToken::Delim('^'),
+            CssToken::Delim('='),
+            CssToken::StringToken("a".to_string()),
+            CssToken::CloseSquareBracket,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    #[test]
+    fn test_url() {
+        // 引用符なしの url(...) は Url トークン、引用符つきは Function + 文字列 + `)`
+        let style = "@import url( css/a.css ); url(\"b.css\") url(a b)".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::AtKeyword("import".to_string()),
+            CssToken::Whitespace,
+            CssToken::Url("css/a.css".to_string()),
+            CssToken::SemiColon,
+            CssToken::Whitespace,
+            CssToken::Function("url".to_string()),
+            CssToken::StringToken("b.css".to_string()),
+            CssToken::CloseParenthesis,
+            CssToken::Whitespace,
+            CssToken::BadUrl,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    #[test]
+    fn test_comments_escapes_and_cdo_cdc() {
+        let style = "<!-- a/* x */b/**/ \\31 0\\.5 \"a\\\nb\\\"\" -->".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Cdo,
+            CssToken::Whitespace,
+            CssToken::Ident("a".to_string()),
+            CssToken::Ident("b".to_string()),
+            CssToken::Whitespace,
+            CssToken::Ident("10.5".to_string()),
+            CssToken::Whitespace,
+            CssToken::StringToken("ab\"".to_string()),
+            CssToken::Whitespace,
+            CssToken::Cdc,
+        ];
+        assert_eq!(expected.to_vec(), t.collect::<Vec<CssToken>>());
+    }
+
+    #[test]
+    fn test_position() {
+        // 行と列は 1 始まり。コメントの後ろのトークンはコメントの次の位置、`\r\n` は 1 つの改行
+        let mut t = CssTokenizer::new("p {\r\n  /* x */color: red;\n}".to_string());
+        let mut positions = Vec::new();
+        while let Some(token) = t.next() {
+            let position = t.position();
+            positions.push((token, position.line, position.column));
+        }
+        assert_eq!(positions[0], (CssToken::Ident("p".to_string()), 1, 1));
+        assert_eq!(positions[4], (CssToken::Ident("color".to_string()), 2, 10));
+        assert_eq!(positions[9], (CssToken::Whitespace, 2, 21));
+        assert_eq!(positions[10], (CssToken::CloseCurly, 3, 1));
+        assert_eq!((t.position().line, t.position().column), (3, 2));
+    }
+
+    #[test]
+    fn test_bad_string_and_preprocessing() {
+        // 文字列の途中の改行は BadString。改行（\r\n）は 1 つの \n にそろえてから読む
+        let t = CssTokenizer::new("\"abc\r\ndef\" \0".to_string());
+        let expected = [
+            CssToken::BadString,
+            CssToken::Whitespace,
+            CssToken::Ident("def".to_string()),
+            CssToken::StringToken(" \u{FFFD}".to_string()),
+        ];
+        assert_eq!(expected.to_vec(), t.collect::<Vec<CssToken>>());
+    }
 }
@@ -0,0 +1,69 @@
+//! ユーザーエージェント（UA）スタイルシート（初心者向け）
+//!
+//! 役割
+//! - 作者が何も書かなくても `<h1>` が大きく、`<a>` に下線が付き、`<p>` が縦に積まれるのは、
+//!   ブラウザが「組み込みの CSS」を持っているからです。ここにその CSS を文字列で置きます。
+//! - 解釈は普通のスタイルシートと同じ `CssParser` で行い、出どころを `CascadeOrigin::UserAgent` にします。
+//!   そのためページ作者の CSS（Author）で自由に上書きできます（`!important` を除く）。
+//!
+//! 要素の既定の見た目を変えたい/増やしたいときは、Rust ではなく下の CSS を編集してください。
+//! Rust 側（プロパティレジストリ `layout::property`）が持つのは CSS の「初期値」（display: inline など）だけです。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `const USER_AGENT_CSS: &str` はバイナリに埋め込まれる文字列定数（TS の `const css = "..."`）。
+//!
+//! 参考: https://html.spec.whatwg.org/multipage/rendering.html#rendering
+
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::token::CssTokenizer;
+use alloc::string::ToString;
+
+/// 組み込みの CSS（HTML 仕様の Rendering 節の、このブラウザが扱う要素だけを抜き出した簡易版）
+const USER_AGENT_CSS: &str = "
+html, body, p, h1, h2 { display: block; }
+head, link, style, script { display: none; }
+
+h1 { font-size: 2em; font-weight: bold; }
+h2 { font-size: 1.5em; font-weight: bold; }
+
+a { text-decoration: underline; }
+";
+
+/// UA スタイルシートを解釈して返す（出どころは `CascadeOrigin::UserAgent`）
+///
+/// 文字列の解釈にはコストがかかるので、呼び出し側（`Page` など）で 1 回だけ作って使い回してください。
+pub fn user_agent_stylesheet() -> StyleSheet {
+    let mut sheet =
+        CssParser::new(CssTokenizer::new(USER_AGENT_CSS.to_string())).parse_stylesheet();
+    sheet.set_origin(CascadeOrigin::UserAgent);
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::ComponentValue;
+    use crate::renderer::css::cssom::Selector;
+    use crate::renderer::css::token::NumericType;
+    use alloc::string::String;
+
+    #[test]
+    fn test_user_agent_stylesheet() {
+        let sheet = user_agent_stylesheet();
+        assert_eq!(sheet.origin, CascadeOrigin::UserAgent);
+
+        // h1 の既定フォントサイズが CSS として入っている
+        let h1 = sheet
+            .rules
+            .iter()
+            .find(|r| r.selector == Selector::TypeSelector(String::from("h1")))
+            .expect("h1 rule should exist");
+        assert_eq!(h1.declarations[0].property, "font-size");
+        assert_eq!(
+            h1.declarations[0].value,
+            ComponentValue::Dimension(2.0, NumericType::Integer, String::from("em"))
+        );
+    }
+}
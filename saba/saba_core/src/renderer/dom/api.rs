@@ -9,6 +9,10 @@
 //! - 返り値 `Option<Rc<RefCell<Node>>>` は、“見つかったら Some(ノード)、なければ None”。
 //! - `Rc<RefCell<Node>>` は「共有 + 内部可変」なノード参照です（TS/Python/Go の参照共有に近い）。
 //!
+//! ツリーの変更（`append_child` / `remove_child` / `set_attribute`）もここに置きます。
+//! これらは Document が持つ id 索引（`dom::id_index`）を更新するので、DOM を書き換えるときは
+//! `Node` のリンクを直接いじらず、これらの関数を通してください。
+//!
 //! 例（概念）
 //! - ツリー: Document → html → head, body → body 配下に p, h1…
 //! - 呼び出し: `get_target_element_node(Some(document), ElementKind::Body)`
@@ -18,13 +22,15 @@ use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
+use alloc::format;
 use alloc::rc::Rc;
+use alloc::rc::Weak;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
-/// id 属性が一致する最初の要素ノードを返します（深さ優先探索）。
+/// id 属性が一致する最初の要素ノードを返します。
 ///
 /// 引数
 /// - `node`: 探索の起点となるノード（`Some(root)` を渡す想定）。
@@ -34,11 +40,25 @@ use core::cell::RefCell;
 /// - 見つかれば `Some(Rc<RefCell<Node>>)`、無ければ `None`。
 ///
 /// アルゴリズム概略
-/// - 現在ノードが要素なら属性を走査して `id == id_name` を判定。
-/// - 見つからなければ「子 → 兄弟」の順で再帰（DFS）。
+/// - 起点が Document なら、Document が持つ id 索引を 1 回引くだけで済ませます（ツリー全体を走査しない）。
+/// - それ以外（切り離された部分木など）は、従来どおり深さ優先探索にフォールバックします。
 pub fn get_element_by_id(
     node: Option<Rc<RefCell<Node>>>,
     id_name: &String,
+) -> Option<Rc<RefCell<Node>>> {
+    if let Some(n) = &node {
+        let index = n.borrow().id_index();
+        if let Some(index) = index {
+            return index.borrow().get(id_name);
+        }
+    }
+    get_element_by_id_by_scan(node, id_name)
+}
+
+// 索引を使わずに DFS で id を探す（「自分 → 子 → 兄弟」の順）
+fn get_element_by_id_by_scan(
+    node: Option<Rc<RefCell<Node>>>,
+    id_name: &String,
 ) -> Option<Rc<RefCell<Node>>> {
     match node {
         Some(n) => {
@@ -51,8 +71,8 @@ pub fn get_element_by_id(
                 }
             }
             // 2) 見つからなければ、子 → 兄弟の順で検索を進める
-            let result1 = get_element_by_id(n.borrow().first_child(), id_name);
-            let result2 = get_element_by_id(n.borrow().next_sibling(), id_name);
+            let result1 = get_element_by_id_by_scan(n.borrow().first_child(), id_name);
+            let result2 = get_element_by_id_by_scan(n.borrow().next_sibling(), id_name);
             // 子側で見つからなかったら兄弟側の結果を返す
             if result1.is_none() {
                 return result2;
@@ -64,6 +84,230 @@ pub fn get_element_by_id(
     }
 }
 
+/// `node` が属する Document ノードを返す（親を根まで辿る）
+///
+/// - 根が Document でない（ツリーに繋がっていない）ときは `None`。
+pub fn owner_document(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.clone();
+    loop {
+        let parent = current.borrow().parent().upgrade();
+        match parent {
+            Some(p) => current = p,
+            None => break,
+        }
+    }
+    let is_document = current.borrow().kind() == NodeKind::Document;
+    if is_document {
+        Some(current)
+    } else {
+        None
+    }
+}
+
+// `node` 以下の部分木にある「id を持つ要素」を (id, ノード) の組で集める
+fn collect_ids(node: &Rc<RefCell<Node>>, result: &mut Vec<(String, Rc<RefCell<Node>>)>) {
+    if let Some(e) = node.borrow().get_element() {
+        if let Some(id) = e.get_attribute("id") {
+            result.push((id, node.clone()));
+        }
+    }
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        collect_ids(&c, result);
+        child = c.borrow().next_sibling();
+    }
+}
+
+// 部分木の id を Document の索引へ登録 / 索引から削除する
+fn update_id_index(node: &Rc<RefCell<Node>>, register: bool) {
+    let index = match owner_document(node) {
+        Some(document) => document.borrow().id_index(),
+        None => None,
+    };
+    let index = match index {
+        Some(index) => index,
+        None => return,
+    };
+
+    let mut ids = Vec::new();
+    collect_ids(node, &mut ids);
+    for (id, n) in ids {
+        if register {
+            index.borrow_mut().insert(&id, &n);
+        } else {
+            index.borrow_mut().remove(&id, &n);
+        }
+    }
+}
+
+/// `child` を `parent` の最後の子として追加する
+/// 仕様: https://dom.spec.whatwg.org/#concept-node-append
+///
+/// - 兄弟/親子のリンク（first_child / last_child / previous_sibling / next_sibling / parent）を張ります。
+/// - 追加した部分木の中で `id` を持つ要素は、Document の id 索引に登録されます。
+pub fn append_child(parent: &Rc<RefCell<Node>>, child: Rc<RefCell<Node>>) {
+    let last_child = parent.borrow().last_child().upgrade();
+    match last_child {
+        Some(last) => {
+            last.borrow_mut().set_next_sibling(Some(child.clone()));
+            child
+                .borrow_mut()
+                .set_previous_sibling(Rc::downgrade(&last));
+        }
+        None => {
+            parent.borrow_mut().set_first_child(Some(child.clone()));
+        }
+    }
+    parent.borrow_mut().set_last_child(Rc::downgrade(&child));
+    child.borrow_mut().set_parent(Rc::downgrade(parent));
+
+    update_id_index(&child, true);
+}
+
+/// `child` を `parent` の子から取り外す
+/// 仕様: https://dom.spec.whatwg.org/#concept-node-remove
+///
+/// - 取り外す前に、部分木の `id` を Document の id 索引から外します。
+/// - `child` が `parent` の子でなければ何もしません。
+pub fn remove_child(parent: &Rc<RefCell<Node>>, child: &Rc<RefCell<Node>>) {
+    // 兄（直前の兄弟）は、先頭から辿って確実に求める
+    let mut previous: Option<Rc<RefCell<Node>>> = None;
+    let mut current = parent.borrow().first_child();
+    loop {
+        match current {
+            Some(c) if Rc::ptr_eq(&c, child) => break,
+            Some(c) => {
+                current = c.borrow().next_sibling();
+                previous = Some(c);
+            }
+            None => return,
+        }
+    }
+
+    update_id_index(child, false);
+
+    let next = child.borrow().next_sibling();
+    match &previous {
+        Some(p) => p.borrow_mut().set_next_sibling(next.clone()),
+        None => parent.borrow_mut().set_first_child(next.clone()),
+    }
+    match &next {
+        Some(n) => n.borrow_mut().set_previous_sibling(match &previous {
+            Some(p) => Rc::downgrade(p),
+            None => Weak::new(),
+        }),
+        None => parent.borrow_mut().set_last_child(match &previous {
+            Some(p) => Rc::downgrade(p),
+            None => Weak::new(),
+        }),
+    }
+
+    let mut c = child.borrow_mut();
+    c.set_parent(Weak::new());
+    c.set_previous_sibling(Weak::new());
+    c.set_next_sibling(None);
+}
+
+/// 要素ノード `node` の属性 `name` に `value` を設定する
+///
+/// - `id` 属性が変わる場合は、古い id を索引から外し、新しい id で登録し直します。
+/// - 要素でないノードに対しては何もしません。
+pub fn set_attribute(node: &Rc<RefCell<Node>>, name: &str, value: &str) {
+    let is_id = name == "id";
+    if is_id {
+        update_id_index_of_element(node, false);
+    }
+
+    if let NodeKind::Element(ref mut e) = node.borrow_mut().kind {
+        e.set_attribute(name, value);
+    }
+
+    if is_id {
+        update_id_index_of_element(node, true);
+    }
+}
+
+/// 要素の `style` 属性（インラインスタイル）から、プロパティ `name` の値を読む
+///
+/// - 例: `style="color: red; display: none"` で `name="color"` → `Some("red")`
+/// - 同じプロパティが複数あれば後ろのものを返します（CSS の後勝ちに合わせる）。
+/// - JS の `element.style.color` の読み取りに使います。
+///   https://drafts.csswg.org/cssom/#dom-cssstyledeclaration-getpropertyvalue
+pub fn get_style_property(node: &Rc<RefCell<Node>>, name: &str) -> Option<String> {
+    let style = node.borrow().get_element()?.get_attribute("style")?;
+    split_inline_style(&style)
+        .into_iter()
+        .rev()
+        .find(|(property, _)| property == name)
+        .map(|(_, value)| value)
+}
+
+/// 要素の `style` 属性のプロパティ `name` を `value` に書き換える（無ければ末尾に足す）
+///
+/// - `value` が空文字列ならそのプロパティを取り除きます（`el.style.color = ""` と同じ）。
+/// - 書き換えた結果は `color: red; display: none;` の形で `style` 属性に書き戻します。
+/// - JS の `element.style.color = "red"` に使います。
+///   https://drafts.csswg.org/cssom/#dom-cssstyledeclaration-setproperty
+pub fn set_style_property(node: &Rc<RefCell<Node>>, name: &str, value: &str) {
+    let style = match node.borrow().get_element() {
+        Some(e) => e.get_attribute("style").unwrap_or_default(),
+        None => return,
+    };
+
+    let mut declarations = split_inline_style(&style);
+    let value = value.trim();
+    declarations.retain(|(property, _)| property != name);
+    if !value.is_empty() {
+        declarations.push((name.to_string(), value.to_string()));
+    }
+
+    let text = declarations
+        .iter()
+        .map(|(property, value)| format!("{}: {};", property, value))
+        .collect::<Vec<String>>()
+        .join(" ");
+    set_attribute(node, "style", &text);
+}
+
+// `color: red; display: none` → [("color", "red"), ("display", "none")]
+// 値の中身は解釈せず文字列のまま扱う（解釈はカスケード時に CssParser が行う）
+fn split_inline_style(style: &str) -> Vec<(String, String)> {
+    style
+        .split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .map(|(property, value)| {
+            (
+                property.trim().to_ascii_lowercase(),
+                value.trim().to_string(),
+            )
+        })
+        .filter(|(property, _)| !property.is_empty())
+        .collect()
+}
+
+// 要素自身（子孫は含まない）の id を索引へ登録 / 索引から削除する
+fn update_id_index_of_element(node: &Rc<RefCell<Node>>, register: bool) {
+    let id = match node.borrow().get_element() {
+        Some(e) => e.get_attribute("id"),
+        None => None,
+    };
+    let id = match id {
+        Some(id) => id,
+        None => return,
+    };
+    let index = match owner_document(node) {
+        Some(document) => document.borrow().id_index(),
+        None => None,
+    };
+    if let Some(index) = index {
+        if register {
+            index.borrow_mut().insert(&id, node);
+        } else {
+            index.borrow_mut().remove(&id, node);
+        }
+    }
+}
+
 /// ツリーを深さ優先で探索し、最初に見つかった `element_kind` の要素ノードを返す
 ///
 /// - 探索順: 「自分 → 子（first_child）→ 兄弟（next_sibling）」の順で DFS。
@@ -128,6 +372,67 @@ pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
     content
 }
 
+/// 文書のスタイルシートの出どころ（`get_style_sources` の戻り値）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleSource {
+    /// `<style>` の中身（CSS 文字列）
+    Inline(String),
+    /// `<link rel="stylesheet" href="...">` の `href`（書かれたままの相対 URL）
+    External(String),
+}
+
+/// 文書内の `<style>` と `<link rel="stylesheet">` を、文書順（DFS 順）にすべて集める
+///
+/// - 後に出てきたシートほどカスケードで強いので、順序が意味を持ちます。
+/// - `rel` は空白区切りのトークン列で、大文字小文字は区別しません（`rel="Alternate stylesheet"` も拾う簡易版）。
+/// - `href` が無い/空の `<link>` は読み込めないので含めません。
+///
+/// 例
+/// - `<head><link rel=stylesheet href=a.css><style>p{}</style></head>`
+///   → `[External("a.css"), Inline("p{}")]`
+///
+/// 仕様: https://html.spec.whatwg.org/multipage/links.html#link-type-stylesheet
+pub fn get_style_sources(root: Rc<RefCell<Node>>) -> Vec<StyleSource> {
+    let mut sources = Vec::new();
+    collect_style_sources(Some(root), &mut sources);
+    sources
+}
+
+fn collect_style_sources(node: Option<Rc<RefCell<Node>>>, sources: &mut Vec<StyleSource>) {
+    let node = match node {
+        Some(n) => n,
+        None => return,
+    };
+
+    if let Some(element) = node.borrow().get_element() {
+        match element.kind() {
+            ElementKind::Style => {
+                if let Some(child) = node.borrow().first_child() {
+                    if let NodeKind::Text(ref s) = child.borrow().kind() {
+                        sources.push(StyleSource::Inline(s.clone()));
+                    }
+                }
+            }
+            ElementKind::Link => {
+                let is_stylesheet = element.get_attribute("rel").is_some_and(|rel| {
+                    rel.split_whitespace()
+                        .any(|t| t.eq_ignore_ascii_case("stylesheet"))
+                });
+                match element.get_attribute("href") {
+                    Some(href) if is_stylesheet && !href.is_empty() => {
+                        sources.push(StyleSource::External(href));
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    collect_style_sources(node.borrow().first_child(), sources);
+    collect_style_sources(node.borrow().next_sibling(), sources);
+}
+
 /// DOM から `<script>` タグの“テキスト中身”だけを取り出すヘルパー
 ///
 /// 仕様（このプロジェクト内での前提）
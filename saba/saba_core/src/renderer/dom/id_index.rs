@@ -0,0 +1,214 @@
+//! id 索引（`id` 属性 → 要素ノード）の最小実装
+//!
+//! 目的
+//! - `document.getElementById("x")` のたびに DOM ツリー全体を走査すると、
+//!   ループ内で何度も要素を探すスクリプトが大きなページで遅くなります。
+//! - そこで Document ノードに「id → 要素」の索引を持たせ、検索を索引引き 1 回で済ませます。
+//!
+//! ルール（DOM 仕様に合わせた点）
+//! - 同じ id を持つ要素が複数あるときは「ツリー順（文書順）で最初の要素」を返します。
+//!   そのため 1 つの id に対して要素のリストを“ツリー順に並べて”保持します。
+//! - 索引の更新は `dom::api` の `append_child` / `remove_child` / `set_attribute` が行います。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `BTreeMap<String, Vec<...>>` は TS の `Map<string, Node[]>`、Python の `dict[str, list]` に相当。
+//! - 要素は `Weak` で持ちます。索引がノードの寿命を延ばさない（取り外された要素を生かし続けない）ためです。
+//!
+//! 例（概念）
+//! ```text
+//! <p id="a"></p> <h1 id="a"></h1> <a id="b"></a>
+//! → { "a": [p, h1], "b": [a] }   get("a") == p（ツリー順で最初）
+//! ```
+
+use crate::renderer::dom::node::Node;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::rc::Weak;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+
+#[derive(Debug, Clone, Default)]
+pub struct IdIndex {
+    // id ごとの要素リスト（ツリー順に並べる）
+    map: BTreeMap<String, Vec<Weak<RefCell<Node>>>>,
+}
+
+impl IdIndex {
+    pub fn new() -> Self {
+        Self {
+            map: BTreeMap::new(),
+        }
+    }
+
+    /// `id` に対応する、ツリー順で最初の要素を返す（無ければ `None`）
+    pub fn get(&self, id: &str) -> Option<Rc<RefCell<Node>>> {
+        match self.map.get(id) {
+            Some(nodes) => nodes.iter().find_map(|n| n.upgrade()),
+            None => None,
+        }
+    }
+
+    /// `node` を `id` のリストへ登録する（ツリー順を保つ位置に挿入）
+    ///
+    /// - 追記だけでなく、既存要素より“前”に挿入された要素にも対応するため、
+    ///   リストを先頭から見て「自分より後ろにある最初の要素」の手前に入れます。
+    /// - 既に解放された要素（`upgrade()` できない Weak）はついでに取り除きます。
+    pub fn insert(&mut self, id: &str, node: &Rc<RefCell<Node>>) {
+        let nodes = self.map.entry(id.to_string()).or_default();
+        nodes.retain(|n| n.upgrade().is_some());
+
+        let mut index = nodes.len();
+        for (i, n) in nodes.iter().enumerate() {
+            if let Some(n) = n.upgrade() {
+                if Rc::ptr_eq(&n, node) {
+                    // 二重登録はしない
+                    return;
+                }
+                if compare_tree_order(node, &n) == Ordering::Less {
+                    index = i;
+                    break;
+                }
+            }
+        }
+        nodes.insert(index, Rc::downgrade(node));
+    }
+
+    /// `node` を `id` のリストから外す
+    pub fn remove(&mut self, id: &str, node: &Rc<RefCell<Node>>) {
+        if let Some(nodes) = self.map.get_mut(id) {
+            nodes.retain(|n| match n.upgrade() {
+                Some(n) => !Rc::ptr_eq(&n, node),
+                None => false,
+            });
+            if nodes.is_empty() {
+                self.map.remove(id);
+            }
+        }
+    }
+}
+
+// ルートから `node` までの祖先の列（先頭がルート、末尾が node 自身）
+fn ancestors_path(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut path = Vec::new();
+    let mut current = Some(node.clone());
+    while let Some(n) = current {
+        current = n.borrow().parent().upgrade();
+        path.push(n);
+    }
+    path.reverse();
+    path
+}
+
+/// 2 つのノードのツリー順（文書順）を比べる
+/// 仕様: https://dom.spec.whatwg.org/#concept-tree-order
+///
+/// - 祖先は子孫より前。
+/// - それ以外は、枝分かれした位置の兄弟同士を `next_sibling` で辿って前後を決めます。
+pub fn compare_tree_order(a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> Ordering {
+    if Rc::ptr_eq(a, b) {
+        return Ordering::Equal;
+    }
+
+    let path_a = ancestors_path(a);
+    let path_b = ancestors_path(b);
+
+    let mut i = 0;
+    while i < path_a.len() && i < path_b.len() && Rc::ptr_eq(&path_a[i], &path_b[i]) {
+        i += 1;
+    }
+
+    // 片方がもう片方の祖先
+    if i == path_a.len() {
+        return Ordering::Less;
+    }
+    if i == path_b.len() {
+        return Ordering::Greater;
+    }
+
+    // 枝分かれ位置の兄弟を比べる: a 側から弟方向へ辿って b 側に出会えば a が前
+    let mut sibling = path_a[i].borrow().next_sibling();
+    while let Some(s) = sibling {
+        if Rc::ptr_eq(&s, &path_b[i]) {
+            return Ordering::Less;
+        }
+        sibling = s.borrow().next_sibling();
+    }
+    Ordering::Greater
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::append_child;
+    use crate::renderer::dom::api::get_element_by_id;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::api::remove_child;
+    use crate::renderer::dom::api::set_attribute;
+    use crate::renderer::dom::node::Element;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::dom::node::NodeKind;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn create_document(html: &str) -> Rc<RefCell<Node>> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    fn element_kind_of(node: &Option<Rc<RefCell<Node>>>) -> Option<ElementKind> {
+        node.as_ref().and_then(|n| n.borrow().element_kind())
+    }
+
+    #[test]
+    fn test_duplicated_ids() {
+        // 同じ id が 2 つ → ツリー順で最初の <p> が返る
+        let document =
+            create_document(r#"<html><body><p id="x">a</p><h1 id="x">b</h1></body></html>"#);
+        let found = get_element_by_id(Some(document), &"x".to_string());
+        assert_eq!(Some(ElementKind::P), element_kind_of(&found));
+    }
+
+    #[test]
+    fn test_insert_before_existing_id_in_tree_order() {
+        // 後から <head> に追加した要素は、ツリー順では <body> 内の要素より前になる
+        let document = create_document(r#"<html><head></head><body><p id="x"></p></body></html>"#);
+        let head = get_target_element_node(Some(document.clone()), ElementKind::Head)
+            .expect("head should exist");
+        let h1 = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            "h1",
+            Vec::new(),
+        )))));
+        set_attribute(&h1, "id", "x");
+        append_child(&head, h1);
+
+        let found = get_element_by_id(Some(document), &"x".to_string());
+        assert_eq!(Some(ElementKind::H1), element_kind_of(&found));
+    }
+
+    #[test]
+    fn test_remove_child_and_change_id() {
+        let document =
+            create_document(r#"<html><body><p id="x"></p><h1 id="y"></h1></body></html>"#);
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+
+        // 取り外した要素は見つからなくなる
+        let p =
+            get_element_by_id(Some(document.clone()), &"x".to_string()).expect("p should exist");
+        remove_child(&body, &p);
+        assert!(get_element_by_id(Some(document.clone()), &"x".to_string()).is_none());
+
+        // id 属性を書き換えると、古い id では見つからず新しい id で見つかる
+        let h1 =
+            get_element_by_id(Some(document.clone()), &"y".to_string()).expect("h1 should exist");
+        set_attribute(&h1, "id", "z");
+        assert!(get_element_by_id(Some(document.clone()), &"y".to_string()).is_none());
+        let found = get_element_by_id(Some(document), &"z".to_string());
+        assert_eq!(Some(ElementKind::H1), element_kind_of(&found));
+    }
+}
@@ -1,2 +1,4 @@
 pub mod api;
+pub mod id_index;
 pub mod node;
+pub mod range;
@@ -33,6 +33,7 @@
 //! - レイアウト/描画: この DOM ツリーをもとにフレームツリー/レイアウトツリーを作成し描画（ここでは未実装）。
 //! - イベント/スクリプト: Window/Document を起点にイベント配信や JS 実行（ここでは最小限）。
 
+use crate::renderer::dom::id_index::IdIndex;
 use crate::renderer::html::attribute::Attribute;
 use alloc::format;
 use alloc::rc::Rc;
@@ -80,6 +81,8 @@ pub struct Node {
     last_child: Weak<RefCell<Node>>,
     previous_sibling: Weak<RefCell<Node>>,
     next_sibling: Option<Rc<RefCell<Node>>>,
+    // Document ノードだけが持つ id 索引（それ以外は None）
+    id_index: Option<Rc<RefCell<IdIndex>>>,
 }
 
 impl PartialEq for Node {
@@ -90,7 +93,12 @@ impl PartialEq for Node {
 
 impl Node {
     // ノードを新規に作成。リンク（親/兄弟/子）は空で、種別だけを持ちます。
+    // Document ノードのときは空の id 索引も用意します。
     pub fn new(kind: NodeKind) -> Self {
+        let id_index = match kind {
+            NodeKind::Document => Some(Rc::new(RefCell::new(IdIndex::new()))),
+            _ => None,
+        };
         Self {
             kind,
             window: Weak::new(),
@@ -99,6 +107,7 @@ impl Node {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            id_index,
         }
     }
 
@@ -152,6 +161,11 @@ impl Node {
         self.next_sibling.as_ref().cloned()
     }
 
+    // Document ノードなら id 索引を返す（`getElementById` の高速化に使う）。
+    pub fn id_index(&self) -> Option<Rc<RefCell<IdIndex>>> {
+        self.id_index.clone()
+    }
+
     // ノード種別（Document / Element / Text）を取得。
     pub fn kind(&self) -> NodeKind {
         self.kind.clone()
@@ -247,12 +261,24 @@ impl Element {
         None
     }
 
-    // 要素がデフォルトでブロック要素かインライン要素か決める
-    pub fn is_block_element(&self) -> bool {
-        match self.kind {
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P => true,
-            _ => false,
+    /// 属性 `name` に `value` を設定する（無ければ末尾に追加、あれば値を置き換え）
+    ///
+    /// 注意
+    /// - `id` を書き換える場合は、Document の id 索引も更新する必要があります。
+    ///   ノード単位で扱う `dom::api::set_attribute` を使ってください。
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        for attr in &mut self.attributes {
+            if attr.name() == name {
+                attr.set_value(value);
+                return;
+            }
+        }
+        let mut attr = Attribute::new();
+        for c in name.chars() {
+            attr.add_char(c, true);
         }
+        attr.set_value(value);
+        self.attributes.push(attr);
     }
 }
 
@@ -263,6 +289,8 @@ pub enum ElementKind {
     Html,
     /// https://html.spec.whatwg.org/multipage/semantics.html#the-head-element
     Head,
+    /// https://html.spec.whatwg.org/multipage/semantics.html#the-link-element
+    Link,
     /// https://html.spec.whatwg.org/multipage/semantics.html#the-style-element
     Style,
     /// https://html.spec.whatwg.org/multipage/scripting.html#the-script-element
@@ -292,6 +320,7 @@ impl Display for ElementKind {
         let s = match self {
             ElementKind::Html => "html",
             ElementKind::Head => "head",
+            ElementKind::Link => "link",
             ElementKind::Style => "style",
             ElementKind::Script => "script",
             ElementKind::Body => "body",
@@ -313,6 +342,7 @@ impl FromStr for ElementKind {
         match s {
             "html" => Ok(ElementKind::Html),
             "head" => Ok(ElementKind::Head),
+            "link" => Ok(ElementKind::Link),
             "style" => Ok(ElementKind::Style),
             "script" => Ok(ElementKind::Script),
             "body" => Ok(ElementKind::Body),
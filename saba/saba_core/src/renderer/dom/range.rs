@@ -0,0 +1,406 @@
+//! Range / Selection（文書中の「範囲」と「選択」）の最小実装
+//!
+//! 役割（実ブラウザでの位置づけ）
+//! - マウスでテキストをドラッグすると、ブラウザは「どのノードの何文字目から、どのノードの何文字目まで」
+//!   という範囲（Range）を作り、それを選択（Selection）として保持します。
+//! - コピー時は Range に含まれるテキストを連結して文字列にします（`Range::to_string`）。
+//!
+//! 用語（DOM 仕様）
+//! - 境界点（boundary point）: `(ノード, オフセット)` の組。
+//!   - Text ノードのオフセットは「何文字目の前か」（0 なら先頭の前、len なら末尾の後ろ）。
+//!   - 要素ノードのオフセットは「何番目の子の前か」。
+//! - Range: 開始と終了の 2 つの境界点。開始 ≦ 終了（ツリー順）を常に保ちます。
+//! - Selection: ユーザーが操作する「起点（anchor）」と「現在位置（focus）」。
+//!   後ろから前へドラッグすると focus が anchor より前になるため、Range に直すときに並べ替えます。
+//!
+//! 仕様
+//! - Range: https://dom.spec.whatwg.org/#ranges
+//! - 境界点の比較: https://dom.spec.whatwg.org/#concept-range-bp-position
+//! - Selection: https://w3c.github.io/selection-api/
+//!
+//! 簡易化
+//! - 仕様のオフセットは UTF-16 のコード単位ですが、ここでは `char` 単位で数えます。
+//! - 1 つの Selection が持つ Range は 1 つだけです（実ブラウザもほぼ同じ）。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `BoundaryPoint` は TS の `{ node: Node; offset: number }` に相当。
+//! - `Selection::range()` は `window.getSelection().getRangeAt(0)` のイメージです。
+//!
+//! 例（概念）
+//! ```text
+//! <p>hello</p><p>world</p> で "hello" の 3 文字目から "world" の 3 文字目までを選択
+//! → start = (Text("hello"), 2), end = (Text("world"), 3)
+//! → to_string() == "llowor"（"llo" + "wor"）
+//! ```
+
+use crate::renderer::dom::id_index::compare_tree_order;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+/// 境界点 `(node, offset)`
+#[derive(Debug, Clone)]
+pub struct BoundaryPoint {
+    node: Rc<RefCell<Node>>,
+    offset: usize,
+}
+
+impl PartialEq for BoundaryPoint {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.node, &other.node) && self.offset == other.offset
+    }
+}
+
+impl BoundaryPoint {
+    pub fn new(node: Rc<RefCell<Node>>, offset: usize) -> Self {
+        Self { node, offset }
+    }
+
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// 2 つの境界点の前後を比べる（仕様の "position of a boundary point"）
+    ///
+    /// - 同じノードならオフセットの大小。
+    /// - 片方のノードがもう片方の祖先なら、「祖先側のオフセット」と「子孫を含む子の位置」を比べる。
+    /// - それ以外はノードのツリー順で決まる。
+    pub fn compare(&self, other: &BoundaryPoint) -> Ordering {
+        if Rc::ptr_eq(&self.node, &other.node) {
+            return self.offset.cmp(&other.offset);
+        }
+
+        if compare_tree_order(&self.node, &other.node) == Ordering::Greater {
+            return other.compare(self).reverse();
+        }
+
+        // self.node が other.node の祖先なら、other.node を含む self.node の子を探す
+        let mut child = other.node.clone();
+        loop {
+            let parent = child.borrow().parent().upgrade();
+            match parent {
+                Some(p) => {
+                    if Rc::ptr_eq(&p, &self.node) {
+                        if index_of(&child) < self.offset {
+                            return Ordering::Greater;
+                        }
+                        break;
+                    }
+                    child = p;
+                }
+                None => break,
+            }
+        }
+
+        Ordering::Less
+    }
+}
+
+// 親から見て何番目の子か
+fn index_of(node: &Rc<RefCell<Node>>) -> usize {
+    let mut index = 0;
+    let mut previous = node.borrow().previous_sibling().upgrade();
+    while let Some(p) = previous {
+        index += 1;
+        previous = p.borrow().previous_sibling().upgrade();
+    }
+    index
+}
+
+/// 開始と終了の境界点からなる範囲
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    start: BoundaryPoint,
+    end: BoundaryPoint,
+}
+
+impl Range {
+    /// `(node, offset)` の位置に潰れた（collapsed）範囲を作る
+    pub fn new(node: Rc<RefCell<Node>>, offset: usize) -> Self {
+        let point = BoundaryPoint::new(node, offset);
+        Self {
+            start: point.clone(),
+            end: point,
+        }
+    }
+
+    pub fn start(&self) -> BoundaryPoint {
+        self.start.clone()
+    }
+
+    pub fn end(&self) -> BoundaryPoint {
+        self.end.clone()
+    }
+
+    /// 開始を設定する。終了より後ろになった場合は終了も同じ位置へ（仕様の setStart）
+    pub fn set_start(&mut self, node: Rc<RefCell<Node>>, offset: usize) {
+        self.start = BoundaryPoint::new(node, offset);
+        if self.start.compare(&self.end) == Ordering::Greater {
+            self.end = self.start.clone();
+        }
+    }
+
+    /// 終了を設定する。開始より前になった場合は開始も同じ位置へ（仕様の setEnd）
+    pub fn set_end(&mut self, node: Rc<RefCell<Node>>, offset: usize) {
+        self.end = BoundaryPoint::new(node, offset);
+        if self.end.compare(&self.start) == Ordering::Less {
+            self.start = self.end.clone();
+        }
+    }
+
+    /// 開始と終了が同じ位置か（何も選んでいない状態）
+    pub fn collapsed(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Text ノード `node` のうち、この範囲に含まれる部分を `(開始, 終了)` の文字オフセットで返す
+    ///
+    /// - 範囲と重ならなければ `None`。
+    /// - 選択ハイライトの描画や、文字列化で使います。
+    pub fn text_offsets(&self, node: &Rc<RefCell<Node>>) -> Option<(usize, usize)> {
+        let length = match node.borrow().kind() {
+            NodeKind::Text(t) => t.chars().count(),
+            _ => return None,
+        };
+
+        let start = if Rc::ptr_eq(&self.start.node, node) {
+            self.start.offset
+        } else if BoundaryPoint::new(node.clone(), 0).compare(&self.start) != Ordering::Less {
+            0
+        } else {
+            length
+        };
+        let end = if Rc::ptr_eq(&self.end.node, node) {
+            self.end.offset
+        } else if BoundaryPoint::new(node.clone(), length).compare(&self.end) != Ordering::Greater {
+            length
+        } else {
+            0
+        };
+
+        if start < end {
+            Some((start, end.min(length)))
+        } else {
+            None
+        }
+    }
+
+    // ツリー順に Text ノードを辿り、範囲内の部分を `out` に追加する
+    fn collect_text(&self, node: &Option<Rc<RefCell<Node>>>, out: &mut String) {
+        let mut current = node.clone();
+        while let Some(n) = current {
+            if let NodeKind::Text(t) = n.borrow().kind() {
+                if let Some((start, end)) = self.text_offsets(&n) {
+                    out.extend(t.chars().skip(start).take(end - start));
+                }
+            }
+            self.collect_text(&n.borrow().first_child(), out);
+            current = n.borrow().next_sibling();
+        }
+    }
+}
+
+/// 範囲に含まれるテキストを連結する（仕様の stringifier / `range.toString()`）
+impl Display for Range {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let mut root = self.start.node.clone();
+        loop {
+            let parent = root.borrow().parent().upgrade();
+            match parent {
+                Some(p) => root = p,
+                None => break,
+            }
+        }
+
+        let mut s = String::new();
+        self.collect_text(&Some(root), &mut s);
+        write!(f, "{}", s)
+    }
+}
+
+/// ユーザーの選択（起点 anchor と現在位置 focus）
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Selection {
+    anchor: Option<BoundaryPoint>,
+    focus: Option<BoundaryPoint>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self {
+            anchor: None,
+            focus: None,
+        }
+    }
+
+    pub fn anchor(&self) -> Option<BoundaryPoint> {
+        self.anchor.clone()
+    }
+
+    pub fn focus(&self) -> Option<BoundaryPoint> {
+        self.focus.clone()
+    }
+
+    /// 選択を `(node, offset)` に潰す（マウスを押した位置から選択を始める）
+    pub fn collapse(&mut self, node: Rc<RefCell<Node>>, offset: usize) {
+        let point = BoundaryPoint::new(node, offset);
+        self.anchor = Some(point.clone());
+        self.focus = Some(point);
+    }
+
+    /// anchor はそのままに、focus を `(node, offset)` へ動かす（ドラッグ中の更新）
+    ///
+    /// 選択が無いときは何もしません。
+    pub fn extend(&mut self, node: Rc<RefCell<Node>>, offset: usize) {
+        if self.anchor.is_some() {
+            self.focus = Some(BoundaryPoint::new(node, offset));
+        }
+    }
+
+    /// 選択を解除する
+    pub fn remove_all_ranges(&mut self) {
+        self.anchor = None;
+        self.focus = None;
+    }
+
+    /// anchor と focus を前後の順に並べた Range を返す（選択が無ければ None）
+    pub fn range(&self) -> Option<Range> {
+        match (&self.anchor, &self.focus) {
+            (Some(anchor), Some(focus)) => {
+                let (start, end) = if anchor.compare(focus) == Ordering::Greater {
+                    (focus.clone(), anchor.clone())
+                } else {
+                    (anchor.clone(), focus.clone())
+                };
+                Some(Range { start, end })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        match self.range() {
+            Some(range) => range.collapsed(),
+            None => true,
+        }
+    }
+}
+
+/// 選択中のテキスト（`window.getSelection().toString()`）
+impl Display for Selection {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self.range() {
+            Some(range) => write!(f, "{}", range),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::string::ToString;
+
+    fn create_document(html: &str) -> Rc<RefCell<Node>> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    // <body> 直下の n 番目の要素の、最初の子（Text）を返す
+    fn text_of_nth_child(document: &Rc<RefCell<Node>>, n: usize) -> Rc<RefCell<Node>> {
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+        let mut child = body.borrow().first_child().expect("child should exist");
+        for _ in 0..n {
+            let next = child.borrow().next_sibling().expect("sibling should exist");
+            child = next;
+        }
+        let text = child.borrow().first_child().expect("text should exist");
+        text
+    }
+
+    #[test]
+    fn test_compare_boundary_points() {
+        let document = create_document("<html><body><p>hello</p><p>world</p></body></html>");
+        let hello = text_of_nth_child(&document, 0);
+        let world = text_of_nth_child(&document, 1);
+        let body = hello
+            .borrow()
+            .parent()
+            .upgrade()
+            .expect("p should exist")
+            .borrow()
+            .parent()
+            .upgrade()
+            .expect("body should exist");
+
+        let a = BoundaryPoint::new(hello.clone(), 3);
+        assert_eq!(Ordering::Less, a.compare(&BoundaryPoint::new(hello, 4)));
+        assert_eq!(
+            Ordering::Less,
+            a.compare(&BoundaryPoint::new(world.clone(), 0))
+        );
+        // (body, 1) は 1 つ目の <p> の後ろ・2 つ目の <p> の前
+        let between = BoundaryPoint::new(body, 1);
+        assert_eq!(Ordering::Greater, between.compare(&a));
+        assert_eq!(
+            Ordering::Less,
+            between.compare(&BoundaryPoint::new(world, 0))
+        );
+    }
+
+    #[test]
+    fn test_range_to_string() {
+        let document = create_document("<html><body><p>hello</p><p>world</p></body></html>");
+        let hello = text_of_nth_child(&document, 0);
+        let world = text_of_nth_child(&document, 1);
+
+        let mut range = Range::new(hello.clone(), 1);
+        range.set_end(hello.clone(), 4);
+        assert_eq!("ell", range.to_string());
+
+        range.set_end(world.clone(), 3);
+        assert_eq!("ellowor", range.to_string());
+        assert_eq!(Some((1, 5)), range.text_offsets(&hello));
+        assert_eq!(Some((0, 3)), range.text_offsets(&world));
+
+        // 終了より後ろに開始を置くと、潰れた範囲になる
+        range.set_start(world.clone(), 4);
+        assert!(range.collapsed());
+        assert_eq!("", range.to_string());
+    }
+
+    #[test]
+    fn test_backward_selection() {
+        let document = create_document("<html><body><p>hello</p><p>world</p></body></html>");
+        let hello = text_of_nth_child(&document, 0);
+        let world = text_of_nth_child(&document, 1);
+
+        // 後ろから前へドラッグしても、Range は前 → 後ろの順になる
+        let mut selection = Selection::new();
+        selection.collapse(world.clone(), 2);
+        assert!(selection.is_collapsed());
+        selection.extend(hello.clone(), 3);
+        let range = selection.range().expect("range should exist");
+        assert!(Rc::ptr_eq(&hello, &range.start().node()));
+        assert_eq!("lowo", selection.to_string());
+
+        selection.remove_all_ranges();
+        assert_eq!(None, selection.range());
+    }
+}
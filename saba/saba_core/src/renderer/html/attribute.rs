@@ -57,4 +57,9 @@ impl Attribute {
     pub fn value(&self) -> String {
         self.value.clone()
     }
+
+    // 値をまるごと置き換える（スクリプトからの属性変更などで使います）。
+    pub fn set_value(&mut self, value: &str) {
+        self.value = String::from(value);
+    }
 }
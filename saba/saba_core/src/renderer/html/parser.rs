@@ -1,3 +1,4 @@
+use crate::renderer::dom::api::append_child;
 use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
@@ -134,27 +135,10 @@ impl HtmlParser {
         // 3) それ以外の文字は、新しい Text ノードを生成。
         let node = Rc::new(RefCell::new(self.create_char(c)));
 
-        // 4) 親（current）に子がすでに居る場合は、先頭子の `next_sibling` にぶら下げる。
-        //    NOTE: 一般的には「最後の子の next_sibling に追加」するのが自然ですが、
-        //    ここでは簡略化のため first_child の next として接続しています。
-        //    将来的に正確な木構造を保つなら、最後の子を辿って末尾に繋ぐのが安全です（TODO候補）。
-        if current.borrow().first_child().is_some() {
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-        } else {
-            // 親に子がいなければ最初の子として登録。
-            current.borrow_mut().set_first_child(Some(node.clone()));
-        }
-
-        // 5) 親の last_child を更新し、子から親への逆リンク（parent）を設定。
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
+        // 4) 親（current）の最後の子として追加し、親子/兄弟リンクを張る。
+        append_child(&current, node.clone());
 
-        // 6) “現在の挿入位置”をこの Text ノードへ更新。
+        // 5) “現在の挿入位置”をこの Text ノードへ更新。
         //    以降の連続する文字は上の 1) の分岐で同一ノードへ連結されます。
         self.stack_of_open_elements.push(node);
     }
@@ -169,6 +153,7 @@ impl HtmlParser {
     /// - 直近の挿入先（stack_of_open_elements の末尾、なければ Document）に、
     ///   新しい Element ノードを子として追加します。
     /// - 兄弟がいる場合は、最後の子の直後に連結します（末尾へ追加）。
+    /// - `id` を持つ要素は、このとき Document の id 索引にも登録されます。
     /// - 追加後、その要素を“現在開いている要素”としてスタックに push します。
     fn insert_element(&mut self, tag: &str, attributes: Vec<Attribute>) {
         let window = self.window.borrow();
@@ -183,42 +168,10 @@ impl HtmlParser {
         //    Rc<RefCell<_>> に包むことで“共有 + 内部可変”にします（DOM 編集がしやすい）。
         let node = Rc::new(RefCell::new(self.create_element(tag, attributes)));
 
-        // 3) 末尾に追加するため、最後の子（last_sibling）を探す
-        if current.borrow().first_child().is_some() {
-            // すでに子要素がある場合は、先頭から next_sibling を辿って末尾へ
-            let mut last_sibling = current.borrow().first_child();
-            loop {
-                last_sibling = match last_sibling {
-                    Some(ref node) => {
-                        if node.borrow().next_sibling().is_some() {
-                            node.borrow().next_sibling()
-                        } else {
-                            break;
-                        }
-                    }
-                    None => unimplemented!("last_sibling should be Some"),
-                };
-            }
-
-            // 4) 末尾の兄弟の next_sibling と、新ノードの previous_sibling を接続
-            last_sibling
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone())); // 兄弟ノードの直後に追加
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &last_sibling.expect("last_sibling should be Some"),
-            ))
-        } else {
-            // 兄弟ノードが存在しない場合（= 子がまだいない）
-            current.borrow_mut().set_first_child(Some(node.clone())); // 最初の子として登録
-        }
-
-        // 5) 親子リンクの仕上げ（last_child と parent）
-        current.borrow_mut().set_last_child(Rc::downgrade(&node)); // 親の最後の子を更新
-        node.borrow_mut().set_parent(Rc::downgrade(&current)); // 子から親への逆リンク
+        // 3) 親の末尾に追加（兄弟/親子リンクの接続と、id 索引への登録は append_child が行う）
+        append_child(&current, node.clone());
 
-        // 6) ツリービルダーの規則: 開始タグを見たら、その要素を「開いている要素スタック」に積む
+        // 4) ツリービルダーの規則: 開始タグを見たら、その要素を「開いている要素スタック」に積む
         self.stack_of_open_elements.push(node);
     }
 
@@ -348,6 +301,14 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
+                            if tag == "link" {
+                                // <link> は中身も終了タグも持たない（void 要素）ので、挿入してすぐ閉じる
+                                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inhead
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_until(ElementKind::Link);
+                                token = self.t.next();
+                                continue;
+                            }
                             // 仕様書には定められていないが、このブラウザは仕様を全て実装している
                             // わけではないので、<head>が省略されているHTML文書を扱うために必要。
                             // これがないと<head>が省略されているHTML文書で無限ループが発生
@@ -11,7 +11,7 @@
 //! - `Assignment     → LeftHandSide '=' AssignmentExpression | AdditiveExpression`
 //! - `Additive       → LeftHandSide (('+'|'-') AssignmentExpression)?`
 //! - `LeftHandSide   → MemberExpression`
-//! - `Member         → Primary`
+//! - `Member         → Primary ('.' Identifier)*`
 //! - `Primary        → Identifier | StringLiteral | NumericLiteral`
 //!
 //! 型ブリッジ（TS / Python の感覚）
@@ -323,28 +323,20 @@ impl JsParser {
     ///
     /// 例
     /// - `foo.bar` → `MemberExpression { object: Identifier("foo"), property: Identifier("bar") }`
+    /// - `foo.bar.baz` → `MemberExpression { object: MemberExpression(foo, bar), property: Identifier("baz") }`
+    ///   （`.` が続く限り左から順に包んでいく。`el.style.color` のようなアクセスに使う）
     fn member_expression(&mut self) -> Option<Rc<Node>> {
         // オブジェクト側（左側）の式を読む
-        let expr = self.primary_expression();
+        let mut expr = self.primary_expression();
 
-        let t = match self.t.peek() {
-            Some(token) => token,
-            None => return expr,
-        };
-
-        match t {
-            Token::Punctuator(c) => {
-                if c == &'.' {
-                    // '.'を消費する
-                    assert!(self.t.next().is_some());
-                    // プロパティ名（識別子）を読み、MemberExpression を作る
-                    return Node::new_member_expression(expr, self.identifier());
-                }
-
-                expr
-            }
-            _ => expr,
+        // '.' が続く間、プロパティ名（識別子）を読み、MemberExpression で包む
+        while let Some(Token::Punctuator('.')) = self.t.peek() {
+            // '.'を消費する
+            assert!(self.t.next().is_some());
+            expr = Node::new_member_expression(expr, self.identifier());
         }
+
+        expr
     }
 
     /// 呼び出しの実引数を読み取り、式ノードの配列として返します。
@@ -726,6 +718,7 @@ impl JsParser {
 mod tests {
     use super::*;
     use alloc::string::ToString;
+    use alloc::vec;
     // このモジュールでは AST パーサ `JsParser::parse_ast()` の出力形を確認します。
     // - `Program.body` に文が順に入ることを前提に、各ケースのノード形状を比較します。
     // - 検証対象: NumericLiteral / AdditiveExpression / VariableDeclaration / VariableDeclarator /
@@ -942,4 +935,29 @@ mod tests {
         expected.set_body(body);
         assert_eq!(expected, parser.parse_ast());
     }
+
+    #[test]
+    fn test_nested_member_expression() {
+        // `.` の連続は左から順に包む: var c=el.style.color;
+        // 期待: init = MemberExpression(MemberExpression(el, style), color)
+        let input = "var c=el.style.color;".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let mut expected = Program::new();
+        let body = vec![Rc::new(Node::VariableDeclaration {
+            declarations: [Some(Rc::new(Node::VariableDeclarator {
+                id: Some(Rc::new(Node::Identifier("c".to_string()))),
+                init: Some(Rc::new(Node::MemberExpression {
+                    object: Some(Rc::new(Node::MemberExpression {
+                        object: Some(Rc::new(Node::Identifier("el".to_string()))),
+                        property: Some(Rc::new(Node::Identifier("style".to_string()))),
+                    })),
+                    property: Some(Rc::new(Node::Identifier("color".to_string()))),
+                })),
+            }))]
+            .to_vec(),
+        })];
+        expected.set_body(body);
+        assert_eq!(expected, parser.parse_ast());
+    }
 }
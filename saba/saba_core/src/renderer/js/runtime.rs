@@ -10,13 +10,48 @@
 //! 実装メモ（用語ブリッジ）
 //! - TS/Python の感覚: `RuntimeValue` は実行時値の共用体、`eval(node)` は再帰評価。
 //! - 環境は `Rc<RefCell<Environment>>` でリンクし、外側の環境へ参照できます。
+//!
+//! DOM 連携
+//! - `document.getElementById(id)` で要素を取り、`el.textContent = "..."` で中身を書き換えられます。
+//! - `el.style.color` / `el.style.backgroundColor` で `style` 属性（インラインスタイル）を読み書きできます。
+//!   JS 側の camelCase は CSS のプロパティ名（`background-color`）に直します。
+//! - DOM を書き換えたかどうかは `dom_modified()` で分かります。ページはそれを見てスタイルとレイアウトをやり直します。
+//!
+//! スタイルの参照（CSSOM）
+//! - `window.getComputedStyle(el)` の `.color` や `.getPropertyValue("margin-top")` で、
+//!   レイアウト結果（`LayoutObject` の `ComputedStyle`）から解決値を文字列で読めます（`layout::resolved_value`）。
+//!   レイアウトはページから渡されたシート（`set_stylesheets`）で、必要になったときに作ります。
+//!   DOM やシートを書き換えたら作り直します（実ブラウザの「強制レイアウト」と同じ）。
+//! - `document.styleSheets.item(i)` で文書のシートを取り、`cssRules.length` / `cssRules.item(i)`、
+//!   `insertRule(text, i)` / `deleteRule(i)` でルールを読み書きできます。
+//!   ルールからは `cssText` / `selectorText` / `style.color` を読め、`style.color = ...` で書き換えられます。
+//! - このパーサは `a[0]` や `f(x).y` を読めないので、添字の代わりに `item(i)` を使い、呼び出しの結果は一度変数に入れます。
+//!   例: `var s = getComputedStyle(el); var c = s.color;`
+//! - ルールは「シートの何番目か」で指すので、前のルールを消すと同じ変数が次のルールを指します（簡易版）。
+//! - シートを書き換えたかどうかは `styles_modified()` で分かり、ページは `stylesheets()` を受け取ってカスケードからやり直します。
 //! - no_std 前提のため、動的確保は `alloc` クレートに依存します。
 
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::selector::MatchingContext;
+use crate::renderer::css::serialize::serialize_component_values;
+use crate::renderer::css::serialize::serialize_declaration;
+use crate::renderer::css::serialize::serialize_rule;
+use crate::renderer::css::serialize::serialize_selector;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::api::append_child;
 use crate::renderer::dom::api::get_element_by_id;
+use crate::renderer::dom::api::get_style_property;
+use crate::renderer::dom::api::remove_child;
+use crate::renderer::dom::api::set_attribute;
+use crate::renderer::dom::api::set_style_property;
 use crate::renderer::dom::node::Node as DomNode;
 use crate::renderer::dom::node::NodeKind as DomNodeKind;
 use crate::renderer::js::ast::Node;
 use crate::renderer::js::ast::Program;
+use crate::renderer::layout::layout_view::LayoutView;
+use crate::renderer::layout::resolved_value::resolved_value;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
@@ -40,6 +75,25 @@ pub enum RuntimeValue {
         object: Rc<RefCell<DomNode>>,
         property: Option<String>,
     },
+    /// `getComputedStyle(el)` の結果（読み取り専用）。`property` は読むプロパティ名（`backgroundColor` など）
+    /// https://drafts.csswg.org/cssom/#dom-window-getcomputedstyle
+    ComputedStyle {
+        object: Rc<RefCell<DomNode>>,
+        property: Option<String>,
+    },
+    /// `document.styleSheets.item(i)` のシート。`index` はランタイムが持つシートの並びでの位置
+    /// https://drafts.csswg.org/cssom/#cssstylesheet
+    StyleSheet {
+        index: usize,
+        property: Option<String>,
+    },
+    /// `sheet.cssRules.item(i)` のルール（`sheet` のシートの `index` 番目）
+    /// https://drafts.csswg.org/cssom/#cssstylerule
+    CssRule {
+        sheet: usize,
+        index: usize,
+        property: Option<String>,
+    },
 }
 
 /// `+` 演算の定義（`RuntimeValue + RuntimeValue`）
@@ -107,6 +161,10 @@ impl Display for RuntimeValue {
             } => {
                 format!("HtmlElement: {:#?}", object)
             }
+            // スタイル関係の値はブラウザの `String(value)` と同じ見た目にする
+            RuntimeValue::ComputedStyle { .. } => "[object CSSStyleDeclaration]".to_string(),
+            RuntimeValue::StyleSheet { .. } => "[object CSSStyleSheet]".to_string(),
+            RuntimeValue::CssRule { .. } => "[object CSSStyleRule]".to_string(),
         };
         // 最後にフォーマッタ `f` に書き込みます。OK/Err を caller に返します。
         write!(f, "{}", s)
@@ -203,6 +261,16 @@ pub struct JsRuntime {
     dom_root: Rc<RefCell<DomNode>>,
     functions: Vec<Function>,
     env: Rc<RefCell<Environment>>,
+    // スクリプトが DOM（テキストや属性）を書き換えたか
+    dom_modified: bool,
+    // カスケードに使うシート（先頭から弱い順。`document.styleSheets` には出どころが Author のものだけ見せる）
+    stylesheets: Vec<StyleSheet>,
+    // セレクタのマッチングに使うページの状態（:hover やビューポートの大きさ）
+    context: MatchingContext,
+    // スクリプトがシートを書き換えたか
+    styles_modified: bool,
+    // getComputedStyle のために作ったレイアウト。DOM やシートが変わったら捨てる
+    layout_view: Option<LayoutView>,
 }
 
 impl JsRuntime {
@@ -211,9 +279,193 @@ impl JsRuntime {
             dom_root,
             functions: Vec::new(),
             env: Rc::new(RefCell::new(Environment::new(None))),
+            dom_modified: false,
+            stylesheets: Vec::new(),
+            context: MatchingContext::new(),
+            styles_modified: false,
+            layout_view: None,
         }
     }
 
+    /// スクリプトから見えるシートと、スタイル計算に使うページの状態を渡す
+    ///
+    /// - `stylesheets` はカスケードに使うすべてのシート（UA スタイルシートも含む）。
+    ///   `document.styleSheets` には出どころが `CascadeOrigin::Author` のものだけが並びます。
+    /// - 渡さなければシートは無く、`getComputedStyle` は初期値と継承だけで計算した値を返します。
+    pub fn set_stylesheets(&mut self, stylesheets: Vec<StyleSheet>, context: MatchingContext) {
+        self.stylesheets = stylesheets;
+        self.context = context;
+        self.layout_view = None;
+    }
+
+    /// これまでの実行で DOM を書き換えたら true（スタイル/レイアウトのやり直しが必要）
+    pub fn dom_modified(&self) -> bool {
+        self.dom_modified
+    }
+
+    /// これまでの実行でシート（ルールや宣言）を書き換えたら true（`stylesheets()` でカスケードからやり直す）
+    pub fn styles_modified(&self) -> bool {
+        self.styles_modified
+    }
+
+    /// 書き換えを反映したシート（`set_stylesheets` で渡したものと同じ並び）
+    pub fn stylesheets(&self) -> &[StyleSheet] {
+        &self.stylesheets
+    }
+
+    // DOM を書き換えた。作ってあったレイアウトはもう使えない
+    fn mark_dom_modified(&mut self) {
+        self.dom_modified = true;
+        self.layout_view = None;
+    }
+
+    // シートを書き換えた。作ってあったレイアウトはもう使えない
+    fn mark_styles_modified(&mut self) {
+        self.styles_modified = true;
+        self.layout_view = None;
+    }
+
+    /// 値として使うときに、DOM やスタイルのプロパティ参照を中身の値に置き換える
+    ///
+    /// - `el.style.color` は評価すると「要素 + プロパティ名」（`HtmlElement { property: "style.color" }`）
+    ///   になるので、`var c = el.style.color;` や `+` の計算ではここで文字列に読み替えます。
+    /// - `getComputedStyle(el).color`、`rule.cssText`、`sheet.cssRules.length` なども同じです。
+    /// - それ以外の値はそのまま返します。
+    fn read_property(&mut self, value: Option<RuntimeValue>) -> Option<RuntimeValue> {
+        match &value {
+            Some(RuntimeValue::HtmlElement {
+                object,
+                property: Some(p),
+            }) => {
+                if p == "style.cssText" {
+                    let text = match RefCell::borrow(object).get_element() {
+                        Some(e) => e.get_attribute("style").unwrap_or_default(),
+                        None => String::new(),
+                    };
+                    return Some(RuntimeValue::StringLiteral(text));
+                }
+                if let Some(name) = p.strip_prefix("style.") {
+                    let text =
+                        get_style_property(object, &css_property_name(name)).unwrap_or_default();
+                    return Some(RuntimeValue::StringLiteral(text));
+                }
+            }
+            Some(RuntimeValue::ComputedStyle {
+                object,
+                property: Some(p),
+            }) => {
+                let text = self.computed_value(&object.clone(), &css_property_name(p));
+                return Some(RuntimeValue::StringLiteral(text));
+            }
+            Some(RuntimeValue::StyleSheet {
+                index,
+                property: Some(p),
+            }) if p == "cssRules.length" => {
+                let length = self.stylesheets.get(*index).map_or(0, |s| s.rules.len());
+                return Some(RuntimeValue::Number(length as u64));
+            }
+            Some(RuntimeValue::CssRule {
+                sheet,
+                index,
+                property: Some(p),
+            }) => {
+                let text = self.rule_property(*sheet, *index, p).unwrap_or_default();
+                return Some(RuntimeValue::StringLiteral(text));
+            }
+            Some(RuntimeValue::StringLiteral(name)) if name == "document.styleSheets.length" => {
+                let length = self.document_sheets().count();
+                return Some(RuntimeValue::Number(length as u64));
+            }
+            _ => {}
+        }
+        value
+    }
+
+    // 要素 `node` のプロパティ `name`（kebab-case）の解決値。レイアウトが無ければここで作る
+    // レイアウトツリーに無い要素（display: none など）は空文字列
+    fn computed_value(&mut self, node: &Rc<RefCell<DomNode>>, name: &str) -> String {
+        if self.layout_view.is_none() {
+            self.layout_view = Some(LayoutView::new_with_context(
+                self.dom_root.clone(),
+                &self.stylesheets,
+                &self.context,
+            ));
+        }
+        let object = match &self.layout_view {
+            Some(view) => view.find_layout_object(node),
+            None => None,
+        };
+        match object {
+            Some(object) => resolved_value(&RefCell::borrow(&object), name).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    // 文書のシート（出どころが Author）のランタイム内での位置を、`document.styleSheets` の順に並べる
+    fn document_sheets(&self) -> impl Iterator<Item = usize> + '_ {
+        self.stylesheets
+            .iter()
+            .enumerate()
+            .filter(|(_, sheet)| sheet.origin == CascadeOrigin::Author)
+            .map(|(i, _)| i)
+    }
+
+    // ルールのプロパティ（`cssText` / `selectorText` / `style.color` / `style.cssText`）を読む
+    fn rule_property(&self, sheet: usize, index: usize, property: &str) -> Option<String> {
+        let rule = self.stylesheets.get(sheet)?.rules.get(index)?;
+        match property {
+            "cssText" => Some(serialize_rule(rule)),
+            "selectorText" => Some(serialize_selector(&rule.selector)),
+            "style.cssText" => Some(
+                rule.declarations
+                    .iter()
+                    .map(|d| format!("{};", serialize_declaration(d)))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            ),
+            _ => {
+                let name = css_property_name(property.strip_prefix("style.")?);
+                rule.declarations
+                    .iter()
+                    .rev()
+                    .find(|d| d.property == name)
+                    .map(|d| serialize_component_values(&d.values))
+            }
+        }
+    }
+
+    // ルールの宣言 `name`（kebab-case）を `value` に書き換える（`rule.style.color = "blue"`）
+    // 空文字列ならその宣言を取り除く。読めない値なら何もしない（CSSOM の setProperty と同じ）
+    fn set_rule_property(&mut self, sheet: usize, index: usize, name: &str, value: &str) {
+        let declarations = CssParser::new(CssTokenizer::new(format!("{}: {}", name, value)))
+            .parse_declaration_list();
+        let rule = match self
+            .stylesheets
+            .get_mut(sheet)
+            .and_then(|s| s.rules.get_mut(index))
+        {
+            Some(rule) => rule,
+            None => return,
+        };
+        if !value.trim().is_empty() && declarations.is_empty() {
+            return;
+        }
+        rule.declarations.retain(|d| d.property != name);
+        rule.declarations.extend(declarations);
+        self.mark_styles_modified();
+    }
+
+    // `i` 番目の実引数を評価して値にする（無ければ None）
+    fn argument(
+        &mut self,
+        arguments: &[Option<Rc<Node>>],
+        i: usize,
+        env: Rc<RefCell<Environment>>,
+    ) -> Option<RuntimeValue> {
+        let value = self.eval(arguments.get(i)?, env);
+        self.read_property(value)
+    }
+
     /// ブラウザ組み込み API（DOM など）を呼び出す窓口。
     ///
     /// 返り値（タプル）
@@ -252,8 +504,112 @@ impl JsRuntime {
             );
         }
 
-        // 未対応の API 名 → 呼び出しは行わず（false, None）で上位に委ねる
-        (false, None)
+        // 例: window.getComputedStyle(el)（`window.` は省略できる）
+        if func == &RuntimeValue::StringLiteral("window.getComputedStyle".to_string())
+            || func == &RuntimeValue::StringLiteral("getComputedStyle".to_string())
+        {
+            return match self.argument(arguments, 0, env) {
+                Some(RuntimeValue::HtmlElement {
+                    object,
+                    property: None,
+                }) => (
+                    true,
+                    Some(RuntimeValue::ComputedStyle {
+                        object,
+                        property: None,
+                    }),
+                ),
+                _ => (true, None),
+            };
+        }
+
+        // 例: document.styleSheets.item(0)
+        if func == &RuntimeValue::StringLiteral("document.styleSheets.item".to_string()) {
+            let index = match self.argument(arguments, 0, env) {
+                Some(RuntimeValue::Number(i)) => i as usize,
+                _ => return (true, None),
+            };
+            let sheet = self
+                .document_sheets()
+                .nth(index)
+                .map(|index| RuntimeValue::StyleSheet {
+                    index,
+                    property: None,
+                });
+            return (true, sheet);
+        }
+
+        match func {
+            // 例: getComputedStyle(el).getPropertyValue("margin-top")（名前は CSS のまま）
+            RuntimeValue::ComputedStyle {
+                object,
+                property: Some(p),
+            } if p == "getPropertyValue" => {
+                let name = match self.argument(arguments, 0, env) {
+                    Some(name) => name.to_string(),
+                    None => return (true, None),
+                };
+                let text = self.computed_value(object, &name);
+                (true, Some(RuntimeValue::StringLiteral(text)))
+            }
+            RuntimeValue::StyleSheet {
+                index: sheet,
+                property: Some(p),
+            } => {
+                let sheet = *sheet;
+                let len = match self.stylesheets.get(sheet) {
+                    Some(s) => s.rules.len(),
+                    None => return (true, None),
+                };
+                match p.as_str() {
+                    // 例: sheet.cssRules.item(0)
+                    "cssRules.item" => match self.argument(arguments, 0, env) {
+                        Some(RuntimeValue::Number(i)) if (i as usize) < len => (
+                            true,
+                            Some(RuntimeValue::CssRule {
+                                sheet,
+                                index: i as usize,
+                                property: None,
+                            }),
+                        ),
+                        _ => (true, None),
+                    },
+                    // 例: sheet.insertRule("p { color: red }", 0)。入れた位置を返す
+                    // ルールが 1 つでない・読めない・位置が範囲外なら何もしない（ブラウザでは例外）
+                    "insertRule" => {
+                        let text = match self.argument(arguments, 0, env.clone()) {
+                            Some(text) => text.to_string(),
+                            None => return (true, None),
+                        };
+                        let index = match self.argument(arguments, 1, env) {
+                            Some(RuntimeValue::Number(i)) => i as usize,
+                            _ => 0,
+                        };
+                        let mut parsed = CssParser::new(CssTokenizer::new(text)).parse_stylesheet();
+                        if parsed.rules.len() != 1 || !parsed.imports.is_empty() || index > len {
+                            return (true, None);
+                        }
+                        let rule = parsed.rules.remove(0);
+                        self.stylesheets[sheet].rules.insert(index, rule);
+                        self.mark_styles_modified();
+                        (true, Some(RuntimeValue::Number(index as u64)))
+                    }
+                    // 例: sheet.deleteRule(0)
+                    "deleteRule" => {
+                        if let Some(RuntimeValue::Number(i)) = self.argument(arguments, 0, env) {
+                            if (i as usize) < len {
+                                self.stylesheets[sheet].rules.remove(i as usize);
+                                self.mark_styles_modified();
+                            }
+                        }
+                        (true, None)
+                    }
+                    _ => (false, None),
+                }
+            }
+            // 未対応の API 名 → 呼び出しは行わず（false, None）で上位に委ねる
+            _ => (false, None),
+        }
     }
 
     /// AST ノードを評価して値（`RuntimeValue`）を得ます。
@@ -279,11 +635,13 @@ impl JsRuntime {
                 left,
                 right,
             } => {
-                let left_value = match self.eval(&left, env.clone()) {
+                let left_value = self.eval(&left, env.clone());
+                let left_value = match self.read_property(left_value) {
                     Some(value) => value,
                     None => return None,
                 };
-                let right_value = match self.eval(&right, env.clone()) {
+                let right_value = self.eval(&right, env.clone());
+                let right_value = match self.read_property(right_value) {
                     Some(value) => value,
                     None => return None,
                 };
@@ -316,17 +674,37 @@ impl JsRuntime {
                 if let Some(node) = left {
                     if let Node::Identifier(id) = node.borrow() {
                         let new_value = self.eval(right, env.clone());
+                        let new_value = self.read_property(new_value);
                         env.borrow_mut().update_variable(id.to_string(), new_value);
                         return None;
                     }
                 }
 
-                // もし左辺の値がDOMツリーのノードを表すHtmlElementならば、DOMツリーを更新する
-                if let Some(RuntimeValue::HtmlElement { object, property }) =
-                    self.eval(left, env.clone())
+                let target = self.eval(left, env.clone());
+
+                // rule.style.color = "blue"; はシートのルールの宣言を書き換える
+                if let Some(RuntimeValue::CssRule {
+                    sheet,
+                    index,
+                    property: Some(p),
+                }) = &target
                 {
+                    if let Some(name) = p.strip_prefix("style.") {
+                        let value = self.eval(right, env.clone());
+                        let value = self
+                            .read_property(value)
+                            .map(|v| v.to_string())
+                            .unwrap_or_default();
+                        self.set_rule_property(*sheet, *index, &css_property_name(name), &value);
+                    }
+                    return None;
+                }
+
+                // もし左辺の値がDOMツリーのノードを表すHtmlElementならば、DOMツリーを更新する
+                if let Some(RuntimeValue::HtmlElement { object, property }) = target {
                     // 右辺の値を先に評価（文字列や数値等）。DOM 反映時に文字列化して使うことがあります。
-                    let right_value = match self.eval(right, env.clone()) {
+                    let right_value = self.eval(right, env.clone());
+                    let right_value = match self.read_property(right_value) {
                         Some(value) => value,
                         None => return None,
                     };
@@ -334,12 +712,34 @@ impl JsRuntime {
                     if let Some(p) = property {
                         // target.textContent = "foobar"; のようにノードのテキストを変更する
                         // 補足: textContent は「要素の直下のテキストノード」を入れ替えるイメージで実装。
+                        // 既存の子は remove_child で取り外すので、子孫の id も索引から外れます。
                         if p == "textContent" {
-                            object
-                                .borrow_mut()
-                                .set_first_child(Some(Rc::new(RefCell::new(DomNode::new(
-                                    DomNodeKind::Text(right_value.to_string()),
-                                )))));
+                            loop {
+                                let child = RefCell::borrow(&object).first_child();
+                                match child {
+                                    Some(c) => remove_child(&object, &c),
+                                    None => break,
+                                }
+                            }
+                            append_child(
+                                &object,
+                                Rc::new(RefCell::new(DomNode::new(DomNodeKind::Text(
+                                    right_value.to_string(),
+                                )))),
+                            );
+                            self.mark_dom_modified();
+                        } else if p == "style.cssText" {
+                            // target.style.cssText = "color: red"; は style 属性をまるごと置き換える
+                            set_attribute(&object, "style", &right_value.to_string());
+                            self.mark_dom_modified();
+                        } else if let Some(name) = p.strip_prefix("style.") {
+                            // target.style.backgroundColor = "red"; は style 属性の 1 プロパティだけを書き換える
+                            set_style_property(
+                                &object,
+                                &css_property_name(name),
+                                &right_value.to_string(),
+                            );
+                            self.mark_dom_modified();
                         }
                         // ここに他のプロパティ（innerText / innerHTML など）を追加していく設計にできます。
                     }
@@ -370,13 +770,43 @@ impl JsRuntime {
                 //   「要素 + プロパティ名」の組み合わせを 1 つの値として扱えます。
                 // - `property_value.to_string()` は `Display` 実装経由で文字列化（Identifier → その名前）。
                 // もしオブジェクトがDOMノードの場合、HtmlElementの`property`を更新する
-                if let RuntimeValue::HtmlElement { object, property } = object_value {
-                    assert!(property.is_none());
-                    // HtmlElementの`property`に`property_value`の文字列をセットする
-                    return Some(RuntimeValue::HtmlElement {
-                        object,
-                        property: Some(property_value.to_string()),
-                    });
+                // `el.style.color` のように 2 段目以降のプロパティは "style.color" とつなげて持つ
+                // getComputedStyle の結果・シート・ルールも同じように `property` をつないでいく
+                let chain = |property: Option<String>| match property {
+                    Some(p) => Some(format!("{}.{}", p, property_value)),
+                    None => Some(property_value.to_string()),
+                };
+                match object_value {
+                    RuntimeValue::HtmlElement { object, property } => {
+                        return Some(RuntimeValue::HtmlElement {
+                            object,
+                            property: chain(property),
+                        });
+                    }
+                    RuntimeValue::ComputedStyle { object, property } => {
+                        return Some(RuntimeValue::ComputedStyle {
+                            object,
+                            property: chain(property),
+                        });
+                    }
+                    RuntimeValue::StyleSheet { index, property } => {
+                        return Some(RuntimeValue::StyleSheet {
+                            index,
+                            property: chain(property),
+                        });
+                    }
+                    RuntimeValue::CssRule {
+                        sheet,
+                        index,
+                        property,
+                    } => {
+                        return Some(RuntimeValue::CssRule {
+                            sheet,
+                            index,
+                            property: chain(property),
+                        });
+                    }
+                    _ => {}
                 }
 
                 // 補足: `+` は RuntimeValue の Add 実装により、数値以外では文字列結合として働きます。
@@ -398,6 +828,7 @@ impl JsRuntime {
                 if let Some(node) = id {
                     if let Node::Identifier(id) = node.borrow() {
                         let init = self.eval(&init, env.clone());
+                        let init = self.read_property(init);
                         env.borrow_mut().add_variable(id.to_string(), init);
                     }
                 }
@@ -484,9 +915,9 @@ impl JsRuntime {
                     if let Some(RuntimeValue::StringLiteral(name)) =
                         self.eval(&function.params[i], new_env.clone())
                     {
-                        new_env
-                            .borrow_mut()
-                            .add_variable(name, self.eval(item, new_env.clone()));
+                        let value = self.eval(item, new_env.clone());
+                        let value = self.read_property(value);
+                        new_env.borrow_mut().add_variable(name, value);
                     }
                 }
 
@@ -506,9 +937,27 @@ impl JsRuntime {
     }
 }
 
+/// JS のプロパティ名（camelCase）を CSS のプロパティ名（kebab-case）に直す
+/// 例: `backgroundColor` → `background-color`, `color` → `color`
+fn css_property_name(name: &str) -> String {
+    let mut s = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            s.push('-');
+            s.push(c.to_ascii_lowercase());
+        } else {
+            s.push(c);
+        }
+    }
+    s
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::css::user_agent::user_agent_stylesheet;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
     use crate::renderer::js::ast::JsParser;
     use crate::renderer::js::token::JsLexer;
     use alloc::string::ToString; // `"...".to_string()` を使うためのトレイト（no_std では自動導入されない）
@@ -708,4 +1157,164 @@ mod tests {
             i += 1;
         }
     }
+
+    // <p id=t> を持つ DOM でスクリプトを実行し、<p> 要素とランタイムを返す
+    fn run_with_paragraph(js: &str) -> (Rc<RefCell<DomNode>>, JsRuntime) {
+        let html = "<html><body><p id=t style=\"color: red\">x</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = RefCell::borrow(&window).document();
+        let p =
+            get_element_by_id(Some(document.clone()), &"t".to_string()).expect("p should exist");
+
+        let ast = JsParser::new(JsLexer::new(js.to_string())).parse_ast();
+        let mut runtime = JsRuntime::new(document);
+        runtime.execute(&ast);
+        (p, runtime)
+    }
+
+    #[test]
+    fn test_style_write() {
+        // camelCase のプロパティ名は kebab-case にして style 属性へ書き込む
+        let (p, runtime) = run_with_paragraph(
+            "var el = document.getElementById(\"t\"); el.style.backgroundColor = \"blue\";",
+        );
+        assert!(runtime.dom_modified());
+        assert_eq!(
+            get_style_property(&p, "background-color"),
+            Some("blue".to_string())
+        );
+        // 既存の宣言は残る
+        assert_eq!(get_style_property(&p, "color"), Some("red".to_string()));
+
+        // 同じプロパティへの書き込みは上書き
+        let (p, _) = run_with_paragraph(
+            "var el = document.getElementById(\"t\"); el.style.color = \"green\";",
+        );
+        assert_eq!(get_style_property(&p, "color"), Some("green".to_string()));
+    }
+
+    #[test]
+    fn test_style_read() {
+        // 読み出した値は文字列として変数に入り、別のプロパティへも書ける
+        let (p, _) = run_with_paragraph(
+            "var el = document.getElementById(\"t\"); var c = el.style.color; el.style.backgroundColor = c;",
+        );
+        assert_eq!(
+            get_style_property(&p, "background-color"),
+            Some("red".to_string())
+        );
+
+        // 読むだけなら DOM は変わらない
+        let (_, runtime) =
+            run_with_paragraph("var el = document.getElementById(\"t\"); var c = el.style.color;");
+        assert!(!runtime.dom_modified());
+    }
+
+    // <p id=t> と非表示にする <h1 id=n> を持つ文書に、UA スタイルシートと `css` を当ててスクリプトを実行する
+    // 最後の文の値（`var out = ...; out` の out）とランタイムを返す
+    fn run_with_stylesheet(css: &str, js: &str) -> (Option<RuntimeValue>, JsRuntime) {
+        let html = "<html><body><p id=t>x</p><h1 id=n>y</h1></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = RefCell::borrow(&window).document();
+        let sheet = CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet();
+
+        let ast = JsParser::new(JsLexer::new(js.to_string())).parse_ast();
+        let mut runtime = JsRuntime::new(document);
+        runtime.set_stylesheets(
+            [user_agent_stylesheet(), sheet].to_vec(),
+            MatchingContext::new(),
+        );
+        let mut result = None;
+        for node in ast.body() {
+            result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+        }
+        (result, runtime)
+    }
+
+    fn string(s: &str) -> Option<RuntimeValue> {
+        Some(RuntimeValue::StringLiteral(s.to_string()))
+    }
+
+    #[test]
+    fn test_get_computed_style() {
+        let css = "p { color: rgb(0, 128, 0); font-size: 20px; margin-top: 1em; width: 100px; } \
+                   h1 { display: none; }";
+        // 色は rgb()、長さは px（em はフォントサイズから）。getPropertyValue は CSS の名前で引く
+        let (result, runtime) = run_with_stylesheet(
+            css,
+            "var s = window.getComputedStyle(document.getElementById(\"t\")); \
+             var out = s.color + \"|\" + s.marginTop + \"|\" + s.getPropertyValue(\"font-size\") + \"|\" + s.width + \"|\" + s.display; out",
+        );
+        assert_eq!(result, string("rgb(0, 128, 0)|20px|20px|100px|block"));
+        assert!(!runtime.dom_modified());
+
+        // レイアウトツリーに無い要素（display: none）は空文字列
+        let (result, _) = run_with_stylesheet(
+            css,
+            "var s = getComputedStyle(document.getElementById(\"n\")); var out = s.display; out",
+        );
+        assert_eq!(result, string(""));
+
+        // background-color は継承しないので、親に色があっても初期値（transparent）
+        let (result, _) = run_with_stylesheet(
+            "body { background-color: red; }",
+            "var s = getComputedStyle(document.getElementById(\"t\")); var out = s.backgroundColor; out",
+        );
+        assert_eq!(result, string("rgba(0, 0, 0, 0)"));
+
+        // DOM を書き換えたら、次に読むときにレイアウトし直す
+        let (result, _) = run_with_stylesheet(
+            css,
+            "var el = document.getElementById(\"t\"); var s = getComputedStyle(el); var a = s.width; \
+             el.style.width = \"30px\"; var out = a + \"|\" + s.width; out",
+        );
+        assert_eq!(result, string("100px|30px"));
+    }
+
+    #[test]
+    fn test_style_sheets() {
+        let css = "p { color: red; } h1 { color: blue; }";
+        let (result, runtime) = run_with_stylesheet(
+            css,
+            "var sheet = document.styleSheets.item(0); var n = document.styleSheets.length; \
+             sheet.insertRule(\"#t { color: green }\", 1); sheet.deleteRule(0); \
+             var rule = sheet.cssRules.item(0); var t = rule.cssText; rule.style.marginTop = \"5px\"; \
+             var out = n + \"|\" + sheet.cssRules.length + \"|\" + t + \"|\" + rule.selectorText + \"|\" + rule.style.cssText; out",
+        );
+        // UA スタイルシートは document.styleSheets に出ない
+        assert_eq!(
+            result,
+            string("1|2|#t { color: green; }|#t|color: green; margin-top: 5px;")
+        );
+        assert!(runtime.styles_modified());
+        assert!(!runtime.dom_modified());
+        assert_eq!(runtime.stylesheets()[1].rules.len(), 2);
+
+        // 書き換えたルールは getComputedStyle にすぐ反映される
+        let (result, _) = run_with_stylesheet(
+            css,
+            "var el = document.getElementById(\"t\"); var s = getComputedStyle(el); var a = s.color; \
+             var sheet = document.styleSheets.item(0); sheet.insertRule(\"#t { color: lime }\", 2); \
+             var out = a + \"|\" + s.color; out",
+        );
+        assert_eq!(result, string("rgb(255, 0, 0)|rgb(0, 255, 0)"));
+
+        // 読めないルール・範囲外の位置は入れない。読むだけならシートは変わらない
+        let (result, runtime) = run_with_stylesheet(
+            css,
+            "var sheet = document.styleSheets.item(0); sheet.insertRule(\"p) { }\", 0); \
+             sheet.insertRule(\"a { }\", 5); sheet.deleteRule(7); var out = sheet.cssRules.length; out",
+        );
+        assert_eq!(result, Some(RuntimeValue::Number(2)));
+        assert!(!runtime.styles_modified());
+    }
+
+    #[test]
+    fn test_css_property_name() {
+        assert_eq!(css_property_name("color"), "color".to_string());
+        assert_eq!(
+            css_property_name("backgroundColor"),
+            "background-color".to_string()
+        );
+    }
 }
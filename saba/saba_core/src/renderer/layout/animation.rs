@@ -0,0 +1,1039 @@
+//! CSS トランジションとキーフレームアニメーション（初心者向け）
+//!
+//! 役割
+//! - `transition-*` と `animation-*`（`@keyframes`）で、プロパティの値を時間とともに変えます。
+//! - 時刻は `AnimationTimeline` が持ちます。時刻を進めるのは組み込み側（UI）の時計で、
+//!   `Page::tick(now)` にその値（ミリ秒）を渡します。テストでは好きな時刻を渡して 1 コマずつ確かめられます。
+//!
+//! スタイル計算とのつながり
+//! 1) カスケードと defaulting で、アニメーションを適用する前のスタイル（after-change style）を決める
+//! 2) `AnimationTimeline::animate` が
+//!    - 前回のスタイルから値が変わったプロパティ（`transition-property` に含まれるもの）のトランジションを始め、
+//!    - `animation-name` のアニメーションを始め（続いているものはそのまま）、
+//!    - 今の時刻の値をスタイルに上書きする（アニメーション → トランジションの順。トランジションが勝つ）
+//! 3) 上書きした値は子に継承される（`color` のアニメーションは中のテキストの色も変える）
+//!
+//! 補間できる値（それ以外はトランジションせず、キーフレームでは区間の真ん中で切り替わる）
+//! - 色（各チャンネルに alpha を掛けた値で補間する）
+//! - 長さ（`10px` → `20px`。単位が違えば `calc()` にする）、`font-size`、`font-weight`
+//! - 数値（`opacity` など）
+//!
+//! 例: `p { transition: color 1s linear }` で color が黒から赤に変わったら、
+//!     0.5 秒後の color は `rgb(128, 0, 0)`、1 秒後からは赤。
+//!
+//! 簡易化している点
+//! - 途中で戻したトランジションを短くする調整（reversing shortening factor）はしない
+//! - キーフレームの中の `inherit` / `unset` / `var()` は無視する。`em` はその要素の font-size で決める
+//! - `animation-play-state`、`transitionend` などのイベントは未対応
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `AnimationTimeline` は Web Animations の `document.timeline` にあたります。
+//! - 要素ごとの状態は DOM ノードのアドレスをキーにした `BTreeMap` に持ちます（TS の `WeakMap<Element, ...>` に近い）。
+//!
+//! 仕様
+//! - https://drafts.csswg.org/css-transitions-1/
+//! - https://drafts.csswg.org/css-animations-1/
+//! - https://drafts.csswg.org/web-animations-1/
+use crate::error::Error;
+use crate::renderer::css::cssom::Keyframe;
+use crate::renderer::css::cssom::KeyframesRule;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use crate::renderer::layout::calc::CalcNode;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::computed_style::DisplayType;
+use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::FontWeight;
+use crate::renderer::layout::computed_style::Length;
+use crate::renderer::layout::computed_style::LengthUnit;
+use crate::renderer::layout::property::PropertyId;
+use crate::renderer::layout::property::PropertyValue;
+use crate::renderer::layout::property::SpecifiedValue;
+use crate::renderer::layout::property::PROPERTIES;
+use crate::renderer::layout::shorthand::expand_declaration;
+use crate::renderer::layout::shorthand::Longhands;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::rc::Weak;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::str::FromStr;
+
+/// イージング関数（時間の進み具合 0〜1 → 値の進み具合）
+/// https://drafts.csswg.org/css-easing-1/
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TimingFunction {
+    Linear,
+    /// `cubic-bezier(x1, y1, x2, y2)`。`ease` などのキーワードも、決まった値のこの形になる
+    CubicBezier(f64, f64, f64, f64),
+    /// `steps(n, position)`
+    Steps(u32, StepPosition),
+}
+
+/// `steps()` のどこで値が跳ぶか
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StepPosition {
+    /// 始まった瞬間に 1 段目へ跳ぶ（`start` / `jump-start`）
+    JumpStart,
+    /// 各区間の終わりで跳ぶ（`end` / `jump-end`。既定）
+    JumpEnd,
+    /// 始まりと終わりの値も 1 段として数える（`jump-none`）
+    JumpNone,
+    /// 始まりと終わりの両方で跳ぶ（`jump-both`）
+    JumpBoth,
+}
+
+impl TimingFunction {
+    pub const EASE: TimingFunction = TimingFunction::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    pub const EASE_IN: TimingFunction = TimingFunction::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    pub const EASE_OUT: TimingFunction = TimingFunction::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    pub const EASE_IN_OUT: TimingFunction = TimingFunction::CubicBezier(0.42, 0.0, 0.58, 1.0);
+
+    /// 時間の進み具合 `progress`（0〜1）に対する値の進み具合
+    ///
+    /// 例: linear は 0.5 → 0.5、`steps(4)` は 0.5 → 0.5、0.3 → 0.25、`ease` は 0.5 → 約 0.8
+    pub fn output(&self, progress: f64) -> f64 {
+        match *self {
+            TimingFunction::Linear => progress,
+            TimingFunction::CubicBezier(x1, y1, x2, y2) => {
+                if progress <= 0.0 || progress >= 1.0 {
+                    return progress;
+                }
+                // 始点 (0, 0)・終点 (1, 1) の 3 次ベジェ曲線の 1 成分
+                let bezier = |a: f64, b: f64, t: f64| {
+                    let u = 1.0 - t;
+                    3.0 * u * u * t * a + 3.0 * u * t * t * b + t * t * t
+                };
+                // x(t) = progress となる t を二分法で探す（x1, x2 が 0〜1 なので x は t について単調増加）
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..32 {
+                    let middle = (low + high) / 2.0;
+                    if bezier(x1, x2, middle) < progress {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                bezier(y1, y2, (low + high) / 2.0)
+            }
+            TimingFunction::Steps(steps, position) => {
+                // https://drafts.csswg.org/css-easing-1/#step-easing-algo
+                let steps = steps as i64;
+                let mut step = (progress * steps as f64) as i64;
+                if matches!(position, StepPosition::JumpStart | StepPosition::JumpBoth) {
+                    step += 1;
+                }
+                let jumps = match position {
+                    StepPosition::JumpNone => steps - 1,
+                    StepPosition::JumpBoth => steps + 1,
+                    StepPosition::JumpStart | StepPosition::JumpEnd => steps,
+                };
+                step.clamp(0, jumps) as f64 / jumps as f64
+            }
+        }
+    }
+}
+
+// キーワードのイージング関数（`cubic-bezier()` / `steps()` の関数は `layout::property` が読む）
+impl FromStr for TimingFunction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "linear" => Ok(Self::Linear),
+            "ease" => Ok(Self::EASE),
+            "ease-in" => Ok(Self::EASE_IN),
+            "ease-out" => Ok(Self::EASE_OUT),
+            "ease-in-out" => Ok(Self::EASE_IN_OUT),
+            "step-start" => Ok(Self::Steps(1, StepPosition::JumpStart)),
+            "step-end" => Ok(Self::Steps(1, StepPosition::JumpEnd)),
+            _ => Err(Error::UnexpectedInput(format!(
+                "timing function {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+impl FromStr for StepPosition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "start" | "jump-start" => Ok(Self::JumpStart),
+            "end" | "jump-end" => Ok(Self::JumpEnd),
+            "jump-none" => Ok(Self::JumpNone),
+            "jump-both" => Ok(Self::JumpBoth),
+            _ => Err(Error::UnexpectedInput(format!(
+                "step position {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// 繰り返すたびに進む向き（animation-direction）
+/// https://drafts.csswg.org/css-animations-1/#animation-direction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnimationDirection {
+    Normal,
+    Reverse,
+    /// 奇数回目（0 始まり）は逆向き
+    Alternate,
+    /// 偶数回目（0 始まり）は逆向き
+    AlternateReverse,
+}
+
+impl FromStr for AnimationDirection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "reverse" => Ok(Self::Reverse),
+            "alternate" => Ok(Self::Alternate),
+            "alternate-reverse" => Ok(Self::AlternateReverse),
+            _ => Err(Error::UnexpectedInput(format!(
+                "animation-direction {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// 始まる前・終わった後にもキーフレームの値を残すか（animation-fill-mode）
+/// https://drafts.csswg.org/css-animations-1/#animation-fill-mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnimationFillMode {
+    None,
+    /// 終わった後も最後の値を残す
+    Forwards,
+    /// `animation-delay` の間も最初の値にする
+    Backwards,
+    Both,
+}
+
+impl FromStr for AnimationFillMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "forwards" => Ok(Self::Forwards),
+            "backwards" => Ok(Self::Backwards),
+            "both" => Ok(Self::Both),
+            _ => Err(Error::UnexpectedInput(format!(
+                "animation-fill-mode {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+/// `from` から `to` へ `progress` だけ進んだ値（0 = from、1 = to。イージングで少しはみ出すこともある）
+///
+/// - 補間できない組み合わせ（`display`、`auto` と長さなど）なら None
+/// - `currentColor` はその要素の color（`current_color`）として補間する
+///
+/// 仕様: https://drafts.csswg.org/css-values-4/#interpolation
+pub fn interpolate(
+    from: &PropertyValue,
+    to: &PropertyValue,
+    progress: f64,
+    current_color: Color,
+) -> Option<PropertyValue> {
+    let lerp = |a: f64, b: f64| a + (b - a) * progress;
+    let color = |value: &PropertyValue| match value {
+        PropertyValue::Color(color) => Some(*color),
+        PropertyValue::CurrentColor => Some(current_color),
+        _ => None,
+    };
+    match (from, to) {
+        (PropertyValue::Number(a), PropertyValue::Number(b)) => {
+            Some(PropertyValue::Number(lerp(*a, *b)))
+        }
+        (PropertyValue::Length(a), PropertyValue::Length(b)) => {
+            Some(PropertyValue::Length(interpolate_length(a, b, progress)))
+        }
+        (PropertyValue::FontSize(a), PropertyValue::FontSize(b)) => Some(PropertyValue::FontSize(
+            FontSize::new(lerp(a.px(), b.px()).max(0.0)),
+        )),
+        (PropertyValue::FontWeight(a), PropertyValue::FontWeight(b)) => {
+            let weight = lerp(a.value() as f64, b.value() as f64).clamp(1.0, 1000.0);
+            Some(PropertyValue::FontWeight(FontWeight::new(
+                (weight + 0.5) as u16,
+            )))
+        }
+        _ => match (color(from), color(to)) {
+            (Some(a), Some(b)) => Some(PropertyValue::Color(interpolate_color(a, b, progress))),
+            _ => None,
+        },
+    }
+}
+
+// 長さ: 単位が同じなら値を、違えば `calc(a * (1 - p) + b * p)` にする
+fn interpolate_length(from: &Length, to: &Length, progress: f64) -> Length {
+    if let (Length::Dimension(a, from_unit), Length::Dimension(b, to_unit)) = (from, to) {
+        if from_unit == to_unit {
+            return Length::new(a + (b - a) * progress, *from_unit);
+        }
+    }
+    let node = |length: &Length| match length {
+        Length::Dimension(value, unit) => CalcNode::Dimension(*value, *unit),
+        Length::Calc(node) => (**node).clone(),
+    };
+    let scaled = |length: &Length, factor: f64| {
+        CalcNode::Mul(Box::new(CalcNode::Number(factor)), Box::new(node(length)))
+    };
+    Length::calc(CalcNode::Add(
+        Box::new(scaled(from, 1.0 - progress)),
+        Box::new(scaled(to, progress)),
+    ))
+}
+
+// 色: alpha を掛けた（premultiplied）各チャンネルを補間し、補間した alpha で割り戻す
+// https://www.w3.org/TR/css-color-4/#interpolation-alpha
+fn interpolate_color(from: Color, to: Color, progress: f64) -> Color {
+    let alpha = from.alpha() + (to.alpha() - from.alpha()) * progress;
+    if alpha <= 0.0 {
+        return Color::transparent();
+    }
+    let channel = |a: u8, b: u8| {
+        let a = a as f64 * from.alpha();
+        let b = b as f64 * to.alpha();
+        let value = (a + (b - a) * progress) / alpha;
+        (value.clamp(0.0, 255.0) + 0.5) as u8
+    };
+    Color::rgba(
+        channel(from.red(), to.red()),
+        channel(from.green(), to.green()),
+        channel(from.blue(), to.blue()),
+        alpha,
+    )
+}
+
+/// アニメーションの時刻と、要素ごとのトランジション・アニメーションの状態
+///
+/// - 時刻（ミリ秒）は `set_current_time` で組み込み側の時計から受け取ります。自分では進めません。
+/// - スタイル計算のたびに `start_style_update` → 要素ごとに `animate` → `finish_style_update` と呼ばれます
+///   （`LayoutView::new_with_timeline`）。トランジションの開始は、前回のスタイル計算の値と比べて決めます。
+#[derive(Debug, Clone, Default)]
+pub struct AnimationTimeline {
+    current_time: f64,
+    // トランジションかアニメーションを持つ要素の状態（キーは DOM ノードのアドレス）
+    elements: BTreeMap<usize, ElementAnimations>,
+}
+
+// 1 つの要素の状態
+#[derive(Debug, Clone)]
+struct ElementAnimations {
+    node: Weak<RefCell<Node>>,
+    // 前回のスタイル計算で決まった、アニメーションを適用する前のスタイル
+    base: ComputedStyle,
+    transitions: Vec<Transition>,
+    animations: Vec<Animation>,
+    // 今回のスタイル計算で見たか（見なかった要素は DOM から消えたか display:none の中）
+    seen: bool,
+}
+
+// 動いているトランジション 1 つ
+#[derive(Debug, Clone)]
+struct Transition {
+    property: PropertyId,
+    from: PropertyValue,
+    to: PropertyValue,
+    // 値が変わった時刻（ここから delay だけ待って動き始める）
+    start_time: f64,
+    delay: f64,
+    duration: f64,
+    timing_function: TimingFunction,
+}
+
+// `animation-name` に書かれたアニメーション 1 つ
+#[derive(Debug, Clone)]
+struct Animation {
+    name: String,
+    // `animation-name` の何番目か（`animation-duration` などの同じ番目の値を使う）
+    index: usize,
+    // アニメーションが付いた時刻
+    start_time: f64,
+    // 終わったか（終わった後は、fill-mode で値を残すかどうかだけ）
+    finished: bool,
+}
+
+// `animation-*` の値のうち、1 つのアニメーションに当たるもの
+struct AnimationTiming {
+    duration: f64,
+    delay: f64,
+    iterations: f64,
+    direction: AnimationDirection,
+    fill_mode: AnimationFillMode,
+    timing_function: TimingFunction,
+}
+
+impl AnimationTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 今の時刻（ミリ秒）
+    pub fn current_time(&self) -> f64 {
+        self.current_time
+    }
+
+    /// 時刻を組み込み側の時計の値にする（反映されるのは次のスタイル計算から）
+    pub fn set_current_time(&mut self, now: f64) {
+        self.current_time = now;
+    }
+
+    /// まだ動いているトランジション・アニメーションがあるか（次のフレームが要るか）
+    ///
+    /// - `infinite` のアニメーションがあれば常に true
+    /// - `animation-delay` で待っている間も true（始まったら値が変わるため）
+    pub fn is_running(&self) -> bool {
+        self.elements.values().any(|element| {
+            !element.transitions.is_empty() || element.animations.iter().any(|a| !a.finished)
+        })
+    }
+
+    /// すべての状態を捨てる（別の文書を表示するとき）。時刻はそのまま
+    pub fn clear(&mut self) {
+        self.elements.clear();
+    }
+
+    /// スタイル計算を始める
+    pub fn start_style_update(&mut self) {
+        for element in self.elements.values_mut() {
+            element.seen = false;
+        }
+    }
+
+    /// スタイル計算を終える。今回見なかった要素のトランジション・アニメーションは止める
+    pub fn finish_style_update(&mut self) {
+        self.elements.retain(|_, element| element.seen);
+    }
+
+    /// 要素 `node` のスタイル（カスケードと defaulting を終えたもの）に、今の時刻の値を上書きする
+    ///
+    /// - 前回から値が変わったプロパティは、`transition-*` に従ってトランジションを始める
+    /// - `animation-name` に新しく現れたアニメーションは、今の時刻から始める
+    /// - `display: none` の要素では、どちらも止める
+    /// - `stylesheets` は `@keyframes` を探すのに使う
+    pub fn animate(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        style: &mut ComputedStyle,
+        stylesheets: &[StyleSheet],
+    ) {
+        if !matches!(node.borrow().kind(), NodeKind::Element(_)) {
+            return;
+        }
+        let key = Rc::as_ptr(node) as usize;
+        if style.display() == DisplayType::DisplayNone {
+            self.elements.remove(&key);
+            return;
+        }
+
+        let wanted = has_transitions(style) || has_animations(style);
+        let known = matches!(
+            self.elements.get(&key),
+            Some(element) if element.node.upgrade().is_some_and(|n| Rc::ptr_eq(&n, node))
+        );
+        if !known {
+            if !wanted {
+                self.elements.remove(&key);
+                return;
+            }
+            // 初めて見た要素は、前の値が無いのでトランジションしない
+            self.elements.insert(
+                key,
+                ElementAnimations {
+                    node: Rc::downgrade(node),
+                    base: style.clone(),
+                    transitions: Vec::new(),
+                    animations: Vec::new(),
+                    seen: false,
+                },
+            );
+        }
+
+        let now = self.current_time;
+        let element = match self.elements.get_mut(&key) {
+            Some(element) => element,
+            None => return,
+        };
+        element.seen = true;
+        element.update_transitions(style, now);
+        element.base = style.clone();
+        element.update_animations(style, now);
+        element.apply(style, stylesheets, now);
+
+        if !wanted && element.transitions.is_empty() && element.animations.is_empty() {
+            self.elements.remove(&key);
+        }
+    }
+}
+
+impl ElementAnimations {
+    // 前回のスタイル（`base`）と比べて、値が変わったプロパティのトランジションを始める
+    // https://drafts.csswg.org/css-transitions-1/#starting
+    fn update_transitions(&mut self, after: &ComputedStyle, now: f64) {
+        self.transitions.retain(|t| now < t.end_time());
+        for definition in PROPERTIES.iter() {
+            let id = definition.id;
+            let to = after.get(id);
+            let running = self.transitions.iter().position(|t| t.property == id);
+            if let Some(i) = running {
+                if self.transitions[i].to == *to {
+                    continue;
+                }
+            }
+            // 途中で値が変わったら、今の値から新しい値へ向かい直す
+            let from = match running {
+                Some(i) => self.transitions.remove(i).value_at(now, after.color()),
+                None => self.base.get(id).clone(),
+            };
+            if from == *to {
+                continue;
+            }
+            let (duration, delay, timing_function) = match transition_for(after, id) {
+                Some(transition) => transition,
+                None => continue,
+            };
+            // 補間できない値（display など）はトランジションしない
+            if interpolate(&from, to, 0.0, after.color()).is_none() {
+                continue;
+            }
+            self.transitions.push(Transition {
+                property: id,
+                from,
+                to: to.clone(),
+                start_time: now,
+                delay,
+                duration,
+                timing_function,
+            });
+        }
+    }
+
+    // `animation-name` に合わせて、アニメーションの並びを作り直す（続いているものは開始時刻を引き継ぐ）
+    fn update_animations(&mut self, style: &ComputedStyle, now: f64) {
+        let mut animations = Vec::new();
+        if let PropertyValue::List(names) = style.get(PropertyId::AnimationName) {
+            for (index, name) in names.iter().enumerate() {
+                if let PropertyValue::Ident(name) = name {
+                    let start_time = match self.animations.iter().find(|a| a.name == *name) {
+                        Some(animation) => animation.start_time,
+                        None => now,
+                    };
+                    animations.push(Animation {
+                        name: name.clone(),
+                        index,
+                        start_time,
+                        finished: false,
+                    });
+                }
+            }
+        }
+        self.animations = animations;
+    }
+
+    // 今の時刻の値を `style` に上書きする（アニメーションは後ろに書いたものが勝ち、トランジションはその上）
+    fn apply(&mut self, style: &mut ComputedStyle, stylesheets: &[StyleSheet], now: f64) {
+        for animation in self.animations.iter_mut() {
+            let keyframes = match find_keyframes(stylesheets, &animation.name) {
+                Some(keyframes) => keyframes,
+                None => {
+                    // 同じ名前の @keyframes が無ければ何もしない
+                    animation.finished = true;
+                    continue;
+                }
+            };
+            let timing = AnimationTiming::new(&self.base, animation.index);
+            let (progress, finished) = timing.progress(now - animation.start_time);
+            animation.finished = finished;
+            if let Some(progress) = progress {
+                apply_keyframes(
+                    keyframes,
+                    progress,
+                    timing.timing_function,
+                    &self.base,
+                    style,
+                );
+            }
+        }
+        for transition in &self.transitions {
+            let value = transition.value_at(now, style.color());
+            set_animated_value(style, transition.property, value);
+        }
+    }
+}
+
+impl Transition {
+    // 値の変化が終わる時刻
+    fn end_time(&self) -> f64 {
+        self.start_time + self.delay + self.duration.max(0.0)
+    }
+
+    // 時刻 `now` の値（delay の間は元の値）
+    fn value_at(&self, now: f64, current_color: Color) -> PropertyValue {
+        let elapsed = now - self.start_time - self.delay;
+        if elapsed < 0.0 {
+            return self.from.clone();
+        }
+        let progress = if self.duration <= 0.0 {
+            1.0
+        } else {
+            (elapsed / self.duration).min(1.0)
+        };
+        let progress = self.timing_function.output(progress);
+        interpolate(&self.from, &self.to, progress, current_color).unwrap_or(self.to.clone())
+    }
+}
+
+impl AnimationTiming {
+    // `index` 番目のアニメーションの値（リストが短ければ繰り返して使う）
+    fn new(style: &ComputedStyle, index: usize) -> Self {
+        let duration = match list_item(style, PropertyId::AnimationDuration, index) {
+            PropertyValue::Time(time) => *time,
+            _ => 0.0,
+        };
+        let delay = match list_item(style, PropertyId::AnimationDelay, index) {
+            PropertyValue::Time(time) => *time,
+            _ => 0.0,
+        };
+        let iterations = match list_item(style, PropertyId::AnimationIterationCount, index) {
+            PropertyValue::Number(count) => *count,
+            _ => 1.0,
+        };
+        let direction = match list_item(style, PropertyId::AnimationDirection, index) {
+            PropertyValue::AnimationDirection(direction) => *direction,
+            _ => AnimationDirection::Normal,
+        };
+        let fill_mode = match list_item(style, PropertyId::AnimationFillMode, index) {
+            PropertyValue::AnimationFillMode(fill_mode) => *fill_mode,
+            _ => AnimationFillMode::None,
+        };
+        let timing_function = match list_item(style, PropertyId::AnimationTimingFunction, index) {
+            PropertyValue::TimingFunction(timing_function) => *timing_function,
+            _ => TimingFunction::EASE,
+        };
+        Self {
+            duration,
+            delay,
+            iterations,
+            direction,
+            fill_mode,
+            timing_function,
+        }
+    }
+
+    // 付いてから `elapsed` ミリ秒の、キーフレームの上での位置（0〜1。向きを反映済み）と、終わったか
+    // 値を出さない（始まる前・終わった後で fill-mode が値を残さない）ときは None
+    // https://drafts.csswg.org/web-animations-1/#calculating-the-directed-progress
+    fn progress(&self, elapsed: f64) -> (Option<f64>, bool) {
+        let active_duration = if self.duration <= 0.0 {
+            0.0
+        } else {
+            self.duration * self.iterations
+        };
+        let time = elapsed - self.delay;
+        let (overall, finished) = if time < 0.0 {
+            if !matches!(
+                self.fill_mode,
+                AnimationFillMode::Backwards | AnimationFillMode::Both
+            ) {
+                return (None, false);
+            }
+            (0.0, false)
+        } else if time >= active_duration {
+            if !matches!(
+                self.fill_mode,
+                AnimationFillMode::Forwards | AnimationFillMode::Both
+            ) {
+                return (None, true);
+            }
+            (self.iterations, true)
+        } else {
+            (time / self.duration, false)
+        };
+
+        // 何回目（0 始まり）の、どこまで進んだか
+        let (mut iteration, mut progress) = if overall.is_infinite() {
+            (0, 1.0)
+        } else {
+            let iteration = overall as u64;
+            (iteration, overall - iteration as f64)
+        };
+        // 終わった時点がちょうど回の区切りなら、前の回の終わりとする（`to` の値を残す）
+        if finished && progress == 0.0 && overall > 0.0 {
+            iteration -= 1;
+            progress = 1.0;
+        }
+        let forwards = match self.direction {
+            AnimationDirection::Normal => true,
+            AnimationDirection::Reverse => false,
+            AnimationDirection::Alternate => iteration % 2 == 0,
+            AnimationDirection::AlternateReverse => iteration % 2 == 1,
+        };
+        let progress = if forwards { progress } else { 1.0 - progress };
+        (Some(progress), finished)
+    }
+}
+
+// トランジションの指定（`transition-property` が空でなく、時間が 0 より長いものが 1 つでもある）があるか
+fn has_transitions(style: &ComputedStyle) -> bool {
+    match style.get(PropertyId::TransitionProperty) {
+        PropertyValue::List(properties) => {
+            (0..properties.len()).any(|i| combined_duration(style, i) > 0.0)
+        }
+        _ => false,
+    }
+}
+
+// `animation-name` に `none` 以外の名前があるか
+fn has_animations(style: &ComputedStyle) -> bool {
+    match style.get(PropertyId::AnimationName) {
+        PropertyValue::List(names) => names
+            .iter()
+            .any(|name| matches!(name, PropertyValue::Ident(_))),
+        _ => false,
+    }
+}
+
+// `index` 番目のトランジションの duration と delay の和（これが正のときだけトランジションする）
+fn combined_duration(style: &ComputedStyle, index: usize) -> f64 {
+    let time = |id| match list_item(style, id, index) {
+        PropertyValue::Time(time) => *time,
+        _ => 0.0,
+    };
+    time(PropertyId::TransitionDuration).max(0.0) + time(PropertyId::TransitionDelay)
+}
+
+// プロパティ `id` のトランジションの (duration, delay, イージング)。当てはまる項目が無ければ None
+// `transition-property` に何度も現れたら、後ろの項目を使う
+fn transition_for(style: &ComputedStyle, id: PropertyId) -> Option<(f64, f64, TimingFunction)> {
+    let properties = match style.get(PropertyId::TransitionProperty) {
+        PropertyValue::List(properties) => properties,
+        _ => return None,
+    };
+    let index = properties.iter().rposition(|property| {
+        matches!(property, PropertyValue::Ident(name) if name == "all" || name == id.definition().name)
+    })?;
+    if combined_duration(style, index) <= 0.0 {
+        return None;
+    }
+    let duration = match list_item(style, PropertyId::TransitionDuration, index) {
+        PropertyValue::Time(time) => *time,
+        _ => 0.0,
+    };
+    let delay = match list_item(style, PropertyId::TransitionDelay, index) {
+        PropertyValue::Time(time) => *time,
+        _ => 0.0,
+    };
+    let timing_function = match list_item(style, PropertyId::TransitionTimingFunction, index) {
+        PropertyValue::TimingFunction(timing_function) => *timing_function,
+        _ => TimingFunction::EASE,
+    };
+    Some((duration, delay, timing_function))
+}
+
+// カンマ区切りのプロパティの `index` 番目の値（短ければ先頭から繰り返す）
+// https://drafts.csswg.org/css-transitions-1/#transition-property-property
+fn list_item(style: &ComputedStyle, id: PropertyId, index: usize) -> &PropertyValue {
+    match style.get(id) {
+        PropertyValue::List(items) if !items.is_empty() => &items[index % items.len()],
+        value => value,
+    }
+}
+
+// 名前が `name` の `@keyframes`（同じ名前が複数あれば最後のもの）
+fn find_keyframes<'a>(stylesheets: &'a [StyleSheet], name: &str) -> Option<&'a KeyframesRule> {
+    stylesheets
+        .iter()
+        .flat_map(|sheet| sheet.keyframes.iter())
+        .filter(|keyframes| keyframes.name == name)
+        .last()
+}
+
+// トランジション・アニメーション自身のプロパティ（キーフレームの中では使えない）
+fn is_animation_property(id: PropertyId) -> bool {
+    matches!(
+        id,
+        PropertyId::TransitionProperty
+            | PropertyId::TransitionDuration
+            | PropertyId::TransitionTimingFunction
+            | PropertyId::TransitionDelay
+            | PropertyId::AnimationName
+            | PropertyId::AnimationDuration
+            | PropertyId::AnimationTimingFunction
+            | PropertyId::AnimationDelay
+            | PropertyId::AnimationIterationCount
+            | PropertyId::AnimationDirection
+            | PropertyId::AnimationFillMode
+    )
+}
+
+// キーフレームの上の位置 `progress` の値を `style` に上書きする
+//
+// - プロパティごとに、そのプロパティを指定したキーフレームだけを時点の順に並べる
+// - 0% / 100% に指定が無ければ、アニメーション前の値（`base`）をその時点の値にする
+// - `progress` を挟む 2 つのキーフレームの間を、前のキーフレームのイージングで補間する
+//   （キーフレームに `animation-timing-function` が無ければ要素の値）
+// https://drafts.csswg.org/css-animations-1/#keyframes
+fn apply_keyframes(
+    rule: &KeyframesRule,
+    progress: f64,
+    timing_function: TimingFunction,
+    base: &ComputedStyle,
+    style: &mut ComputedStyle,
+) {
+    // (時点, そのキーフレームの値, イージング)。同じ時点なら後に書いたものが後ろ
+    let mut frames: Vec<(f64, Longhands, TimingFunction)> = Vec::new();
+    for keyframe in &rule.keyframes {
+        let (values, keyframe_timing) = keyframe_values(keyframe, base);
+        for offset in &keyframe.offsets {
+            let timing = keyframe_timing.unwrap_or(timing_function);
+            frames.push((*offset, values.clone(), timing));
+        }
+    }
+    frames.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    for definition in PROPERTIES.iter() {
+        let id = definition.id;
+        let mut points: Vec<(f64, &PropertyValue, TimingFunction)> = frames
+            .iter()
+            .filter_map(|(offset, values, timing)| {
+                values
+                    .iter()
+                    .rev()
+                    .find(|(property, _)| *property == id)
+                    .map(|(_, value)| (*offset, value, *timing))
+            })
+            .collect();
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => continue,
+        };
+        if first > 0.0 {
+            points.insert(0, (0.0, base.get(id), timing_function));
+        }
+        if last < 1.0 {
+            points.push((1.0, base.get(id), timing_function));
+        }
+
+        // progress を含む区間 [points[i], points[i + 1]]
+        let i = points
+            .iter()
+            .rposition(|(offset, _, _)| *offset <= progress)
+            .unwrap_or(0)
+            .min(points.len() - 2);
+        let (start, from, timing) = points[i];
+        let (end, to, _) = points[i + 1];
+        let local = if end > start {
+            (progress - start) / (end - start)
+        } else {
+            1.0
+        };
+        let eased = timing.output(local);
+        let value = match interpolate(from, to, eased, base.color()) {
+            Some(value) => value,
+            // 補間できない値は真ん中で切り替える
+            None if eased < 0.5 => from.clone(),
+            None => to.clone(),
+        };
+        set_animated_value(style, id, value);
+    }
+}
+
+// キーフレームの宣言を計算値にしたもの（`!important` は CSSOM で除いてある）と、
+// キーフレームの `animation-timing-function`
+fn keyframe_values(
+    keyframe: &Keyframe,
+    base: &ComputedStyle,
+) -> (Longhands, Option<TimingFunction>) {
+    let font_size = base.font_size().px();
+    let root_font_size = base.root_font_size().px();
+    let mut values = Vec::new();
+    let mut timing_function = None;
+    for declaration in &keyframe.declarations {
+        for (id, specified) in expand_declaration(declaration) {
+            if id == PropertyId::AnimationTimingFunction {
+                if let SpecifiedValue::Value(PropertyValue::List(items)) = &specified {
+                    if let Some(PropertyValue::TimingFunction(timing)) = items.first() {
+                        timing_function = Some(*timing);
+                    }
+                }
+                continue;
+            }
+            if is_animation_property(id) {
+                continue;
+            }
+            let value = match specified {
+                SpecifiedValue::Value(value) => value,
+                SpecifiedValue::Initial => (id.definition().initial)(),
+                _ => continue,
+            };
+            let value = match (id, value) {
+                (PropertyId::FontSize, PropertyValue::Length(length)) => {
+                    match length.absolutize(font_size, root_font_size) {
+                        Length::Dimension(px, LengthUnit::Px) => {
+                            PropertyValue::FontSize(FontSize::new(px.max(0.0)))
+                        }
+                        Length::Dimension(percent, LengthUnit::Percent) => {
+                            PropertyValue::FontSize(FontSize::new(font_size * percent / 100.0))
+                        }
+                        _ => continue,
+                    }
+                }
+                (_, PropertyValue::Length(length)) => {
+                    PropertyValue::Length(length.absolutize(font_size, root_font_size))
+                }
+                (_, PropertyValue::Bolder | PropertyValue::Lighter) => continue,
+                (_, value) => value,
+            };
+            values.push((id, value));
+        }
+    }
+    (values, timing_function)
+}
+
+// 補間した値をスタイルに入れる（イージングではみ出した opacity は 0〜1 に収める）
+fn set_animated_value(style: &mut ComputedStyle, id: PropertyId, value: PropertyValue) {
+    let value = match (id, value) {
+        (PropertyId::Opacity, PropertyValue::Number(opacity)) => {
+            PropertyValue::Number(opacity.clamp(0.0, 1.0))
+        }
+        (_, value) => value,
+    };
+    style.set(id, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        let difference = a - b;
+        -0.001 < difference && difference < 0.001
+    }
+
+    #[test]
+    fn test_timing_functions() {
+        assert_eq!(TimingFunction::Linear.output(0.25), 0.25);
+        // ease は速く動き始めてゆっくり止まる
+        assert!(close(TimingFunction::EASE.output(0.5), 0.8024));
+        assert!(close(TimingFunction::EASE_IN_OUT.output(0.5), 0.5));
+        assert_eq!(TimingFunction::EASE.output(1.0), 1.0);
+
+        let steps = |n, position: &str| {
+            let position = StepPosition::from_str(position).expect("valid position");
+            let f = TimingFunction::Steps(n, position);
+            [0.0, 0.3, 0.5, 1.0].map(|p| f.output(p))
+        };
+        assert_eq!(steps(4, "end"), [0.0, 0.25, 0.5, 1.0]);
+        assert_eq!(steps(4, "start"), [0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(steps(3, "jump-none"), [0.0, 0.0, 0.5, 1.0]);
+        assert_eq!(steps(3, "jump-both"), [0.25, 0.25, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let black = Color::black();
+        assert_eq!(
+            interpolate(
+                &PropertyValue::Color(black),
+                &PropertyValue::Color(Color::rgb(255, 0, 100)),
+                0.5,
+                black
+            ),
+            Some(PropertyValue::Color(Color::rgb(128, 0, 50)))
+        );
+        // 透明な色の RGB は効かない（alpha を掛けてから補間する）
+        assert_eq!(
+            interpolate(
+                &PropertyValue::Color(Color::transparent()),
+                &PropertyValue::Color(Color::rgb(0, 0, 255)),
+                0.5,
+                black
+            ),
+            Some(PropertyValue::Color(Color::rgba(0, 0, 255, 0.5)))
+        );
+        // currentColor はその要素の color として補間する
+        assert_eq!(
+            interpolate(
+                &PropertyValue::CurrentColor,
+                &PropertyValue::Color(Color::white()),
+                1.0,
+                black
+            ),
+            Some(PropertyValue::Color(Color::white()))
+        );
+        assert_eq!(
+            interpolate(
+                &PropertyValue::Number(0.0),
+                &PropertyValue::Number(1.0),
+                0.25,
+                black
+            ),
+            Some(PropertyValue::Number(0.25))
+        );
+        assert_eq!(
+            interpolate(
+                &PropertyValue::Length(Length::px(10.0)),
+                &PropertyValue::Length(Length::px(20.0)),
+                0.5,
+                black
+            ),
+            Some(PropertyValue::Length(Length::px(15.0)))
+        );
+        // 単位が違えば calc() にする（% の基準が 200px なら 0px と 100px の真ん中）
+        match interpolate(
+            &PropertyValue::Length(Length::px(0.0)),
+            &PropertyValue::Length(Length::new(50.0, LengthUnit::Percent)),
+            0.5,
+            black,
+        ) {
+            Some(PropertyValue::Length(length)) => {
+                use crate::renderer::layout::layout_object::LayoutSize;
+                assert_eq!(length.to_px(200, LayoutSize::new(0, 0)), 50);
+            }
+            other => panic!("unexpected value: {:?}", other),
+        }
+        // 補間できない組み合わせ
+        assert_eq!(
+            interpolate(
+                &PropertyValue::Auto,
+                &PropertyValue::Length(Length::px(20.0)),
+                0.5,
+                black
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_directed_progress() {
+        let timing = AnimationTiming {
+            duration: 1000.0,
+            delay: 500.0,
+            iterations: 2.0,
+            direction: AnimationDirection::Alternate,
+            fill_mode: AnimationFillMode::Forwards,
+            timing_function: TimingFunction::Linear,
+        };
+        // delay の間は値を出さない（backwards ではない）
+        assert_eq!(timing.progress(0.0), (None, false));
+        assert_eq!(timing.progress(750.0), (Some(0.25), false));
+        // 2 回目は逆向き
+        assert_eq!(timing.progress(1750.0), (Some(0.75), false));
+        // 終わった後は最後の値（2 回目の終わり = 0）を残す
+        assert_eq!(timing.progress(3000.0), (Some(0.0), true));
+    }
+}
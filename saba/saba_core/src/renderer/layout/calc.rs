@@ -0,0 +1,367 @@
+//! 数式の関数（`calc()` / `min()` / `max()` / `clamp()`）
+//!
+//! 役割
+//! - `width: calc(100% - 20px)` のような値を、式の木（`CalcNode`）に読みます。
+//! - 式の型（数値か長さか）を確かめ、単位の組み合わせがおかしい式は読めない値として捨てます。
+//!   - `+` / `-` と min / max / clamp の引数は、同じ型どうしでしか使えない（`1px + 2` は不可）
+//!   - `*` は片方が数値のときだけ、`/` は右が数値のときだけ使える（`10px * 2px` は不可）
+//! - 値を決めるのは 2 段階です（`Length` と同じ）。
+//!   1) 計算値: em / rem / ch / pt を px にする。% / vw / vh が無ければ、式をその場で 1 つの px にまとめる
+//!   2) レイアウト: 残った % と vw / vh を包含ブロックとビューポートから決めて、式を計算する
+//!
+//! 例
+//! ```text
+//! calc(100% - 20px)          幅 300px の包含ブロックなら 280px
+//! min(50%, 200px)            幅 300px なら 150px、幅 600px なら 200px
+//! clamp(10px, 2em, 30px)     font-size 16px なら 2em = 32px が上限を超えるので 30px
+//! calc(1px + 2)              読めない（長さと数値は足せない）
+//! ```
+//!
+//! 簡易化している点
+//! - 空白は宣言を読むときに取り除かれているので、`+` / `-` の前後に空白が必要という規則は確かめない。
+//! - 角度・時間などの型は扱わない（長さと数値だけ）。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `CalcNode` は TS の判別共用体 `{ kind: "add"; left; right } | ...` のような木。
+//!   `Box` は子の木を指すポインタです（再帰する型の大きさを決めるため）。
+//!
+//! 仕様: https://www.w3.org/TR/css-values-4/#math
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::layout::computed_style::LengthUnit;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// 数式の木
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    /// 単位の無い数値（`2`）
+    Number(f64),
+    /// 単位付きの長さ・%（`20px`、`100%`）
+    Dimension(f64, LengthUnit),
+    Add(Box<CalcNode>, Box<CalcNode>),
+    Sub(Box<CalcNode>, Box<CalcNode>),
+    Mul(Box<CalcNode>, Box<CalcNode>),
+    Div(Box<CalcNode>, Box<CalcNode>),
+    Min(Vec<CalcNode>),
+    Max(Vec<CalcNode>),
+    /// `clamp(min, value, max)`
+    Clamp(Box<CalcNode>, Box<CalcNode>, Box<CalcNode>),
+}
+
+/// 式の型
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CalcType {
+    Number,
+    /// 長さ（% を含む）
+    Length,
+}
+
+/// 値が数式の関数か（`calc(` などで始まるか）
+pub fn is_math_function(values: &[ComponentValue]) -> bool {
+    matches!(
+        values,
+        [ComponentValue::Function(name), ..] if math_function_name(name).is_some()
+    )
+}
+
+fn math_function_name(name: &str) -> Option<&'static str> {
+    ["calc", "min", "max", "clamp"]
+        .into_iter()
+        .find(|f| name.eq_ignore_ascii_case(f))
+}
+
+/// 値全体が 1 つの数式の関数なら、式の木に読む
+///
+/// - 書き方が正しくない・型が合わない式は None
+///
+/// 例: `[Function("calc"), Percentage(100), Delim('-'), Dimension(20, "px"), )]`
+///     → `Sub(Dimension(100, Percent), Dimension(20, Px))`
+pub fn parse(values: &[ComponentValue]) -> Option<CalcNode> {
+    let mut parser = Parser { values, pos: 0 };
+    let node = parser.math_function()?;
+    if parser.pos != values.len() {
+        return None;
+    }
+    node.calc_type()?;
+    Some(node)
+}
+
+// 再帰下降で読む（`pos` が次に読むコンポーネント値）
+//
+// sum     := product (('+' | '-') product)*
+// product := value (('*' | '/') value)*
+// value   := 数値 | 長さ | % | '(' sum ')' | 数式の関数
+struct Parser<'a> {
+    values: &'a [ComponentValue],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&ComponentValue> {
+        self.values.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&ComponentValue> {
+        let value = self.values.get(self.pos);
+        self.pos += 1;
+        value
+    }
+
+    fn expect(&mut self, expected: ComponentValue) -> Option<()> {
+        (self.next()? == &expected).then_some(())
+    }
+
+    fn math_function(&mut self) -> Option<CalcNode> {
+        let name = match self.next()? {
+            ComponentValue::Function(name) => math_function_name(name)?,
+            _ => return None,
+        };
+        let mut args = Vec::new();
+        loop {
+            args.push(self.sum()?);
+            match self.next()? {
+                ComponentValue::Comma => continue,
+                ComponentValue::CloseParenthesis => break,
+                _ => return None,
+            }
+        }
+        match (name, args.len()) {
+            ("calc", 1) => args.pop(),
+            ("min", _) => Some(CalcNode::Min(args)),
+            ("max", _) => Some(CalcNode::Max(args)),
+            ("clamp", 3) => {
+                let max = args.pop()?;
+                let value = args.pop()?;
+                let min = args.pop()?;
+                Some(CalcNode::Clamp(
+                    Box::new(min),
+                    Box::new(value),
+                    Box::new(max),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Option<CalcNode> {
+        let mut node = self.product()?;
+        loop {
+            let add = match self.peek() {
+                Some(ComponentValue::Delim('+')) => true,
+                Some(ComponentValue::Delim('-')) => false,
+                _ => return Some(node),
+            };
+            self.pos += 1;
+            let right = Box::new(self.product()?);
+            node = if add {
+                CalcNode::Add(Box::new(node), right)
+            } else {
+                CalcNode::Sub(Box::new(node), right)
+            };
+        }
+    }
+
+    fn product(&mut self) -> Option<CalcNode> {
+        let mut node = self.value()?;
+        loop {
+            let mul = match self.peek() {
+                Some(ComponentValue::Delim('*')) => true,
+                Some(ComponentValue::Delim('/')) => false,
+                _ => return Some(node),
+            };
+            self.pos += 1;
+            let right = Box::new(self.value()?);
+            node = if mul {
+                CalcNode::Mul(Box::new(node), right)
+            } else {
+                CalcNode::Div(Box::new(node), right)
+            };
+        }
+    }
+
+    fn value(&mut self) -> Option<CalcNode> {
+        match self.peek()? {
+            ComponentValue::Number(n, _) => {
+                let n = *n;
+                self.pos += 1;
+                Some(CalcNode::Number(n))
+            }
+            ComponentValue::Percentage(n) => {
+                let n = *n;
+                self.pos += 1;
+                Some(CalcNode::Dimension(n, LengthUnit::Percent))
+            }
+            ComponentValue::Dimension(n, _, unit) => {
+                let node = CalcNode::Dimension(*n, LengthUnit::from_str(unit).ok()?);
+                self.pos += 1;
+                Some(node)
+            }
+            ComponentValue::OpenParenthesis => {
+                self.pos += 1;
+                let node = self.sum()?;
+                self.expect(ComponentValue::CloseParenthesis)?;
+                Some(node)
+            }
+            ComponentValue::Function(_) => self.math_function(),
+            _ => None,
+        }
+    }
+}
+
+impl CalcNode {
+    /// 式の型。型が合わない式なら None
+    ///
+    /// 例: `10px * 2` → Length、`10px * 2px` → None、`(1 + 2) / 3` → Number
+    pub fn calc_type(&self) -> Option<CalcType> {
+        match self {
+            CalcNode::Number(_) => Some(CalcType::Number),
+            CalcNode::Dimension(..) => Some(CalcType::Length),
+            CalcNode::Add(left, right) | CalcNode::Sub(left, right) => {
+                let t = left.calc_type()?;
+                (right.calc_type()? == t).then_some(t)
+            }
+            CalcNode::Mul(left, right) => match (left.calc_type()?, right.calc_type()?) {
+                (CalcType::Number, t) | (t, CalcType::Number) => Some(t),
+                _ => None,
+            },
+            CalcNode::Div(left, right) => match right.calc_type()? {
+                CalcType::Number => left.calc_type(),
+                CalcType::Length => None,
+            },
+            CalcNode::Min(args) | CalcNode::Max(args) => {
+                let t = args.first()?.calc_type()?;
+                for arg in args {
+                    if arg.calc_type()? != t {
+                        return None;
+                    }
+                }
+                Some(t)
+            }
+            CalcNode::Clamp(min, value, max) => {
+                let t = value.calc_type()?;
+                (min.calc_type()? == t && max.calc_type()? == t).then_some(t)
+            }
+        }
+    }
+
+    /// 式に `unit` の長さが含まれるか
+    pub fn has_unit(&self, unit: LengthUnit) -> bool {
+        match self {
+            CalcNode::Number(_) => false,
+            CalcNode::Dimension(_, u) => *u == unit,
+            CalcNode::Add(left, right)
+            | CalcNode::Sub(left, right)
+            | CalcNode::Mul(left, right)
+            | CalcNode::Div(left, right) => left.has_unit(unit) || right.has_unit(unit),
+            CalcNode::Min(args) | CalcNode::Max(args) => args.iter().any(|a| a.has_unit(unit)),
+            CalcNode::Clamp(min, value, max) => {
+                min.has_unit(unit) || value.has_unit(unit) || max.has_unit(unit)
+            }
+        }
+    }
+
+    /// 長さ（`Dimension`）を `f` で置き換えた式
+    pub fn map_dimensions(&self, f: &dyn Fn(f64, LengthUnit) -> CalcNode) -> CalcNode {
+        let map = |node: &CalcNode| Box::new(node.map_dimensions(f));
+        match self {
+            CalcNode::Number(n) => CalcNode::Number(*n),
+            CalcNode::Dimension(n, unit) => f(*n, *unit),
+            CalcNode::Add(left, right) => CalcNode::Add(map(left), map(right)),
+            CalcNode::Sub(left, right) => CalcNode::Sub(map(left), map(right)),
+            CalcNode::Mul(left, right) => CalcNode::Mul(map(left), map(right)),
+            CalcNode::Div(left, right) => CalcNode::Div(map(left), map(right)),
+            CalcNode::Min(args) => {
+                CalcNode::Min(args.iter().map(|a| a.map_dimensions(f)).collect())
+            }
+            CalcNode::Max(args) => {
+                CalcNode::Max(args.iter().map(|a| a.map_dimensions(f)).collect())
+            }
+            CalcNode::Clamp(min, value, max) => CalcNode::Clamp(map(min), map(value), map(max)),
+        }
+    }
+
+    /// 式を計算する（長さは `dimension` で px にする）
+    ///
+    /// - 0 で割ると無限大（0 / 0 は NaN）になる。px にするとき `computed_style::clamp_px` で有限の値にする
+    /// - `clamp` は min が max より大きければ min を使う
+    pub fn evaluate(&self, dimension: &dyn Fn(f64, LengthUnit) -> f64) -> f64 {
+        let eval = |node: &CalcNode| node.evaluate(dimension);
+        match self {
+            CalcNode::Number(n) => *n,
+            CalcNode::Dimension(n, unit) => dimension(*n, *unit),
+            CalcNode::Add(left, right) => eval(left) + eval(right),
+            CalcNode::Sub(left, right) => eval(left) - eval(right),
+            CalcNode::Mul(left, right) => eval(left) * eval(right),
+            CalcNode::Div(left, right) => eval(left) / eval(right),
+            CalcNode::Min(args) => args.iter().map(eval).fold(f64::INFINITY, f64::min),
+            CalcNode::Max(args) => args.iter().map(eval).fold(f64::NEG_INFINITY, f64::max),
+            CalcNode::Clamp(min, value, max) => eval(value).min(eval(max)).max(eval(min)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssToken;
+    use crate::renderer::css::token::CssTokenizer;
+    use alloc::string::String;
+
+    fn parse_str(css: &str) -> Option<CalcNode> {
+        let values: Vec<ComponentValue> = CssTokenizer::new(String::from(css))
+            .filter(|t| *t != CssToken::Whitespace)
+            .collect();
+        parse(&values)
+    }
+
+    // % は 300px、それ以外の長さは px の値そのまま
+    fn px(node: &CalcNode) -> f64 {
+        node.evaluate(&|n, unit| match unit {
+            LengthUnit::Percent => n * 3.0,
+            _ => n,
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_str("calc(100% - 20px)"),
+            Some(CalcNode::Sub(
+                Box::new(CalcNode::Dimension(100.0, LengthUnit::Percent)),
+                Box::new(CalcNode::Dimension(20.0, LengthUnit::Px)),
+            ))
+        );
+        // * と / は + と - より先に計算する
+        let node = parse_str("calc(10px + 2 * 3px / (1 + 2))").expect("should parse");
+        assert_eq!(px(&node), 12.0);
+        let node = parse_str("min(50%, max(10px, 200px))").expect("should parse");
+        assert_eq!(px(&node), 150.0);
+        let node = parse_str("CLAMP(10px, calc(50% + 100px), 200px)").expect("should parse");
+        assert_eq!(px(&node), 200.0);
+        let node = parse_str("calc(-2 * 10px - -5px)").expect("should parse");
+        assert_eq!(px(&node), -15.0);
+
+        assert_eq!(parse_str("calc(100% - 20px"), None);
+        // `+` と `-` の前後には空白が要る（`-5px` は負の長さ 1 つになる）
+        assert_eq!(parse_str("calc(10px -5px)"), None);
+        assert_eq!(parse_str("calc()"), None);
+        assert_eq!(parse_str("clamp(1px, 2px)"), None);
+        assert_eq!(parse_str("calc(1px 2px)"), None);
+        assert_eq!(parse_str("calc(10furlong)"), None);
+        assert_eq!(parse_str("calc(1px) 2px"), None);
+    }
+
+    #[test]
+    fn test_calc_type() {
+        let calc_type = |css| parse_str(css).and_then(|node| node.calc_type());
+        assert_eq!(calc_type("calc(1px * 2)"), Some(CalcType::Length));
+        assert_eq!(calc_type("calc(2 * 50%)"), Some(CalcType::Length));
+        assert_eq!(calc_type("calc((1 + 2) / 3)"), Some(CalcType::Number));
+        // 長さと数値は足せない。長さどうしは掛けられず、長さでは割れない
+        assert_eq!(calc_type("calc(1px + 2)"), None);
+        assert_eq!(calc_type("calc(1px * 2px)"), None);
+        assert_eq!(calc_type("calc(2 / 1px)"), None);
+        assert_eq!(calc_type("min(1px, 2)"), None);
+    }
+}
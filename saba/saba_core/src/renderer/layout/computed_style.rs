@@ -1,285 +1,748 @@
+use crate::constants::CHAR_HEIGHT;
+use crate::constants::CHAR_WIDTH;
 use crate::error::Error;
-use crate::renderer::dom::node::ElementKind;
-use crate::renderer::dom::node::Node;
-use crate::renderer::dom::node::NodeKind;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::layout::calc::CalcNode;
+use crate::renderer::layout::custom_property;
+use crate::renderer::layout::custom_property::CustomProperties;
+use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::named_colors;
+use crate::renderer::layout::property::parse_specified;
+use crate::renderer::layout::property::PropertyId;
+use crate::renderer::layout::property::PropertyValue;
+use crate::renderer::layout::property::Side;
+use crate::renderer::layout::property::SpecifiedValue;
+use crate::renderer::layout::property::PROPERTIES;
+use crate::renderer::layout::shorthand;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::format;
-use alloc::rc::Rc;
 use alloc::string::String;
-use alloc::string::ToString;
-use core::cell::RefCell;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str::FromStr;
 
+/// 1 つのノードのスタイル（プロパティごとの計算値）
+///
+/// - 値はプロパティレジストリ（`layout::property`）の `PropertyId` を添字にした配列で持ちます。
+/// - `cascading_style` で宣言の指定値が入り（`set_specified`）、`defaulting` で計算値が決まります。
+///   それ以降は `color()` や `font_size()` などで値を読めます。
 #[derive(Debug, Clone, PartialEq)]
 pub struct ComputedStyle {
-    background_color: Option<Color>,
-    color: Option<Color>,
-    display: Option<DisplayType>,
-    font_size: Option<FontSize>,
-    text_decoration: Option<TextDecoration>,
-    height: Option<f64>,
-    width: Option<f64>,
+    // カスケードで残った指定値（defaulting で計算値に置き換えて空にする）
+    specified: Vec<Option<SpecifiedValue>>,
+    // 計算値（defaulting の後はすべて Some）
+    values: Vec<Option<PropertyValue>>,
+    // カスタムプロパティ（`--name`）の指定値。`var()` を含んだまま持つ
+    specified_custom: BTreeMap<String, Vec<ComponentValue>>,
+    // カスタムプロパティの計算値（親から継承したものを含む。`var()` は置き換え済み）
+    custom: CustomProperties,
+    // ルート要素（<html>）の font-size。`rem` の基準で、子孫へそのまま引き継ぐ
+    root_font_size: FontSize,
 }
 
 impl ComputedStyle {
     pub fn new() -> Self {
         Self {
-            background_color: None,
-            color: None,
-            display: None,
-            font_size: None,
-            text_decoration: None,
-            height: None,
-            width: None,
-        }
-    }
-
-    pub fn defaulting(&mut self, node: &Rc<RefCell<Node>>, parent_style: Option<ComputedStyle>) {
-        // もし親ノードが存在し、親のCSSの値が初期値とは異なる場合、値を継承する
-        if let Some(parent_style) = parent_style {
-            if self.background_color.is_none() && parent_style.background_color() != Color::white()
-            {
-                self.background_color = Some(parent_style.background_color());
+            specified: vec![None; PROPERTIES.len()],
+            values: vec![None; PROPERTIES.len()],
+            specified_custom: BTreeMap::new(),
+            custom: CustomProperties::new(),
+            root_font_size: FontSize::MEDIUM,
+        }
+    }
+
+    /// 宣言から読み取った指定値を入れる（同じプロパティなら後から入れたものが勝つ）
+    pub fn set_specified(&mut self, id: PropertyId, value: SpecifiedValue) {
+        self.specified[id as usize] = Some(value);
+    }
+
+    /// カスタムプロパティ（`--brand-color: #0af` など）の指定値を入れる（後から入れたものが勝つ）
+    pub fn set_specified_custom(&mut self, name: String, values: Vec<ComponentValue>) {
+        self.specified_custom.insert(name, values);
+    }
+
+    /// 指定値を計算値にする（defaulting）
+    ///
+    /// - 値が指定されていればそれを使う
+    /// - `inherit` なら親の値、`initial` なら初期値
+    /// - 指定が無い / `unset` なら、継承するプロパティは親の値、しないものは初期値
+    ///   （親がいない＝ルートなら初期値）
+    ///
+    /// https://www.w3.org/TR/css-cascade-4/#defaulting
+    pub fn defaulting(&mut self, parent_style: Option<ComputedStyle>) {
+        // カスタムプロパティを先に決め、`var()` を含む値（ショートハンドも）を置き換えてから読む
+        // 置き換えられない・読めない値は `unset` と同じ（invalid at computed-value time）
+        let inherited_custom = match &parent_style {
+            Some(parent) => parent.custom.clone(),
+            None => CustomProperties::new(),
+        };
+        self.custom = custom_property::compute(
+            &inherited_custom,
+            &core::mem::take(&mut self.specified_custom),
+        );
+        for definition in PROPERTIES.iter() {
+            let specified = &mut self.specified[definition.id as usize];
+            let custom = &self.custom;
+            let substitute = |values: &[ComponentValue]| {
+                custom_property::substitute(values, &mut |name| custom.get(name).cloned())
+            };
+            let resolved = match specified {
+                Some(SpecifiedValue::Unparsed(values)) => {
+                    substitute(values).and_then(|values| parse_specified(definition, &values))
+                }
+                // ショートハンドは置き換えてから展開し、このロングハンドの分を取り出す
+                Some(SpecifiedValue::PendingShorthand(name, values)) => substitute(values)
+                    .and_then(|values| shorthand::expand(shorthand::lookup(name)?, &values))
+                    .and_then(|longhands| {
+                        longhands
+                            .into_iter()
+                            .find(|(id, _)| *id == definition.id)
+                            .map(|(_, value)| value)
+                    }),
+                _ => continue,
+            };
+            *specified = Some(resolved.unwrap_or(SpecifiedValue::Unset));
+        }
+
+        for definition in PROPERTIES.iter() {
+            let i = definition.id as usize;
+            let inherited = || match &parent_style {
+                Some(parent) => parent.get(definition.id).clone(),
+                None => (definition.initial)(),
+            };
+            let value = match self.specified[i].take() {
+                Some(SpecifiedValue::Value(value)) => value,
+                Some(SpecifiedValue::Inherit) => inherited(),
+                Some(SpecifiedValue::Initial) => (definition.initial)(),
+                Some(SpecifiedValue::Unset)
+                | Some(SpecifiedValue::Unparsed(_))
+                | Some(SpecifiedValue::PendingShorthand(..))
+                | None => {
+                    if definition.inherited {
+                        inherited()
+                    } else {
+                        (definition.initial)()
+                    }
+                }
+            };
+            self.values[i] = Some(value);
+        }
+
+        // `color: currentColor` は `color: inherit` と同じ（自分の color は親の color から決まる）
+        // https://www.w3.org/TR/css-color-4/#resolving-other-colors
+        let color = &mut self.values[PropertyId::Color as usize];
+        if color == &Some(PropertyValue::CurrentColor) {
+            *color = Some(match &parent_style {
+                Some(parent) => PropertyValue::Color(parent.color()),
+                None => (PropertyId::Color.definition().initial)(),
+            });
+        }
+
+        // font-size と font-weight の相対指定（em / % / larger / bolder など）は親の値から決める
+        // ルート要素の font-size の `rem` は初期値（medium）が基準
+        // https://www.w3.org/TR/css-values-4/#rem
+        let (parent_font_size, root_font_size) = match &parent_style {
+            Some(parent) => (parent.font_size(), parent.root_font_size),
+            None => (FontSize::MEDIUM, FontSize::MEDIUM),
+        };
+        let parent_font_weight = match &parent_style {
+            Some(parent) => parent.font_weight(),
+            None => FontWeight::NORMAL,
+        };
+        let font_size = &mut self.values[PropertyId::FontSize as usize];
+        if let Some(PropertyValue::Length(length)) = font_size {
+            // % も em も親の font-size が基準（font-size に vw / vh は使えないのでビューポートは見ない）
+            let px = length
+                .absolutize(parent_font_size.px(), root_font_size.px())
+                .resolve(parent_font_size.px(), LayoutSize::new(0, 0));
+            *font_size = Some(PropertyValue::FontSize(FontSize::new(px.max(0.0))));
+        }
+        let font_weight = &mut self.values[PropertyId::FontWeight as usize];
+        match font_weight {
+            Some(PropertyValue::Bolder) => {
+                *font_weight = Some(PropertyValue::FontWeight(parent_font_weight.bolder()))
             }
-            if self.color.is_none() && parent_style.color() != Color::black() {
-                self.color = Some(parent_style.color());
+            Some(PropertyValue::Lighter) => {
+                *font_weight = Some(PropertyValue::FontWeight(parent_font_weight.lighter()))
             }
-            if self.font_size.is_none() && parent_style.font_size() != FontSize::Medium {
-                self.font_size = Some(parent_style.font_size());
+            _ => {}
+        }
+
+        // ルート要素なら、自分の font-size が子孫の `rem` の基準になる
+        self.root_font_size = match &parent_style {
+            Some(_) => root_font_size,
+            None => self.font_size(),
+        };
+
+        // em / rem / ch / pt は、ここで px にしておく（子に継承されるのは px の値）
+        // % / vw / vh は包含ブロックやビューポートが決まるレイアウト時まで残す
+        let font_size = self.font_size().px();
+        let root_font_size = self.root_font_size.px();
+        for value in self.values.iter_mut().flatten() {
+            if let PropertyValue::Length(length) = value {
+                *length = length.absolutize(font_size, root_font_size);
             }
-            if self.text_decoration.is_none()
-                && parent_style.text_decoration() != TextDecoration::None
-            {
-                self.text_decoration = Some(parent_style.text_decoration());
+        }
+
+        // line-height の % はその要素の font-size が基準。子に継承されるのは px の値
+        // （数値で指定したときは数値のまま継承し、子の font-size に掛ける）
+        // https://www.w3.org/TR/css-inline-3/#line-height-property
+        if let Some(PropertyValue::Length(length)) =
+            &mut self.values[PropertyId::LineHeight as usize]
+        {
+            if length.has_unit(LengthUnit::Percent) {
+                *length = Length::px(length.resolve(font_size, LayoutSize::new(0, 0)));
             }
         }
+    }
 
-        // 各プロパティに対して、初期値を設定する
-        if self.background_color.is_none() {
-            self.background_color = Some(Color::white());
+    /// 計算値をそのまま上書きする（選択範囲のハイライト色など、描画側で作るスタイル用）
+    pub fn set(&mut self, id: PropertyId, value: PropertyValue) {
+        self.values[id as usize] = Some(value);
+    }
+
+    /// カスタムプロパティの計算値（無い・無効なら None）
+    pub fn custom_property(&self, name: &str) -> Option<Vec<ComponentValue>> {
+        self.custom.get(name).cloned()
+    }
+
+    /// 計算値を読む（defaulting の前に呼ぶとパニック）
+    pub fn get(&self, id: PropertyId) -> &PropertyValue {
+        match &self.values[id as usize] {
+            Some(value) => value,
+            None => panic!("failed to access CSS property: {}", id.definition().name),
         }
-        if self.color.is_none() {
-            self.color = Some(Color::black());
+    }
+
+    pub fn background_color(&self) -> Color {
+        match self.get(PropertyId::BackgroundColor) {
+            PropertyValue::Color(color) => *color,
+            PropertyValue::CurrentColor => self.color(),
+            v => panic!("unexpected background-color value: {:?}", v),
         }
-        if self.display.is_none() {
-            self.display = Some(DisplayType::default(node));
+    }
+
+    pub fn color(&self) -> Color {
+        match self.get(PropertyId::Color) {
+            PropertyValue::Color(color) => *color,
+            v => panic!("unexpected color value: {:?}", v),
         }
-        if self.font_size.is_none() {
-            self.font_size = Some(FontSize::default(node));
+    }
+
+    pub fn display(&self) -> DisplayType {
+        match self.get(PropertyId::Display) {
+            PropertyValue::Display(display) => *display,
+            v => panic!("unexpected display value: {:?}", v),
         }
-        if self.text_decoration.is_none() {
-            self.text_decoration = Some(TextDecoration::default(node));
+    }
+
+    /// ルート要素の font-size（`rem` の基準）
+    pub fn root_font_size(&self) -> FontSize {
+        self.root_font_size
+    }
+
+    pub fn font_size(&self) -> FontSize {
+        match self.get(PropertyId::FontSize) {
+            PropertyValue::FontSize(font_size) => *font_size,
+            v => panic!("unexpected font-size value: {:?}", v),
         }
-        if self.height.is_none() {
-            self.height = Some(0.0);
+    }
+
+    pub fn font_weight(&self) -> FontWeight {
+        match self.get(PropertyId::FontWeight) {
+            PropertyValue::FontWeight(weight) => *weight,
+            v => panic!("unexpected font-weight value: {:?}", v),
         }
-        if self.width.is_none() {
-            self.width = Some(0.0);
+    }
+
+    pub fn font_style(&self) -> FontStyle {
+        match self.get(PropertyId::FontStyle) {
+            PropertyValue::FontStyle(style) => *style,
+            v => panic!("unexpected font-style value: {:?}", v),
         }
     }
 
-    pub fn set_background_color(&mut self, color: Color) {
-        self.background_color = Some(color);
+    pub fn font_family(&self) -> Vec<String> {
+        match self.get(PropertyId::FontFamily) {
+            PropertyValue::FontFamily(families) => families.clone(),
+            v => panic!("unexpected font-family value: {:?}", v),
+        }
     }
 
-    pub fn background_color(&self) -> Color {
-        self.background_color
-            .clone()
-            .expect("failed to access CSS property: background_color")
+    /// フォントに関する計算値をまとめて返す（描画側に渡す用）
+    pub fn font(&self) -> Font {
+        Font {
+            size: self.font_size(),
+            weight: self.font_weight(),
+            style: self.font_style(),
+            families: self.font_family(),
+        }
     }
 
-    pub fn set_color(&mut self, color: Color) {
-        self.color = Some(color);
+    /// 装飾線の位置（text-decoration-line）
+    pub fn text_decoration(&self) -> TextDecoration {
+        match self.get(PropertyId::TextDecorationLine) {
+            PropertyValue::TextDecoration(text_decoration) => *text_decoration,
+            v => panic!("unexpected text-decoration value: {:?}", v),
+        }
     }
 
-    pub fn color(&self) -> Color {
-        self.color
-            .clone()
-            .expect("failed to access CSS property: color")
+    /// 行の中での揃え方（text-align）
+    pub fn text_align(&self) -> TextAlign {
+        match self.get(PropertyId::TextAlign) {
+            PropertyValue::TextAlign(text_align) => *text_align,
+            v => panic!("unexpected text-align value: {:?}", v),
+        }
     }
 
-    pub fn set_display(&mut self, display: DisplayType) {
-        self.display = Some(display);
+    /// 行の高さ（px）。`normal` なら None（フォントの大きさから決める）
+    ///
+    /// - 数値は font-size に掛ける（`line-height: 1.5` で font-size 16px → 24px）
+    pub fn line_height(&self) -> Option<f64> {
+        match self.get(PropertyId::LineHeight) {
+            PropertyValue::Normal => None,
+            PropertyValue::Number(n) => Some(n * self.font_size().px()),
+            PropertyValue::Length(length) => {
+                Some(length.resolve(0.0, LayoutSize::new(0, 0)).max(0.0))
+            }
+            v => panic!("unexpected line-height value: {:?}", v),
+        }
     }
 
-    pub fn display(&self) -> DisplayType {
-        self.display
-            .expect("failed to access CSS property: display")
+    /// 空白と改行の扱い（white-space）
+    pub fn white_space(&self) -> WhiteSpace {
+        match self.get(PropertyId::WhiteSpace) {
+            PropertyValue::WhiteSpace(white_space) => *white_space,
+            v => panic!("unexpected white-space value: {:?}", v),
+        }
     }
 
-    pub fn font_size(&self) -> FontSize {
-        self.font_size
-            .expect("failed to access CSS property: font_size")
+    /// 文字の後ろに足す間隔（px。`normal` は 0）
+    pub fn letter_spacing(&self) -> f64 {
+        self.spacing(PropertyId::LetterSpacing)
     }
 
-    pub fn text_decoration(&self) -> TextDecoration {
-        self.text_decoration
-            .expect("failed to access CSS property: text_decoration")
+    /// スペースの文字に足す間隔（px。`normal` は 0）
+    pub fn word_spacing(&self) -> f64 {
+        self.spacing(PropertyId::WordSpacing)
+    }
+
+    fn spacing(&self, id: PropertyId) -> f64 {
+        match self.get(id) {
+            PropertyValue::Normal => 0.0,
+            PropertyValue::Length(length) => length.resolve(0.0, LayoutSize::new(0, 0)),
+            v => panic!("unexpected {:?} value: {:?}", id, v),
+        }
+    }
+
+    /// 最初の行の字下げ（text-indent。% は包含ブロックの幅が基準）
+    pub fn text_indent(&self) -> Length {
+        self.length(PropertyId::TextIndent)
+    }
+
+    /// 大文字・小文字の変換（text-transform）
+    pub fn text_transform(&self) -> TextTransform {
+        match self.get(PropertyId::TextTransform) {
+            PropertyValue::TextTransform(text_transform) => *text_transform,
+            v => panic!("unexpected text-transform value: {:?}", v),
+        }
+    }
+
+    /// 指定された高さ。`auto` なら None（子の高さから決まる）
+    pub fn height(&self) -> Option<Length> {
+        match self.get(PropertyId::Height) {
+            PropertyValue::Length(height) => Some(height.clone()),
+            PropertyValue::Auto => None,
+            v => panic!("unexpected height value: {:?}", v),
+        }
+    }
+
+    /// 指定された幅。`auto` なら None（親の幅いっぱいに広がる）
+    pub fn width(&self) -> Option<Length> {
+        match self.get(PropertyId::Width) {
+            PropertyValue::Length(width) => Some(width.clone()),
+            PropertyValue::Auto => None,
+            v => panic!("unexpected width value: {:?}", v),
+        }
+    }
+
+    /// 不透明度（0.0 = 透明 〜 1.0 = 不透明）。子孫も含めた全体にかかる
+    pub fn opacity(&self) -> f64 {
+        match self.get(PropertyId::Opacity) {
+            PropertyValue::Number(opacity) => *opacity,
+            v => panic!("unexpected opacity value: {:?}", v),
+        }
     }
 
-    pub fn set_height(&mut self, height: f64) {
-        self.height = Some(height);
+    pub fn box_sizing(&self) -> BoxSizing {
+        match self.get(PropertyId::BoxSizing) {
+            PropertyValue::BoxSizing(box_sizing) => *box_sizing,
+            v => panic!("unexpected box-sizing value: {:?}", v),
+        }
     }
 
-    pub fn height(&self) -> f64 {
-        self.height.expect("failed to access CSS property: height")
+    /// 指定された margin。`auto` なら None（レイアウトで余った幅から決まる）
+    pub fn margin(&self, side: Side) -> Option<Length> {
+        match self.get(PropertyId::margin(side)) {
+            PropertyValue::Length(margin) => Some(margin.clone()),
+            PropertyValue::Auto => None,
+            v => panic!("unexpected margin value: {:?}", v),
+        }
     }
 
-    pub fn set_width(&mut self, width: f64) {
-        self.width = Some(width);
+    pub fn padding(&self, side: Side) -> Length {
+        self.length(PropertyId::padding(side))
     }
 
-    pub fn width(&self) -> f64 {
-        self.width.expect("failed to access CSS property: width")
+    /// 枠線の太さ。線の種類が `none` か `hidden` なら指定にかかわらず 0
+    /// https://www.w3.org/TR/css-backgrounds-3/#border-width
+    pub fn border_width(&self, side: Side) -> Length {
+        if self.border_style(side).is_none() {
+            return Length::px(0.0);
+        }
+        self.length(PropertyId::border_width(side))
+    }
+
+    pub fn border_style(&self, side: Side) -> BorderStyle {
+        match self.get(PropertyId::border_style(side)) {
+            PropertyValue::BorderStyle(style) => *style,
+            v => panic!("unexpected border-style value: {:?}", v),
+        }
+    }
+
+    /// 枠線の色。`currentColor` なら同じ要素の `color`
+    pub fn border_color(&self, side: Side) -> Color {
+        match self.get(PropertyId::border_color(side)) {
+            PropertyValue::Color(color) => *color,
+            PropertyValue::CurrentColor => self.color(),
+            v => panic!("unexpected border-color value: {:?}", v),
+        }
+    }
+
+    // 長さのプロパティ（margin-top など）を読む
+    fn length(&self, id: PropertyId) -> Length {
+        match self.get(id) {
+            PropertyValue::Length(length) => length.clone(),
+            v => panic!("unexpected {} value: {:?}", id.definition().name, v),
+        }
     }
 }
 
-// CSS の色（color）を表す最小構造体
+// CSS の色（color）を表す構造体（RGB + 不透明度）
 //
 // 役割（実ブラウザのどの部分？）
-// - CSS の color/background-color などで使う色を、名前（例: "red"）と 6 桁の #RRGGBB で保持します。
-// - 実装をシンプルにするため、対応色は固定のサンプルのみ。未対応は Error を返します。
+// - CSS の color/background-color などで使う色を、赤・緑・青（0〜255）と alpha（0.0 = 透明 〜 1.0 = 不透明）で保持します。
+// - 書き方はいろいろあっても、計算値はすべてこの形になります。
+//   - 色名: `red`（全 148 色。`named_colors` を参照）、`transparent`
+//   - 16 進: `#rgb` / `#rgba` / `#rrggbb` / `#rrggbbaa`
+//   - 関数: `rgb()` / `rgba()` / `hsl()` / `hsla()`（読み取りは `layout::property`）
 //
 // 言語ブリッジ（TS / Python / Go）
-// - `Option<String>` は “ある/ない” を型で表す（TS: string | undefined, Python: Optional[str]）。
 // - 16進 → 数値変換（code_u32）は `int('RRGGBB', 16)` のイメージ。
-#[derive(Debug, Clone, PartialEq)]
+// - Copy なので、TS の `{ r, g, b, a }` オブジェクトを値として受け渡す感覚で使えます。
+//
+// 仕様: https://www.w3.org/TR/css-color-4/
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
-    name: Option<String>, // 例: Some("red")。コードから生成した場合でも逆引きで Some(...) を入れる
-    code: String,         // 例: "#ff0000"（常に # + 6桁の小文字16進）
+    red: u8,
+    green: u8,
+    blue: u8,
+    alpha: f64,
 }
 
 impl Color {
-    // 色名から Color を作る（固定の代表色のみ対応）
-    // 例: Color::from_name("red") → Ok(Color { name: Some("red"), code: "#ff0000" })
-    pub fn from_name(name: &str) -> Result<Self, Error> {
-        let code = match name {
-            "black" => "#000000".to_string(),
-            "silver" => "#c0c0c0".to_string(),
-            "gray" => "#808080".to_string(),
-            "white" => "#ffffff".to_string(),
-            "maroon" => "#800000".to_string(),
-            "red" => "#ff0000".to_string(),
-            "purple" => "#800080".to_string(),
-            "fuchsia" => "#ff00ff".to_string(),
-            "green" => "#008000".to_string(),
-            "lime" => "#00ff00".to_string(),
-            "olive" => "#808000".to_string(),
-            "yellow" => "#ffff00".to_string(),
-            "navy" => "#000080".to_string(),
-            "blue" => "#0000ff".to_string(),
-            "teal" => "#008080".to_string(),
-            "aqua" => "#00ffff".to_string(),
-            "orange" => "#ffa500".to_string(),
-            "lightgray" => "#d3d3d3".to_string(),
-            _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color name {:?} is not supported yet",
-                    name
-                )));
-            }
-        };
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::rgba(red, green, blue, 1.0)
+    }
 
-        Ok(Self {
-            name: Some(name.to_string()),
-            code,
-        })
+    /// alpha は 0.0〜1.0 に収める
+    pub fn rgba(red: u8, green: u8, blue: u8, alpha: f64) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha: alpha.clamp(0.0, 1.0),
+        }
     }
 
-    // #RRGGBB から Color を作る（6桁固定の簡易版）
-    // 例: Color::from_code("#00ff00") → Ok(Color { name: Some("lime"), code: "#00ff00" })
-    // 注意: #付き7文字のみを許可。短縮形 #0f0 や alpha #RRGGBBAA は未対応。
+    // 0xRRGGBB から不透明な色を作る
+    fn from_u32(code: u32) -> Self {
+        Self::rgb((code >> 16) as u8, (code >> 8) as u8, code as u8)
+    }
+
+    // 色名から Color を作る（大文字小文字は区別しない）
+    // 例: Color::from_name("red") → Ok(rgb(255, 0, 0)) / Color::from_name("transparent") → Ok(rgba(0, 0, 0, 0))
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Ok(Self::transparent());
+        }
+        match named_colors::lookup(&name) {
+            Some(code) => Ok(Self::from_u32(code)),
+            None => Err(Error::UnexpectedInput(format!(
+                "color name {:?} is not supported yet",
+                name
+            ))),
+        }
+    }
+
+    // #rgb / #rgba / #rrggbb / #rrggbbaa から Color を作る
+    // 例: Color::from_code("#00ff00") → Ok(rgb(0, 255, 0)) / Color::from_code("#f008") → Ok(rgba(255, 0, 0, 0.53…))
+    // 1 桁の形式は同じ数字を 2 回並べたものとみなす（`#f00` = `#ff0000`）
     pub fn from_code(code: &str) -> Result<Self, Error> {
-        if code.chars().nth(0) != Some('#') || code.len() != 7 {
-            return Err(Error::UnexpectedInput(format!(
-                "invalid color code {}",
-                code
-            )));
-        }
-
-        let name = match code {
-            "#000000" => "black".to_string(),
-            "#c0c0c0" => "silver".to_string(),
-            "#808080" => "gray".to_string(),
-            "#ffffff" => "white".to_string(),
-            "#800000" => "maroon".to_string(),
-            "#ff0000" => "red".to_string(),
-            "#800080" => "purple".to_string(),
-            "#ff00ff" => "fuchsia".to_string(),
-            "#008000" => "green".to_string(),
-            "#00ff00" => "lime".to_string(),
-            "#808000" => "olive".to_string(),
-            "#ffff00" => "yellow".to_string(),
-            "#000080" => "navy".to_string(),
-            "#0000ff" => "blue".to_string(),
-            "#008080" => "teal".to_string(),
-            "#00ffff" => "aqua".to_string(),
-            "#ffa500" => "orange".to_string(),
-            "#d3d3d3" => "lightgray".to_string(),
-            _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color code {:?} is not supported yet",
-                    code
-                )));
-            }
+        let invalid = || Error::UnexpectedInput(format!("invalid color code {}", code));
+        let hex = code.strip_prefix('#').ok_or_else(invalid)?;
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|c| c.to_digit(16).expect("hex digit") as u8)
+            .collect();
+        let channels: Vec<u8> = match digits.len() {
+            3 | 4 => digits.iter().map(|d| d * 17).collect(),
+            6 | 8 => digits.chunks(2).map(|d| d[0] * 16 + d[1]).collect(),
+            _ => return Err(invalid()),
+        };
+        let alpha = match channels.get(3) {
+            Some(a) => *a as f64 / 255.0,
+            None => 1.0,
         };
+        Ok(Self::rgba(channels[0], channels[1], channels[2], alpha))
+    }
 
-        Ok(Self {
-            name: Some(name),
-            code: code.to_string(),
-        })
+    /// HSL（色相・彩度・明度）から Color を作る
+    ///
+    /// - `hue`: 色相（度。0 = 赤、120 = 緑、240 = 青。範囲外は 360 で折り返す）
+    /// - `saturation` / `lightness`: 0.0〜1.0
+    ///
+    /// 例: from_hsl(120.0, 1.0, 0.25, 1.0) → rgb(0, 128, 0)
+    /// https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Self {
+        // 色相を 0〜360 に折り返す（no_std なので % の代わりに整数への切り捨てを使う）
+        let mut hue = hue - 360.0 * ((hue / 360.0) as i64 as f64);
+        if hue < 0.0 {
+            hue += 360.0;
+        }
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+
+        let f = |n: f64| {
+            let mut k = n + hue / 30.0;
+            if k >= 12.0 {
+                k -= 12.0;
+            }
+            let a = saturation * lightness.min(1.0 - lightness);
+            let channel = lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+            (channel * 255.0 + 0.5) as u8
+        };
+        Self::rgba(f(0.0), f(8.0), f(4.0), alpha)
     }
 
-    // 定数ショートカット（便利メソッド）: 白/黒
+    // 定数ショートカット（便利メソッド）: 白/黒/透明
     pub fn white() -> Self {
-        Self {
-            name: Some("white".to_string()),
-            code: "#ffffff".to_string(),
-        }
+        Self::rgb(255, 255, 255)
     }
 
     pub fn black() -> Self {
-        Self {
-            name: Some("black".to_string()),
-            code: "#000000".to_string(),
-        }
+        Self::rgb(0, 0, 0)
+    }
+
+    /// `transparent`（透明な黒 = rgba(0, 0, 0, 0)）
+    pub fn transparent() -> Self {
+        Self::rgba(0, 0, 0, 0.0)
+    }
+
+    pub fn red(&self) -> u8 {
+        self.red
     }
 
-    // #RRGGBB を 0xRRGGBB の数値（u32）に変換する
-    // 例: "#00ff00" → 0x00ff00（= 65280）
-    // 注意: unwrap() はサンプル向け（固定 6 桁を前提）。実運用ではエラーハンドリング推奨。
+    pub fn green(&self) -> u8 {
+        self.green
+    }
+
+    pub fn blue(&self) -> u8 {
+        self.blue
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// 完全に透明（塗っても何も変わらない）か
+    pub fn is_transparent(&self) -> bool {
+        self.alpha == 0.0
+    }
+
+    // RGB を 0xRRGGBB の数値（u32）にする（alpha は含まない）
+    // 例: rgb(0, 255, 0) → 0x00ff00（= 65280）
     pub fn code_u32(&self) -> u32 {
-        u32::from_str_radix(self.code.trim_start_matches('#'), 16).unwrap()
+        ((self.red as u32) << 16) | ((self.green as u32) << 8) | self.blue as u32
     }
 }
 
-// 文字の大きさ（font-size）の簡易列挙
+// 文字の大きさ（font-size の計算値。px）
 //
-// - CSS の絶対サイズ（absolute-size）に対応するサンプル：medium / x-large / xx-large。
-// - 実ブラウザは相対指定（em/rem/%）や継承、ユーザー設定など多くの要素を考慮しますが、
-//   ここでは見出しタグに応じた“わかりやすい差”だけを表現します。
+// - キーワード（`medium` など）は CSS Fonts の表に従って px にする（medium = 16px）。
+// - `em` / `%` / `larger` / `smaller` は親の font-size から計算値を決める（`ComputedStyle::defaulting`）。
+// - 描画側（UI）は、描けるフォントの大きさのうち最も近いものを選ぶ。
 // 仕様: https://www.w3.org/TR/css-fonts-4/#absolute-size-mapping
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub enum FontSize {
-    Medium,
-    XLarge,
-    XXLarge,
+pub struct FontSize {
+    px: f64,
 }
 
 impl FontSize {
-    // 要素種別に応じた既定フォントサイズを返す（学習用の最小ルール）
-    // - <h1> → XXLarge, <h2> → XLarge, その他 → Medium
-    // - これは“ブラウザのスタイルシート（UA スタイル）”の超簡易版と考えてください。
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::H1 => FontSize::XXLarge,
-                ElementKind::H2 => FontSize::XLarge,
-                _ => FontSize::Medium,
-            },
-            _ => FontSize::Medium,
+    /// 初期値 `medium`（描画に使う等幅フォントの文字の高さ CHAR_HEIGHT と同じ 16px）
+    pub const MEDIUM: FontSize = FontSize { px: 16.0 };
+
+    pub fn new(px: f64) -> Self {
+        Self { px }
+    }
+
+    /// 文字の高さ（px）。`em` の単位はこの値を基準にする
+    pub fn px(&self) -> f64 {
+        self.px
+    }
+
+    /// medium（16px）を 1 としたときの倍率（文字幅・行の高さの見積もりに使う）
+    pub fn scale(&self) -> f64 {
+        self.px / CHAR_HEIGHT as f64
+    }
+}
+
+// キーワード → FontSize への変換（絶対サイズのキーワード。medium を 1 とした倍率）
+// 入力例: "medium" / "x-large" / "xx-large"
+// `"xx-large".parse::<FontSize>()` のように使える（ElementKind と同じく FromStr を実装）
+impl FromStr for FontSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let scale = match s {
+            "xx-small" => 3.0 / 5.0,
+            "x-small" => 3.0 / 4.0,
+            "small" => 8.0 / 9.0,
+            "medium" => 1.0,
+            "large" => 6.0 / 5.0,
+            "x-large" => 3.0 / 2.0,
+            "xx-large" => 2.0,
+            "xxx-large" => 3.0,
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "font-size {:?} is not supported yet",
+                    s
+                )))
+            }
+        };
+        Ok(Self::new(Self::MEDIUM.px * scale))
+    }
+}
+
+// 文字の太さ（font-weight の計算値。1〜1000、normal = 400、bold = 700）
+// 仕様: https://www.w3.org/TR/css-fonts-4/#font-weight-prop
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FontWeight {
+    value: u16,
+}
+
+impl FontWeight {
+    pub const NORMAL: FontWeight = FontWeight { value: 400 };
+    pub const BOLD: FontWeight = FontWeight { value: 700 };
+
+    pub fn new(value: u16) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+
+    /// 太字として描くか（600 以上。太字の書体が 1 つしか無いときの目安）
+    pub fn is_bold(&self) -> bool {
+        self.value >= 600
+    }
+
+    /// 親の太さから `bolder` の値を決める
+    /// https://www.w3.org/TR/css-fonts-4/#relative-weights
+    pub fn bolder(&self) -> Self {
+        let value = match self.value {
+            0..=349 => 400,
+            350..=549 => 700,
+            550..=899 => 900,
+            v => v,
+        };
+        Self::new(value)
+    }
+
+    /// 親の太さから `lighter` の値を決める
+    pub fn lighter(&self) -> Self {
+        let value = match self.value {
+            0..=99 => self.value,
+            100..=549 => 100,
+            550..=749 => 400,
+            _ => 700,
+        };
+        Self::new(value)
+    }
+}
+
+// 文字の傾き（font-style）
+// - `oblique` の角度指定（`oblique 10deg`）は未対応
+// 仕様: https://www.w3.org/TR/css-fonts-4/#font-style-prop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl FromStr for FontStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "italic" => Ok(Self::Italic),
+            "oblique" => Ok(Self::Oblique),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-style {:?} is not supported yet",
+                s
+            ))),
         }
     }
 }
 
+// テキストを描くためのフォント指定（DisplayItem::Text で描画側に渡す）
+//
+// - 描画側は `families` を先頭から見て、持っている書体があればそれを使う（無ければ既定の書体）。
+// - 大きさ・太さ・傾きも、描ける範囲で最も近いものを選ぶ。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    size: FontSize,
+    weight: FontWeight,
+    style: FontStyle,
+    families: Vec<String>,
+}
+
+impl Font {
+    pub fn size(&self) -> FontSize {
+        self.size
+    }
+
+    pub fn weight(&self) -> FontWeight {
+        self.weight
+    }
+
+    pub fn style(&self) -> FontStyle {
+        self.style
+    }
+
+    pub fn families(&self) -> Vec<String> {
+        self.families.clone()
+    }
+}
+
 // CSS の display プロパティ（要素の“並び方”）に対応する値
 //
 // - 最小実装として `block` / `inline` / `none` の3種類のみを扱います。
@@ -296,25 +759,6 @@ pub enum DisplayType {
 }
 
 impl DisplayType {
-    // 要素の“既定表示形式（UA スタイルシートの超簡易版）”を返す
-    // ルール（学習用）
-    // - Document はブロック
-    // - Element: ブロック要素なら Block、そうでなければ Inline（`is_block_element()` に依存）
-    // - Text はインライン（テキストは行内に流れる）
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Document => DisplayType::Block,
-            NodeKind::Element(e) => {
-                if e.is_block_element() {
-                    DisplayType::Block
-                } else {
-                    DisplayType::Inline
-                }
-            }
-            NodeKind::Text(_) => DisplayType::Inline,
-        }
-    }
-
     // 文字列 → DisplayType への変換
     // 入力例: "block" / "inline" / "none"
     // 未対応のキーワードは Error を返して早期発見（将来拡張時に追加）。
@@ -331,30 +775,771 @@ impl DisplayType {
     }
 }
 
-// CSS の text-decoration プロパティ（下線など）に対する最小の列挙
+// CSS の text-decoration-line プロパティ（下線など）に対する最小の列挙
 //
-// - 学習用に `none` と `underline` のみをサポートしています。
-// - 実ブラウザには `overline` / `line-through` / `blink`（非推奨）などもあります。
-//   必要になったらここに列挙子を追加していきます。
+// - `none` / `underline` / `overline` / `line-through` のどれか 1 つを持ちます
+//   （`underline overline` のような組み合わせと、非推奨の `blink` は未対応）。
+// - 描画側（UI）が描くのはいまは下線だけで、上線と打ち消し線は値を持つだけです。
 // 仕様: https://w3c.github.io/csswg-drafts/css-text-decor/#text-decoration-property
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TextDecoration {
     None,
     Underline,
+    Overline,
+    LineThrough,
+}
+
+// 文字列 → TextDecoration への変換（<a> の下線は UA スタイルシートが指定する）
+// 入力例: "none" / "underline" / "line-through"
+impl FromStr for TextDecoration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "underline" => Ok(Self::Underline),
+            "overline" => Ok(Self::Overline),
+            "line-through" => Ok(Self::LineThrough),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-decoration {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// 装飾線の種類（text-decoration-style）
+//
+// - 描画側はまだ下線を描かないので、値を持つだけ
+// 仕様: https://www.w3.org/TR/css-text-decor-3/#text-decoration-style-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextDecorationStyle {
+    Solid,
+    Double,
+    Dotted,
+    Dashed,
+    Wavy,
+}
+
+impl FromStr for TextDecorationStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solid" => Ok(Self::Solid),
+            "double" => Ok(Self::Double),
+            "dotted" => Ok(Self::Dotted),
+            "dashed" => Ok(Self::Dashed),
+            "wavy" => Ok(Self::Wavy),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-decoration-style {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
 }
 
-impl TextDecoration {
-    // 既定の装飾（UA スタイルの超簡易版）を返す
-    // ルール（学習用）
-    // - アンカータグ <a> は下線（Underline）
-    // - それ以外は None
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::A => TextDecoration::Underline,
-                _ => TextDecoration::None,
-            },
-            _ => TextDecoration::None,
+// リストの行頭記号（list-style-type）
+//
+// - 描画側はまだ行頭記号を描かないので、値を持つだけ
+// - 文字列（`list-style-type: "-"`）や `@counter-style` の名前は未対応
+// 仕様: https://www.w3.org/TR/css-lists-3/#text-markers
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ListStyleType {
+    None,
+    Disc,
+    Circle,
+    Square,
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl FromStr for ListStyleType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "disc" => Ok(Self::Disc),
+            "circle" => Ok(Self::Circle),
+            "square" => Ok(Self::Square),
+            "decimal" => Ok(Self::Decimal),
+            "lower-alpha" | "lower-latin" => Ok(Self::LowerAlpha),
+            "upper-alpha" | "upper-latin" => Ok(Self::UpperAlpha),
+            "lower-roman" => Ok(Self::LowerRoman),
+            "upper-roman" => Ok(Self::UpperRoman),
+            _ => Err(Error::UnexpectedInput(format!(
+                "list-style-type {:?} is not supported yet",
+                s
+            ))),
         }
     }
 }
+
+// 行頭記号を置く位置（list-style-position）
+// 仕様: https://www.w3.org/TR/css-lists-3/#list-style-position-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ListStylePosition {
+    Inside,
+    Outside,
+}
+
+impl FromStr for ListStylePosition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inside" => Ok(Self::Inside),
+            "outside" => Ok(Self::Outside),
+            _ => Err(Error::UnexpectedInput(format!(
+                "list-style-position {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// 行の中での揃え方（text-align）
+//
+// - `start` / `end` は左から右へ書く前提で `left` / `right` と同じに扱う
+// - `justify` は最後の行（と改行で終わる行）以外で、スペースの幅を広げて行の幅いっぱいにする
+// 仕様: https://www.w3.org/TR/css-text-3/#text-align-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Right,
+    Center,
+    Justify,
+}
+
+impl FromStr for TextAlign {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" | "start" => Ok(Self::Left),
+            "right" | "end" => Ok(Self::Right),
+            "center" => Ok(Self::Center),
+            "justify" => Ok(Self::Justify),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-align {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// 空白と改行の扱い（white-space）
+//
+// | 値       | 連続する空白 | 改行       | 行の折り返し |
+// |----------|--------------|------------|--------------|
+// | normal   | 1 つにまとめる | 空白にする | する         |
+// | nowrap   | 1 つにまとめる | 空白にする | しない       |
+// | pre      | 残す         | 残す       | しない       |
+// | pre-wrap | 残す         | 残す       | する         |
+// | pre-line | 1 つにまとめる | 残す       | する         |
+// 仕様: https://www.w3.org/TR/css-text-3/#white-space-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+    PreWrap,
+    NoWrap,
+    PreLine,
+}
+
+impl WhiteSpace {
+    /// 連続する空白を 1 つにまとめるか
+    pub fn collapses_spaces(&self) -> bool {
+        matches!(self, Self::Normal | Self::NoWrap | Self::PreLine)
+    }
+
+    /// 改行を行の区切りとして残すか
+    pub fn preserves_newlines(&self) -> bool {
+        matches!(self, Self::Pre | Self::PreWrap | Self::PreLine)
+    }
+
+    /// 行の幅に収まらないとき折り返すか
+    pub fn wraps(&self) -> bool {
+        matches!(self, Self::Normal | Self::PreWrap | Self::PreLine)
+    }
+}
+
+impl FromStr for WhiteSpace {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "pre" => Ok(Self::Pre),
+            "pre-wrap" => Ok(Self::PreWrap),
+            "nowrap" => Ok(Self::NoWrap),
+            "pre-line" => Ok(Self::PreLine),
+            _ => Err(Error::UnexpectedInput(format!(
+                "white-space {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// 大文字・小文字の変換（text-transform）
+//
+// - 変えるのは描く文字だけ。DOM のテキスト（選択してコピーする文字列）はそのまま
+// 仕様: https://www.w3.org/TR/css-text-3/#text-transform-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextTransform {
+    None,
+    /// 単語の最初の文字を大文字にする
+    Capitalize,
+    Uppercase,
+    Lowercase,
+}
+
+impl FromStr for TextTransform {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "capitalize" => Ok(Self::Capitalize),
+            "uppercase" => Ok(Self::Uppercase),
+            "lowercase" => Ok(Self::Lowercase),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-transform {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// CSS の長さの単位
+//
+// - 絶対単位: `px`（画面の 1 ピクセル）、`pt`（1/72 インチ = 4/3 px）
+// - フォント基準: `em`（その要素の font-size）、`rem`（ルート要素の font-size）、`ch`（文字 "0" の幅）
+// - ビューポート基準: `vw` / `vh`（ビューポートの幅・高さの 1%）
+// - `%` は包含ブロック（親のコンテンツボックス）基準。何の何 % かはプロパティごとに決まる
+// 仕様: https://www.w3.org/TR/css-values-4/#lengths
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LengthUnit {
+    Px,
+    Pt,
+    Em,
+    Rem,
+    Ch,
+    Vw,
+    Vh,
+    Percent,
+}
+
+// 単位の文字列 → LengthUnit（`%` はトークナイザが Percentage として分けるのでここには来ない）
+// 単位は大文字小文字を区別しない（`10PX` も `10px`）
+impl FromStr for LengthUnit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "px" => Ok(Self::Px),
+            "pt" => Ok(Self::Pt),
+            "em" => Ok(Self::Em),
+            "rem" => Ok(Self::Rem),
+            "ch" => Ok(Self::Ch),
+            "vw" => Ok(Self::Vw),
+            "vh" => Ok(Self::Vh),
+            _ => Err(Error::UnexpectedInput(format!(
+                "length unit {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// 長さ（`1.5em` → Length::Dimension(1.5, Em)）か、長さになる数式（`calc(100% - 20px)`）
+//
+// 値が px に決まるまでの 2 段階
+// 1) 計算値（ComputedStyle::defaulting）: フォント基準の単位と pt を px にする（`absolutize`）
+//    数式は % / vw / vh を含まなければ、ここで 1 つの px にまとめる
+// 2) レイアウト（LayoutObject::compute_size）: % と vw/vh を包含ブロックとビューポートから px にする（`to_px`）
+//
+// 言語ブリッジ（TS / Python / Go）
+// - TS の `{ value: number; unit: "px" | "em" | ... } | { calc: CalcNode }` のような値。
+//   数式の木を持てるよう Copy ではなく Clone にしている。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Length {
+    Dimension(f64, LengthUnit),
+    /// 型が長さの数式（`calc::parse` で確かめ済み）
+    Calc(Box<CalcNode>),
+}
+
+impl Length {
+    pub fn new(value: f64, unit: LengthUnit) -> Self {
+        Self::Dimension(value, unit)
+    }
+
+    pub fn px(value: f64) -> Self {
+        Self::new(value, LengthUnit::Px)
+    }
+
+    pub fn calc(node: CalcNode) -> Self {
+        Self::Calc(Box::new(node))
+    }
+
+    /// 負の値か（数式は使うときに範囲へ収めるので、ここでは負とみなさない）
+    pub fn is_negative(&self) -> bool {
+        matches!(self, Self::Dimension(value, _) if *value < 0.0)
+    }
+
+    /// `unit` の長さを含むか（数式なら中のどこかに含むか）
+    pub fn has_unit(&self, unit: LengthUnit) -> bool {
+        match self {
+            Self::Dimension(_, u) => *u == unit,
+            Self::Calc(node) => node.has_unit(unit),
+        }
+    }
+
+    /// フォント基準の単位と pt を px にする（% / vw / vh はそのまま）
+    ///
+    /// - `font_size`: その要素の font-size（px）
+    /// - `root_font_size`: ルート要素の font-size（px。`rem` の基準）
+    /// - `ch` は等幅フォントの文字幅（高さの CHAR_WIDTH / CHAR_HEIGHT 倍）
+    /// - 数式は中の長さを px にし、% / vw / vh が残らなければ計算して 1 つの px にする
+    ///
+    /// 例: font_size=32 のとき `1.5em` → 48px、`2ch` → 32px、`12pt` → 16px、`calc(1em + 2px)` → 34px
+    ///     root_font_size=20 のとき `2rem` → 40px
+    pub fn absolutize(&self, font_size: f64, root_font_size: f64) -> Self {
+        match self {
+            Self::Dimension(value, unit) => {
+                let (value, unit) = absolutize(*value, *unit, font_size, root_font_size);
+                Self::new(value, unit)
+            }
+            Self::Calc(node) => {
+                let node = node.map_dimensions(&|value, unit| {
+                    let (value, unit) = absolutize(value, unit, font_size, root_font_size);
+                    CalcNode::Dimension(value, unit)
+                });
+                if LAYOUT_UNITS.iter().any(|unit| node.has_unit(*unit)) {
+                    Self::calc(node)
+                } else {
+                    Self::px(node.evaluate(&|value, _| value))
+                }
+            }
+        }
+    }
+
+    /// レイアウト時に px（整数）にする
+    ///
+    /// - `percentage_base`: % の基準になる長さ（包含ブロックの幅など）
+    /// - `viewport`: vw / vh の基準になるビューポートの大きさ
+    ///
+    /// 例: percentage_base=200 のとき `25%` → 50、`calc(100% - 20px)` → 180、
+    ///     viewport=(600, 400) のとき `10vh` → 40
+    ///     無限大（`calc(1px / 0)`）や大きすぎる値は `clamp_px` で有限の範囲に収める
+    pub fn to_px(&self, percentage_base: i64, viewport: LayoutSize) -> i64 {
+        clamp_px(self.resolve(percentage_base as f64, viewport))
+    }
+
+    // % と vw / vh を決めて px（小数）にする
+    fn resolve(&self, percentage_base: f64, viewport: LayoutSize) -> f64 {
+        let dimension = |value: f64, unit| match unit {
+            LengthUnit::Percent => value * percentage_base / 100.0,
+            LengthUnit::Vw => value * viewport.width() as f64 / 100.0,
+            LengthUnit::Vh => value * viewport.height() as f64 / 100.0,
+            // 計算値では px になっているはずだが、念のため初期値の font-size で解決する
+            _ => absolutize(value, unit, FontSize::MEDIUM.px(), FontSize::MEDIUM.px()).0,
+        };
+        match self {
+            Self::Dimension(value, unit) => dimension(*value, *unit),
+            Self::Calc(node) => node.evaluate(&dimension),
+        }
+    }
+}
+
+/// レイアウトで扱う長さの上限（px。Blink の LayoutUnit の上限 2^25 - 1 と同じ）
+///
+/// いくつか足し合わせても i64 からあふれない大きさにしておく。
+pub const MAX_LAYOUT_PX: f64 = 33_554_431.0;
+
+/// px（小数）をレイアウトで使う整数にする
+///
+/// - ±`MAX_LAYOUT_PX` の範囲に収める（無限大もここに収まる）
+/// - NaN（`calc(0px / 0)` など）は 0
+///
+/// https://www.w3.org/TR/css-values-4/#calc-range
+pub fn clamp_px(px: f64) -> i64 {
+    if px.is_nan() {
+        return 0;
+    }
+    px.clamp(-MAX_LAYOUT_PX, MAX_LAYOUT_PX) as i64
+}
+
+// レイアウトまで値が決まらない単位
+const LAYOUT_UNITS: [LengthUnit; 3] = [LengthUnit::Percent, LengthUnit::Vw, LengthUnit::Vh];
+
+// フォント基準の単位と pt の値を px にする（% / vw / vh はそのまま返す）
+fn absolutize(
+    value: f64,
+    unit: LengthUnit,
+    font_size: f64,
+    root_font_size: f64,
+) -> (f64, LengthUnit) {
+    let px = match unit {
+        LengthUnit::Px => value,
+        LengthUnit::Pt => value * 4.0 / 3.0,
+        LengthUnit::Em => value * font_size,
+        LengthUnit::Rem => value * root_font_size,
+        LengthUnit::Ch => value * font_size * CHAR_WIDTH as f64 / CHAR_HEIGHT as f64,
+        LengthUnit::Vw | LengthUnit::Vh | LengthUnit::Percent => return (value, unit),
+    };
+    (px, LengthUnit::Px)
+}
+
+// CSS の border-style プロパティ（枠線の種類）
+//
+// - `<line-style>` のキーワードはすべて受け付けますが、描画はまだ `solid`（1 本の実線）だけです。
+//   `dashed` / `dotted` / `double` / `groove` / `ridge` / `inset` / `outset` は実線として描きます。
+// - `hidden` は `none` と同じく枠線なし（太さ 0）として扱います（表の枠線の競合解決は未対応）。
+// 仕様: https://www.w3.org/TR/css-backgrounds-3/#border-style
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BorderStyle {
+    None,
+    Hidden,
+    Dotted,
+    Dashed,
+    Solid,
+    Double,
+    Groove,
+    Ridge,
+    Inset,
+    Outset,
+}
+
+impl BorderStyle {
+    /// 枠線が無い種類か（`none` と `hidden`）。このとき枠線の太さは 0 になる
+    pub fn is_none(&self) -> bool {
+        matches!(self, Self::None | Self::Hidden)
+    }
+}
+
+impl FromStr for BorderStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "hidden" => Ok(Self::Hidden),
+            "dotted" => Ok(Self::Dotted),
+            "dashed" => Ok(Self::Dashed),
+            "solid" => Ok(Self::Solid),
+            "double" => Ok(Self::Double),
+            "groove" => Ok(Self::Groove),
+            "ridge" => Ok(Self::Ridge),
+            "inset" => Ok(Self::Inset),
+            "outset" => Ok(Self::Outset),
+            _ => Err(Error::UnexpectedInput(format!(
+                "border-style {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// CSS の box-sizing プロパティ（width/height がどの箱の大きさを指すか）
+//
+// - `content-box`（初期値）: width は中身だけの幅。padding と border はその外側に足される。
+// - `border-box`: width は border まで含めた幅。中身の幅は padding と border を引いた残り。
+// 仕様: https://www.w3.org/TR/css-sizing-3/#box-sizing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BoxSizing {
+    ContentBox,
+    BorderBox,
+}
+
+impl FromStr for BoxSizing {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "content-box" => Ok(Self::ContentBox),
+            "border-box" => Ok(Self::BorderBox),
+            _ => Err(Error::UnexpectedInput(format!(
+                "box-sizing {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red() -> Color {
+        Color::from_name("red").expect("red should be supported")
+    }
+
+    // 親: color=red, display=block, font-size=xx-large
+    fn parent_style() -> ComputedStyle {
+        let mut parent = ComputedStyle::new();
+        parent.set_specified(
+            PropertyId::Color,
+            SpecifiedValue::Value(PropertyValue::Color(red())),
+        );
+        parent.set_specified(
+            PropertyId::Display,
+            SpecifiedValue::Value(PropertyValue::Display(DisplayType::Block)),
+        );
+        parent.set_specified(
+            PropertyId::FontSize,
+            SpecifiedValue::Value(PropertyValue::FontSize(FontSize::new(48.0))),
+        );
+        parent.defaulting(None);
+        parent
+    }
+
+    #[test]
+    fn test_defaulting_without_declarations() {
+        // 継承するプロパティ（color, font-size）は親の値、しないもの（display）は初期値
+        let mut style = ComputedStyle::new();
+        style.defaulting(Some(parent_style()));
+        assert_eq!(style.color(), red());
+        assert_eq!(style.font_size(), FontSize::new(48.0));
+        assert_eq!(style.display(), DisplayType::Inline);
+
+        // 親がいなければすべて初期値
+        let mut root = ComputedStyle::new();
+        root.defaulting(None);
+        assert_eq!(root.color(), Color::black());
+        assert_eq!(root.background_color(), Color::transparent());
+    }
+
+    #[test]
+    fn test_defaulting_keywords() {
+        let mut style = ComputedStyle::new();
+        style.set_specified(PropertyId::Color, SpecifiedValue::Initial);
+        style.set_specified(PropertyId::Display, SpecifiedValue::Inherit);
+        style.set_specified(PropertyId::FontSize, SpecifiedValue::Unset);
+        style.defaulting(Some(parent_style()));
+
+        // initial: 親が赤でも初期値（黒）
+        assert_eq!(style.color(), Color::black());
+        // inherit: 継承しないプロパティでも親の値
+        assert_eq!(style.display(), DisplayType::Block);
+        // unset: font-size は継承するプロパティなので親の値
+        assert_eq!(style.font_size(), FontSize::new(48.0));
+    }
+
+    #[test]
+    fn test_border() {
+        let mut style = ComputedStyle::new();
+        style.set_specified(
+            PropertyId::Color,
+            SpecifiedValue::Value(PropertyValue::Color(red())),
+        );
+        style.set_specified(
+            PropertyId::BorderTopStyle,
+            SpecifiedValue::Value(PropertyValue::BorderStyle(BorderStyle::Solid)),
+        );
+        style.defaulting(None);
+
+        // 種類が solid なら初期値の太さ（medium = 3px）、none なら 0
+        assert_eq!(style.border_width(Side::Top), Length::px(3.0));
+        assert_eq!(style.border_width(Side::Bottom), Length::px(0.0));
+        // 色の初期値 currentColor は color の値になる
+        assert_eq!(style.border_color(Side::Top), red());
+        // width/height の初期値は auto
+        assert_eq!(style.width(), None);
+    }
+
+    #[test]
+    fn test_length_resolution() {
+        // 計算値: em はその要素の font-size（xx-large = 48px）基準で px になる
+        let mut style = ComputedStyle::new();
+        style.set_specified(
+            PropertyId::MarginTop,
+            SpecifiedValue::Value(PropertyValue::Length(Length::new(0.5, LengthUnit::Em))),
+        );
+        style.set_specified(
+            PropertyId::MarginLeft,
+            SpecifiedValue::Value(PropertyValue::Length(Length::new(
+                50.0,
+                LengthUnit::Percent,
+            ))),
+        );
+        style.defaulting(Some(parent_style()));
+        assert_eq!(style.margin(Side::Top), Some(Length::px(24.0)));
+        // % はレイアウトまで残る
+        let left = style
+            .margin(Side::Left)
+            .expect("margin-left should not be auto");
+        assert_eq!(left, Length::new(50.0, LengthUnit::Percent));
+
+        // レイアウト: % は包含ブロック、vw/vh はビューポート基準
+        let viewport = LayoutSize::new(600, 400);
+        assert_eq!(left.to_px(200, viewport), 100);
+        assert_eq!(Length::new(10.0, LengthUnit::Vh).to_px(200, viewport), 40);
+        assert_eq!(Length::new(12.0, LengthUnit::Pt).to_px(200, viewport), 16);
+        assert_eq!(Length::new(2.0, LengthUnit::Rem).to_px(200, viewport), 32);
+    }
+
+    #[test]
+    fn test_calc_resolution() {
+        // 計算値: % / vw / vh を含まない式は px にまとまり、含む式はレイアウトまで残る
+        let mut style = ComputedStyle::new();
+        let calc = |node| SpecifiedValue::Value(PropertyValue::Length(Length::calc(node)));
+        let em = |n| Box::new(CalcNode::Dimension(n, LengthUnit::Em));
+        style.set_specified(
+            PropertyId::FontSize,
+            calc(CalcNode::Add(
+                em(1.0),
+                Box::new(CalcNode::Dimension(12.0, LengthUnit::Pt)),
+            )),
+        );
+        style.set_specified(
+            PropertyId::MarginTop,
+            calc(CalcNode::Mul(em(0.5), Box::new(CalcNode::Number(2.0)))),
+        );
+        style.set_specified(
+            PropertyId::MarginLeft,
+            calc(CalcNode::Min(vec![
+                CalcNode::Dimension(50.0, LengthUnit::Percent),
+                CalcNode::Dimension(2.0, LengthUnit::Em),
+            ])),
+        );
+        style.defaulting(Some(parent_style()));
+
+        // font-size の em は親（48px）基準、それ以外の em は自分の font-size（64px）基準
+        assert_eq!(style.font_size(), FontSize::new(64.0));
+        assert_eq!(style.margin(Side::Top), Some(Length::px(64.0)));
+        let left = style
+            .margin(Side::Left)
+            .expect("margin-left should not be auto");
+        assert!(left.has_unit(LengthUnit::Percent));
+        let viewport = LayoutSize::new(600, 400);
+        assert_eq!(left.to_px(200, viewport), 100);
+        assert_eq!(left.to_px(1000, viewport), 128);
+
+        // 無限大や大きすぎる値は有限の範囲に収め、NaN は 0 にする
+        let px = |n| Box::new(CalcNode::Dimension(n, LengthUnit::Px));
+        let divide = |n, d| Length::calc(CalcNode::Div(px(n), Box::new(CalcNode::Number(d))));
+        let max = MAX_LAYOUT_PX as i64;
+        assert_eq!(divide(1.0, 0.0).to_px(200, viewport), max);
+        assert_eq!(divide(-1.0, 0.0).to_px(200, viewport), -max);
+        assert_eq!(divide(0.0, 0.0).to_px(200, viewport), 0);
+        assert_eq!(Length::px(1e300).to_px(200, viewport), max);
+    }
+
+    #[test]
+    fn test_relative_font() {
+        // 親: font-size 48px, font-weight 400
+        let mut style = ComputedStyle::new();
+        style.set_specified(
+            PropertyId::FontSize,
+            SpecifiedValue::Value(PropertyValue::Length(Length::new(
+                50.0,
+                LengthUnit::Percent,
+            ))),
+        );
+        style.set_specified(
+            PropertyId::FontWeight,
+            SpecifiedValue::Value(PropertyValue::Bolder),
+        );
+        // 自分の font-size を基準にする em は、計算後の font-size（24px）で解決される
+        style.set_specified(
+            PropertyId::MarginTop,
+            SpecifiedValue::Value(PropertyValue::Length(Length::new(1.0, LengthUnit::Em))),
+        );
+        style.defaulting(Some(parent_style()));
+
+        assert_eq!(style.font_size(), FontSize::new(24.0));
+        assert_eq!(style.font_weight(), FontWeight::BOLD);
+        assert_eq!(style.margin(Side::Top), Some(Length::px(24.0)));
+        assert_eq!("x-large".parse::<FontSize>().map(|s| s.px()), Ok(24.0));
+    }
+
+    #[test]
+    fn test_line_height() {
+        // % は自分の font-size（48px）で px にしてから継承し、数値は子の font-size に掛け直す
+        let mut parent = ComputedStyle::new();
+        parent.set_specified(
+            PropertyId::FontSize,
+            SpecifiedValue::Value(PropertyValue::FontSize(FontSize::new(48.0))),
+        );
+        parent.set_specified(
+            PropertyId::LineHeight,
+            SpecifiedValue::Value(PropertyValue::Length(Length::new(
+                50.0,
+                LengthUnit::Percent,
+            ))),
+        );
+        parent.defaulting(None);
+        assert_eq!(parent.line_height(), Some(24.0));
+
+        let mut child = ComputedStyle::new();
+        child.set_specified(
+            PropertyId::FontSize,
+            SpecifiedValue::Value(PropertyValue::Length(Length::px(10.0))),
+        );
+        child.defaulting(Some(parent.clone()));
+        assert_eq!(child.line_height(), Some(24.0));
+
+        parent.set(PropertyId::LineHeight, PropertyValue::Number(2.0));
+        let mut child = ComputedStyle::new();
+        child.set_specified(
+            PropertyId::FontSize,
+            SpecifiedValue::Value(PropertyValue::Length(Length::px(10.0))),
+        );
+        child.defaulting(Some(parent));
+        assert_eq!(child.line_height(), Some(20.0));
+        assert_eq!(child.white_space(), WhiteSpace::Normal);
+        assert_eq!(child.letter_spacing(), 0.0);
+    }
+
+    #[test]
+    fn test_current_color() {
+        // `color: currentColor` は親の color、background-color の currentColor は自分の color
+        let mut style = ComputedStyle::new();
+        style.set_specified(
+            PropertyId::Color,
+            SpecifiedValue::Value(PropertyValue::CurrentColor),
+        );
+        style.set_specified(
+            PropertyId::BackgroundColor,
+            SpecifiedValue::Value(PropertyValue::CurrentColor),
+        );
+        style.defaulting(Some(parent_style()));
+        assert_eq!(style.color(), red());
+        assert_eq!(style.background_color(), red());
+    }
+
+    #[test]
+    fn test_color_syntax() {
+        assert_eq!(Color::from_name("Red"), Ok(Color::rgb(255, 0, 0)));
+        assert_eq!(Color::from_name("grey"), Color::from_name("gray"));
+        assert!(Color::from_name("transparent").is_ok_and(|c| c.is_transparent()));
+        assert!(Color::from_name("reddish").is_err());
+
+        assert_eq!(Color::from_code("#f00"), Ok(Color::rgb(255, 0, 0)));
+        assert_eq!(Color::from_code("#1E90FF"), Color::from_name("dodgerblue"));
+        assert_eq!(
+            Color::from_code("#ff000080"),
+            Ok(Color::rgba(255, 0, 0, 128.0 / 255.0))
+        );
+        assert!(Color::from_code("ff0000").is_err());
+        assert!(Color::from_code("#ggg").is_err());
+        assert_eq!(Color::rgb(0, 255, 0).code_u32(), 0x00ff00);
+
+        // 色相は 360 で折り返す
+        assert_eq!(Color::from_hsl(240.0, 1.0, 0.5, 1.0), Color::rgb(0, 0, 255));
+        assert_eq!(
+            Color::from_hsl(-120.0, 1.0, 0.5, 1.0),
+            Color::rgb(0, 0, 255)
+        );
+        assert_eq!(
+            Color::from_hsl(0.0, 0.0, 1.0, 0.5),
+            Color::rgba(255, 255, 255, 0.5)
+        );
+    }
+}
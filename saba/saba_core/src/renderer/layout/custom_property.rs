@@ -0,0 +1,246 @@
+//! カスタムプロパティ（`--brand-color: #0af`）と `var()` の置き換え
+//!
+//! 役割
+//! - `--` で始まる名前のプロパティは、値を解釈せずトークン列のまま持ちます（どのプロパティの値にも使えるため）。
+//!   カスタムプロパティはすべて継承します。
+//! - `var(--name, fallback)` を、計算値を決めるときにそのトークン列で置き換えます。
+//!   置き換えてから、ふつうのプロパティの値として読み直します（`property::parse_specified`）。
+//!
+//! 無効になる場合（invalid at computed-value time）
+//! - 参照したカスタムプロパティが無く、fallback も無い
+//! - カスタムプロパティどうしが循環して参照している（`--a: var(--b); --b: var(--a)`）。
+//!   循環に入っているものはすべて値が無いものとして扱います（fallback があっても無効）。
+//! - 置き換えた結果が、そのプロパティの値として読めない
+//!
+//! 無効になったふつうのプロパティは `unset` と同じ（継承するものは親の値、しないものは初期値）になります。
+//!
+//! 例
+//! ```text
+//! :root { --brand: #0af; }     p { color: var(--brand); }  → color は #0af
+//! p { margin-top: var(--gap, 8px); }                       → --gap が無ければ 8px
+//! ```
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `CustomProperties` は名前 → トークン列の辞書（TS の `Map<string, Token[]>`）。
+//!
+//! 仕様: https://www.w3.org/TR/css-variables-1/
+use crate::renderer::css::cssom::ComponentValue;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// カスタムプロパティの計算値（`var()` は置き換え済み）
+pub type CustomProperties = BTreeMap<String, Vec<ComponentValue>>;
+
+/// カスタムプロパティの名前か（`--` で始まる）
+pub fn is_custom_property(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+/// 値に `var()` が含まれるか
+pub fn contains_var(values: &[ComponentValue]) -> bool {
+    values.iter().any(is_var_start)
+}
+
+fn is_var_start(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Function(name) if name.eq_ignore_ascii_case("var"))
+}
+
+/// 値の中の `var()` を置き換える
+///
+/// - `lookup` でカスタムプロパティの（置き換え済みの）値を引く。無ければ fallback を使う
+/// - 値も fallback も無い `var()` があれば None（無効）
+///
+/// 例: `[Function("var"), Ident("--x"), Comma, Number(1), )]` で `--x` が無い → `[Number(1)]`
+pub fn substitute(
+    values: &[ComponentValue],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<ComponentValue>>,
+) -> Option<Vec<ComponentValue>> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        if is_var_start(&values[i]) {
+            // 対応する `)` を探す（fallback の中の入れ子の括弧も数える）
+            let mut depth = 0;
+            let mut end = None;
+            for (j, v) in values.iter().enumerate().skip(i) {
+                match v {
+                    ComponentValue::Function(_) | ComponentValue::OpenParenthesis => depth += 1,
+                    ComponentValue::CloseParenthesis => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(j);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let end = end?;
+            let args = &values[i + 1..end];
+            let (name, fallback) = match args {
+                [ComponentValue::Ident(name)] => (name, None),
+                [ComponentValue::Ident(name), ComponentValue::Comma, fallback @ ..] => {
+                    (name, Some(fallback))
+                }
+                _ => return None,
+            };
+            if !is_custom_property(name) {
+                return None;
+            }
+            match (lookup(name), fallback) {
+                (Some(value), _) => result.extend(value),
+                (None, Some(fallback)) => result.extend(substitute(fallback, lookup)?),
+                (None, None) => return None,
+            }
+            i = end + 1;
+        } else {
+            result.push(values[i].clone());
+            i += 1;
+        }
+    }
+    Some(result)
+}
+
+/// 要素のカスタムプロパティの計算値を決める
+///
+/// - `inherited`: 親の計算値（ルートなら空）。指定が無いものはそのまま受け継ぐ
+/// - `specified`: この要素で指定された値（カスケードで勝ったもの。`var()` を含んでよい）
+///   `initial` は値が無い状態、`inherit` / `unset` は親の値にする
+pub fn compute(
+    inherited: &CustomProperties,
+    specified: &BTreeMap<String, Vec<ComponentValue>>,
+) -> CustomProperties {
+    let mut resolver = Resolver {
+        inherited,
+        specified,
+        computed: BTreeMap::new(),
+        stack: Vec::new(),
+        in_cycle: BTreeSet::new(),
+    };
+    let mut result = inherited.clone();
+    for name in specified.keys() {
+        match resolver.resolve(name) {
+            Some(value) => result.insert(name.clone(), value),
+            None => result.remove(name),
+        };
+    }
+    result
+}
+
+// 参照をたどって値を決める（深さ優先。たどっている途中の名前を stack に積み、循環を見つける）
+struct Resolver<'a> {
+    inherited: &'a CustomProperties,
+    specified: &'a BTreeMap<String, Vec<ComponentValue>>,
+    // 決まった値（None は無効）
+    computed: BTreeMap<String, Option<Vec<ComponentValue>>>,
+    stack: Vec<String>,
+    in_cycle: BTreeSet<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, name: &str) -> Option<Vec<ComponentValue>> {
+        if let Some(value) = self.computed.get(name) {
+            return value.clone();
+        }
+        if let Some(start) = self.stack.iter().position(|n| n == name) {
+            // 循環: stack のこの名前から先はすべて循環に入っている
+            for n in &self.stack[start..] {
+                self.in_cycle.insert(n.clone());
+            }
+            return None;
+        }
+        let specified = match self.specified.get(name) {
+            Some(values) => values,
+            None => return self.inherited.get(name).cloned(),
+        };
+
+        let value = match specified.as_slice() {
+            [ComponentValue::Ident(keyword)] if keyword == "initial" => None,
+            [ComponentValue::Ident(keyword)] if keyword == "inherit" || keyword == "unset" => {
+                self.inherited.get(name).cloned()
+            }
+            _ => {
+                self.stack.push(String::from(name));
+                let value = substitute(specified, &mut |n| self.resolve(n));
+                self.stack.pop();
+                value
+            }
+        };
+        let value = if self.in_cycle.contains(name) {
+            None
+        } else {
+            value
+        };
+        self.computed.insert(String::from(name), value.clone());
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssToken;
+    use crate::renderer::css::token::CssTokenizer;
+
+    fn tokens(css: &str) -> Vec<ComponentValue> {
+        CssTokenizer::new(String::from(css))
+            .filter(|t| *t != CssToken::Whitespace)
+            .collect()
+    }
+
+    fn map(entries: &[(&str, &str)]) -> BTreeMap<String, Vec<ComponentValue>> {
+        entries
+            .iter()
+            .map(|(name, css)| (String::from(*name), tokens(css)))
+            .collect()
+    }
+
+    #[test]
+    fn test_substitute() {
+        let custom = map(&[("--x", "red")]);
+        let mut lookup = |n: &str| custom.get(n).cloned();
+        assert_eq!(
+            substitute(&tokens("1px solid var(--x)"), &mut lookup),
+            Some(tokens("1px solid red"))
+        );
+        assert_eq!(
+            substitute(&tokens("var(--y, var(--x))"), &mut lookup),
+            Some(tokens("red"))
+        );
+        assert_eq!(substitute(&tokens("var(--y)"), &mut lookup), None);
+        assert_eq!(substitute(&tokens("var(y, red)"), &mut lookup), None);
+    }
+
+    #[test]
+    fn test_compute() {
+        let parent = map(&[("--brand", "blue"), ("--gap", "4px")]);
+        let specified = map(&[
+            ("--accent", "var(--brand)"),
+            ("--gap", "initial"),
+            ("--missing", "var(--nope)"),
+        ]);
+        let computed = compute(&parent, &specified);
+        assert_eq!(computed.get("--brand"), Some(&tokens("blue")));
+        assert_eq!(computed.get("--accent"), Some(&tokens("blue")));
+        assert_eq!(computed.get("--gap"), None);
+        assert_eq!(computed.get("--missing"), None);
+    }
+
+    #[test]
+    fn test_cycles() {
+        // 循環に入っているものは fallback があっても無効。循環を参照するだけなら fallback を使える
+        let specified = map(&[
+            ("--a", "var(--b, red)"),
+            ("--b", "var(--a)"),
+            ("--c", "var(--a, green)"),
+            ("--self", "var(--self, blue)"),
+        ]);
+        let computed = compute(&BTreeMap::new(), &specified);
+        assert_eq!(computed.get("--a"), None);
+        assert_eq!(computed.get("--b"), None);
+        assert_eq!(computed.get("--c"), Some(&tokens("green")));
+        assert_eq!(computed.get("--self"), None);
+    }
+}
@@ -1,20 +1,31 @@
-use crate::alloc::string::ToString;
 use crate::constants::CHAR_HEIGHT_WITH_PADDING;
 use crate::constants::CHAR_WIDTH;
-use crate::constants::CONTENT_AREA_WIDTH;
-use crate::constants::WINDOW_PADDING;
-use crate::constants::WINDOW_WIDTH;
+use crate::constants::SELECTION_COLOR;
 use crate::display_item::DisplayItem;
-use crate::renderer::css::cssom::ComponentValue;
 use crate::renderer::css::cssom::Declaration;
 use crate::renderer::css::cssom::Selector;
-use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::selector;
+use crate::renderer::css::selector::MatchingContext;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
+use crate::renderer::dom::range::Range;
+use crate::renderer::layout::animation::AnimationTimeline;
+use crate::renderer::layout::computed_style::clamp_px;
+use crate::renderer::layout::computed_style::BoxSizing;
 use crate::renderer::layout::computed_style::Color;
 use crate::renderer::layout::computed_style::ComputedStyle;
 use crate::renderer::layout::computed_style::DisplayType;
-use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::Length;
+use crate::renderer::layout::computed_style::LengthUnit;
+use crate::renderer::layout::computed_style::TextAlign;
+use crate::renderer::layout::computed_style::TextTransform;
+use crate::renderer::layout::computed_style::WhiteSpace;
+use crate::renderer::layout::custom_property::is_custom_property;
+use crate::renderer::layout::property::PropertyId;
+use crate::renderer::layout::property::PropertyValue;
+use crate::renderer::layout::property::Side;
+use crate::renderer::layout::shorthand::expand_declaration;
+use crate::renderer::layout::style_resolver::StyleResolver;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
@@ -22,55 +33,254 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
-/// 単語境界（スペース）での折り返し位置を、右から左へ探す
-/// 仕様（参考）: https://drafts.csswg.org/css-text/#word-break-property
+/// タブを展開するときのタブ幅（スペースの個数）
+/// https://drafts.csswg.org/css-text/#tab-size-property
+const TAB_SIZE: usize = 8;
+
+/// white-space で残す改行で区切った、テキストの段落
 ///
-/// 概要
-/// - 1 行に収めたい最大インデックス `max_index` から左へ向かって走査し、
-///   最初に見つかったスペースの位置を返します。
-/// - 見つからなければ `max_index` を返して、そこで強制的に折り返します。
+/// - `chars` は空白を処理した後の各文字と、その文字が DOM の Text ノードで何文字目か。
+/// - `end` は段落を終える改行の DOM オフセット（最後の段落はテキストの長さ）。
+///   文字の無い段落（空行）でキャレットを置く位置に使う。
+#[derive(Debug, Clone, PartialEq)]
+struct Paragraph {
+    chars: Vec<(char, usize)>,
+    end: usize,
+}
+
+/// white-space に従って空白と改行を処理し、段落に分ける
+/// 仕様: https://drafts.csswg.org/css-text/#white-space-processing
 ///
-/// 例: line="hello world", max_index=8 → 5（"hello| world" の '|' 位置）
-fn find_index_for_line_break(line: String, max_index: usize) -> usize {
-    for i in (0..max_index).rev() {
-        if line.chars().collect::<Vec<char>>()[i] == ' ' {
-            return i;
+/// - スペースを詰める値（normal / nowrap / pre-line）: 連続する空白（スペース・タブ・残さない改行）を
+///   1 個のスペースに圧縮し、段落の前後のスペースは取り除く。
+/// - スペースを残す値（pre / pre-wrap）: タブは次のタブ位置（TAB_SIZE の倍数）までのスペースに展開する。
+///   展開したスペースはどれもタブの DOM オフセットを持つ。
+/// - 改行を残す値（pre / pre-wrap / pre-line）: 改行で段落を区切る（改行そのものは文字に含めない）。
+///
+/// 例
+/// - "a  b\n", normal → [a b]（オフセット [0, 1, 3]）
+/// - "a \n b", pre-line → [a] と [b]
+/// - "a\tb", pre → [a       b]（a の後ろに 7 個のスペース）
+fn process_white_space(text: &str, white_space: WhiteSpace) -> Vec<Paragraph> {
+    let mut paragraphs = vec![];
+    let mut chars: Vec<(char, usize)> = vec![];
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\n' if white_space.preserves_newlines() => {
+                paragraphs.push(Paragraph {
+                    chars: core::mem::take(&mut chars),
+                    end: i,
+                });
+            }
+            ' ' | '\t' | '\n' if white_space.collapses_spaces() => {
+                if !chars.is_empty() && chars.last().map(|(c, _)| *c) != Some(' ') {
+                    chars.push((' ', i));
+                }
+            }
+            '\t' => {
+                let width = TAB_SIZE - chars.len() % TAB_SIZE;
+                chars.extend(core::iter::repeat((' ', i)).take(width));
+            }
+            _ => chars.push((c, i)),
+        }
+    }
+    paragraphs.push(Paragraph {
+        chars,
+        end: text.chars().count(),
+    });
+
+    if white_space.collapses_spaces() {
+        for paragraph in paragraphs.iter_mut() {
+            if paragraph.chars.last().map(|(c, _)| *c) == Some(' ') {
+                paragraph.chars.pop();
+            }
         }
     }
-    max_index
+    paragraphs
 }
 
-/// 等幅フォントの幅ベースで、テキストを複数行へ分割する（簡易ワードラップ）
-/// 仕様（参考）: https://drafts.csswg.org/css-text/#word-break-property
+/// text-transform に従って大文字・小文字を変える
+/// 仕様: https://drafts.csswg.org/css-text/#text-transform-property
 ///
-/// 概要
-/// - 各文字の見かけ幅を `char_width`（px）として、1行に収まる最大文字数を計算。
-/// - その範囲内で右端からスペースを探し、そこで改行。残りの文字列に対して再帰的に繰り返す。
-/// - スペースが見つからない（長い単語）場合は強制的に分割（簡易実装）。
+/// - 1 文字が複数の文字になる場合（"ß" → "SS"）は、どれも元の文字の DOM オフセットを持つ。
+/// - capitalize は単語（空白の後、または段落の先頭から始まる文字列）の最初の文字だけを大文字にする。
 ///
-/// 例
-/// - WINDOW_WIDTH=600, WINDOW_PADDING=5, char_width=8 のとき
-///   1行あたりの概算最大文字数 = (WINDOW_WIDTH + WINDOW_PADDING) / char_width
-///   line が長ければ "... ... ..." のスペース位置で折り返し、Vec<String> に分割結果を返す。
-fn split_text(line: String, char_width: i64) -> Vec<String> {
-    let mut result: Vec<String> = vec![];
-    if line.len() as i64 * char_width > (WINDOW_WIDTH + WINDOW_PADDING) {
-        let s = line.split_at(find_index_for_line_break(
-            line.clone(),
-            ((WINDOW_WIDTH + WINDOW_PADDING) / char_width) as usize,
-        ));
-        result.push(s.0.to_string());
-        result.extend(split_text(s.1.trim().to_string(), char_width))
-    } else {
-        result.push(line);
+/// 例: "hello world", capitalize → "Hello World"
+fn transform_text(chars: Vec<(char, usize)>, text_transform: TextTransform) -> Vec<(char, usize)> {
+    let mut result = vec![];
+    let mut word_start = true;
+    for (c, offset) in chars {
+        match text_transform {
+            TextTransform::Uppercase => result.extend(c.to_uppercase().map(|u| (u, offset))),
+            TextTransform::Capitalize if word_start => {
+                result.extend(c.to_uppercase().map(|u| (u, offset)))
+            }
+            TextTransform::Lowercase => result.extend(c.to_lowercase().map(|l| (l, offset))),
+            TextTransform::None | TextTransform::Capitalize => result.push((c, offset)),
+        }
+        word_start = c.is_whitespace();
     }
     result
 }
 
+/// 段落を行に分ける（貪欲な折り返し）。各行の `[start, end)`（`chars` のインデックス）を返す
+/// 仕様（参考）: https://drafts.csswg.org/css-text/#line-breaking
+///
+/// - `advances` は各文字の送り幅（px）。最初の行は `first_width`、それ以降は `width` に収める。
+/// - 折り返すのは white-space が折り返しを許すときだけ（pre / nowrap は 1 行のまま）。
+/// - 単語の途中で溢れたら、直前のスペースの後ろで折り返す。スペースが無い長い単語は文字の間で切る。
+/// - 行末のスペースは幅を超えてもよい（ぶら下げ）。スペースを詰める値では行末のスペースを取り除く。
+///
+/// 例: "hello world"（1 文字 8px）、幅 64px → [0, 5)（"hello"）と [6, 11)（"world"）
+fn break_lines(
+    chars: &[(char, usize)],
+    advances: &[i64],
+    first_width: i64,
+    width: i64,
+    white_space: WhiteSpace,
+) -> Vec<(usize, usize)> {
+    let mut lines = vec![];
+    let mut start = 0;
+    loop {
+        let available = if lines.is_empty() { first_width } else { width };
+        let mut end = start;
+        let mut x: i64 = 0;
+        // この行に乗せた最後のスペース（その後ろで折り返せる）
+        let mut last_space = None;
+        while end < chars.len() {
+            let is_space = chars[end].0 == ' ';
+            if white_space.wraps()
+                && !is_space
+                && end > start
+                && x.saturating_add(advances[end]) > available
+            {
+                break;
+            }
+            if is_space {
+                last_space = Some(end);
+            }
+            x = x.saturating_add(advances[end]);
+            end += 1;
+        }
+        if end == chars.len() {
+            lines.push((start, end));
+            return lines;
+        }
+
+        let (mut line_end, next) = match last_space {
+            Some(space) => (space + 1, space + 1),
+            None => (end, end),
+        };
+        if white_space.collapses_spaces() {
+            while line_end > start && chars[line_end - 1].0 == ' ' {
+                line_end -= 1;
+            }
+        }
+        lines.push((start, line_end));
+        start = next;
+    }
+}
+
+/// 折り返し後のテキスト 1 行分（描画とテキスト選択で共有する）
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    text: String,
+    // 行の左上座標（text-align と text-indent でずらした後）
+    point: LayoutPoint,
+    // 各文字の左端の x（行の左端から。最後の要素は行の右端）と行の高さ（px）
+    // letter-spacing / word-spacing / 両端揃えで、文字ごとに送り幅が変わる
+    positions: Vec<i64>,
+    height: i64,
+    // 各文字が DOM の Text ノードで何文字目か
+    offsets: Vec<usize>,
+    // 行末（最後の文字の直後）の DOM オフセット
+    end_offset: usize,
+}
+
+impl TextLine {
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        LayoutSize::new(*self.positions.last().unwrap_or(&0), self.height)
+    }
+
+    /// 座標 `(x,y)` が行の矩形の中にあるか
+    pub fn contains(&self, position: (i64, i64)) -> bool {
+        let size = self.size();
+        self.point.x() <= position.0
+            && position.0 <= self.point.x() + size.width()
+            && self.point.y() <= position.1
+            && position.1 < self.point.y() + size.height()
+    }
+
+    /// x 座標に最も近い文字境界の DOM オフセットを返す（文字の中央より右なら次の境界）
+    pub fn offset_at_x(&self, x: i64) -> usize {
+        let x = x - self.point.x();
+        for (i, offset) in self.offsets.iter().enumerate() {
+            if x * 2 < self.positions[i] + self.positions[i + 1] {
+                return *offset;
+            }
+        }
+        self.end_offset
+    }
+
+    /// 行の始まりと終わりの DOM オフセット
+    pub fn start_offset(&self) -> usize {
+        match self.offsets.first() {
+            Some(o) => *o,
+            None => self.end_offset,
+        }
+    }
+
+    pub fn end_offset(&self) -> usize {
+        self.end_offset
+    }
+
+    /// DOM オフセットの範囲 `[start, end)` のうち、この行に乗っている部分の矩形を返す
+    pub fn rect_for_offsets(&self, start: usize, end: usize) -> Option<(LayoutPoint, LayoutSize)> {
+        let first = self.offsets.iter().position(|o| start <= *o && *o < end)?;
+        let count = self.offsets[first..]
+            .iter()
+            .take_while(|o| start <= **o && **o < end)
+            .count();
+        Some((
+            LayoutPoint::new(self.point.x() + self.positions[first], self.point.y()),
+            LayoutSize::new(
+                self.positions[first + count] - self.positions[first],
+                self.height,
+            ),
+        ))
+    }
+
+    /// 行を、文字を等間隔（`char_width` ずつ）に並べられるひと続きの文字列（ラン）に分ける
+    ///
+    /// - 描画側は等幅で文字を並べるので、字間や両端揃えで送り幅が変わる文字の後ろでランを切る。
+    /// - 返り値は各ランの文字列と、行の左端からの x。
+    ///
+    /// 例: "a b"（1 文字 8px、word-spacing: 4px）→ [("a ", 0), ("b", 20)]
+    fn runs(&self, char_width: i64) -> Vec<(String, i64)> {
+        let mut runs: Vec<(String, i64)> = vec![];
+        for (i, c) in self.text.chars().enumerate() {
+            let continues = i > 0 && self.positions[i] - self.positions[i - 1] == char_width;
+            match runs.last_mut() {
+                Some((text, _)) if continues => text.push(c),
+                _ => runs.push((String::from(c), self.positions[i])),
+            }
+        }
+        runs
+    }
+}
+
 /// DOM ノードからレイアウトオブジェクト（描画用ノード）を1つ生成する
 ///
 /// 概要
-/// - 与えられた DOM `node` に対して、CSSOM のルールを適用（カスケード）し、
+/// - 与えられた DOM `node` に対して、スタイルシートのルールを適用（カスケード）し、
 ///   既定値/継承でスタイルを補完した上で `LayoutObject` を作ります。
 /// - `display:none` の場合はオブジェクトを生成せず `None` を返します（レイアウトツリーから除外）。
 /// - 最終的な `display` に基づき、`Block`/`Inline`/`Text` などの種類を確定します。
@@ -78,7 +288,9 @@ fn split_text(line: String, char_width: i64) -> Vec<String> {
 /// 引数
 /// - `node`: 変換対象の DOM ノード（`None` のとき何もしない）
 /// - `parent_obj`: 親レイアウト（継承や接続に使う）。ルートのときは `None`。
-/// - `cssom`: スタイルシート（セレクタ照合して宣言を適用）。
+/// - `resolver`: スタイルシートの列（UA/利用者/ページ作者）とページの状態（`:hover` など）を持ち、
+///   一致するルールを索引で探す。同じ属性の兄弟のスタイルが計算済みなら、それを共有する。
+/// - `timeline`: トランジション・アニメーションの状態。
 ///
 /// 戻り値
 /// - `Some(Rc<RefCell<LayoutObject>>)` 生成できた場合
@@ -86,42 +298,54 @@ fn split_text(line: String, char_width: i64) -> Vec<String> {
 pub fn create_layout_object(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &StyleSheet,
+    resolver: &mut StyleResolver,
+    timeline: &mut AnimationTimeline,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     if let Some(n) = node {
         // 1) DOM ノードに対応する LayoutObject の“器”を作る（まだスタイル未適用）
         // LayoutObjectを作成する
         let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj)));
 
-        // 2) CSSOM の各ルールについて、セレクタにマッチするなら宣言を適用（カスケーディング）
-        //    - is_node_selected: セレクタとこのノード（タグ名/クラス/ID等の簡易版）を照合
-        //    - cascading_style: 指定された宣言群を style に反映（後勝ち）
-        // CSSのルールをセレクタで選択されたノードに適用する
-        for rule in &cssom.rules {
-            if layout_object.borrow().is_node_selected(&rule.selector) {
-                layout_object
-                    .borrow_mut()
-                    .cascading_style(rule.declarations.clone());
-            }
+        // 2) 同じ親・同じタグと属性の兄弟のスタイルが計算済みなら、それを使う（スタイル共有）
+        //    - 3) 4) の計算を丸ごと省ける。`:first-child` などで兄弟と違いが出るときは共有しない
+        let shared_style = resolver.shared_style(n, parent_obj);
+        if let Some(style) = shared_style {
+            layout_object.borrow_mut().style = style;
+        } else {
+            // 3) 一致するルールの宣言を集めて適用（カスケーディング）
+            //    - cascaded_declarations: 出どころ/!important → 詳細度 → 出現順で弱い順に並べる
+            //    - cascading_style: 並んだ宣言を style に反映（後勝ちなので最も強い宣言が残る）
+            // CSSのルールをセレクタで選択されたノードに適用する
+            let declarations = resolver.cascaded_declarations(n);
+            layout_object.borrow_mut().cascading_style(declarations);
+
+            // 4) 指定が無いプロパティは既定値 or 親からの継承で補う（defaulting）
+            //    - 例: color は親から継承、display は要素種別により既定値、など
+            // CSSでスタイルが指定されていない場合、デフォルトの値または親のノードから継承した値を使用する
+            let parent_style = if let Some(parent) = parent_obj {
+                Some(parent.borrow().style())
+            } else {
+                None
+            };
+            layout_object.borrow_mut().defaulting_style(parent_style);
+            resolver.remember(n, parent_obj, &layout_object.borrow().style);
         }
 
-        // 3) 指定が無いプロパティは既定値 or 親からの継承で補う（defaulting）
-        //    - 例: color は親から継承、display は要素種別により既定値、など
-        // CSSでスタイルが指定されていない場合、デフォルトの値または親のノードから継承した値を使用する
-        let parent_style = if let Some(parent) = parent_obj {
-            Some(parent.borrow().style())
-        } else {
-            None
-        };
-        layout_object.borrow_mut().defaulting_style(n, parent_style);
+        // 5) トランジション・アニメーション中のプロパティは、今の時刻の値で上書きする
+        //    - 子は親の上書き後の値を継承する（親の color をアニメーションすれば子の文字色も変わる）
+        timeline.animate(
+            n,
+            &mut layout_object.borrow_mut().style,
+            resolver.stylesheets(),
+        );
 
-        // 4) display:none ならレイアウトツリーに“存在しない”扱い → ここで除外
+        // 6) display:none ならレイアウトツリーに“存在しない”扱い → ここで除外
         // displayプロパティがnoneの場合、ノードを作成しない
         if layout_object.borrow().style().display() == DisplayType::DisplayNone {
             return None;
         }
 
-        // 5) 最終的な display に基づいて、このノードの種類（Block/Inline/Text）を確定
+        // 7) 最終的な display に基づいて、このノードの種類（Block/Inline/Text）を確定
         // displayプロパティの最終的な値を使用してノードの種類を決定する
         layout_object.borrow_mut().update_kind();
         return Some(layout_object);
@@ -149,6 +373,13 @@ pub struct LayoutObject {
     style: ComputedStyle,
     point: LayoutPoint,
     size: LayoutSize,
+    // compute_size で px に解決した margin / border / padding（ブロック要素以外は常に 0）
+    margin: EdgeSizes,
+    border: EdgeSizes,
+    padding: EdgeSizes,
+    // compute_size で決めたテキストの行の幅と、最初の行の字下げ（px。テキスト以外は常に 0）
+    line_width: i64,
+    text_indent: i64,
 }
 
 impl PartialEq for LayoutObject {
@@ -173,6 +404,11 @@ impl LayoutObject {
             style: ComputedStyle::new(),
             point: LayoutPoint::new(0, 0),
             size: LayoutSize::new(0, 0),
+            margin: EdgeSizes::default(),
+            border: EdgeSizes::default(),
+            padding: EdgeSizes::default(),
+            line_width: 0,
+            text_indent: 0,
         }
     }
 
@@ -180,7 +416,7 @@ impl LayoutObject {
     ///
     /// ざっくりの方針（学習用）
     /// - display:none のときは何も描かない（空ベクタ）
-    /// - Block 要素: 背景などの矩形（Rect）を 1 枚追加
+    /// - Block 要素: 背景の矩形（Rect）を 1 枚、枠線があれば辺ごとに Border を追加
     /// - Inline 要素: 現時点では描かない（将来 `<img>` などをここで処理）
     /// - Text ノード: テキストを折り返し単位で複数の Text DisplayItem に分割
     ///
@@ -196,14 +432,16 @@ impl LayoutObject {
         match self.kind {
             LayoutObjectKind::Block => {
                 // (d1)
-                // ブロック要素は背景（や枠線など）を塗る前提の最小モデル。
-                // ここでは常に 1 枚の Rect を返し、色や大きさは ComputedStyle/レイアウト結果に従う。
+                // ブロック要素は背景と枠線を塗る前提の最小モデル。
+                // 背景の Rect はボーダーボックス（枠線の外側まで）全体に塗り、その上に枠線の辺を重ねる。
                 if let NodeKind::Element(_e) = self.node_kind() {
-                    return vec![DisplayItem::Rect {
+                    let mut v = vec![DisplayItem::Rect {
                         style: self.style(),
                         layout_point: self.point(),
                         layout_size: self.size(),
                     }];
+                    v.extend(self.border_items());
+                    return v;
                 }
             }
             LayoutObjectKind::Inline => { // (d2)
@@ -212,44 +450,29 @@ impl LayoutObject {
             }
             LayoutObjectKind::Text => {
                 // (d3)
-                // テキストはフォントサイズ（ratio）と等幅フォント幅（CHAR_WIDTH）から
-                // 折り返し幅を計算し、行ごとに DisplayItem::Text を生成します。
+                // テキストは text_lines で組んだ行ごとに、等間隔に並べられるランの単位で
+                // DisplayItem::Text を生成します。
+                // フォントの指定（大きさ・太さ・傾き・ファミリ）は `font` で描画側に渡します。
+                // line-height が normal の行の高さより大きければ、差の半分（ハーフレディング）だけ下げて描きます。
                 // 例:
-                //   point=(x,y), ratio=1, CHAR_HEIGHT_WITH_PADDING=20, 行が3つ
-                //   → Text("line1", point=(x, y))
-                //   → Text("line2", point=(x, y+20))
-                //   → Text("line3", point=(x, y+40))
-                if let NodeKind::Text(t) = self.node_kind() {
+                //   point=(x,y), font-size=16px, line-height: 30px, 行が3つ
+                //   → Text("line1", point=(x, y+5))
+                //   → Text("line2", point=(x, y+35))
+                //   → Text("line3", point=(x, y+65))
+                if let NodeKind::Text(_) = self.node_kind() {
+                    let (char_width, normal_line_height) = self.char_size();
                     let mut v = vec![];
-
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    // 改行はスペースに置換し、連続スペースを 1 個に圧縮（見た目の乱れを抑える）
-                    let plain_text = t
-                        .replace("\n", " ")
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    // 1 行あたりに乗る最大幅（px）を与えてテキストを折り返す
-                    let lines = split_text(plain_text, CHAR_WIDTH * ratio);
-                    let mut i = 0;
-                    for line in lines {
-                        let item = DisplayItem::Text {
-                            text: line,
-                            style: self.style(),
-                            layout_point: LayoutPoint::new(
-                                self.point().x(),
-                                self.point().y() + CHAR_HEIGHT_WITH_PADDING * i,
-                            ),
-                        };
-                        v.push(item);
-                        i += 1;
+                    for line in self.text_lines() {
+                        let y = line.point.y() + (line.height - normal_line_height) / 2;
+                        for (text, x) in line.runs(char_width) {
+                            v.push(DisplayItem::Text {
+                                text,
+                                style: self.style(),
+                                font: self.style.font(),
+                                layout_point: LayoutPoint::new(line.point.x() + x, y),
+                            });
+                        }
                     }
-
                     return v;
                 }
             }
@@ -258,28 +481,289 @@ impl LayoutObject {
         vec![]
     }
 
+    /// テキストノードを折り返した各行を返す（テキスト以外は空）
+    /// 仕様: https://drafts.csswg.org/css-text/
+    ///
+    /// - white-space に従って空白を詰め（または残し）、残す改行で段落に分ける（`process_white_space`）。
+    /// - text-transform で大文字・小文字を変える。
+    /// - 各文字の送り幅は 1 文字の幅 + letter-spacing（スペースには word-spacing も足す）。
+    /// - compute_size で決めた行の幅（`line_width`）で折り返す。最初の行は text-indent の分だけ狭い。
+    /// - text-align で行を横にずらす（justify は段落の最後の行以外で、余りをスペースに配る）。
+    /// - 行は行の高さ（line-height。normal なら `char_size` の高さ）ずつ下にずらして置く。
+    ///
+    /// 例: "hello world"（1 文字 8px）、行の幅 100px、text-align: center
+    /// → 1 行（幅 88px）、x は 6px 右にずれる
+    pub fn text_lines(&self) -> Vec<TextLine> {
+        let t = match self.node_kind() {
+            NodeKind::Text(t) => t,
+            _ => return vec![],
+        };
+
+        let (char_width, _) = self.char_size();
+        let line_height = self.line_height();
+        let white_space = self.style.white_space();
+        let text_align = self.style.text_align();
+        let letter_spacing = clamp_px(self.style.letter_spacing());
+        let word_spacing = clamp_px(self.style.word_spacing());
+        let advance = |c: char| {
+            let spacing = if c == ' ' { word_spacing } else { 0 };
+            char_width
+                .saturating_add(letter_spacing)
+                .saturating_add(spacing)
+                .max(0)
+        };
+
+        let mut lines: Vec<TextLine> = vec![];
+        for paragraph in process_white_space(&t, white_space) {
+            let chars = transform_text(paragraph.chars, self.style.text_transform());
+            let advances: Vec<i64> = chars.iter().map(|(c, _)| advance(*c)).collect();
+            let first_width = if lines.is_empty() {
+                self.line_width - self.text_indent
+            } else {
+                self.line_width
+            };
+            let breaks = break_lines(&chars, &advances, first_width, self.line_width, white_space);
+            let break_count = breaks.len();
+            for (i, (start, end)) in breaks.into_iter().enumerate() {
+                let indent = if lines.is_empty() {
+                    self.text_indent
+                } else {
+                    0
+                };
+                let available = self.line_width - indent;
+                let mut line_advances = advances[start..end].to_vec();
+                let width = line_advances.iter().fold(0i64, |w, a| w.saturating_add(*a));
+                let extra = available.saturating_sub(width).max(0);
+
+                let shift = match text_align {
+                    TextAlign::Left => 0,
+                    TextAlign::Right => extra,
+                    TextAlign::Center => extra / 2,
+                    TextAlign::Justify => {
+                        // 段落の最後の行（と強制的に切った行）はそろえない
+                        let spaces: Vec<usize> = (start..end)
+                            .filter(|j| chars[*j].0 == ' ')
+                            .filter(|j| chars[*j..end].iter().any(|(c, _)| *c != ' '))
+                            .collect();
+                        if i + 1 < break_count && !spaces.is_empty() {
+                            let count = spaces.len() as i64;
+                            for (k, j) in spaces.iter().enumerate() {
+                                let share = extra / count + i64::from((k as i64) < extra % count);
+                                line_advances[j - start] += share;
+                            }
+                        }
+                        0
+                    }
+                };
+
+                let mut positions = vec![0i64];
+                for a in line_advances {
+                    positions.push(positions[positions.len() - 1].saturating_add(a));
+                }
+                let end_offset = match chars[start..end].last() {
+                    Some((_, o)) => o + 1,
+                    None => paragraph.end,
+                };
+                lines.push(TextLine {
+                    text: chars[start..end].iter().map(|(c, _)| *c).collect(),
+                    point: LayoutPoint::new(
+                        self.point().x() + indent + shift,
+                        self.point().y() + line_height * lines.len() as i64,
+                    ),
+                    positions,
+                    height: line_height,
+                    offsets: chars[start..end].iter().map(|(_, o)| *o).collect(),
+                    end_offset,
+                });
+            }
+        }
+        lines
+    }
+
+    /// 行の高さ（px）。line-height が normal なら `char_size` の高さ
+    fn line_height(&self) -> i64 {
+        match self.style.line_height() {
+            Some(height) => clamp_px(height),
+            None => self.char_size().1,
+        }
+    }
+
+    /// ブロックの最初の子か（text-indent はブロックの最初の行にだけかかる）
+    fn is_first_in_block(&self) -> bool {
+        let parent = match self.parent.upgrade() {
+            Some(parent) => parent,
+            None => return false,
+        };
+        let parent = parent.borrow();
+        parent.kind == LayoutObjectKind::Block
+            && match &parent.first_child {
+                Some(first) => core::ptr::eq(first.as_ptr(), self),
+                None => false,
+            }
+    }
+
+    /// 枠線を辺ごとの塗り潰し矩形（DisplayItem::Border）にする
+    ///
+    /// - 上下の辺はボーダーボックスの幅いっぱい、左右の辺は上下の辺に挟まれた残りの高さで描く。
+    /// - 太さ 0 の辺（border-style: none / hidden を含む）は描かない。
+    /// - 線の種類（dashed / dotted / double など）はまだ区別せず、どれも実線として描く。
+    ///
+    /// 具体例: point=(0,0), size=(100,50), 全辺 solid 2px
+    /// → 上 (0,0) 100x2 / 右 (98,2) 2x46 / 下 (0,48) 100x2 / 左 (0,2) 2x46
+    fn border_items(&self) -> Vec<DisplayItem> {
+        let border = self.border();
+        let (x, y) = (self.point.x(), self.point.y());
+        let (width, height) = (self.size.width(), self.size.height());
+        let side_height = height - border.top() - border.bottom();
+
+        let mut v = vec![];
+        for side in Side::ALL {
+            let (point, size) = match side {
+                Side::Top => (LayoutPoint::new(x, y), LayoutSize::new(width, border.top())),
+                Side::Right => (
+                    LayoutPoint::new(x + width - border.right(), y + border.top()),
+                    LayoutSize::new(border.right(), side_height),
+                ),
+                Side::Bottom => (
+                    LayoutPoint::new(x, y + height - border.bottom()),
+                    LayoutSize::new(width, border.bottom()),
+                ),
+                Side::Left => (
+                    LayoutPoint::new(x, y + border.top()),
+                    LayoutSize::new(border.left(), side_height),
+                ),
+            };
+            if size.width() <= 0 || size.height() <= 0 {
+                continue;
+            }
+            v.push(DisplayItem::Border {
+                color: self.style.border_color(side),
+                layout_point: point,
+                layout_size: size,
+            });
+        }
+        v
+    }
+
+    /// 1 文字の幅と行の高さ（px）
+    ///
+    /// - 等幅フォント（medium = 16px で幅 CHAR_WIDTH、行の高さ CHAR_HEIGHT_WITH_PADDING）を
+    ///   font-size に比例して拡大・縮小した見積もり。
+    /// - 文字幅は 0 にならないようにする（文字が同じ位置に重なって描かれないように）。
+    ///
+    /// 例: font-size=32px → (16, 40)
+    fn char_size(&self) -> (i64, i64) {
+        let scale = self.style.font_size().scale();
+        let char_width = clamp_px(CHAR_WIDTH as f64 * scale);
+        let line_height = clamp_px(CHAR_HEIGHT_WITH_PADDING as f64 * scale);
+        (char_width.max(1), line_height)
+    }
+
+    /// 選択範囲 `range` のうち、このテキストに重なる部分のハイライト矩形を返す
+    ///
+    /// - 文字より先に描くことで、ハイライトの上に文字が乗るようにします（LayoutView 側で順番を保証）。
+    pub fn selection_highlight(&self, range: &Range) -> Vec<DisplayItem> {
+        let (start, end) = match range.text_offsets(&self.node) {
+            Some(offsets) => offsets,
+            None => return vec![],
+        };
+
+        let mut style = self.style();
+        style.set(
+            PropertyId::BackgroundColor,
+            PropertyValue::Color(
+                Color::from_name(SELECTION_COLOR).expect("selection color should be supported"),
+            ),
+        );
+
+        let mut v = vec![];
+        for line in self.text_lines() {
+            if let Some((point, size)) = line.rect_for_offsets(start, end) {
+                v.push(DisplayItem::Rect {
+                    style: style.clone(),
+                    layout_point: point,
+                    layout_size: size,
+                });
+            }
+        }
+        v
+    }
+
     /// 子のサイズをもとに、このノードのレイアウトサイズ（幅・高さ）を計算する
     ///
     /// ルール（学習用の簡易モデル）
-    /// - Block: 親の幅をそのまま使い、高さは“子の高さの合計”（ただしインラインが横並びの場合は注意）。
+    /// - Block: ボックスモデルに従う（下の図）。`size` はボーダーボックス（枠線の外側まで）の大きさ。
+    ///   - 幅: `width: auto` なら親のコンテンツ幅から自分の margin/border/padding を引いた残りが中身の幅。
+    ///     `width` の指定があればそれが中身の幅（`box-sizing: border-box` なら border まで含めた幅）。
+    ///     このとき左右の margin が `auto` なら、余った幅をそこに振り分ける（両方なら中央寄せ）。
+    ///   - 高さ: 中身の高さは“子の高さと子の margin の合計”（`height` の指定があればそれを使う）。
+    ///   - 隣り合うブロックの上下の margin は重ねる（大きい方だけを使う。margin の相殺）。
     /// - Inline: 幅=子の幅の合計, 高さ=子の高さの合計（横並び/改行の厳密処理は省略）。
-    /// - Text: 文字数×フォント比率×等幅フォント幅で幅を見積もり、コンテンツ幅を超えたら折り返し行数で高さを算出。
+    /// - Text: 親のコンテンツ幅で行を組み（`text_lines`）、行の右端の最大で幅、行の高さの合計で高さを算出。
+    ///
+    /// ```text
+    /// +---------------- margin ----------------+
+    /// |  +------------- border -------------+  |
+    /// |  |  +---------- padding ---------+  |  |
+    /// |  |  |          content          |  |  |
+    /// |  |  +---------------------------+  |  |
+    /// |  +----------------------------------+  |
+    /// +----------------------------------------+
+    /// ```
+    /// https://www.w3.org/TR/css-box-3/#box-model
     ///
     /// 具体例
     /// - <div>（Block）に子が <p>(20px 高) と <h1>(24px 高) → 高さ=44px, 幅=親幅。
+    /// - 親幅 600 の <p style="margin-left:10; padding-left:5; border-left-width:2; border-left-style:solid">
+    ///   → 中身の幅 = 600 - 10 - 5 - 2 = 583, size の幅 = 583 + 5 + 2 = 590。
     /// - <span>（Inline）に "Hi"(文字高さ16px, 幅 2×8px) と 子 <a> の幅足し込み → 幅=合計, 高さ=16px など。
-    pub fn compute_size(&mut self, parent_size: LayoutSize) {
+    pub fn compute_size(&mut self, parent_size: LayoutSize, viewport: LayoutSize) {
         let mut size = LayoutSize::new(0, 0);
 
         match self.kind() {
             LayoutObjectKind::Block => {
-                size.set_width(parent_size.width());
+                // margin / border / padding を px にする（% は上下も含めて包含ブロックの“幅”が基準）
+                // https://www.w3.org/TR/css-box-3/#margin-physical
+                let base = parent_size.width();
+                // margin の `auto` はいったん 0 にしておき、幅が決まってから左右に振り分ける
+                self.margin = self.resolve_edges(
+                    |style, side| style.margin(side).unwrap_or(Length::px(0.0)),
+                    base,
+                    viewport,
+                );
+                self.border = self.resolve_edges(ComputedStyle::border_width, base, viewport);
+                self.padding = self.resolve_edges(ComputedStyle::padding, base, viewport);
+
+                // 中身の外側に付く padding + border の合計（左右・上下）
+                let border = self.border();
+                let padding = self.padding();
+                let extra_width = border.horizontal().saturating_add(padding.horizontal());
+                let extra_height = border.vertical().saturating_add(padding.vertical());
+
+                let content_width = match self.style.width() {
+                    Some(width) => {
+                        let width = width.to_px(base, viewport);
+                        match self.style.box_sizing() {
+                            BoxSizing::ContentBox => width,
+                            BoxSizing::BorderBox => width.saturating_sub(extra_width).max(0),
+                        }
+                    }
+                    None => parent_size
+                        .width()
+                        .saturating_sub(self.margin().horizontal())
+                        .saturating_sub(extra_width)
+                        .max(0),
+                };
+                size.set_width(content_width.saturating_add(extra_width));
+                self.resolve_auto_margins(parent_size.width(), size.width());
 
-                // 全ての子ノードの高さを足し合わせた結果が高さになる。
+                // 全ての子ノードの高さ（と子の上下の margin）を足し合わせた結果が高さになる。
                 // ただし、インライン要素が横に並んでいる場合は注意が必要
-                let mut height = 0;
+                let mut height: i64 = 0;
                 let mut child = self.first_child();
                 let mut previous_child_kind = LayoutObjectKind::Block;
+                // 直前に積んだ子の margin-bottom（次の子の margin-top と重ねるため保留しておく）
+                let mut previous_margin_bottom = 0;
                 while child.is_some() {
                     let c = match child {
                         Some(c) => c,
@@ -292,19 +776,33 @@ impl LayoutObject {
                     if previous_child_kind == LayoutObjectKind::Block
                         || c.borrow().kind() == LayoutObjectKind::Block
                     {
-                        height += c.borrow().size.height();
+                        let margin = c.borrow().margin();
+                        height = height
+                            .saturating_add(collapse_margins(previous_margin_bottom, margin.top()))
+                            .saturating_add(c.borrow().size.height());
+                        previous_margin_bottom = margin.bottom();
                     }
 
                     previous_child_kind = c.borrow().kind();
                     child = c.borrow().next_sibling();
                 }
-                size.set_height(height);
+                // 最後の子の margin-bottom も中身に含める（親の外へ突き抜ける相殺は省略）
+                height = height.saturating_add(previous_margin_bottom);
+
+                let content_height = match self.specified_height(parent_size, viewport) {
+                    Some(h) => match self.style.box_sizing() {
+                        BoxSizing::ContentBox => h,
+                        BoxSizing::BorderBox => h.saturating_sub(extra_height).max(0),
+                    },
+                    None => height,
+                };
+                size.set_height(content_height.saturating_add(extra_height));
             }
             LayoutObjectKind::Inline => {
                 // 全ての子ノードの高さと横幅を足し合わせた結果が現在のノードの高さと横幅とになる
                 // 注: 本来は “同じ行の最大高さ＝行の高さ” だが、学習用に単純合計としている。
-                let mut width = 0;
-                let mut height = 0;
+                let mut width: i64 = 0;
+                let mut height: i64 = 0;
                 let mut child = self.first_child();
                 while child.is_some() {
                     let c = match child {
@@ -312,8 +810,8 @@ impl LayoutObject {
                         None => panic!("first child should exist"),
                     };
 
-                    width += c.borrow().size.width();
-                    height += c.borrow().size.height();
+                    width = width.saturating_add(c.borrow().size.width());
+                    height = height.saturating_add(c.borrow().size.height());
 
                     child = c.borrow().next_sibling();
                 }
@@ -322,31 +820,22 @@ impl LayoutObject {
                 size.set_height(height);
             }
             LayoutObjectKind::Text => {
-                if let NodeKind::Text(t) = self.node_kind() {
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    // 文字幅 = 等幅フォント幅 × フォント倍率 × 文字数
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-                    if width > CONTENT_AREA_WIDTH {
-                        // テキストが複数行のとき
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_div(CONTENT_AREA_WIDTH)
-                        } else {
-                            width.wrapping_div(CONTENT_AREA_WIDTH) + 1
-                        };
-                        // 行高 = 文字高さ(行送り込) × フォント倍率 × 行数
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
-                    } else {
-                        // テキストが1行に収まるとき
-                        size.set_width(width);
-                        // 行高 = 文字高さ(行送り込) × フォント倍率
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio);
-                    }
-                }
+                // 親のコンテンツ幅で行を組み、幅 = 最も右に届く行の右端、高さ = 行の高さの合計
+                let base = parent_size.width();
+                self.line_width = base;
+                self.text_indent = if self.is_first_in_block() {
+                    self.style.text_indent().to_px(base, viewport)
+                } else {
+                    0
+                };
+                let lines = self.text_lines();
+                let width = lines
+                    .iter()
+                    .map(|line| line.point.x() + line.size().width() - self.point.x())
+                    .max()
+                    .unwrap_or(0);
+                size.set_width(width.max(0));
+                size.set_height(lines.iter().map(|line| line.height).sum());
             }
         }
 
@@ -365,25 +854,35 @@ impl LayoutObject {
     /// - `previous_sibling_kind`: 直前の兄弟の種類（Block/Inline/Text）。配置方向を決めるヒント
     /// - `previous_sibling_point`: 直前の兄弟の配置座標（Some の時のみ利用）
     /// - `previous_sibling_size`: 直前の兄弟のサイズ（高さ/幅の足し込みに使用）
+    /// - `previous_sibling_margin_bottom`: 直前の兄弟の margin-bottom（自分の margin-top と重ねる）
+    ///
+    /// `point` はボーダーボックスの左上。自分の margin の分だけ内側へずらして置きます。
     pub fn compute_position(
         &mut self,
         parent_point: LayoutPoint,
         previous_sibling_kind: LayoutObjectKind,
         previous_sibling_point: Option<LayoutPoint>,
         previous_sibling_size: Option<LayoutSize>,
+        previous_sibling_margin_bottom: i64,
     ) {
         let mut point = LayoutPoint::new(0, 0);
+        let margin = self.margin();
 
         match (self.kind(), previous_sibling_kind) {
             // もしブロック要素が兄弟ノードの場合、Y軸方向に進む
-            // 具体例: <p> の次に <h1> → h1.y = p.y + p.height / h1.x = 親の x
+            // 具体例: <p> の次に <h1> → h1.y = p.y + p.height + (p の margin-bottom と h1 の margin-top の大きい方)
+            //         h1.x = 親の x + h1 の margin-left
             (LayoutObjectKind::Block, _) | (_, LayoutObjectKind::Block) => {
                 if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
-                    point.set_y(pos.y() + size.height());
+                    point.set_y(
+                        pos.y()
+                            + size.height()
+                            + collapse_margins(previous_sibling_margin_bottom, margin.top()),
+                    );
                 } else {
-                    point.set_y(parent_point.y());
+                    point.set_y(parent_point.y() + margin.top());
                 }
-                point.set_x(parent_point.x());
+                point.set_x(parent_point.x() + margin.left());
             }
             // もしインライン要素が並ぶ場合、X軸方向に進む
             // 具体例: <span>a</span><span>b</span> → 2つ目の x = 1つ目の x + 1つ目の幅, y は同じ
@@ -407,132 +906,50 @@ impl LayoutObject {
     }
 
     //  CSSのルールをレイアウトツリーのノードに適用すべきかどうかを判断する
-    //  具体的には、与えられた `selector`（簡易: タグ/クラス/id）と、このレイアウトオブジェクトが
-    //  参照している DOM ノード（Element）の情報を突き合わせ、マッチすれば true を返す。
+    //  具体的には、与えられた `selector` と、このレイアウトオブジェクトが参照している
+    //  DOM ノード（Element）を突き合わせ、マッチすれば true を返す。
     //
-    //  簡易仕様（学習用）
-    //  - TypeSelector("p")  → 要素名が "p" のときに一致
-    //  - ClassSelector("note") → 属性 `class="note"` のときに一致（複数クラスは未対応）
-    //  - IdSelector("main") → 属性 `id="main"` のときに一致
+    //  実際の判定は `css::selector::matches` に任せる（右から左へのマッチング）。
+    //  - TypeSelector("p") / ClassSelector("note") / IdSelector("main") などの単純セレクタ
+    //  - `a.button#go` のような複合セレクタ、`body > p` のような結合子、`h1, h2` のリスト
     //  - UnknownSelector → 常に不一致
-    //  注: 現実の CSS は複合セレクタやスペース区切り（子孫/子/兄弟）など多様だが、ここでは最小限のみ。
-    pub fn is_node_selected(&self, selector: &Selector) -> bool {
-        match &self.node_kind() {
-            NodeKind::Element(e) => match selector {
-                // タグ名（タイプセレクタ）: 例) p { ... }
-                Selector::TypeSelector(type_name) => {
-                    // ElementKind → 文字列（"p" など）に直して完全一致で判定
-                    if e.kind().to_string() == *type_name {
-                        return true;
-                    }
-                    false
-                }
-                // クラスセレクタ: 例) .note { ... }
-                Selector::ClassSelector(class_name) => {
-                    // 属性列から name="class" を探し、値が完全一致するかで判定
-                    // 注意: 本実装は "class1 class2" のような複数クラスを分割しない（簡易）
-                    for attr in &e.attributes() {
-                        if attr.name() == "class" && attr.value() == *class_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                // ID セレクタ: 例) #main { ... }
-                Selector::IdSelector(id_name) => {
-                    // 属性列から name="id" を探し、値が完全一致するかで判定
-                    for attr in &e.attributes() {
-                        if attr.name() == "id" && attr.value() == *id_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                // 未対応（あるいは読み飛ばし対象）のセレクタは常に不一致
-                Selector::UnknownSelector => false,
-            },
-            // テキスト/ドキュメントはセレクタの対象外とする
-            _ => false,
-        }
+    //  テキスト/ドキュメントはセレクタの対象外なので常に false になる。
+    //  `context` は `:hover`/`:focus` など、ユーザー操作で変わる状態の判定に使う。
+    pub fn is_node_selected(&self, selector: &Selector, context: &MatchingContext) -> bool {
+        selector::matches(selector, &self.node, context)
     }
 
     // ノードがセレクタによって選択されている場合、そのCSSルールをノードに適用する
     // CSSの宣言リストを引数に取り、各宣言のプロパティをノードに適用する
     //
     // 関数の概要
-    // - `declarations` に含まれる `property: value` を 1 件ずつこの LayoutObject の ComputedStyle に反映します。
-    // - サポート範囲（学習用）: background-color / color / display
-    // - 値の受け取り方は簡易トークンベース（ComponentValue = CssToken）。
+    // - `declarations`（カスケードで弱い順に並んだ宣言）を 1 件ずつ、プロパティレジストリ
+    //   （`layout::property`）で指定値に読み替えて ComputedStyle に入れます。後の宣言が勝ちます。
+    // - 対応プロパティや値の読み方はレジストリの表で決まり、ここにはプロパティごとの分岐はありません。
     //
     // 具体例
-    // - background-color: Ident("red")         → set_background_color(#ff0000)
-    // - background-color: HashToken("#00ff00") → set_background_color(#00ff00)
-    // - color: Ident("blue")                   → set_color(#0000ff)
-    // - display: Ident("block")                → set_display(DisplayType::Block)
+    // - background-color: Ident("red")         → BackgroundColor = Value(Color(#ff0000))
+    // - background-color: HashToken("00ff00", Unrestricted) → BackgroundColor = Value(Color(#00ff00))
+    // - color: Ident("inherit")                → Color = Inherit（defaulting で親の値になる）
+    // - display: Ident("flex")                 → 未対応の値なので無視
     pub fn cascading_style(&mut self, declarations: Vec<Declaration>) {
         for declaration in declarations {
-            match declaration.property.as_str() {
-                "background-color" => {
-                    // 例1) background-color: red;  → Ident("red") を色名に解釈
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
-                    }
-
-                    // 例2) background-color: #00ff00; → HashToken("#00ff00") を #RRGGBB として解釈
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(&color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
-                    }
-                }
-                "color" => {
-                    // 例3) color: blue; → Ident("blue")
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(&value) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
-                    }
-
-                    // 例4) color: #0000ff; → HashToken("#0000ff")
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(&color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
-                    }
-                }
-                "display" => {
-                    // 例5) display: block; / inline; / none; → Ident("block"|"inline"|"none")
-                    if let ComponentValue::Ident(value) = declaration.value {
-                        let display_type = match DisplayType::from_str(&value) {
-                            Ok(display_type) => display_type,
-                            Err(_) => DisplayType::DisplayNone,
-                        };
-                        self.style.set_display(display_type)
-                    }
-                }
-                _ => {}
+            // カスタムプロパティ（`--name`）は値を解釈せずにそのまま持つ
+            if is_custom_property(&declaration.property) {
+                self.style
+                    .set_specified_custom(declaration.property, declaration.values);
+                continue;
+            }
+            // ショートハンドはロングハンドに展開する
+            // 未対応のプロパティ・値の宣言は読み飛ばす（それより前の宣言が残る）
+            for (id, value) in expand_declaration(&declaration) {
+                self.style.set_specified(id, value);
             }
         }
     }
 
-    pub fn defaulting_style(
-        &mut self,
-        node: &Rc<RefCell<Node>>,
-        parent_style: Option<ComputedStyle>,
-    ) {
-        self.style.defaulting(node, parent_style);
+    pub fn defaulting_style(&mut self, parent_style: Option<ComputedStyle>) {
+        self.style.defaulting(parent_style);
     }
 
     // カスケード、デフォルティングを経てCSSの値が最終的に決定した後、あらためてLayoutObjectのノードがブロック要素になるかインライン要素になるかを決定する
@@ -576,6 +993,11 @@ impl LayoutObject {
         self.node.borrow().kind().clone()
     }
 
+    // このレイアウトオブジェクトの元になった DOM ノード（アクセシビリティツリー構築などで使う）
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
     pub fn set_first_child(&mut self, first_child: Option<Rc<RefCell<LayoutObject>>>) {
         self.first_child = first_child;
     }
@@ -607,6 +1029,113 @@ impl LayoutObject {
     pub fn size(&self) -> LayoutSize {
         self.size
     }
+
+    /// margin / border / padding の太さ（px。compute_size で決まる）
+    ///
+    /// - ボックスモデルを扱うのはブロック要素だけ（インライン要素とテキストは常に 0 として扱う簡易版）。
+    pub fn margin(&self) -> EdgeSizes {
+        self.margin
+    }
+
+    pub fn border(&self) -> EdgeSizes {
+        self.border
+    }
+
+    pub fn padding(&self) -> EdgeSizes {
+        self.padding
+    }
+
+    // 4 辺の長さ（margin-top など）を px にする
+    fn resolve_edges(
+        &self,
+        length_of: fn(&ComputedStyle, Side) -> Length,
+        percentage_base: i64,
+        viewport: LayoutSize,
+    ) -> EdgeSizes {
+        let [top, right, bottom, left] =
+            Side::ALL.map(|side| length_of(&self.style, side).to_px(percentage_base, viewport));
+        EdgeSizes::new(top, right, bottom, left)
+    }
+
+    // `margin-left` / `margin-right` の `auto` を、包含ブロックの幅の余りで埋める
+    //
+    // - `width` の指定があるときだけ: 両方 `auto` なら余りを半分ずつ（中央寄せ）、片方だけならその辺に全部。
+    // - `width: auto` のとき（幅が余りを全部使う）や、余りが無いときは 0 のまま。上下の `auto` も 0。
+    // https://www.w3.org/TR/CSS2/visudet.html#blockwidth
+    //
+    // 具体例: 親幅 600 で `width:100px; margin:0 auto` → 左右の margin は 250 ずつ
+    fn resolve_auto_margins(&mut self, parent_width: i64, border_box_width: i64) {
+        if self.style.width().is_none() {
+            return;
+        }
+        let auto_left = self.style.margin(Side::Left).is_none();
+        let auto_right = self.style.margin(Side::Right).is_none();
+        let remaining = parent_width
+            .saturating_sub(border_box_width)
+            .saturating_sub(self.margin.left())
+            .saturating_sub(self.margin.right())
+            .max(0);
+        match (auto_left, auto_right) {
+            (true, true) => {
+                self.margin.left = remaining / 2;
+                self.margin.right = remaining - remaining / 2;
+            }
+            (true, false) => self.margin.left = remaining,
+            (false, true) => self.margin.right = remaining,
+            (false, false) => {}
+        }
+    }
+
+    // `height` の指定を px にする（`auto` なら None）
+    //
+    // - `%` は包含ブロックの高さが決まっているとき（親に `height` の指定があるか、親がいない）だけ使える。
+    //   親の高さが子から決まる場合は循環するので `auto` として扱う。
+    //   https://www.w3.org/TR/CSS2/visudet.html#the-height-property
+    fn specified_height(&self, parent_size: LayoutSize, viewport: LayoutSize) -> Option<i64> {
+        let height = self.style.height()?;
+        if height.has_unit(LengthUnit::Percent) {
+            if let Some(parent) = self.parent.upgrade() {
+                parent.borrow().style.height()?;
+            }
+        }
+        Some(height.to_px(parent_size.height(), viewport))
+    }
+
+    /// 子を並べ始める位置（コンテンツボックスの左上 = border と padding の内側）
+    pub fn content_point(&self) -> LayoutPoint {
+        let border = self.border();
+        let padding = self.padding();
+        LayoutPoint::new(
+            self.point.x() + border.left() + padding.left(),
+            self.point.y() + border.top() + padding.top(),
+        )
+    }
+
+    /// 子が使える大きさ（コンテンツボックスの幅・高さ）
+    pub fn content_size(&self) -> LayoutSize {
+        let border = self.border();
+        let padding = self.padding();
+        LayoutSize::new(
+            (self.size.width() - border.horizontal() - padding.horizontal()).max(0),
+            (self.size.height() - border.vertical() - padding.vertical()).max(0),
+        )
+    }
+}
+
+/// 隣り合う上下の margin を 1 つに重ねる（margin の相殺）
+///
+/// - 両方が正なら大きい方、両方が負なら小さい方、符号が違えば足し合わせる。
+///
+/// 例: (20, 10) → 20 / (20, -5) → 15
+/// https://www.w3.org/TR/CSS2/box.html#collapsing-margins
+fn collapse_margins(a: i64, b: i64) -> i64 {
+    if a >= 0 && b >= 0 {
+        a.max(b)
+    } else if a < 0 && b < 0 {
+        a.min(b)
+    } else {
+        a + b
+    }
 }
 
 // LayoutObjectの位置を表すデータ構造
@@ -639,6 +1168,52 @@ impl LayoutPoint {
     }
 }
 
+// margin / border / padding のように、ボックスの 4 辺それぞれに付く太さ（px）
+#[derive(Debug, Clone, PartialEq, Copy, Default)]
+pub struct EdgeSizes {
+    top: i64,
+    right: i64,
+    bottom: i64,
+    left: i64,
+}
+
+impl EdgeSizes {
+    pub fn new(top: i64, right: i64, bottom: i64, left: i64) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub fn top(&self) -> i64 {
+        self.top
+    }
+
+    pub fn right(&self) -> i64 {
+        self.right
+    }
+
+    pub fn bottom(&self) -> i64 {
+        self.bottom
+    }
+
+    pub fn left(&self) -> i64 {
+        self.left
+    }
+
+    // 左右の合計
+    pub fn horizontal(&self) -> i64 {
+        self.left.saturating_add(self.right)
+    }
+
+    // 上下の合計
+    pub fn vertical(&self) -> i64 {
+        self.top.saturating_add(self.bottom)
+    }
+}
+
 // LayoutObjectのサイズを表すデータ構造
 // レイアウトツリー構築の際に、各要素のサイズを計算する
 #[derive(Debug, Clone, PartialEq, Copy)]
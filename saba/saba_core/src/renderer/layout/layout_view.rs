@@ -8,6 +8,7 @@
 //!
 //! 全体フロー
 //! 1) <body> の直下から、描画対象のみのレイアウトツリーを構築（build_layout_tree）
+//!    - 各要素のスタイルは `StyleResolver` で計算（ルールの索引・祖先の Bloom フィルタ・兄弟のスタイル共有）
 //! 2) 各ノードのサイズを自前ルールで算出（calculate_node_size → compute_size）
 //! 3) 各ノードの位置（左上座標）を決定（calculate_node_position → compute_position）
 //!
@@ -17,20 +18,26 @@
 //! - UI 側ではツールバーやウィンドウ余白を引いた“コンテンツ座標”に変換してから呼びます。
 //!
 //! 制約（学習用の簡易化）
-//! - 行折り返しや margin/padding/border、line-height 等の厳密処理は省略
+//! - 行は等幅フォントの見積もりで組む（インライン要素をまたいだ行の組み立てや、行ごとの高さの調整は省略）
+//! - margin/padding/border はブロック要素だけが持つ（親子の間での margin の相殺は省略。`margin: auto` は左右の中央寄せだけ）
 //! - display は block/inline/none のみ
 //! - テキストは等幅フォントで粗い見積り
-use crate::constants::CONTENT_AREA_WIDTH;
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::selector::MatchingContext;
 use crate::renderer::dom::api::get_target_element_node;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
+use crate::renderer::dom::range::BoundaryPoint;
+use crate::renderer::dom::range::Range;
+use crate::renderer::layout::animation::AnimationTimeline;
 use crate::renderer::layout::layout_object::create_layout_object;
 use crate::renderer::layout::layout_object::LayoutObject;
 use crate::renderer::layout::layout_object::LayoutObjectKind;
 use crate::renderer::layout::layout_object::LayoutPoint;
 use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::layout_object::TextLine;
+use crate::renderer::layout::style_resolver::StyleResolver;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
 use core::cell::RefCell;
@@ -49,21 +56,22 @@ use core::cell::RefCell;
 fn build_layout_tree(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &StyleSheet,
+    resolver: &mut StyleResolver,
+    timeline: &mut AnimationTimeline,
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     // 1) まず現在の DOM ノードから LayoutObject を作成してみる
     //    - `create_layout_object` は CSSOM を参照して `display:none` なら None を返す
     // `create_layout_object`関数によって、ノードとなるLayoutObjectの作成を試みる。
     // CSSによって"display:none"が指定されていた場合、ノードは作成されない
     let mut target_node = node.clone();
-    let mut layout_object = create_layout_object(node, parent_obj, cssom);
+    let mut layout_object = create_layout_object(node, parent_obj, resolver, timeline);
     // 2) 作れなかった（= display:none 等）場合、兄弟へ進み“作れるまで”繰り返し
     // もしノードが作成されなかった場合、DOMノードの兄弟ノードを使用してLayoutObjectの
     // 作成を試みる。LayoutObjectが作成されるまで、兄弟ノードを辿り続ける
     while layout_object.is_none() {
         if let Some(n) = target_node {
             target_node = n.borrow().next_sibling().clone();
-            layout_object = create_layout_object(&target_node, parent_obj, cssom);
+            layout_object = create_layout_object(&target_node, parent_obj, resolver, timeline);
         } else {
             // 兄弟ノードが無ければ、これ以上作る要素は無い → ここまでで終了
             // もし兄弟ノードがない場合、処理するべきDOMツリーは終了したので、今まで
@@ -76,13 +84,14 @@ fn build_layout_tree(
         let original_first_child = n.borrow().first_child();
         let original_next_sibling = n.borrow().next_sibling();
         // 3) 子と兄弟について再帰的にレイアウトツリーを作る
-        //    - 子の親は“今作った LayoutObject”
-        //    - 兄弟は“同じ親”を共有するので、ここでは親 None → 上の呼び出し側が適切に接続する
+        //    - 子の親は“今作った LayoutObject”。子を作る間は、このノードを祖先フィルタに入れておく
+        //    - 兄弟は“同じ親”を共有する（兄弟も親のスタイルを継承する）。接続は呼び出し側が行う
         // もし子ノードに"display:node"が指定されていた場合、LayoutObjectは作成され
         // ないため、子ノードの兄弟ノードを使用してLayoutObjectの作成を試みる。
         // LayoutObjectが作成されるか、辿るべき兄弟ノードがなくなるまで処理を繰り返す
-        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
+        resolver.enter(&n);
+        let mut first_child =
+            build_layout_tree(&original_first_child, &layout_object, resolver, timeline);
 
         // 4) 子が `display:none` で作られなかった場合 → 子の“兄弟”を順に試す
         //    LayoutObject が作れるまで、または辿る兄弟が尽きるまで進める
@@ -93,7 +102,8 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                first_child = build_layout_tree(&original_dom_node, &layout_object, cssom);
+                first_child =
+                    build_layout_tree(&original_dom_node, &layout_object, resolver, timeline);
 
                 if first_child.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -106,6 +116,10 @@ fn build_layout_tree(
                 break;
             }
         }
+        resolver.leave();
+
+        let mut next_sibling =
+            build_layout_tree(&original_next_sibling, parent_obj, resolver, timeline);
 
         // 5) 兄弟が `display:none` で作られなかった場合 → 兄弟の“次の兄弟”を順に試す
         //    LayoutObject が作れるまで、または辿る兄弟が尽きるまで進める
@@ -119,7 +133,8 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                next_sibling = build_layout_tree(&original_dom_node, &None, cssom);
+                next_sibling =
+                    build_layout_tree(&original_dom_node, parent_obj, resolver, timeline);
 
                 if next_sibling.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -149,6 +164,8 @@ fn build_layout_tree(
 #[derive(Debug, Clone)]
 pub struct LayoutView {
     root: Option<Rc<RefCell<LayoutObject>>>,
+    // レイアウトする領域の大きさ（% や vw/vh の基準。`@media` の判定にも同じ大きさを使う）
+    viewport: LayoutSize,
 }
 
 impl LayoutView {
@@ -157,19 +174,66 @@ impl LayoutView {
     /// 手順
     /// - <body> のノードを起点に、`display:none` を除外したレイアウトツリーを作る（build_layout_tree）
     /// - その後、update_layout でサイズ → 位置の順に確定
-    pub fn new(root: Rc<RefCell<Node>>, cssom: &StyleSheet) -> Self {
+    /// - 要素の既定の見た目には UA スタイルシート `user_agent` を `cssom` の前に使う
+    ///   （`css::user_agent::user_agent_stylesheet` で作ったものを、呼び出し側で使い回す）
+    pub fn new(root: Rc<RefCell<Node>>, user_agent: &StyleSheet, cssom: &StyleSheet) -> Self {
+        let stylesheets = [user_agent.clone(), cssom.clone()];
+        Self::new_with_context(root, &stylesheets, &MatchingContext::new())
+    }
+
+    /// `new` と同じだが、複数のスタイルシート（UA/利用者/ページ作者）をカスケードし、
+    /// `:hover`/`:focus`/`:active`/`:visited` の判定にページの状態 `context` を使う
+    ///
+    /// - ビューポートの大きさは `context.media()`（既定はコンテンツ領域）。`@media` もこの大きさで判定する
+    pub fn new_with_context(
+        root: Rc<RefCell<Node>>,
+        stylesheets: &[StyleSheet],
+        context: &MatchingContext,
+    ) -> Self {
+        Self::new_with_timeline(root, stylesheets, context, &mut AnimationTimeline::new())
+    }
+
+    /// `new_with_context` と同じだが、トランジション・アニメーションの状態を `timeline` に持ち越す
+    ///
+    /// - 前回のスタイルと比べて値が変わったプロパティはトランジションを始め、
+    ///   実行中のものは `timeline` の今の時刻の値でレイアウトする
+    /// - 時刻を進めるのは呼び出し側（`Page::tick`）の役目
+    pub fn new_with_timeline(
+        root: Rc<RefCell<Node>>,
+        stylesheets: &[StyleSheet],
+        context: &MatchingContext,
+        timeline: &mut AnimationTimeline,
+    ) -> Self {
+        timeline.start_style_update();
         // レイアウトツリーは描画される要素だけを持つツリーなので、<body>タグを取得し、その子要素以下をレイアウトツリーのノードに変換する。
-        let body_root = get_target_element_node(Some(root), ElementKind::Body);
+        let body_root = get_target_element_node(Some(root.clone()), ElementKind::Body);
+        // <html> のスタイルも計算して <body> の親にする（ツリーには入れない）
+        // `:root { --brand: ... }` のカスタムプロパティや、継承するプロパティを <body> が受け継ぐため
+        let html = get_target_element_node(Some(root), ElementKind::Html);
+        // ルールの索引はここで 1 回だけ作り、すべての要素のカスケードで使い回す
+        let mut resolver = StyleResolver::new(stylesheets, context);
+        let html_obj = create_layout_object(&html, &None, &mut resolver, timeline);
+        if let Some(html) = &html {
+            resolver.enter(html);
+        }
 
+        let media = context.media();
         let mut tree = Self {
-            root: build_layout_tree(&body_root, &None, cssom),
+            root: build_layout_tree(&body_root, &html_obj, &mut resolver, timeline),
+            viewport: LayoutSize::new(media.width(), media.height()),
         };
+        timeline.finish_style_update();
 
         tree.update_layout();
 
         tree
     }
 
+    /// レイアウトした領域の大きさ
+    pub fn viewport(&self) -> LayoutSize {
+        self.viewport
+    }
+
     /// 画面上の座標 `(x,y)` にあるレイアウトノードを返す（子優先のヒットテスト）
     ///
     /// 入力
@@ -229,7 +293,7 @@ impl LayoutView {
     }
 
     // レイアウトツリーのノードの位置を再起的に計算する関数
-    // 第1引数が計算するターゲットとなるノード、第2引数が親ノードの位置、第3引数は自分より前の兄弟ノードの種類、第4引数は自分より前の兄弟ノードの位置、第5引数は自分より前の兄弟ノードのサイズ
+    // 第1引数が計算するターゲットとなるノード、第2引数が親ノードのコンテンツの位置、第3引数は自分より前の兄弟ノードの種類、第4引数は自分より前の兄弟ノードの位置、第5引数は自分より前の兄弟ノードのサイズ、第6引数は自分より前の兄弟ノードの margin-bottom
     //
     // 概要
     // - Block は縦方向（Y）へ、Inline は横方向（X）へ配置を進める簡易ルール。
@@ -240,6 +304,7 @@ impl LayoutView {
         previous_sibling_kind: LayoutObjectKind,
         previous_sibling_point: Option<LayoutPoint>,
         previous_sibling_size: Option<LayoutSize>,
+        previous_sibling_margin_bottom: i64,
     ) {
         if let Some(n) = node {
             n.borrow_mut().compute_position(
@@ -247,16 +312,19 @@ impl LayoutView {
                 previous_sibling_kind,
                 previous_sibling_point,
                 previous_sibling_size,
+                previous_sibling_margin_bottom,
             );
 
             // ノード（node）の子ノードの位置を計算をする
+            // 子は border と padding の内側（コンテンツボックス）から並べる
             let first_child = n.borrow().first_child();
             Self::calculate_node_position(
                 &first_child,
-                n.borrow().point(),
+                n.borrow().content_point(),
                 LayoutObjectKind::Block,
                 None,
                 None,
+                0,
             );
 
             // ノード（node）の兄弟ノードの位置を計算する
@@ -267,42 +335,55 @@ impl LayoutView {
                 n.borrow().kind(),
                 Some(n.borrow().point()),
                 Some(n.borrow().size()),
+                n.borrow().margin().bottom(),
             );
         }
     }
 
     // レイアウトツリーの各ノードのサイズを再起的に計算する関数
-    // 第一引数がターゲットとなるノード、第二引数は親ノードのサイズ
+    // 第一引数がターゲットとなるノード、第二引数は親ノードのサイズ、第三引数はビューポート（vw/vh の基準）の大きさ
     //
     // 概要
     // - Block の幅は“先に”親幅で確定し、高さは子のサイズ確定後に集計。
     // - Inline/Text は子（テキスト）のサイズに依存するため、子の計算後に自分を計算。
-    fn calculate_node_size(node: &Option<Rc<RefCell<LayoutObject>>>, parent_size: LayoutSize) {
+    fn calculate_node_size(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        parent_size: LayoutSize,
+        viewport: LayoutSize,
+    ) {
         if let Some(n) = node {
             // ノードがブロック要素の場合、子ノードのレイアウトを計算する前に横幅を決める
             if n.borrow().kind() == LayoutObjectKind::Block {
-                n.borrow_mut().compute_size(parent_size);
+                n.borrow_mut().compute_size(parent_size, viewport);
             }
 
+            // 子が使える幅は border と padding の内側（コンテンツボックス）
+            // インライン要素の幅は子から決まるので、その中のテキストは親から渡された幅で折り返す
             let first_child = n.borrow().first_child();
-            Self::calculate_node_size(&first_child, n.borrow().size());
+            let content_size = if n.borrow().kind() == LayoutObjectKind::Block {
+                n.borrow().content_size()
+            } else {
+                parent_size
+            };
+            Self::calculate_node_size(&first_child, content_size, viewport);
 
             let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_size(&next_sibling, parent_size);
+            Self::calculate_node_size(&next_sibling, parent_size, viewport);
 
             // 子ノードのサイズが決まった後にサイズを計算する。
             // ブロック要素のとき、高さは子ノードの高さに依存する
             // インライン要素のとき、高さも横幅も子ノードに依存する
-            n.borrow_mut().compute_size(parent_size);
+            n.borrow_mut().compute_size(parent_size, viewport);
         }
     }
 
     /// レイアウトの再計算（サイズ→位置の順）
     ///
-    /// - まずコンテンツ領域幅（CONTENT_AREA_WIDTH）を親幅に見立ててサイズ計算
+    /// - まずビューポート（既定はコンテンツ領域 CONTENT_AREA_WIDTH x CONTENT_AREA_HEIGHT）を
+    ///   ルートの包含ブロックに見立ててサイズ計算（% や vw/vh はここを基準に px になる）
     /// - 次に (0,0) を起点に座標を割り当てていきます
     fn update_layout(&mut self) {
-        Self::calculate_node_size(&self.root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
+        Self::calculate_node_size(&self.root, self.viewport, self.viewport);
 
         Self::calculate_node_position(
             &self.root,
@@ -310,6 +391,7 @@ impl LayoutView {
             LayoutObjectKind::Block,
             None,
             None,
+            0,
         );
     }
 
@@ -325,19 +407,37 @@ impl LayoutView {
     //   2) p.paint()    → 段落の背景や枠などを push
     //   3) text.paint() → 文字列 "text" を描く命令を push
     //   4) p の兄弟が無ければ body の兄弟へ、無ければ終了
-    fn paint_node(node: &Option<Rc<RefCell<LayoutObject>>>, display_items: &mut Vec<DisplayItem>) {
+    // - 選択範囲 `selection` があれば、テキストの前にハイライト矩形を push（文字がハイライトの上に乗る）
+    // - `opacity` が 1 未満の要素は、自分と子孫の命令を 1 つの Group にまとめる
+    //   （重なった子どうしが透けて見えないよう、まとめて 1 枚として薄くするため）
+    fn paint_node(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        selection: Option<&Range>,
+        display_items: &mut Vec<DisplayItem>,
+    ) {
         match node {
             Some(n) => {
-                // 1) 自分自身の描画命令を収集
-                display_items.extend(n.borrow_mut().paint());
+                // 1) 選択ハイライト → 自分自身の描画命令を収集
+                let mut items = Vec::new();
+                if let Some(range) = selection {
+                    items.extend(n.borrow().selection_highlight(range));
+                }
+                items.extend(n.borrow_mut().paint());
 
                 // 2) 子を先に描画（前順）
                 let first_child = n.borrow().first_child();
-                Self::paint_node(&first_child, display_items);
+                Self::paint_node(&first_child, selection, &mut items);
+
+                let opacity = n.borrow().style().opacity();
+                if opacity < 1.0 {
+                    display_items.push(DisplayItem::Group { opacity, items });
+                } else {
+                    display_items.extend(items);
+                }
 
                 // 3) 兄弟を描画
                 let next_sibling = n.borrow().next_sibling();
-                Self::paint_node(&next_sibling, display_items);
+                Self::paint_node(&next_sibling, selection, display_items);
             }
             None => (),
         }
@@ -352,17 +452,122 @@ impl LayoutView {
     /// 例
     /// - <p>Hi</p> → [Rect(.. 背景 ..), Text(.. "Hi" ..)] のような命令が並ぶ想定。
     pub fn paint(&self) -> Vec<DisplayItem> {
+        self.paint_with_selection(None)
+    }
+
+    /// `paint` と同じだが、選択範囲のハイライト（Rect）も一緒に描く
+    ///
+    /// 例
+    /// - <p>Hi</p> で "H" を選択 → [Rect(p の背景), Rect(ハイライト), Text("Hi")]
+    pub fn paint_with_selection(&self, selection: Option<&Range>) -> Vec<DisplayItem> {
         let mut display_items = Vec::new();
 
-        Self::paint_node(&self.root, &mut display_items);
+        Self::paint_node(&self.root, selection, &mut display_items);
 
         display_items
     }
 
+    // テキストの行を、ツリー順（= 描画順）にすべて集める
+    fn collect_text_lines(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        lines: &mut Vec<(Rc<RefCell<Node>>, TextLine)>,
+    ) {
+        if let Some(n) = node {
+            for line in n.borrow().text_lines() {
+                lines.push((n.borrow().node(), line));
+            }
+            Self::collect_text_lines(&n.borrow().first_child(), lines);
+            Self::collect_text_lines(&n.borrow().next_sibling(), lines);
+        }
+    }
+
+    /// 座標 `(x,y)` に最も近い「テキスト中の位置」を返す（`document.caretPositionFromPoint` 相当）
+    ///
+    /// 使いどころ
+    /// - マウスのドラッグ位置を、DOM の Text ノードと文字オフセットの組（境界点）に変換して、
+    ///   `Selection` の anchor/focus にします。
+    ///
+    /// ルール（簡易版）
+    /// - y が行の高さの範囲に入っている行のうち、x が最も近い行を選び、その中で最も近い文字境界を返す。
+    /// - どの行にも入らなければ、y より上にある最後の行の「行末」を返す（テキストの下までドラッグした場合）。
+    /// - すべての行より上なら、最初の行の「行頭」を返す。
+    /// - テキストが 1 つも無ければ `None`。
+    pub fn caret_position_from_point(&self, position: (i64, i64)) -> Option<BoundaryPoint> {
+        let mut lines = Vec::new();
+        Self::collect_text_lines(&self.root, &mut lines);
+
+        // 1) y が行の帯に入っている行から、x が最も近いものを選ぶ
+        // (x 方向の距離, lines の添字)
+        let mut best: Option<(i64, usize)> = None;
+        for (i, (_, line)) in lines.iter().enumerate() {
+            let top = line.point().y();
+            if position.1 < top || top + line.size().height() <= position.1 {
+                continue;
+            }
+            let left = line.point().x();
+            let right = left + line.size().width();
+            let distance = if position.0 < left {
+                left - position.0
+            } else if position.0 > right {
+                position.0 - right
+            } else {
+                0
+            };
+            match best {
+                Some((d, _)) if d <= distance => {}
+                _ => best = Some((distance, i)),
+            }
+        }
+        if let Some((_, i)) = best {
+            let (node, line) = &lines[i];
+            return Some(BoundaryPoint::new(
+                node.clone(),
+                line.offset_at_x(position.0),
+            ));
+        }
+
+        // 2) テキストより下: y より上にある最後の行の行末
+        if let Some((node, line)) = lines
+            .iter()
+            .filter(|(_, line)| line.point().y() <= position.1)
+            .last()
+        {
+            return Some(BoundaryPoint::new(node.clone(), line.end_offset()));
+        }
+
+        // 3) テキストより上: 最初の行の行頭
+        lines
+            .first()
+            .map(|(node, line)| BoundaryPoint::new(node.clone(), line.start_offset()))
+    }
+
     /// レイアウトツリーのルート（描画される最上位の LayoutObject）を返す
     pub fn root(&self) -> Option<Rc<RefCell<LayoutObject>>> {
         self.root.clone()
     }
+
+    /// DOM ノード `node` から作られた LayoutObject を探す
+    ///
+    /// - `display: none` の要素とその子孫、<body> の外（<head> など）はツリーに無いので None。
+    /// - `getComputedStyle(el)` が要素のスタイルを引くのに使います。
+    pub fn find_layout_object(
+        &self,
+        node: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<LayoutObject>>> {
+        let mut stack: Vec<Rc<RefCell<LayoutObject>>> = self.root.iter().cloned().collect();
+        while let Some(obj) = stack.pop() {
+            if Rc::ptr_eq(&obj.borrow().node(), node) {
+                return Some(obj);
+            }
+            if let Some(sibling) = obj.borrow().next_sibling() {
+                stack.push(sibling);
+            }
+            if let Some(child) = obj.borrow().first_child() {
+                stack.push(child);
+            }
+        }
+        None
+    }
 }
 
 #[cfg(test)]
@@ -370,14 +575,24 @@ mod tests {
     // この tests モジュールでは、レイアウトビューを構築する“最小の足場”を用意します。
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::constants::CONTENT_AREA_WIDTH;
     use crate::renderer::css::cssom::CssParser;
     use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::css::user_agent::user_agent_stylesheet;
+    use crate::renderer::dom::api::get_element_by_id;
     use crate::renderer::dom::api::get_style_content;
     use crate::renderer::dom::node::Element;
     use crate::renderer::dom::node::NodeKind;
     use crate::renderer::html::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::layout::computed_style::BorderStyle;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::computed_style::FontSize;
+    use crate::renderer::layout::computed_style::TextDecoration;
+    use crate::renderer::layout::computed_style::MAX_LAYOUT_PX;
+    use crate::renderer::layout::property::Side;
     use alloc::string::String;
+    use alloc::vec;
     use alloc::vec::Vec;
 
     // テスト用のレイアウトビューを作るユーティリティ
@@ -398,7 +613,7 @@ mod tests {
         let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
 
         // 3) DOM + CSSOM → LayoutView（以降のテストはこの戻り値を使って検証していく）
-        LayoutView::new(dom, &cssom)
+        LayoutView::new(dom, &user_agent_stylesheet(), &cssom)
     }
 
     #[test]
@@ -549,4 +764,539 @@ mod tests {
             .next_sibling()
             .is_none());
     }
+
+    #[test]
+    fn test_hover_style() {
+        // a:hover のルールは、MatchingContext でカーソルが乗っている要素にだけ適用される
+        let html = "<html><head><style>a:hover{display:none;}</style></head><body><p><a id=link href=\"/\">link</a></p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let dom = window.borrow().document();
+        let style = get_style_content(dom.clone());
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+
+        // ホバーしていなければ <a> は表示される
+        let ua = user_agent_stylesheet();
+        let layout_view = LayoutView::new(dom.clone(), &ua, &cssom);
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child();
+        assert!(p.expect("p should exist").borrow().first_child().is_some());
+
+        // <a> にカーソルが乗っていると display:none が効いて消える
+        let link = get_element_by_id(Some(dom.clone()), &"link".to_string());
+        let mut context = MatchingContext::new();
+        context.set_hovered(link);
+        let layout_view = LayoutView::new_with_context(dom, &[ua, cssom], &context);
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child();
+        assert!(p.expect("p should exist").borrow().first_child().is_none());
+    }
+
+    #[test]
+    fn test_user_agent_defaults() {
+        // h1 の大きさ・ブロック表示・<a> の下線は UA スタイルシートから来るので、作者の CSS で上書きできる
+        let html = "<html><head><style>a{text-decoration:none;}</style></head><body><h1>t</h1><p><a href=\"/\">x</a></p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+        let h1 = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("h1 should exist");
+        assert_eq!(h1.borrow().kind(), LayoutObjectKind::Block);
+        assert_eq!(h1.borrow().style().font_size(), FontSize::new(32.0));
+        assert!(h1.borrow().style().font_weight().is_bold());
+
+        let p = h1.borrow().next_sibling().expect("p should exist");
+        let a = p.borrow().first_child().expect("a should exist");
+        assert_eq!(a.borrow().kind(), LayoutObjectKind::Inline);
+        assert_eq!(a.borrow().style().text_decoration(), TextDecoration::None);
+    }
+
+    #[test]
+    fn test_caret_position_from_point() {
+        // "hello" は (0,0)、"world" は (0,20) に 1 文字 8px で並ぶ
+        let html = "<html><head></head><body><p>hello</p><p>world</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        // x=17 は 3 文字目 'l'（16〜24px）の左半分 → その手前の境界 "he|llo"（オフセット 2）
+        let caret = layout_view
+            .caret_position_from_point((17, 5))
+            .expect("caret should exist");
+        assert_eq!(
+            NodeKind::Text("hello".to_string()),
+            caret.node().borrow().kind()
+        );
+        assert_eq!(2, caret.offset());
+
+        // 2 行目
+        let caret = layout_view
+            .caret_position_from_point((20, 25))
+            .expect("caret should exist");
+        assert_eq!(
+            NodeKind::Text("world".to_string()),
+            caret.node().borrow().kind()
+        );
+        assert_eq!(3, caret.offset());
+
+        // テキストより右・下は、最後の行の行末になる
+        let caret = layout_view
+            .caret_position_from_point((500, 300))
+            .expect("caret should exist");
+        assert_eq!(
+            NodeKind::Text("world".to_string()),
+            caret.node().borrow().kind()
+        );
+        assert_eq!(5, caret.offset());
+    }
+
+    #[test]
+    fn test_paint_with_selection() {
+        let html = "<html><head></head><body><p>hello</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+        let start = layout_view
+            .caret_position_from_point((16, 5))
+            .expect("caret should exist");
+        let end = layout_view
+            .caret_position_from_point((40, 5))
+            .expect("caret should exist");
+        let mut range = Range::new(start.node(), start.offset());
+        range.set_end(end.node(), end.offset());
+        assert_eq!("llo", range.to_string());
+
+        let items = layout_view.paint_with_selection(Some(&range));
+        // body の Rect, p の Rect, ハイライトの Rect, テキストの順
+        assert_eq!(4, items.len());
+        match &items[2] {
+            DisplayItem::Rect {
+                layout_point,
+                layout_size,
+                ..
+            } => {
+                assert_eq!(LayoutPoint::new(16, 0), *layout_point);
+                assert_eq!(24, layout_size.width());
+            }
+            _ => panic!("selection highlight should be a Rect"),
+        }
+        match &items[3] {
+            DisplayItem::Text { text, .. } => assert_eq!("hello", text),
+            _ => panic!("text should be painted after the highlight"),
+        }
+    }
+
+    #[test]
+    fn test_paint_opacity_group() {
+        // p の opacity は、p の背景と中のテキストをまとめた Group にかかる
+        let html =
+            "<html><head><style>p{opacity:50%;}</style></head><body><p>hello</p></body></html>"
+                .to_string();
+        let layout_view = create_layout_view(html);
+
+        let items = layout_view.paint();
+        // body の Rect, p の Group の順
+        assert_eq!(2, items.len());
+        match &items[1] {
+            DisplayItem::Group { opacity, items } => {
+                assert_eq!(0.5, *opacity);
+                assert_eq!(2, items.len());
+                assert!(matches!(items[0], DisplayItem::Rect { .. }));
+                assert!(matches!(items[1], DisplayItem::Text { .. }));
+            }
+            _ => panic!("p should be painted as a Group"),
+        }
+    }
+
+    #[test]
+    fn test_box_model() {
+        // p: margin 上10/下20/左5, 上の枠線 solid 2px, padding 上4/左3
+        // #b: border-box で幅 100（枠線と padding を含む）
+        let html = "<html><head><style>p{margin-top:10px;margin-bottom:20px;margin-left:5px;padding-top:4px;padding-left:3px;border-top-style:solid;border-top-width:2px;border-top-color:red;} #b{width:100px;box-sizing:border-box;}</style></head><body><p id=a></p><p id=b>x</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+        let body = layout_view.root().expect("root should exist");
+        let a = body.borrow().first_child().expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+
+        // a: 幅は親幅から margin-left を引いた残り、高さは枠線 + padding のみ
+        assert_eq!(LayoutPoint::new(5, 10), a.borrow().point());
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH - 5, 6),
+            a.borrow().size()
+        );
+
+        // b: a の下端 16 から、margin 20 と 10 を相殺した 20 だけ下
+        assert_eq!(LayoutPoint::new(5, 36), b.borrow().point());
+        assert_eq!(100, b.borrow().size().width());
+        assert_eq!(97, b.borrow().content_size().width());
+
+        // テキストは b の枠線と padding の内側から始まる
+        let text = b.borrow().first_child().expect("text should exist");
+        assert_eq!(LayoutPoint::new(8, 42), text.borrow().point());
+
+        // body の高さには子の margin も含まれる（10 + 6 + 20 + b の高さ + 20）
+        assert_eq!(
+            10 + 6 + 20 + b.borrow().size().height() + 20,
+            body.borrow().size().height()
+        );
+
+        // a の描画: 背景の Rect の後に、上の辺の枠線
+        let items = a.borrow_mut().paint();
+        assert_eq!(2, items.len());
+        assert_eq!(
+            DisplayItem::Border {
+                color: Color::from_name("red").expect("red should be supported"),
+                layout_point: LayoutPoint::new(5, 10),
+                layout_size: LayoutSize::new(CONTENT_AREA_WIDTH - 5, 2),
+            },
+            items[1]
+        );
+    }
+
+    #[test]
+    fn test_custom_properties() {
+        // :root のカスタムプロパティを子孫が受け継ぎ、var() で使える
+        // 参照先が無い・循環している var() は unset と同じ（color は親から継承、margin は初期値 0）
+        let html = "<html><head><style>:root{--brand:red;--gap:8px;} \
+                    #a{color:var(--brand);margin-top:var(--gap);} \
+                    #b{--brand:var(--loop);--loop:var(--brand);color:var(--brand);margin-top:var(--none);} \
+                    #c{margin-top:var(--none, 2px);}</style></head>\
+                    <body><p id=a>x</p><p id=b>y</p><p id=c></p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+        let c = b.borrow().next_sibling().expect("c should exist");
+        let red = Color::from_name("red").expect("red should be supported");
+
+        assert_eq!(red, a.borrow().style().color());
+        assert_eq!(8, a.borrow().margin().top());
+        assert_eq!(Color::black(), b.borrow().style().color());
+        assert_eq!(0, b.borrow().margin().top());
+        assert_eq!(None, b.borrow().style().custom_property("--brand"));
+        assert_eq!(2, c.borrow().margin().top());
+    }
+
+    #[test]
+    fn test_length_units() {
+        // #a: 幅は包含ブロック（body = ビューポート幅）の 50%、margin は font-size（48px）の 0.5 倍
+        // #b: % の高さは、親（body）の高さが子から決まるので auto として扱う
+        let html = "<html><head><style>#a{width:50%;margin-left:0.5em;padding-left:10vw;font-size:48px;} #b{height:50%;}</style></head><body><p id=a></p><p id=b></p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+
+        assert_eq!(24, a.borrow().point().x());
+        assert_eq!(CONTENT_AREA_WIDTH / 10, a.borrow().padding().left());
+        assert_eq!(
+            CONTENT_AREA_WIDTH / 2 + CONTENT_AREA_WIDTH / 10,
+            a.borrow().size().width()
+        );
+        assert_eq!(0, b.borrow().size().height());
+    }
+
+    #[test]
+    fn test_rem() {
+        // rem はルート要素（<html>）の font-size が基準。<html> 自身の rem は初期値（16px）が基準
+        let html = "<html><head><style>html{font-size:1.25rem;} body{font-size:10px;} p{margin-top:2rem;}</style></head><body><p>a</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+
+        assert_eq!(40, p.borrow().margin().top());
+    }
+
+    #[test]
+    fn test_huge_lengths() {
+        // 0 で割った calc() や大きすぎる値でもパニックせず、有限の大きさでレイアウトする
+        for css in [
+            "p{margin:calc(1px / 0);}",
+            "p{padding:1e300px;}",
+            "p{line-height:1e20;}",
+            "p{font:1e300px serif;}",
+            "p{width:1e300px;border:1e300px solid red;letter-spacing:-1e300px;text-align:justify;}",
+        ] {
+            let html = "<html><head><style>".to_string()
+                + css
+                + "</style></head><body><p>hello world</p><p>x</p></body></html>";
+            let layout_view = create_layout_view(html);
+            layout_view.paint();
+        }
+
+        let layout_view = create_layout_view(
+            "<html><head><style>p{margin-top:calc(1px / 0);}</style></head><body><p>a</p></body></html>"
+                .to_string(),
+        );
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        assert_eq!(MAX_LAYOUT_PX as i64, p.borrow().margin().top());
+    }
+
+    #[test]
+    fn test_shorthands() {
+        // ショートハンドはロングハンドに展開され、後に書いたロングハンドがその一部を上書きする
+        let html = "<html><head><style>#a{margin:10px 20px;padding:4px;padding-left:0;border:2px solid red;} \
+                    #b{font:bold 32px serif;border-width:1px;}</style></head>\
+                    <body><p id=a></p><p id=b>x</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+
+        assert_eq!(20, a.borrow().point().x());
+        assert_eq!(10, a.borrow().margin().bottom());
+        assert_eq!(4, a.borrow().padding().top());
+        assert_eq!(0, a.borrow().padding().left());
+        assert_eq!(2, a.borrow().border().right());
+        assert_eq!(
+            Color::from_name("red").expect("red should be supported"),
+            a.borrow().style().border_color(Side::Top)
+        );
+        // border-style の初期値は none なので、幅だけ指定しても枠線は出ない
+        assert_eq!(0, b.borrow().border().top());
+        assert_eq!(FontSize::new(32.0), b.borrow().style().font_size());
+        assert!(b.borrow().style().font_weight().is_bold());
+    }
+
+    #[test]
+    fn test_margin_auto() {
+        // 幅が決まっていれば左右の auto で中央寄せ。片方だけなら余りを全部その辺に。
+        // 幅が auto なら auto の margin は 0（上下の auto も 0）
+        let html = "<html><head><style>#a{width:100px;margin:0 auto;} #b{width:100px;margin-left:auto;margin-right:10px;} \
+                    #c{margin:auto;}</style></head>\
+                    <body><p id=a>x</p><p id=b>x</p><p id=c>x</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+        let c = b.borrow().next_sibling().expect("c should exist");
+
+        let side = (CONTENT_AREA_WIDTH - 100) / 2;
+        assert_eq!(side, a.borrow().margin().left());
+        assert_eq!(side, a.borrow().margin().right());
+        assert_eq!(0, a.borrow().margin().top());
+        assert_eq!(side, a.borrow().point().x());
+        assert_eq!(100, a.borrow().size().width());
+
+        assert_eq!(CONTENT_AREA_WIDTH - 110, b.borrow().point().x());
+
+        assert_eq!(0, c.borrow().margin().left());
+        assert_eq!(0, c.borrow().margin().top());
+        assert_eq!(CONTENT_AREA_WIDTH, c.borrow().size().width());
+    }
+
+    #[test]
+    fn test_border_styles() {
+        // solid 以外の線の種類も受け付けて、いまは実線として描く。hidden は none と同じく枠線なし
+        let html = "<html><head><style>#a{border:1px dashed #ccc;} #b{border:3px hidden red;} \
+                    #c{border-top:thick dotted;border-bottom:2px double;}</style></head>\
+                    <body><p id=a>x</p><p id=b>x</p><p id=c>x</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+        let c = b.borrow().next_sibling().expect("c should exist");
+
+        assert_eq!(
+            BorderStyle::Dashed,
+            a.borrow().style().border_style(Side::Top)
+        );
+        assert_eq!(1, a.borrow().border().top());
+        assert_eq!(1, a.borrow().border().left());
+        assert_eq!(0, b.borrow().border().top());
+        assert_eq!(5, c.borrow().border().top());
+        assert_eq!(2, c.borrow().border().bottom());
+        assert_eq!(0, c.borrow().border().left());
+
+        // #a の 4 辺は #ccc の塗り潰しとして描かれる
+        let borders: Vec<DisplayItem> = layout_view
+            .paint()
+            .into_iter()
+            .filter(|item| matches!(item, DisplayItem::Border { .. }))
+            .collect();
+        assert_eq!(6, borders.len());
+        match &borders[0] {
+            DisplayItem::Border { color, .. } => assert_eq!(
+                Color::from_code("#ccc").expect("#ccc should be supported"),
+                *color
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_math_functions() {
+        // 幅は包含ブロック（body）の幅から決まり、padding の % も同じ幅が基準になる
+        let html = "<html><head><style>#a{width:calc(100% - 2 * 20px);padding-left:min(10%, 8px);} \
+                    #b{width:clamp(10px, 50%, 100px);margin-left:calc((100% - 100px) / 2);}</style></head>\
+                    <body><p id=a></p><p id=b></p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+
+        assert_eq!(8, a.borrow().padding().left());
+        assert_eq!(CONTENT_AREA_WIDTH - 40 + 8, a.borrow().size().width());
+        assert_eq!(100, b.borrow().size().width());
+        assert_eq!((CONTENT_AREA_WIDTH - 100) / 2, b.borrow().point().x());
+    }
+
+    // 描画命令のうち、テキストの文字列と位置だけを順に取り出す
+    fn painted_texts(layout_view: &LayoutView) -> Vec<(String, LayoutPoint)> {
+        layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text_align() {
+        // 幅 100px の p に、1 文字 8px で並べる
+        // justify は最後の行以外で、余り（100 - 72 = 28px）をスペースに配る
+        let html = "<html><head><style>p{width:100px;} #r{text-align:right;} \
+                    #c{text-align:center;} #j{text-align:justify;}</style></head>\
+                    <body><p id=r>hello</p><p id=c>hello</p><p id=j>aaaa bbbb cccc</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        assert_eq!(
+            vec![
+                ("hello".to_string(), LayoutPoint::new(60, 0)),
+                ("hello".to_string(), LayoutPoint::new(30, 20)),
+                ("aaaa ".to_string(), LayoutPoint::new(0, 40)),
+                ("bbbb".to_string(), LayoutPoint::new(68, 40)),
+                ("cccc".to_string(), LayoutPoint::new(0, 60)),
+            ],
+            painted_texts(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_white_space() {
+        // pre は空白と改行をそのまま残し、pre-line は改行だけ残す
+        // nowrap は幅を超えても折り返さない
+        let html = "<html><head><style>p{width:40px;} #pre{white-space:pre;} \
+                    #line{white-space:pre-line;} #nowrap{white-space:nowrap;}</style></head>\
+                    <body><p id=pre>a  b\nc</p><p id=line>a  b \n c</p><p id=nowrap>aaa bbb</p>\
+                    <p>aaa bbb</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        assert_eq!(
+            vec![
+                ("a  b".to_string(), LayoutPoint::new(0, 0)),
+                ("c".to_string(), LayoutPoint::new(0, 20)),
+                ("a b".to_string(), LayoutPoint::new(0, 40)),
+                ("c".to_string(), LayoutPoint::new(0, 60)),
+                ("aaa bbb".to_string(), LayoutPoint::new(0, 80)),
+                ("aaa".to_string(), LayoutPoint::new(0, 100)),
+                ("bbb".to_string(), LayoutPoint::new(0, 120)),
+            ],
+            painted_texts(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_text_spacing_and_transform() {
+        // letter-spacing は文字ごと、word-spacing はスペースに送り幅を足す
+        // text-indent は最初の行だけを字下げする（% は行の幅が基準）
+        let html = "<html><head><style>p{width:100px;} #l{letter-spacing:2px;} \
+                    #w{word-spacing:4px;text-transform:capitalize;} #u{text-transform:uppercase;text-indent:10%;}\
+                    </style></head><body><p id=l>ab</p><p id=w>a big cat</p><p id=u>hello</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        assert_eq!(
+            vec![
+                ("a".to_string(), LayoutPoint::new(0, 0)),
+                ("b".to_string(), LayoutPoint::new(10, 0)),
+                ("A ".to_string(), LayoutPoint::new(0, 20)),
+                ("Big ".to_string(), LayoutPoint::new(20, 20)),
+                ("Cat".to_string(), LayoutPoint::new(56, 20)),
+                ("HELLO".to_string(), LayoutPoint::new(10, 40)),
+            ],
+            painted_texts(&layout_view)
+        );
+
+        // 選択のオフセットも送り幅に合わせる（x=11 は 'b'（10〜20px）の左半分）
+        let caret = layout_view
+            .caret_position_from_point((11, 5))
+            .expect("caret should exist");
+        assert_eq!(1, caret.offset());
+    }
+
+    #[test]
+    fn test_line_height() {
+        // 行の高さは line-height で決まり、文字は差の半分（(30 - 20) / 2）だけ下げて描く
+        // 数値の line-height は継承先のフォントサイズで計算し直す
+        let html = "<html><head><style>#a{line-height:30px;} body{line-height:2;} \
+                    #b{font-size:32px;}</style></head><body><p id=a>hello</p><p id=b>big</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+        let a = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("a should exist");
+        let b = a.borrow().next_sibling().expect("b should exist");
+
+        assert_eq!(30, a.borrow().size().height());
+        assert_eq!(64, b.borrow().size().height());
+        assert_eq!(
+            vec![
+                ("hello".to_string(), LayoutPoint::new(0, 5)),
+                ("big".to_string(), LayoutPoint::new(0, 42)),
+            ],
+            painted_texts(&layout_view)
+        );
+    }
 }
@@ -1,3 +1,11 @@
+pub mod animation;
+pub mod calc;
 pub mod computed_style;
+pub mod custom_property;
 pub mod layout_object;
 pub mod layout_view;
+pub mod named_colors;
+pub mod property;
+pub mod resolved_value;
+pub mod shorthand;
+pub mod style_resolver;
@@ -0,0 +1,1774 @@
+//! CSS プロパティの登録表（プロパティレジストリ）（初心者向け）
+//!
+//! 役割
+//! - 対応している CSS プロパティを 1 つの表（`PROPERTIES`）にまとめます。各項目が持つのは次の 4 つです。
+//!   - 名前（`"background-color"`）
+//!   - 値の読み取り方（`parse`: 宣言の値 → `PropertyValue`）
+//!   - 初期値（`initial`: 何も指定されず、継承もしないときの値）
+//!   - 継承するか（`inherited`: 指定が無いとき親の値を受け継ぐか）
+//! - `ComputedStyle` はこの表をたどるだけで「指定値 → 計算値」を求めます。
+//!   新しいプロパティを増やすときは、`PropertyId` に 1 つ足して表に 1 行足すだけです
+//!   （描画側で使うなら `ComputedStyle` に読み出し用のメソッドも足します）。
+//!
+//! 値の流れ（仕様の用語）
+//! 1) 宣言（`color: red`）を `parse_declaration` で読み、指定値 `SpecifiedValue` にする
+//!    - `inherit` / `initial` / `unset` はどのプロパティでも使えるキーワード（CSS-wide keywords）
+//!    - 読めない値（`display: flex` など未対応の値）の宣言は無視する（前の宣言が残る）
+//! 2) カスケードで後勝ちに並べた宣言を順に適用し、プロパティごとに最後の指定値が残る
+//! 3) `ComputedStyle::defaulting` でキーワードを解決し、計算値 `PropertyValue` にする
+//!    - 値あり → その値 / `inherit` → 親の値 / `initial` → 初期値
+//!    - 指定なし・`unset` → 継承するプロパティなら親の値、しないなら初期値
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `PROPERTIES` は TS の `const PROPERTIES: PropertyDefinition[] = [...]` のような定数表です。
+//!   関数ポインタ `fn(...) -> ...` を持てるので、項目ごとに読み取り方を変えられます。
+//! - `PropertyId as usize` で表や `ComputedStyle` の配列の添字にします（TS の数値 enum と同じ感覚）。
+//!
+//! 仕様: https://www.w3.org/TR/css-cascade-4/#value-stages
+//!
+//! 表にあるのはロングハンド（`margin-top` など 1 つの値を持つプロパティ）だけです。
+//! `margin: 8px` のような一括指定（ショートハンド）は `shorthand` がロングハンドに展開します。
+
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::layout::animation::AnimationDirection;
+use crate::renderer::layout::animation::AnimationFillMode;
+use crate::renderer::layout::animation::StepPosition;
+use crate::renderer::layout::animation::TimingFunction;
+use crate::renderer::layout::calc;
+use crate::renderer::layout::calc::is_math_function;
+use crate::renderer::layout::calc::CalcNode;
+use crate::renderer::layout::calc::CalcType;
+use crate::renderer::layout::computed_style::BorderStyle;
+use crate::renderer::layout::computed_style::BoxSizing;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::DisplayType;
+use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::FontStyle;
+use crate::renderer::layout::computed_style::FontWeight;
+use crate::renderer::layout::computed_style::Length;
+use crate::renderer::layout::computed_style::LengthUnit;
+use crate::renderer::layout::computed_style::ListStylePosition;
+use crate::renderer::layout::computed_style::ListStyleType;
+use crate::renderer::layout::computed_style::TextAlign;
+use crate::renderer::layout::computed_style::TextDecoration;
+use crate::renderer::layout::computed_style::TextDecorationStyle;
+use crate::renderer::layout::computed_style::TextTransform;
+use crate::renderer::layout::computed_style::WhiteSpace;
+use crate::renderer::layout::custom_property::contains_var;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// 対応しているプロパティ（並び順は `PROPERTIES` と同じにする）
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PropertyId {
+    BackgroundColor,
+    Color,
+    Display,
+    FontSize,
+    TextDecorationLine,
+    Height,
+    Width,
+    BoxSizing,
+    MarginTop,
+    MarginRight,
+    MarginBottom,
+    MarginLeft,
+    PaddingTop,
+    PaddingRight,
+    PaddingBottom,
+    PaddingLeft,
+    BorderTopWidth,
+    BorderRightWidth,
+    BorderBottomWidth,
+    BorderLeftWidth,
+    BorderTopStyle,
+    BorderRightStyle,
+    BorderBottomStyle,
+    BorderLeftStyle,
+    BorderTopColor,
+    BorderRightColor,
+    BorderBottomColor,
+    BorderLeftColor,
+    FontFamily,
+    FontStyle,
+    FontWeight,
+    Opacity,
+    TextDecorationStyle,
+    TextDecorationColor,
+    ListStyleType,
+    ListStylePosition,
+    ListStyleImage,
+    FlexGrow,
+    FlexShrink,
+    FlexBasis,
+    TransitionProperty,
+    TransitionDuration,
+    TransitionTimingFunction,
+    TransitionDelay,
+    AnimationName,
+    AnimationDuration,
+    AnimationTimingFunction,
+    AnimationDelay,
+    AnimationIterationCount,
+    AnimationDirection,
+    AnimationFillMode,
+    TextAlign,
+    LineHeight,
+    WhiteSpace,
+    LetterSpacing,
+    WordSpacing,
+    TextIndent,
+    TextTransform,
+}
+
+/// ボックスの 4 辺（margin/padding/border の `-top` などを辺ごとに引くために使う）
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Side {
+    /// CSS の並び順（上 → 右 → 下 → 左、時計回り）
+    pub const ALL: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+}
+
+/// プロパティの計算値
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Color(Color),
+    Display(DisplayType),
+    FontSize(FontSize),
+    TextDecoration(TextDecoration),
+    /// 長さ（計算値では px / % / vw / vh のいずれか。`Length` を参照）
+    Length(Length),
+    /// `auto`（width/height の初期値。値はレイアウト時に決まる）
+    Auto,
+    /// `currentColor`（同じ要素の `color` の値を使う。border-color の初期値）
+    /// `color` 自身に指定したときは親の `color` を使う（計算値には残らない）
+    CurrentColor,
+    BorderStyle(BorderStyle),
+    BoxSizing(BoxSizing),
+    /// フォント名の候補（先に書いたものほど優先）
+    FontFamily(Vec<String>),
+    FontStyle(FontStyle),
+    FontWeight(FontWeight),
+    /// `font-weight: bolder` / `lighter`（親の太さから決まる。計算値には残らない）
+    Bolder,
+    Lighter,
+    /// 単位の無い数値（`opacity` など。`animation-iteration-count: infinite` は無限大）
+    Number(f64),
+    TextDecorationStyle(TextDecorationStyle),
+    ListStyleType(ListStyleType),
+    ListStylePosition(ListStylePosition),
+    /// 画像の URL（`list-style-image: url(dot.png)`）
+    Url(String),
+    /// `none`（`list-style-image` の初期値など、キーワードだけで表す値）
+    None,
+    /// カンマ区切りの値の並び（`transition-duration: 1s, 200ms` など、アニメーションのプロパティ）
+    List(Vec<PropertyValue>),
+    /// 時間（ミリ秒。`1s` は 1000.0）
+    Time(f64),
+    /// キーワードではない名前（`transition-property` のプロパティ名、`animation-name`）
+    Ident(String),
+    TimingFunction(TimingFunction),
+    AnimationDirection(AnimationDirection),
+    AnimationFillMode(AnimationFillMode),
+    TextAlign(TextAlign),
+    WhiteSpace(WhiteSpace),
+    TextTransform(TextTransform),
+    /// `normal`（`line-height` / `letter-spacing` / `word-spacing`。値はフォントや描画側が決める）
+    Normal,
+}
+
+/// 宣言から読み取った指定値
+/// https://www.w3.org/TR/css-cascade-4/#defaulting-keywords
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecifiedValue {
+    Value(PropertyValue),
+    /// `inherit`: 親の計算値を使う
+    Inherit,
+    /// `initial`: 初期値を使う
+    Initial,
+    /// `unset`: 継承するプロパティなら `inherit`、しないなら `initial` と同じ
+    Unset,
+    /// `var()` を含む値（空白を除いたコンポーネント値の列）。計算値を決めるときに置き換えてから読む
+    /// https://www.w3.org/TR/css-variables-1/#using-variables
+    Unparsed(Vec<ComponentValue>),
+    /// `var()` を含むショートハンド（名前と値）。計算値を決めるときに置き換え、展開してから読む
+    /// https://www.w3.org/TR/css-variables-1/#variables-in-shorthands
+    PendingShorthand(&'static str, Vec<ComponentValue>),
+}
+
+/// レジストリの 1 項目
+pub struct PropertyDefinition {
+    pub id: PropertyId,
+    pub name: &'static str,
+    /// 指定が無いとき親の値を受け継ぐか
+    pub inherited: bool,
+    /// 初期値（`FontFamily` が `Vec<String>` を持つため定数ではなく関数にしている）
+    pub initial: fn() -> PropertyValue,
+    /// 宣言の値（空白を除いたコンポーネント値の列）を読む。未対応の値なら None（その宣言は無視される）
+    pub parse: fn(&[ComponentValue]) -> Option<PropertyValue>,
+}
+
+/// 対応しているプロパティの表（`PropertyId` の並びと同じ順）
+///
+/// 継承についての簡略化
+/// - `text-decoration-*` も本来は継承しませんが、下線は子孫のテキストにも描かれるので継承として扱います。
+pub static PROPERTIES: [PropertyDefinition; 58] = [
+    PropertyDefinition {
+        id: PropertyId::BackgroundColor,
+        name: "background-color",
+        inherited: false,
+        initial: || PropertyValue::Color(Color::transparent()),
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::Color,
+        name: "color",
+        inherited: true,
+        initial: || PropertyValue::Color(Color::black()),
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::Display,
+        name: "display",
+        inherited: false,
+        // 要素ごとの既定（p は block など）は UA スタイルシートが指定する
+        initial: || PropertyValue::Display(DisplayType::Inline),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => {
+                    DisplayType::from_str(s).ok().map(PropertyValue::Display)
+                }
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::FontSize,
+        name: "font-size",
+        inherited: true,
+        initial: || PropertyValue::FontSize(FontSize::MEDIUM),
+        parse: |v| length_or_calc(v, parse_font_size, calc_font_size),
+    },
+    PropertyDefinition {
+        id: PropertyId::TextDecorationLine,
+        name: "text-decoration-line",
+        inherited: true,
+        initial: || PropertyValue::TextDecoration(TextDecoration::None),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => TextDecoration::from_str(s)
+                    .ok()
+                    .map(PropertyValue::TextDecoration),
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::Height,
+        name: "height",
+        inherited: false,
+        initial: || PropertyValue::Auto,
+        parse: |v| length_or_calc(v, parse_size, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::Width,
+        name: "width",
+        inherited: false,
+        initial: || PropertyValue::Auto,
+        parse: |v| length_or_calc(v, parse_size, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::BoxSizing,
+        name: "box-sizing",
+        inherited: false,
+        initial: || PropertyValue::BoxSizing(BoxSizing::ContentBox),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => {
+                    BoxSizing::from_str(s).ok().map(PropertyValue::BoxSizing)
+                }
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::MarginTop,
+        name: "margin-top",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_margin, calc_any),
+    },
+    PropertyDefinition {
+        id: PropertyId::MarginRight,
+        name: "margin-right",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_margin, calc_any),
+    },
+    PropertyDefinition {
+        id: PropertyId::MarginBottom,
+        name: "margin-bottom",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_margin, calc_any),
+    },
+    PropertyDefinition {
+        id: PropertyId::MarginLeft,
+        name: "margin-left",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_margin, calc_any),
+    },
+    PropertyDefinition {
+        id: PropertyId::PaddingTop,
+        name: "padding-top",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_non_negative_length_percentage, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::PaddingRight,
+        name: "padding-right",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_non_negative_length_percentage, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::PaddingBottom,
+        name: "padding-bottom",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_non_negative_length_percentage, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::PaddingLeft,
+        name: "padding-left",
+        inherited: false,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_non_negative_length_percentage, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderTopWidth,
+        name: "border-top-width",
+        inherited: false,
+        // `medium`（このブラウザでは 3px）
+        initial: || PropertyValue::Length(Length::px(3.0)),
+        parse: |v| length_or_calc(v, parse_border_width, calc_border_width),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderRightWidth,
+        name: "border-right-width",
+        inherited: false,
+        // `medium`（このブラウザでは 3px）
+        initial: || PropertyValue::Length(Length::px(3.0)),
+        parse: |v| length_or_calc(v, parse_border_width, calc_border_width),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderBottomWidth,
+        name: "border-bottom-width",
+        inherited: false,
+        // `medium`（このブラウザでは 3px）
+        initial: || PropertyValue::Length(Length::px(3.0)),
+        parse: |v| length_or_calc(v, parse_border_width, calc_border_width),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderLeftWidth,
+        name: "border-left-width",
+        inherited: false,
+        // `medium`（このブラウザでは 3px）
+        initial: || PropertyValue::Length(Length::px(3.0)),
+        parse: |v| length_or_calc(v, parse_border_width, calc_border_width),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderTopStyle,
+        name: "border-top-style",
+        inherited: false,
+        initial: || PropertyValue::BorderStyle(BorderStyle::None),
+        parse: |v| single(v, parse_border_style),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderRightStyle,
+        name: "border-right-style",
+        inherited: false,
+        initial: || PropertyValue::BorderStyle(BorderStyle::None),
+        parse: |v| single(v, parse_border_style),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderBottomStyle,
+        name: "border-bottom-style",
+        inherited: false,
+        initial: || PropertyValue::BorderStyle(BorderStyle::None),
+        parse: |v| single(v, parse_border_style),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderLeftStyle,
+        name: "border-left-style",
+        inherited: false,
+        initial: || PropertyValue::BorderStyle(BorderStyle::None),
+        parse: |v| single(v, parse_border_style),
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderTopColor,
+        name: "border-top-color",
+        inherited: false,
+        initial: || PropertyValue::CurrentColor,
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderRightColor,
+        name: "border-right-color",
+        inherited: false,
+        initial: || PropertyValue::CurrentColor,
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderBottomColor,
+        name: "border-bottom-color",
+        inherited: false,
+        initial: || PropertyValue::CurrentColor,
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::BorderLeftColor,
+        name: "border-left-color",
+        inherited: false,
+        initial: || PropertyValue::CurrentColor,
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::FontFamily,
+        name: "font-family",
+        inherited: true,
+        // このブラウザが描けるのは等幅のビットマップフォントだけなので、既定は monospace
+        initial: || PropertyValue::FontFamily(vec![String::from("monospace")]),
+        parse: parse_font_family,
+    },
+    PropertyDefinition {
+        id: PropertyId::FontStyle,
+        name: "font-style",
+        inherited: true,
+        initial: || PropertyValue::FontStyle(FontStyle::Normal),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => {
+                    FontStyle::from_str(s).ok().map(PropertyValue::FontStyle)
+                }
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::FontWeight,
+        name: "font-weight",
+        inherited: true,
+        initial: || PropertyValue::FontWeight(FontWeight::NORMAL),
+        parse: |v| single(v, parse_font_weight),
+    },
+    PropertyDefinition {
+        id: PropertyId::Opacity,
+        name: "opacity",
+        inherited: false,
+        initial: || PropertyValue::Number(1.0),
+        parse: parse_opacity,
+    },
+    PropertyDefinition {
+        id: PropertyId::TextDecorationStyle,
+        name: "text-decoration-style",
+        inherited: true,
+        initial: || PropertyValue::TextDecorationStyle(TextDecorationStyle::Solid),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => TextDecorationStyle::from_str(s)
+                    .ok()
+                    .map(PropertyValue::TextDecorationStyle),
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::TextDecorationColor,
+        name: "text-decoration-color",
+        inherited: true,
+        initial: || PropertyValue::CurrentColor,
+        parse: parse_color,
+    },
+    PropertyDefinition {
+        id: PropertyId::ListStyleType,
+        name: "list-style-type",
+        inherited: true,
+        initial: || PropertyValue::ListStyleType(ListStyleType::Disc),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => ListStyleType::from_str(s)
+                    .ok()
+                    .map(PropertyValue::ListStyleType),
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::ListStylePosition,
+        name: "list-style-position",
+        inherited: true,
+        initial: || PropertyValue::ListStylePosition(ListStylePosition::Outside),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => ListStylePosition::from_str(s)
+                    .ok()
+                    .map(PropertyValue::ListStylePosition),
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::ListStyleImage,
+        name: "list-style-image",
+        inherited: true,
+        initial: || PropertyValue::None,
+        parse: parse_image,
+    },
+    PropertyDefinition {
+        id: PropertyId::FlexGrow,
+        name: "flex-grow",
+        inherited: false,
+        initial: || PropertyValue::Number(0.0),
+        parse: |v| single(v, parse_non_negative_number),
+    },
+    PropertyDefinition {
+        id: PropertyId::FlexShrink,
+        name: "flex-shrink",
+        inherited: false,
+        initial: || PropertyValue::Number(1.0),
+        parse: |v| single(v, parse_non_negative_number),
+    },
+    PropertyDefinition {
+        id: PropertyId::FlexBasis,
+        name: "flex-basis",
+        inherited: false,
+        initial: || PropertyValue::Auto,
+        parse: |v| length_or_calc(v, parse_size, calc_non_negative),
+    },
+    PropertyDefinition {
+        id: PropertyId::TransitionProperty,
+        name: "transition-property",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::Ident(String::from("all"))]),
+        parse: parse_transition_property,
+    },
+    PropertyDefinition {
+        id: PropertyId::TransitionDuration,
+        name: "transition-duration",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::Time(0.0)]),
+        parse: |v| comma_list(v, parse_duration),
+    },
+    PropertyDefinition {
+        id: PropertyId::TransitionTimingFunction,
+        name: "transition-timing-function",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::TimingFunction(TimingFunction::EASE)]),
+        parse: |v| comma_list(v, parse_timing_function),
+    },
+    PropertyDefinition {
+        id: PropertyId::TransitionDelay,
+        name: "transition-delay",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::Time(0.0)]),
+        parse: |v| comma_list(v, parse_time),
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationName,
+        name: "animation-name",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::None]),
+        parse: |v| comma_list(v, parse_animation_name),
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationDuration,
+        name: "animation-duration",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::Time(0.0)]),
+        parse: |v| comma_list(v, parse_duration),
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationTimingFunction,
+        name: "animation-timing-function",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::TimingFunction(TimingFunction::EASE)]),
+        parse: |v| comma_list(v, parse_timing_function),
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationDelay,
+        name: "animation-delay",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::Time(0.0)]),
+        parse: |v| comma_list(v, parse_time),
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationIterationCount,
+        name: "animation-iteration-count",
+        inherited: false,
+        initial: || PropertyValue::List(vec![PropertyValue::Number(1.0)]),
+        parse: |v| comma_list(v, parse_iteration_count),
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationDirection,
+        name: "animation-direction",
+        inherited: false,
+        initial: || {
+            PropertyValue::List(vec![PropertyValue::AnimationDirection(
+                AnimationDirection::Normal,
+            )])
+        },
+        parse: |v| {
+            comma_list(v, |v| match v {
+                [ComponentValue::Ident(s)] => AnimationDirection::from_str(s)
+                    .ok()
+                    .map(PropertyValue::AnimationDirection),
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::AnimationFillMode,
+        name: "animation-fill-mode",
+        inherited: false,
+        initial: || {
+            PropertyValue::List(vec![PropertyValue::AnimationFillMode(
+                AnimationFillMode::None,
+            )])
+        },
+        parse: |v| {
+            comma_list(v, |v| match v {
+                [ComponentValue::Ident(s)] => AnimationFillMode::from_str(s)
+                    .ok()
+                    .map(PropertyValue::AnimationFillMode),
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::TextAlign,
+        name: "text-align",
+        inherited: true,
+        initial: || PropertyValue::TextAlign(TextAlign::Left),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => {
+                    TextAlign::from_str(s).ok().map(PropertyValue::TextAlign)
+                }
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::LineHeight,
+        name: "line-height",
+        inherited: true,
+        initial: || PropertyValue::Normal,
+        parse: parse_line_height,
+    },
+    PropertyDefinition {
+        id: PropertyId::WhiteSpace,
+        name: "white-space",
+        inherited: true,
+        initial: || PropertyValue::WhiteSpace(WhiteSpace::Normal),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => {
+                    WhiteSpace::from_str(s).ok().map(PropertyValue::WhiteSpace)
+                }
+                _ => None,
+            })
+        },
+    },
+    PropertyDefinition {
+        id: PropertyId::LetterSpacing,
+        name: "letter-spacing",
+        inherited: true,
+        initial: || PropertyValue::Normal,
+        parse: parse_spacing,
+    },
+    PropertyDefinition {
+        id: PropertyId::WordSpacing,
+        name: "word-spacing",
+        inherited: true,
+        initial: || PropertyValue::Normal,
+        parse: parse_spacing,
+    },
+    PropertyDefinition {
+        id: PropertyId::TextIndent,
+        name: "text-indent",
+        inherited: true,
+        initial: || PropertyValue::Length(Length::px(0.0)),
+        parse: |v| length_or_calc(v, parse_length_percentage, calc_any),
+    },
+    PropertyDefinition {
+        id: PropertyId::TextTransform,
+        name: "text-transform",
+        inherited: true,
+        initial: || PropertyValue::TextTransform(TextTransform::None),
+        parse: |v| {
+            single(v, |v| match v {
+                ComponentValue::Ident(s) => TextTransform::from_str(s)
+                    .ok()
+                    .map(PropertyValue::TextTransform),
+                _ => None,
+            })
+        },
+    },
+];
+
+impl PropertyId {
+    /// このプロパティのレジストリ項目
+    pub fn definition(self) -> &'static PropertyDefinition {
+        &PROPERTIES[self as usize]
+    }
+
+    /// 辺ごとのプロパティ（例: `PropertyId::margin(Side::Top)` → `margin-top`）
+    pub fn margin(side: Side) -> Self {
+        match side {
+            Side::Top => Self::MarginTop,
+            Side::Right => Self::MarginRight,
+            Side::Bottom => Self::MarginBottom,
+            Side::Left => Self::MarginLeft,
+        }
+    }
+
+    pub fn padding(side: Side) -> Self {
+        match side {
+            Side::Top => Self::PaddingTop,
+            Side::Right => Self::PaddingRight,
+            Side::Bottom => Self::PaddingBottom,
+            Side::Left => Self::PaddingLeft,
+        }
+    }
+
+    pub fn border_width(side: Side) -> Self {
+        match side {
+            Side::Top => Self::BorderTopWidth,
+            Side::Right => Self::BorderRightWidth,
+            Side::Bottom => Self::BorderBottomWidth,
+            Side::Left => Self::BorderLeftWidth,
+        }
+    }
+
+    pub fn border_style(side: Side) -> Self {
+        match side {
+            Side::Top => Self::BorderTopStyle,
+            Side::Right => Self::BorderRightStyle,
+            Side::Bottom => Self::BorderBottomStyle,
+            Side::Left => Self::BorderLeftStyle,
+        }
+    }
+
+    pub fn border_color(side: Side) -> Self {
+        match side {
+            Side::Top => Self::BorderTopColor,
+            Side::Right => Self::BorderRightColor,
+            Side::Bottom => Self::BorderBottomColor,
+            Side::Left => Self::BorderLeftColor,
+        }
+    }
+}
+
+/// プロパティ名からレジストリ項目を探す（未対応なら None）
+pub fn lookup(name: &str) -> Option<&'static PropertyDefinition> {
+    PROPERTIES.iter().find(|p| p.name == name)
+}
+
+/// 宣言 1 つを (プロパティ, 指定値) に読み替える
+///
+/// - 未対応のプロパティや値なら None（呼び出し側はその宣言を無視する）
+///
+/// 例
+/// - `color: red`       → `(Color, Value(Color(red)))`
+/// - `color: inherit`   → `(Color, Inherit)`
+/// - `display: flex`    → None（未対応の値）
+/// - `color: var(--x)`  → `(Color, Unparsed([...]))`（読むのは計算値を決めるとき）
+pub fn parse_declaration(declaration: &Declaration) -> Option<(PropertyId, SpecifiedValue)> {
+    let definition = lookup(&declaration.property)?;
+
+    if contains_var(&declaration.values) {
+        return Some((
+            definition.id,
+            SpecifiedValue::Unparsed(declaration.values.clone()),
+        ));
+    }
+    let value = parse_specified(definition, &declaration.values)?;
+    Some((definition.id, value))
+}
+
+/// 値（空白を除いたコンポーネント値の列）をプロパティ `definition` の指定値として読む
+///
+/// - `inherit` / `initial` / `unset` はどのプロパティにも使える
+/// - 読めない値なら None
+pub fn parse_specified(
+    definition: &PropertyDefinition,
+    values: &[ComponentValue],
+) -> Option<SpecifiedValue> {
+    if let [ComponentValue::Ident(keyword)] = values {
+        let keyword = match keyword.as_str() {
+            "inherit" => Some(SpecifiedValue::Inherit),
+            "initial" => Some(SpecifiedValue::Initial),
+            "unset" => Some(SpecifiedValue::Unset),
+            _ => None,
+        };
+        if let Some(keyword) = keyword {
+            return Some(keyword);
+        }
+    }
+
+    (definition.parse)(values).map(SpecifiedValue::Value)
+}
+
+// 値が 1 つだけの宣言を読む（`color: red blue` のように余分な値があれば無効）
+fn single(
+    values: &[ComponentValue],
+    parse: fn(&ComponentValue) -> Option<PropertyValue>,
+) -> Option<PropertyValue> {
+    match values {
+        [value] => parse(value),
+        _ => None,
+    }
+}
+
+// 色を読む
+// - 色名（`red`）・`transparent`・`currentColor`（キーワードは大文字小文字を区別しない）
+// - 16 進（`#f00` / `#f008` / `#ff0000` / `#ff000080`）
+// - 関数（`rgb()` / `rgba()` / `hsl()` / `hsla()`）。値は Function("rgb"), 引数…, `)` と並んで届く
+// https://www.w3.org/TR/css-color-4/#color-syntax
+fn parse_color(values: &[ComponentValue]) -> Option<PropertyValue> {
+    let color = match values {
+        [ComponentValue::Ident(s)] if s.eq_ignore_ascii_case("currentcolor") => {
+            return Some(PropertyValue::CurrentColor)
+        }
+        [ComponentValue::Ident(name)] => Color::from_name(name).ok()?,
+        [ComponentValue::HashToken(code, _)] => Color::from_code(&format!("#{}", code)).ok()?,
+        [ComponentValue::Function(name), args @ .., ComponentValue::CloseParenthesis] => {
+            parse_color_function(name, args)?
+        }
+        _ => return None,
+    };
+    Some(PropertyValue::Color(color))
+}
+
+// `rgb()` / `hsl()` の引数を読む（`rgba` / `hsla` は同じものの別名）
+// - カンマ区切り: `rgb(255, 0, 0)` / `rgba(255, 0, 0, 0.5)`
+// - 空白区切り:   `rgb(255 0 0)` / `rgb(255 0 0 / 50%)`
+// - rgb の各チャンネルは 0〜255 の数値か %、hsl は色相（数値・角度）と彩度・明度（%）
+// - alpha は 0〜1 の数値か %（省略すると 1 = 不透明）
+fn parse_color_function(name: &str, args: &[ComponentValue]) -> Option<Color> {
+    let comma = ComponentValue::Comma;
+    let (channels, alpha) = if args.contains(&comma) {
+        let mut parts = Vec::new();
+        for part in args.split(|v| *v == comma) {
+            match part {
+                [v] => parts.push(v),
+                _ => return None,
+            }
+        }
+        match parts[..] {
+            [a, b, c] => ([a, b, c], None),
+            [a, b, c, alpha] => ([a, b, c], Some(alpha)),
+            _ => return None,
+        }
+    } else {
+        match args {
+            [a, b, c] => ([a, b, c], None),
+            [a, b, c, ComponentValue::Delim('/'), alpha] => ([a, b, c], Some(alpha)),
+            _ => return None,
+        }
+    };
+
+    let alpha = match alpha {
+        Some(ComponentValue::Number(n, _)) => *n,
+        Some(ComponentValue::Percentage(n)) => n / 100.0,
+        Some(_) => return None,
+        None => 1.0,
+    };
+
+    match name.to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => {
+            let mut rgb = [0u8; 3];
+            for (channel, value) in rgb.iter_mut().zip(channels) {
+                let n = match value {
+                    ComponentValue::Number(n, _) => *n,
+                    ComponentValue::Percentage(n) => n / 100.0 * 255.0,
+                    _ => return None,
+                };
+                *channel = (n.clamp(0.0, 255.0) + 0.5) as u8;
+            }
+            Some(Color::rgba(rgb[0], rgb[1], rgb[2], alpha))
+        }
+        "hsl" | "hsla" => {
+            let hue = match channels[0] {
+                ComponentValue::Number(n, _) => *n,
+                ComponentValue::Dimension(n, _, unit) => match unit.to_ascii_lowercase().as_str() {
+                    "deg" => *n,
+                    "grad" => n * 0.9,
+                    "rad" => n * 180.0 / core::f64::consts::PI,
+                    "turn" => n * 360.0,
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            // 彩度・明度は % で書く（CSS Color 4 では単位の無い数値も % と同じ意味）
+            let percent = |value: &ComponentValue| match value {
+                ComponentValue::Percentage(n) | ComponentValue::Number(n, _) => Some(n / 100.0),
+                _ => None,
+            };
+            Some(Color::from_hsl(
+                hue,
+                percent(channels[1])?,
+                percent(channels[2])?,
+                alpha,
+            ))
+        }
+        _ => None,
+    }
+}
+
+// 長さのプロパティの値を読む
+// - 数式の関数（`calc(100% - 20px)` など）なら、型が長さの式を `check` でプロパティごとに確かめる
+// - それ以外は 1 つのコンポーネント値を `parse` で読む
+fn length_or_calc(
+    values: &[ComponentValue],
+    parse: fn(&ComponentValue) -> Option<PropertyValue>,
+    check: fn(CalcNode) -> Option<Length>,
+) -> Option<PropertyValue> {
+    if !is_math_function(values) {
+        return single(values, parse);
+    }
+    let node = calc::parse(values)?;
+    if node.calc_type()? != CalcType::Length {
+        return None;
+    }
+    check(node).map(PropertyValue::Length)
+}
+
+// margin: どんな長さの式でもよい
+fn calc_any(node: CalcNode) -> Option<Length> {
+    Some(Length::calc(node))
+}
+
+// padding / width / height: 負にならない式（式の値は読むときに決まらないので、使うときに 0 へ切り上げる）
+// https://www.w3.org/TR/css-values-4/#calc-range
+fn calc_non_negative(node: CalcNode) -> Option<Length> {
+    Some(Length::calc(CalcNode::Max(vec![
+        node,
+        CalcNode::Dimension(0.0, LengthUnit::Px),
+    ])))
+}
+
+// border-*-width: % を含まない、負にならない式
+fn calc_border_width(node: CalcNode) -> Option<Length> {
+    if node.has_unit(LengthUnit::Percent) {
+        return None;
+    }
+    calc_non_negative(node)
+}
+
+// letter-spacing / word-spacing: % を含まない式
+fn calc_without_percentage(node: CalcNode) -> Option<Length> {
+    if node.has_unit(LengthUnit::Percent) {
+        return None;
+    }
+    Some(Length::calc(node))
+}
+
+// font-size: vw / vh を含まない式（負の値は計算値で 0 にする）
+fn calc_font_size(node: CalcNode) -> Option<Length> {
+    if node.has_unit(LengthUnit::Vw) || node.has_unit(LengthUnit::Vh) {
+        return None;
+    }
+    Some(Length::calc(node))
+}
+
+// 長さを読む
+// - `10px` / `1.5em` などの単位付きの数値（Dimension）
+// - 単位の無い数値は px とみなす（本来は 0 以外はエラー。古いページ向けの互換のための簡易版）
+// https://www.w3.org/TR/css-values-4/#lengths
+fn parse_length(value: &ComponentValue) -> Option<Length> {
+    match value {
+        ComponentValue::Number(n, _) => Some(Length::px(*n)),
+        ComponentValue::Dimension(n, _, unit) => LengthUnit::from_str(unit)
+            .ok()
+            .map(|unit| Length::new(*n, unit)),
+        _ => None,
+    }
+}
+
+// 長さか % を読む（margin / padding / width / height）
+fn parse_length_percentage(value: &ComponentValue) -> Option<PropertyValue> {
+    let length = match value {
+        ComponentValue::Percentage(n) => Length::new(*n, LengthUnit::Percent),
+        _ => parse_length(value)?,
+    };
+    Some(PropertyValue::Length(length))
+}
+
+// margin-*: 長さ・% か `auto`（`auto` はレイアウトで余った幅に解決する）
+// https://www.w3.org/TR/css-box-3/#margin-physical
+fn parse_margin(value: &ComponentValue) -> Option<PropertyValue> {
+    match value {
+        ComponentValue::Ident(s) if s == "auto" => Some(PropertyValue::Auto),
+        _ => parse_length_percentage(value),
+    }
+}
+
+// padding や width のように負の値を許さない長さ・%
+fn parse_non_negative_length_percentage(value: &ComponentValue) -> Option<PropertyValue> {
+    match parse_length_percentage(value) {
+        Some(PropertyValue::Length(length)) if length.is_negative() => None,
+        length => length,
+    }
+}
+
+// width/height: 長さ・% か `auto`
+fn parse_size(value: &ComponentValue) -> Option<PropertyValue> {
+    match value {
+        ComponentValue::Ident(s) if s == "auto" => Some(PropertyValue::Auto),
+        _ => parse_non_negative_length_percentage(value),
+    }
+}
+
+// border-*-width: 長さか `thin` / `medium` / `thick`（1px / 3px / 5px として扱う）。% は使えない
+// https://www.w3.org/TR/css-backgrounds-3/#typedef-line-width
+fn parse_border_width(value: &ComponentValue) -> Option<PropertyValue> {
+    let length = match value {
+        ComponentValue::Ident(s) => match s.as_str() {
+            "thin" => Length::px(1.0),
+            "medium" => Length::px(3.0),
+            "thick" => Length::px(5.0),
+            _ => return None,
+        },
+        _ => parse_length(value)?,
+    };
+    if length.is_negative() {
+        return None;
+    }
+    Some(PropertyValue::Length(length))
+}
+
+// font-size
+// - 絶対サイズのキーワード（`medium` など）→ px
+// - `larger` / `smaller` → 親の 1.2 倍 / 1.2 分の 1（em として残し、計算値で親の大きさから決める）
+// - 長さ・% → em と % は親の font-size が基準（計算値で決める）。vw / vh は未対応
+// https://www.w3.org/TR/css-fonts-4/#font-size-prop
+fn parse_font_size(value: &ComponentValue) -> Option<PropertyValue> {
+    let length = match value {
+        ComponentValue::Ident(s) => match s.as_str() {
+            "larger" => Length::new(1.2, LengthUnit::Em),
+            "smaller" => Length::new(1.0 / 1.2, LengthUnit::Em),
+            _ => return FontSize::from_str(s).ok().map(PropertyValue::FontSize),
+        },
+        ComponentValue::Percentage(n) => Length::new(*n, LengthUnit::Percent),
+        _ => parse_length(value)?,
+    };
+    if length.is_negative() || length.has_unit(LengthUnit::Vw) || length.has_unit(LengthUnit::Vh) {
+        return None;
+    }
+    Some(PropertyValue::Length(length))
+}
+
+// font-weight: `normal` / `bold` / `bolder` / `lighter` か 1〜1000 の数値
+// https://www.w3.org/TR/css-fonts-4/#font-weight-prop
+fn parse_font_weight(value: &ComponentValue) -> Option<PropertyValue> {
+    match value {
+        ComponentValue::Ident(s) => match s.as_str() {
+            "normal" => Some(PropertyValue::FontWeight(FontWeight::NORMAL)),
+            "bold" => Some(PropertyValue::FontWeight(FontWeight::BOLD)),
+            "bolder" => Some(PropertyValue::Bolder),
+            "lighter" => Some(PropertyValue::Lighter),
+            _ => None,
+        },
+        ComponentValue::Number(n, _) if (1.0..=1000.0).contains(n) => {
+            Some(PropertyValue::FontWeight(FontWeight::new(*n as u16)))
+        }
+        _ => None,
+    }
+}
+
+// font-family: カンマ区切りのフォント名の候補
+// - 引用符付きの名前（`"Times New Roman"`）はそのまま
+// - 引用符なしの名前は識別子を空白 1 つでつなぐ（`Times New Roman` → "Times New Roman"）
+// - 総称ファミリ（`serif` / `sans-serif` / `monospace` など）は小文字にそろえる
+// https://www.w3.org/TR/css-fonts-4/#font-family-prop
+fn parse_font_family(values: &[ComponentValue]) -> Option<PropertyValue> {
+    let mut families = Vec::new();
+    for family in values.split(|v| *v == ComponentValue::Comma) {
+        let name = match family {
+            [ComponentValue::StringToken(name)] => name.clone(),
+            [] => return None,
+            idents => {
+                let mut words = Vec::new();
+                for v in idents {
+                    match v {
+                        ComponentValue::Ident(word) => words.push(word.as_str()),
+                        _ => return None,
+                    }
+                }
+                let name = words.join(" ");
+                match name.to_ascii_lowercase().as_str() {
+                    generic @ ("serif" | "sans-serif" | "monospace" | "cursive" | "fantasy"
+                    | "system-ui") => String::from(generic),
+                    _ => name,
+                }
+            }
+        };
+        families.push(name);
+    }
+    Some(PropertyValue::FontFamily(families))
+}
+
+// flex-grow / flex-shrink: 負でない数値
+fn parse_non_negative_number(value: &ComponentValue) -> Option<PropertyValue> {
+    match value {
+        ComponentValue::Number(n, _) if *n >= 0.0 => Some(PropertyValue::Number(*n)),
+        _ => None,
+    }
+}
+
+// line-height: `normal`・負でない数値（font-size に掛ける）・負でない長さか %、またはそれらの数式
+// https://www.w3.org/TR/css-inline-3/#line-height-property
+fn parse_line_height(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match values {
+        [ComponentValue::Ident(s)] if s == "normal" => Some(PropertyValue::Normal),
+        [value @ ComponentValue::Number(..)] => parse_non_negative_number(value),
+        _ if is_math_function(values) => {
+            let node = calc::parse(values)?;
+            match node.calc_type()? {
+                CalcType::Number => Some(PropertyValue::Number(
+                    node.evaluate(&|value, _| value).max(0.0),
+                )),
+                CalcType::Length => calc_non_negative(node).map(PropertyValue::Length),
+            }
+        }
+        [value] => parse_non_negative_length_percentage(value),
+        _ => None,
+    }
+}
+
+// letter-spacing / word-spacing: `normal` か長さ（負の値で詰めることもできる。% は未対応）
+// https://www.w3.org/TR/css-text-3/#spacing
+fn parse_spacing(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match values {
+        [ComponentValue::Ident(s)] if s == "normal" => Some(PropertyValue::Normal),
+        _ => length_or_calc(
+            values,
+            |v| parse_length(v).map(PropertyValue::Length),
+            calc_without_percentage,
+        ),
+    }
+}
+
+// 画像: `none` か `url(...)`（グラデーションなどの画像関数は未対応）
+// https://www.w3.org/TR/css-images-3/#image-values
+fn parse_image(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match values {
+        [ComponentValue::Ident(s)] if s == "none" => Some(PropertyValue::None),
+        [ComponentValue::Url(url)] => Some(PropertyValue::Url(url.clone())),
+        [ComponentValue::Function(name), ComponentValue::StringToken(url), ComponentValue::CloseParenthesis]
+            if name.eq_ignore_ascii_case("url") =>
+        {
+            Some(PropertyValue::Url(url.clone()))
+        }
+        _ => None,
+    }
+}
+
+// opacity: 数値か %、または数値になる数式（`calc(1 / 3)`）。0（透明）〜 1（不透明）に収める
+// https://www.w3.org/TR/css-color-4/#transparency
+fn parse_opacity(values: &[ComponentValue]) -> Option<PropertyValue> {
+    let opacity = match values {
+        [ComponentValue::Number(n, _)] => *n,
+        [ComponentValue::Percentage(n)] => n / 100.0,
+        _ if is_math_function(values) => {
+            let node = calc::parse(values)?;
+            if node.calc_type()? != CalcType::Number {
+                return None;
+            }
+            node.evaluate(&|value, _| value)
+        }
+        _ => return None,
+    };
+    Some(PropertyValue::Number(opacity.clamp(0.0, 1.0)))
+}
+
+/// 値をいちばん外側のカンマで分ける（`cubic-bezier(0, 0, 1, 1)` の中のカンマでは分けない）
+///
+/// - 空の項目（`1s, , 2s`）があれば None
+///
+/// 例: `color 1s, opacity 2s ease-in` → [`color 1s`, `opacity 2s ease-in`]
+pub fn comma_separated(values: &[ComponentValue]) -> Option<Vec<&[ComponentValue]>> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, value) in values.iter().enumerate() {
+        match value {
+            ComponentValue::Function(_) | ComponentValue::OpenParenthesis => depth += 1,
+            ComponentValue::CloseParenthesis => depth -= 1,
+            ComponentValue::Comma if depth == 0 => {
+                items.push(&values[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&values[start..]);
+    if items.iter().any(|item| item.is_empty()) {
+        return None;
+    }
+    Some(items)
+}
+
+// カンマ区切りの並び。それぞれを `parse` で読み、1 つでも読めなければ無効
+// https://www.w3.org/TR/css-values-4/#mult-comma
+fn comma_list(
+    values: &[ComponentValue],
+    parse: fn(&[ComponentValue]) -> Option<PropertyValue>,
+) -> Option<PropertyValue> {
+    let mut items = Vec::new();
+    for item in comma_separated(values)? {
+        items.push(parse(item)?);
+    }
+    Some(PropertyValue::List(items))
+}
+
+// 時間: `1s` / `200ms`（単位は必須。ミリ秒にする）
+// https://www.w3.org/TR/css-values-4/#time
+fn parse_time(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match values {
+        [ComponentValue::Dimension(n, _, unit)] => match unit.to_ascii_lowercase().as_str() {
+            "s" => Some(PropertyValue::Time(n * 1000.0)),
+            "ms" => Some(PropertyValue::Time(*n)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// `*-duration`: 負でない時間
+fn parse_duration(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match parse_time(values) {
+        Some(PropertyValue::Time(time)) if time < 0.0 => None,
+        time => time,
+    }
+}
+
+// イージング関数
+// - キーワード: `linear` / `ease` / `ease-in` / `ease-out` / `ease-in-out` / `step-start` / `step-end`
+// - `cubic-bezier(x1, y1, x2, y2)`（x は 0〜1）
+// - `steps(n)` / `steps(n, jump-start | jump-end | jump-none | jump-both | start | end)`
+// https://drafts.csswg.org/css-easing-1/#typedef-easing-function
+fn parse_timing_function(values: &[ComponentValue]) -> Option<PropertyValue> {
+    let timing_function = match values {
+        [ComponentValue::Ident(s)] => TimingFunction::from_str(s).ok()?,
+        [ComponentValue::Function(name), args @ .., ComponentValue::CloseParenthesis] => {
+            let args: Vec<&ComponentValue> = args
+                .iter()
+                .filter(|a| **a != ComponentValue::Comma)
+                .collect();
+            match (name.to_ascii_lowercase().as_str(), args.as_slice()) {
+                (
+                    "cubic-bezier",
+                    [ComponentValue::Number(x1, _), ComponentValue::Number(y1, _), ComponentValue::Number(x2, _), ComponentValue::Number(y2, _)],
+                ) if (0.0..=1.0).contains(x1) && (0.0..=1.0).contains(x2) => {
+                    TimingFunction::CubicBezier(*x1, *y1, *x2, *y2)
+                }
+                ("steps", [ComponentValue::Number(n, _), rest @ ..]) => {
+                    let position = match rest {
+                        [] => StepPosition::JumpEnd,
+                        [ComponentValue::Ident(s)] => StepPosition::from_str(s).ok()?,
+                        _ => return None,
+                    };
+                    // 段の数は 1 以上の整数（jump-none は 2 以上）
+                    let minimum = if position == StepPosition::JumpNone {
+                        2.0
+                    } else {
+                        1.0
+                    };
+                    if *n < minimum || *n != (*n as u32) as f64 {
+                        return None;
+                    }
+                    TimingFunction::Steps(*n as u32, position)
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(PropertyValue::TimingFunction(timing_function))
+}
+
+// CSS-wide キーワードと `default` は名前（custom-ident）に使えない
+// https://www.w3.org/TR/css-values-4/#custom-idents
+fn is_reserved_ident(s: &str) -> bool {
+    matches!(
+        s.to_ascii_lowercase().as_str(),
+        "inherit" | "initial" | "unset" | "default"
+    )
+}
+
+// transition-property: `none` か、プロパティ名と `all` のカンマ区切り（未対応のプロパティ名も書ける）
+// https://drafts.csswg.org/css-transitions-1/#transition-property-property
+fn parse_transition_property(values: &[ComponentValue]) -> Option<PropertyValue> {
+    if let [ComponentValue::Ident(s)] = values {
+        if s.eq_ignore_ascii_case("none") {
+            return Some(PropertyValue::None);
+        }
+    }
+    comma_list(values, |v| match v {
+        [ComponentValue::Ident(s)] if !s.eq_ignore_ascii_case("none") && !is_reserved_ident(s) => {
+            Some(PropertyValue::Ident(s.to_ascii_lowercase()))
+        }
+        _ => None,
+    })
+}
+
+// animation-name の 1 項目: `none` か名前（識別子か文字列。大文字小文字を区別する）
+// https://drafts.csswg.org/css-animations-1/#animation-name
+fn parse_animation_name(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match values {
+        [ComponentValue::Ident(s)] if s.eq_ignore_ascii_case("none") => Some(PropertyValue::None),
+        [ComponentValue::Ident(s)] if !is_reserved_ident(s) => {
+            Some(PropertyValue::Ident(s.clone()))
+        }
+        [ComponentValue::StringToken(s)] => Some(PropertyValue::Ident(s.clone())),
+        _ => None,
+    }
+}
+
+// animation-iteration-count の 1 項目: 負でない数値か `infinite`
+fn parse_iteration_count(values: &[ComponentValue]) -> Option<PropertyValue> {
+    match values {
+        [ComponentValue::Ident(s)] if s.eq_ignore_ascii_case("infinite") => {
+            Some(PropertyValue::Number(f64::INFINITY))
+        }
+        [ComponentValue::Number(n, _)] if *n >= 0.0 => Some(PropertyValue::Number(*n)),
+        _ => None,
+    }
+}
+
+fn parse_border_style(value: &ComponentValue) -> Option<PropertyValue> {
+    match value {
+        ComponentValue::Ident(s) => BorderStyle::from_str(s)
+            .ok()
+            .map(PropertyValue::BorderStyle),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssToken;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::css::token::NumericType;
+    use crate::renderer::layout::layout_object::LayoutSize;
+    use alloc::string::String;
+
+    fn declaration(property: &str, value: ComponentValue) -> Declaration {
+        let mut d = Declaration::new();
+        d.set_property(String::from(property));
+        d.set_value(value);
+        d
+    }
+
+    #[test]
+    fn test_registry_order() {
+        // PropertyId を添字に使うので、表の並びと一致していなければならない
+        for (i, p) in PROPERTIES.iter().enumerate() {
+            assert_eq!(p.id as usize, i, "{} is out of order", p.name);
+        }
+    }
+
+    #[test]
+    fn test_parse_declaration() {
+        let red = Color::from_name("red").expect("red should be supported");
+        assert_eq!(
+            parse_declaration(&declaration(
+                "color",
+                ComponentValue::Ident(String::from("red"))
+            )),
+            Some((
+                PropertyId::Color,
+                SpecifiedValue::Value(PropertyValue::Color(red))
+            ))
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "display",
+                ComponentValue::Ident(String::from("unset"))
+            )),
+            Some((PropertyId::Display, SpecifiedValue::Unset))
+        );
+
+        // 未対応の値・プロパティは無視される
+        assert_eq!(
+            parse_declaration(&declaration(
+                "display",
+                ComponentValue::Ident(String::from("flex"))
+            )),
+            None
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "margin",
+                ComponentValue::Number(1.0, NumericType::Integer)
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_box_model_declarations() {
+        assert_eq!(
+            parse_declaration(&declaration(
+                "border-top-width",
+                ComponentValue::Ident(String::from("thick"))
+            )),
+            Some((
+                PropertyId::BorderTopWidth,
+                SpecifiedValue::Value(PropertyValue::Length(Length::px(5.0)))
+            ))
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "border-left-color",
+                ComponentValue::Ident(String::from("currentColor"))
+            )),
+            Some((
+                PropertyId::BorderLeftColor,
+                SpecifiedValue::Value(PropertyValue::CurrentColor)
+            ))
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "width",
+                ComponentValue::Ident(String::from("auto"))
+            )),
+            Some((
+                PropertyId::Width,
+                SpecifiedValue::Value(PropertyValue::Auto)
+            ))
+        );
+
+        // margin は負の値を許すが、padding は許さない
+        assert_eq!(
+            parse_declaration(&declaration(
+                "margin-top",
+                ComponentValue::Number(-4.0, NumericType::Integer)
+            )),
+            Some((
+                PropertyId::MarginTop,
+                SpecifiedValue::Value(PropertyValue::Length(Length::px(-4.0)))
+            ))
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "padding-top",
+                ComponentValue::Number(-4.0, NumericType::Integer)
+            )),
+            None
+        );
+
+        // 単位付きの長さと %（border-width に % は使えない）
+        assert_eq!(
+            parse_declaration(&declaration(
+                "padding-left",
+                ComponentValue::Dimension(1.5, NumericType::Number, String::from("EM"))
+            )),
+            Some((
+                PropertyId::PaddingLeft,
+                SpecifiedValue::Value(PropertyValue::Length(Length::new(1.5, LengthUnit::Em)))
+            ))
+        );
+        assert_eq!(
+            parse_declaration(&declaration("width", ComponentValue::Percentage(50.0))),
+            Some((
+                PropertyId::Width,
+                SpecifiedValue::Value(PropertyValue::Length(Length::new(
+                    50.0,
+                    LengthUnit::Percent
+                )))
+            ))
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "border-top-width",
+                ComponentValue::Percentage(50.0)
+            )),
+            None
+        );
+        assert_eq!(
+            parse_declaration(&declaration(
+                "width",
+                ComponentValue::Dimension(1.0, NumericType::Integer, String::from("furlong"))
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_font_family() {
+        let mut d = Declaration::new();
+        d.set_property(String::from("font-family"));
+        d.set_values(vec![
+            ComponentValue::StringToken(String::from("Noto Sans")),
+            ComponentValue::Comma,
+            ComponentValue::Ident(String::from("Times")),
+            ComponentValue::Ident(String::from("New")),
+            ComponentValue::Ident(String::from("Roman")),
+            ComponentValue::Comma,
+            ComponentValue::Ident(String::from("Serif")),
+        ]);
+        assert_eq!(
+            parse_declaration(&d),
+            Some((
+                PropertyId::FontFamily,
+                SpecifiedValue::Value(PropertyValue::FontFamily(vec![
+                    String::from("Noto Sans"),
+                    String::from("Times New Roman"),
+                    String::from("serif"),
+                ]))
+            ))
+        );
+
+        // 空の候補（`a, , b`）があれば無効
+        d.set_values(vec![
+            ComponentValue::Ident(String::from("a")),
+            ComponentValue::Comma,
+            ComponentValue::Comma,
+            ComponentValue::Ident(String::from("b")),
+        ]);
+        assert_eq!(parse_declaration(&d), None);
+
+        // 値が 1 つのプロパティに複数の値があれば無効
+        d.set_property(String::from("color"));
+        d.set_values(vec![
+            ComponentValue::Ident(String::from("red")),
+            ComponentValue::Ident(String::from("blue")),
+        ]);
+        assert_eq!(parse_declaration(&d), None);
+    }
+
+    #[test]
+    fn test_parse_math_functions() {
+        fn parse(property: &str, css: &str) -> Option<PropertyValue> {
+            let values: Vec<ComponentValue> = CssTokenizer::new(String::from(css))
+                .filter(|t| *t != CssToken::Whitespace)
+                .collect();
+            (lookup(property)?.parse)(&values)
+        }
+        let viewport = LayoutSize::new(600, 400);
+        let px = |value: Option<PropertyValue>, base| match value {
+            Some(PropertyValue::Length(length)) => Some(length.to_px(base, viewport)),
+            _ => None,
+        };
+
+        assert_eq!(px(parse("width", "calc(100% - 20px)"), 300), Some(280));
+        assert_eq!(px(parse("margin-top", "min(10%, 5vw)"), 600), Some(30));
+        assert_eq!(
+            px(parse("margin-top", "calc(10px - 3 * 10px)"), 600),
+            Some(-20)
+        );
+        // 負にならないプロパティは、使うときに 0 へ切り上げる
+        assert_eq!(px(parse("padding-top", "calc(10px - 50%)"), 100), Some(0));
+        assert_eq!(px(parse("border-top-width", "max(1px, 0.5vw)"), 0), Some(3));
+        assert_eq!(
+            parse("opacity", "calc(1 / 4)"),
+            Some(PropertyValue::Number(0.25))
+        );
+
+        // 型が合わない式、プロパティで使えない単位を含む式は無効
+        assert_eq!(parse("width", "calc(100% - 20)"), None);
+        assert_eq!(parse("width", "calc(2 * 3)"), None);
+        assert_eq!(parse("border-top-width", "calc(50% + 1px)"), None);
+        assert_eq!(parse("font-size", "calc(1em + 1vw)"), None);
+        assert_eq!(parse("opacity", "calc(1px)"), None);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        // 値の文字列をトークンに分け、宣言の値と同じく空白を除いて読む
+        fn color(css: &str) -> Option<PropertyValue> {
+            let values: Vec<ComponentValue> = CssTokenizer::new(String::from(css))
+                .filter(|t| *t != CssToken::Whitespace)
+                .collect();
+            parse_color(&values)
+        }
+        let rgba = |r, g, b, a| Some(PropertyValue::Color(Color::rgba(r, g, b, a)));
+
+        assert_eq!(color("RebeccaPurple"), rgba(102, 51, 153, 1.0));
+        assert_eq!(color("transparent"), rgba(0, 0, 0, 0.0));
+        assert_eq!(color("currentcolor"), Some(PropertyValue::CurrentColor));
+        assert_eq!(color("#0f08"), rgba(0, 255, 0, 136.0 / 255.0));
+        assert_eq!(color("rgb(255, 128, 0)"), rgba(255, 128, 0, 1.0));
+        assert_eq!(color("rgba(255, 128, 0, 0.5)"), rgba(255, 128, 0, 0.5));
+        assert_eq!(color("rgb(100% 0% 50% / 25%)"), rgba(255, 0, 128, 0.25));
+        assert_eq!(color("hsl(120, 100%, 25%)"), rgba(0, 128, 0, 1.0));
+        assert_eq!(
+            color("hsla(0.5turn 100% 50% / 0.5)"),
+            rgba(0, 255, 255, 0.5)
+        );
+
+        // 引数の数や区切りが合わないもの・知らない関数は無効
+        assert_eq!(color("rgb(255, 0)"), None);
+        assert_eq!(color("rgb(255, 0 0)"), None);
+        assert_eq!(color("lab(50 0 0)"), None);
+        assert_eq!(color("#12345"), None);
+    }
+
+    #[test]
+    fn test_parse_animation_properties() {
+        fn parse(property: &str, css: &str) -> Option<PropertyValue> {
+            let values: Vec<ComponentValue> = CssTokenizer::new(String::from(css))
+                .filter(|t| *t != CssToken::Whitespace)
+                .collect();
+            (lookup(property)?.parse)(&values)
+        }
+        let list = |items: Vec<PropertyValue>| Some(PropertyValue::List(items));
+
+        assert_eq!(
+            parse("transition-duration", "1s, 200ms"),
+            list(vec![
+                PropertyValue::Time(1000.0),
+                PropertyValue::Time(200.0)
+            ])
+        );
+        assert_eq!(
+            parse("transition-delay", "-0.5s"),
+            list(vec![PropertyValue::Time(-500.0)])
+        );
+        assert_eq!(
+            parse("transition-property", "Color, all"),
+            list(vec![
+                PropertyValue::Ident(String::from("color")),
+                PropertyValue::Ident(String::from("all"))
+            ])
+        );
+        assert_eq!(
+            parse("transition-property", "none"),
+            Some(PropertyValue::None)
+        );
+        assert_eq!(
+            parse(
+                "transition-timing-function",
+                "cubic-bezier(0.1, -1, 0.9, 2), steps(3, start), ease-in"
+            ),
+            list(vec![
+                PropertyValue::TimingFunction(TimingFunction::CubicBezier(0.1, -1.0, 0.9, 2.0)),
+                PropertyValue::TimingFunction(TimingFunction::Steps(3, StepPosition::JumpStart)),
+                PropertyValue::TimingFunction(TimingFunction::EASE_IN),
+            ])
+        );
+        assert_eq!(
+            parse("animation-name", "none, \"fade in\", slide"),
+            list(vec![
+                PropertyValue::None,
+                PropertyValue::Ident(String::from("fade in")),
+                PropertyValue::Ident(String::from("slide"))
+            ])
+        );
+        assert_eq!(
+            parse("animation-iteration-count", "infinite, 2.5"),
+            list(vec![
+                PropertyValue::Number(f64::INFINITY),
+                PropertyValue::Number(2.5)
+            ])
+        );
+        assert_eq!(
+            parse("animation-direction", "alternate-reverse"),
+            list(vec![PropertyValue::AnimationDirection(
+                AnimationDirection::AlternateReverse
+            )])
+        );
+        assert_eq!(
+            parse("animation-fill-mode", "both, forwards"),
+            list(vec![
+                PropertyValue::AnimationFillMode(AnimationFillMode::Both),
+                PropertyValue::AnimationFillMode(AnimationFillMode::Forwards)
+            ])
+        );
+
+        // 単位の無い時間・負の長さ・範囲外の x・空の項目は無効
+        assert_eq!(parse("transition-duration", "1"), None);
+        assert_eq!(parse("transition-duration", "-1s"), None);
+        assert_eq!(parse("transition-duration", "1s,,2s"), None);
+        assert_eq!(
+            parse("transition-timing-function", "cubic-bezier(2, 0, 1, 1)"),
+            None
+        );
+        assert_eq!(
+            parse("transition-timing-function", "steps(1, jump-none)"),
+            None
+        );
+        assert_eq!(parse("transition-property", "color, none"), None);
+        assert_eq!(parse("animation-name", "initial"), None);
+        assert_eq!(parse("animation-iteration-count", "-1"), None);
+    }
+
+    #[test]
+    fn test_parse_text_properties() {
+        fn parse(property: &str, css: &str) -> Option<PropertyValue> {
+            let values: Vec<ComponentValue> = CssTokenizer::new(String::from(css))
+                .filter(|t| *t != CssToken::Whitespace)
+                .collect();
+            (lookup(property)?.parse)(&values)
+        }
+
+        assert_eq!(
+            parse("text-align", "end"),
+            Some(PropertyValue::TextAlign(TextAlign::Right))
+        );
+        assert_eq!(
+            parse("white-space", "pre-wrap"),
+            Some(PropertyValue::WhiteSpace(WhiteSpace::PreWrap))
+        );
+        assert_eq!(
+            parse("text-transform", "uppercase"),
+            Some(PropertyValue::TextTransform(TextTransform::Uppercase))
+        );
+        assert_eq!(parse("line-height", "normal"), Some(PropertyValue::Normal));
+        assert_eq!(
+            parse("line-height", "1.5"),
+            Some(PropertyValue::Number(1.5))
+        );
+        assert_eq!(
+            parse("line-height", "150%"),
+            Some(PropertyValue::Length(Length::new(
+                150.0,
+                LengthUnit::Percent
+            )))
+        );
+        assert_eq!(
+            parse("letter-spacing", "-1px"),
+            Some(PropertyValue::Length(Length::px(-1.0)))
+        );
+        assert_eq!(parse("word-spacing", "normal"), Some(PropertyValue::Normal));
+        assert_eq!(
+            parse("text-indent", "10%"),
+            Some(PropertyValue::Length(Length::new(
+                10.0,
+                LengthUnit::Percent
+            )))
+        );
+
+        // 負の行の高さ・% の字間・知らないキーワードは無効
+        assert_eq!(parse("line-height", "-1"), None);
+        assert_eq!(parse("letter-spacing", "10%"), None);
+        assert_eq!(parse("text-align", "middle"), None);
+    }
+}
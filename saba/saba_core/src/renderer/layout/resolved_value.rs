@@ -0,0 +1,386 @@
+//! 解決値（resolved value）— `getComputedStyle()` がスクリプトに返す値（初心者向け）
+//!
+//! 役割
+//! - レイアウト済みの `LayoutObject` から、プロパティの値を CSS の文字列にして返します。
+//! - 多くのプロパティは計算値（`ComputedStyle`）をそのまま書き出します。
+//!   - 色は `rgb(r, g, b)`（半透明なら `rgba(r, g, b, a)`）。`currentColor` も実際の色にする
+//!   - 長さは px（`2em` → `32px`）。% や vw/vh が残っていればそのまま（`50%`、`calc(100% - 20px)`）
+//! - 次のプロパティはレイアウトで決まった値（使用値）を px で返します（仕様で決まっている）。
+//!   - `width` / `height`（`box-sizing` に合わせてコンテンツボックスか border ボックスの大きさ）
+//!   - `margin-*` / `padding-*` / `border-*-width`
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - TS の `getComputedStyle(el).getPropertyValue("color")` の中身にあたります。
+//!
+//! 仕様: https://drafts.csswg.org/cssom/#resolved-values
+use crate::renderer::css::serialize::serialize_component_values;
+use crate::renderer::layout::animation::AnimationDirection;
+use crate::renderer::layout::animation::AnimationFillMode;
+use crate::renderer::layout::animation::StepPosition;
+use crate::renderer::layout::animation::TimingFunction;
+use crate::renderer::layout::calc::CalcNode;
+use crate::renderer::layout::computed_style::BorderStyle;
+use crate::renderer::layout::computed_style::BoxSizing;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::DisplayType;
+use crate::renderer::layout::computed_style::FontStyle;
+use crate::renderer::layout::computed_style::Length;
+use crate::renderer::layout::computed_style::LengthUnit;
+use crate::renderer::layout::computed_style::ListStylePosition;
+use crate::renderer::layout::computed_style::ListStyleType;
+use crate::renderer::layout::computed_style::TextAlign;
+use crate::renderer::layout::computed_style::TextDecoration;
+use crate::renderer::layout::computed_style::TextDecorationStyle;
+use crate::renderer::layout::computed_style::TextTransform;
+use crate::renderer::layout::computed_style::WhiteSpace;
+use crate::renderer::layout::layout_object::LayoutObject;
+use crate::renderer::layout::property::lookup;
+use crate::renderer::layout::property::PropertyId;
+use crate::renderer::layout::property::PropertyValue;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// `object` のプロパティ `name`（`background-color` / `--brand` など）の解決値
+///
+/// - 対応していないプロパティ名、値の無いカスタムプロパティなら None（スクリプトには空文字列）
+pub fn resolved_value(object: &LayoutObject, name: &str) -> Option<String> {
+    let style = object.style();
+    if name.starts_with("--") {
+        return style
+            .custom_property(name)
+            .map(|values| serialize_component_values(&values));
+    }
+
+    let id = lookup(name)?.id;
+    let px = |value: i64| format!("{}px", value);
+    let used = match id {
+        PropertyId::Width | PropertyId::Height => {
+            let size = match style.box_sizing() {
+                BoxSizing::ContentBox => object.content_size(),
+                BoxSizing::BorderBox => object.size(),
+            };
+            if id == PropertyId::Width {
+                Some(px(size.width()))
+            } else {
+                Some(px(size.height()))
+            }
+        }
+        PropertyId::MarginTop => Some(px(object.margin().top())),
+        PropertyId::MarginRight => Some(px(object.margin().right())),
+        PropertyId::MarginBottom => Some(px(object.margin().bottom())),
+        PropertyId::MarginLeft => Some(px(object.margin().left())),
+        PropertyId::PaddingTop => Some(px(object.padding().top())),
+        PropertyId::PaddingRight => Some(px(object.padding().right())),
+        PropertyId::PaddingBottom => Some(px(object.padding().bottom())),
+        PropertyId::PaddingLeft => Some(px(object.padding().left())),
+        PropertyId::BorderTopWidth => Some(px(object.border().top())),
+        PropertyId::BorderRightWidth => Some(px(object.border().right())),
+        PropertyId::BorderBottomWidth => Some(px(object.border().bottom())),
+        PropertyId::BorderLeftWidth => Some(px(object.border().left())),
+        _ => None,
+    };
+    if used.is_some() {
+        return used;
+    }
+
+    let value = match style.get(id) {
+        // `currentColor` は同じ要素の color
+        PropertyValue::CurrentColor => serialize_color(style.color()),
+        value => serialize_value(value),
+    };
+    Some(value)
+}
+
+/// 計算値を CSS の文字列にする
+pub fn serialize_value(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::Color(color) => serialize_color(*color),
+        PropertyValue::Display(display) => match display {
+            DisplayType::Block => "block",
+            DisplayType::Inline => "inline",
+            DisplayType::DisplayNone => "none",
+        }
+        .to_string(),
+        PropertyValue::FontSize(size) => format!("{}px", size.px()),
+        PropertyValue::TextDecoration(decoration) => match decoration {
+            TextDecoration::None => "none",
+            TextDecoration::Underline => "underline",
+            TextDecoration::Overline => "overline",
+            TextDecoration::LineThrough => "line-through",
+        }
+        .to_string(),
+        PropertyValue::Length(length) => serialize_length(length),
+        PropertyValue::Auto => "auto".to_string(),
+        PropertyValue::CurrentColor => "currentcolor".to_string(),
+        PropertyValue::BorderStyle(style) => match style {
+            BorderStyle::None => "none",
+            BorderStyle::Hidden => "hidden",
+            BorderStyle::Dotted => "dotted",
+            BorderStyle::Dashed => "dashed",
+            BorderStyle::Solid => "solid",
+            BorderStyle::Double => "double",
+            BorderStyle::Groove => "groove",
+            BorderStyle::Ridge => "ridge",
+            BorderStyle::Inset => "inset",
+            BorderStyle::Outset => "outset",
+        }
+        .to_string(),
+        PropertyValue::BoxSizing(box_sizing) => match box_sizing {
+            BoxSizing::ContentBox => "content-box",
+            BoxSizing::BorderBox => "border-box",
+        }
+        .to_string(),
+        // 空白などを含む名前は引用符で囲む（`"Times New Roman", serif`）
+        PropertyValue::FontFamily(families) => families
+            .iter()
+            .map(|family| {
+                if family.chars().all(|c| c.is_alphanumeric() || c == '-') {
+                    family.clone()
+                } else {
+                    format!("\"{}\"", family)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(", "),
+        PropertyValue::FontStyle(style) => match style {
+            FontStyle::Normal => "normal",
+            FontStyle::Italic => "italic",
+            FontStyle::Oblique => "oblique",
+        }
+        .to_string(),
+        PropertyValue::FontWeight(weight) => format!("{}", weight.value()),
+        PropertyValue::Bolder => "bolder".to_string(),
+        PropertyValue::Lighter => "lighter".to_string(),
+        PropertyValue::Number(number) if number.is_infinite() => "infinite".to_string(),
+        PropertyValue::Number(number) => format!("{}", number),
+        PropertyValue::TextDecorationStyle(style) => match style {
+            TextDecorationStyle::Solid => "solid",
+            TextDecorationStyle::Double => "double",
+            TextDecorationStyle::Dotted => "dotted",
+            TextDecorationStyle::Dashed => "dashed",
+            TextDecorationStyle::Wavy => "wavy",
+        }
+        .to_string(),
+        PropertyValue::ListStyleType(list_style_type) => match list_style_type {
+            ListStyleType::None => "none",
+            ListStyleType::Disc => "disc",
+            ListStyleType::Circle => "circle",
+            ListStyleType::Square => "square",
+            ListStyleType::Decimal => "decimal",
+            ListStyleType::LowerAlpha => "lower-alpha",
+            ListStyleType::UpperAlpha => "upper-alpha",
+            ListStyleType::LowerRoman => "lower-roman",
+            ListStyleType::UpperRoman => "upper-roman",
+        }
+        .to_string(),
+        PropertyValue::ListStylePosition(position) => match position {
+            ListStylePosition::Inside => "inside",
+            ListStylePosition::Outside => "outside",
+        }
+        .to_string(),
+        PropertyValue::Url(url) => format!("url(\"{}\")", url),
+        PropertyValue::None => "none".to_string(),
+        PropertyValue::List(items) => items
+            .iter()
+            .map(serialize_value)
+            .collect::<Vec<String>>()
+            .join(", "),
+        // 時間は秒で表す（`200ms` → `0.2s`）
+        PropertyValue::Time(ms) => format!("{}s", ms / 1000.0),
+        PropertyValue::Ident(name) => name.clone(),
+        PropertyValue::TimingFunction(timing_function) => {
+            serialize_timing_function(timing_function)
+        }
+        PropertyValue::AnimationDirection(direction) => match direction {
+            AnimationDirection::Normal => "normal",
+            AnimationDirection::Reverse => "reverse",
+            AnimationDirection::Alternate => "alternate",
+            AnimationDirection::AlternateReverse => "alternate-reverse",
+        }
+        .to_string(),
+        PropertyValue::TextAlign(text_align) => match text_align {
+            TextAlign::Left => "left",
+            TextAlign::Right => "right",
+            TextAlign::Center => "center",
+            TextAlign::Justify => "justify",
+        }
+        .to_string(),
+        PropertyValue::WhiteSpace(white_space) => match white_space {
+            WhiteSpace::Normal => "normal",
+            WhiteSpace::Pre => "pre",
+            WhiteSpace::PreWrap => "pre-wrap",
+            WhiteSpace::NoWrap => "nowrap",
+            WhiteSpace::PreLine => "pre-line",
+        }
+        .to_string(),
+        PropertyValue::TextTransform(text_transform) => match text_transform {
+            TextTransform::None => "none",
+            TextTransform::Capitalize => "capitalize",
+            TextTransform::Uppercase => "uppercase",
+            TextTransform::Lowercase => "lowercase",
+        }
+        .to_string(),
+        PropertyValue::Normal => "normal".to_string(),
+        PropertyValue::AnimationFillMode(fill_mode) => match fill_mode {
+            AnimationFillMode::None => "none",
+            AnimationFillMode::Forwards => "forwards",
+            AnimationFillMode::Backwards => "backwards",
+            AnimationFillMode::Both => "both",
+        }
+        .to_string(),
+    }
+}
+
+// キーワードで書ける関数はキーワードにする（`cubic-bezier(0.25, 0.1, 0.25, 1)` → `ease`）
+// https://drafts.csswg.org/css-easing-1/#serialization
+fn serialize_timing_function(timing_function: &TimingFunction) -> String {
+    let keywords = [
+        (TimingFunction::EASE, "ease"),
+        (TimingFunction::EASE_IN, "ease-in"),
+        (TimingFunction::EASE_OUT, "ease-out"),
+        (TimingFunction::EASE_IN_OUT, "ease-in-out"),
+    ];
+    if let Some((_, keyword)) = keywords.iter().find(|(f, _)| f == timing_function) {
+        return keyword.to_string();
+    }
+    match *timing_function {
+        TimingFunction::Linear => "linear".to_string(),
+        TimingFunction::CubicBezier(x1, y1, x2, y2) => {
+            format!("cubic-bezier({}, {}, {}, {})", x1, y1, x2, y2)
+        }
+        TimingFunction::Steps(steps, position) => match position {
+            StepPosition::JumpEnd => format!("steps({})", steps),
+            StepPosition::JumpStart => format!("steps({}, start)", steps),
+            StepPosition::JumpNone => format!("steps({}, jump-none)", steps),
+            StepPosition::JumpBoth => format!("steps({}, jump-both)", steps),
+        },
+    }
+}
+
+/// 色を `rgb(255, 0, 0)` / `rgba(255, 0, 0, 0.5)` にする
+/// https://www.w3.org/TR/css-color-4/#serializing-sRGB-values
+pub fn serialize_color(color: Color) -> String {
+    if color.alpha() == 1.0 {
+        format!("rgb({}, {}, {})", color.red(), color.green(), color.blue())
+    } else {
+        format!(
+            "rgba({}, {}, {}, {})",
+            color.red(),
+            color.green(),
+            color.blue(),
+            color.alpha()
+        )
+    }
+}
+
+fn serialize_length(length: &Length) -> String {
+    match length {
+        Length::Dimension(value, unit) => format!("{}{}", value, unit_name(*unit)),
+        Length::Calc(node) => format!("calc({})", serialize_calc(node)),
+    }
+}
+
+fn unit_name(unit: LengthUnit) -> &'static str {
+    match unit {
+        LengthUnit::Px => "px",
+        LengthUnit::Pt => "pt",
+        LengthUnit::Em => "em",
+        LengthUnit::Rem => "rem",
+        LengthUnit::Ch => "ch",
+        LengthUnit::Vw => "vw",
+        LengthUnit::Vh => "vh",
+        LengthUnit::Percent => "%",
+    }
+}
+
+// 数式の木を書き戻す（演算は常に括弧で囲む簡易版。`calc()` の外側は呼び出し側が付ける）
+fn serialize_calc(node: &CalcNode) -> String {
+    let list = |nodes: &[CalcNode]| {
+        nodes
+            .iter()
+            .map(serialize_calc)
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    match node {
+        CalcNode::Number(value) => format!("{}", value),
+        CalcNode::Dimension(value, unit) => format!("{}{}", value, unit_name(*unit)),
+        CalcNode::Add(a, b) => format!("{} + {}", serialize_calc(a), serialize_operand(b)),
+        CalcNode::Sub(a, b) => format!("{} - {}", serialize_calc(a), serialize_operand(b)),
+        CalcNode::Mul(a, b) => format!("{} * {}", serialize_operand(a), serialize_operand(b)),
+        CalcNode::Div(a, b) => format!("{} / {}", serialize_operand(a), serialize_operand(b)),
+        CalcNode::Min(nodes) => format!("min({})", list(nodes)),
+        CalcNode::Max(nodes) => format!("max({})", list(nodes)),
+        CalcNode::Clamp(min, value, max) => format!(
+            "clamp({}, {}, {})",
+            serialize_calc(min),
+            serialize_calc(value),
+            serialize_calc(max)
+        ),
+    }
+}
+
+// 演算の右辺や `*` `/` の項。足し算・引き算なら括弧で囲む
+fn serialize_operand(node: &CalcNode) -> String {
+    match node {
+        CalcNode::Add(..) | CalcNode::Sub(..) => format!("({})", serialize_calc(node)),
+        _ => serialize_calc(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::layout::computed_style::FontSize;
+    use crate::renderer::layout::computed_style::FontWeight;
+    use alloc::boxed::Box;
+    use alloc::vec;
+
+    #[test]
+    fn test_serialize_value() {
+        assert_eq!(
+            serialize_value(&PropertyValue::Color(Color::rgb(255, 0, 0))),
+            "rgb(255, 0, 0)"
+        );
+        assert_eq!(
+            serialize_value(&PropertyValue::Color(Color::rgba(0, 0, 255, 0.5))),
+            "rgba(0, 0, 255, 0.5)"
+        );
+        assert_eq!(
+            serialize_value(&PropertyValue::FontSize(FontSize::new(24.0))),
+            "24px"
+        );
+        assert_eq!(
+            serialize_value(&PropertyValue::FontWeight(FontWeight::new(700))),
+            "700"
+        );
+        assert_eq!(
+            serialize_value(&PropertyValue::FontFamily(vec![
+                "Times New Roman".to_string(),
+                "serif".to_string()
+            ])),
+            "\"Times New Roman\", serif"
+        );
+        assert_eq!(
+            serialize_value(&PropertyValue::Length(Length::new(
+                50.0,
+                LengthUnit::Percent
+            ))),
+            "50%"
+        );
+        // calc(100% - (20px + 1vw))
+        let calc = CalcNode::Sub(
+            Box::new(CalcNode::Dimension(100.0, LengthUnit::Percent)),
+            Box::new(CalcNode::Add(
+                Box::new(CalcNode::Dimension(20.0, LengthUnit::Px)),
+                Box::new(CalcNode::Dimension(1.0, LengthUnit::Vw)),
+            )),
+        );
+        assert_eq!(
+            serialize_value(&PropertyValue::Length(Length::calc(calc))),
+            "calc(100% - (20px + 1vw))"
+        );
+    }
+}
@@ -0,0 +1,1039 @@
+//! ショートハンド（一括指定）のプロパティをロングハンドに展開する
+//!
+//! 役割
+//! - `margin: 8px 16px` のような一括指定を、`margin-top: 8px` などのロングハンドの宣言に読み替えます。
+//!   ComputedStyle が持つのはロングハンドだけなので、カスケードの前（宣言を読むとき）に展開します。
+//! - 値に書かれなかった部分は、そのロングハンドの初期値に戻します（`border: solid` の幅は `medium`）。
+//!   https://www.w3.org/TR/css-cascade-4/#shorthand
+//! - `inherit` / `initial` / `unset` は、すべてのロングハンドに同じキーワードを指定したことになります。
+//! - `var()` を含む値は、計算値を決めるときに置き換えてから展開します（`SpecifiedValue::PendingShorthand`）。
+//!
+//! 例
+//! ```text
+//! margin: 8px 16px          → margin-top/bottom: 8px、margin-right/left: 16px
+//! border-top: 2px red      → border-top-width: 2px、border-top-style: none（初期値）、border-top-color: red
+//! font: italic bold 12px/1.5 serif
+//!                           → font-style: italic、font-weight: bold、font-size: 12px、line-height: 1.5、
+//!                             font-family: serif
+//! ```
+//!
+//! 簡易化している点
+//! - ロングハンドを持たない部分（`background-image`、`font-variant` など）は、
+//!   書き方が正しいかだけ確かめて値は捨てます。
+//! - `font` のシステムフォント（`caption` / `menu` など）は未対応。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `SHORTHANDS` は `PROPERTIES` と同じ定数表。`longhands` はそのショートハンドが決めるロングハンドの一覧です。
+//!
+//! 仕様: https://www.w3.org/TR/css-cascade-4/#shorthand
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::layout::custom_property::contains_var;
+use crate::renderer::layout::property::comma_separated;
+use crate::renderer::layout::property::parse_declaration;
+use crate::renderer::layout::property::PropertyId;
+use crate::renderer::layout::property::PropertyValue;
+use crate::renderer::layout::property::Side;
+use crate::renderer::layout::property::SpecifiedValue;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 展開したロングハンドとその値
+pub type Longhands = Vec<(PropertyId, PropertyValue)>;
+
+/// 表の 1 項目
+pub struct ShorthandDefinition {
+    pub name: &'static str,
+    /// 展開先のロングハンド（値に書かれなかったものは初期値になる）
+    pub longhands: &'static [PropertyId],
+    /// 値（空白を除き、`var()` を置き換え済みのコンポーネント値の列）を、ロングハンドの値に展開する。
+    /// 書かれた部分だけを返す。読めない値なら None（その宣言は無視される）
+    pub expand: fn(&[ComponentValue]) -> Option<Longhands>,
+}
+
+const MARGIN: [PropertyId; 4] = [
+    PropertyId::MarginTop,
+    PropertyId::MarginRight,
+    PropertyId::MarginBottom,
+    PropertyId::MarginLeft,
+];
+const PADDING: [PropertyId; 4] = [
+    PropertyId::PaddingTop,
+    PropertyId::PaddingRight,
+    PropertyId::PaddingBottom,
+    PropertyId::PaddingLeft,
+];
+const BORDER_WIDTH: [PropertyId; 4] = [
+    PropertyId::BorderTopWidth,
+    PropertyId::BorderRightWidth,
+    PropertyId::BorderBottomWidth,
+    PropertyId::BorderLeftWidth,
+];
+const BORDER_STYLE: [PropertyId; 4] = [
+    PropertyId::BorderTopStyle,
+    PropertyId::BorderRightStyle,
+    PropertyId::BorderBottomStyle,
+    PropertyId::BorderLeftStyle,
+];
+const BORDER_COLOR: [PropertyId; 4] = [
+    PropertyId::BorderTopColor,
+    PropertyId::BorderRightColor,
+    PropertyId::BorderBottomColor,
+    PropertyId::BorderLeftColor,
+];
+const BORDER: [PropertyId; 12] = [
+    PropertyId::BorderTopWidth,
+    PropertyId::BorderRightWidth,
+    PropertyId::BorderBottomWidth,
+    PropertyId::BorderLeftWidth,
+    PropertyId::BorderTopStyle,
+    PropertyId::BorderRightStyle,
+    PropertyId::BorderBottomStyle,
+    PropertyId::BorderLeftStyle,
+    PropertyId::BorderTopColor,
+    PropertyId::BorderRightColor,
+    PropertyId::BorderBottomColor,
+    PropertyId::BorderLeftColor,
+];
+
+// 1 つの層の中で試す順でもある（最初の時間は duration、2 つ目は delay）
+// https://drafts.csswg.org/css-transitions-1/#transition-shorthand-property
+const TRANSITION: [PropertyId; 4] = [
+    PropertyId::TransitionDuration,
+    PropertyId::TransitionTimingFunction,
+    PropertyId::TransitionDelay,
+    PropertyId::TransitionProperty,
+];
+// 名前は最後に試す（`ease` や `infinite` はキーワードとして読み、名前にはしない）
+// https://drafts.csswg.org/css-animations-1/#animation
+const ANIMATION: [PropertyId; 7] = [
+    PropertyId::AnimationDuration,
+    PropertyId::AnimationTimingFunction,
+    PropertyId::AnimationDelay,
+    PropertyId::AnimationIterationCount,
+    PropertyId::AnimationDirection,
+    PropertyId::AnimationFillMode,
+    PropertyId::AnimationName,
+];
+
+/// 対応しているショートハンドの表
+pub static SHORTHANDS: [ShorthandDefinition; 17] = [
+    ShorthandDefinition {
+        name: "margin",
+        longhands: &MARGIN,
+        expand: |v| box_sides(v, PropertyId::margin),
+    },
+    ShorthandDefinition {
+        name: "padding",
+        longhands: &PADDING,
+        expand: |v| box_sides(v, PropertyId::padding),
+    },
+    ShorthandDefinition {
+        name: "border-width",
+        longhands: &BORDER_WIDTH,
+        expand: |v| box_sides(v, PropertyId::border_width),
+    },
+    ShorthandDefinition {
+        name: "border-style",
+        longhands: &BORDER_STYLE,
+        expand: |v| box_sides(v, PropertyId::border_style),
+    },
+    ShorthandDefinition {
+        name: "border-color",
+        longhands: &BORDER_COLOR,
+        expand: |v| box_sides(v, PropertyId::border_color),
+    },
+    ShorthandDefinition {
+        name: "border-top",
+        longhands: &[
+            PropertyId::BorderTopWidth,
+            PropertyId::BorderTopStyle,
+            PropertyId::BorderTopColor,
+        ],
+        expand: |v| border_side(v, Side::Top),
+    },
+    ShorthandDefinition {
+        name: "border-right",
+        longhands: &[
+            PropertyId::BorderRightWidth,
+            PropertyId::BorderRightStyle,
+            PropertyId::BorderRightColor,
+        ],
+        expand: |v| border_side(v, Side::Right),
+    },
+    ShorthandDefinition {
+        name: "border-bottom",
+        longhands: &[
+            PropertyId::BorderBottomWidth,
+            PropertyId::BorderBottomStyle,
+            PropertyId::BorderBottomColor,
+        ],
+        expand: |v| border_side(v, Side::Bottom),
+    },
+    ShorthandDefinition {
+        name: "border-left",
+        longhands: &[
+            PropertyId::BorderLeftWidth,
+            PropertyId::BorderLeftStyle,
+            PropertyId::BorderLeftColor,
+        ],
+        expand: |v| border_side(v, Side::Left),
+    },
+    ShorthandDefinition {
+        name: "border",
+        longhands: &BORDER,
+        expand: expand_border,
+    },
+    ShorthandDefinition {
+        name: "background",
+        longhands: &[PropertyId::BackgroundColor],
+        expand: expand_background,
+    },
+    ShorthandDefinition {
+        name: "font",
+        longhands: &[
+            PropertyId::FontStyle,
+            PropertyId::FontWeight,
+            PropertyId::FontSize,
+            PropertyId::LineHeight,
+            PropertyId::FontFamily,
+        ],
+        expand: expand_font,
+    },
+    ShorthandDefinition {
+        name: "list-style",
+        longhands: &[
+            PropertyId::ListStylePosition,
+            PropertyId::ListStyleImage,
+            PropertyId::ListStyleType,
+        ],
+        expand: expand_list_style,
+    },
+    ShorthandDefinition {
+        name: "flex",
+        longhands: &[
+            PropertyId::FlexGrow,
+            PropertyId::FlexShrink,
+            PropertyId::FlexBasis,
+        ],
+        expand: expand_flex,
+    },
+    ShorthandDefinition {
+        name: "text-decoration",
+        longhands: &[
+            PropertyId::TextDecorationLine,
+            PropertyId::TextDecorationStyle,
+            PropertyId::TextDecorationColor,
+        ],
+        expand: |v| {
+            any_order(
+                &components(v)?,
+                &[
+                    PropertyId::TextDecorationLine,
+                    PropertyId::TextDecorationStyle,
+                    PropertyId::TextDecorationColor,
+                ],
+            )
+        },
+    },
+    ShorthandDefinition {
+        name: "transition",
+        longhands: &TRANSITION,
+        expand: |v| layers(v, &TRANSITION),
+    },
+    ShorthandDefinition {
+        name: "animation",
+        longhands: &ANIMATION,
+        expand: |v| layers(v, &ANIMATION),
+    },
+];
+
+/// ショートハンドの名前から表の項目を探す（ショートハンドでなければ None）
+pub fn lookup(name: &str) -> Option<&'static ShorthandDefinition> {
+    SHORTHANDS.iter().find(|s| s.name == name)
+}
+
+/// 宣言 1 つを、(ロングハンド, 指定値) の列に読み替える
+///
+/// - ロングハンドの宣言なら `property::parse_declaration` と同じ（1 つだけ）
+/// - ショートハンドなら、そのすべてのロングハンド（書かれなかったものは初期値）
+/// - 未対応のプロパティや読めない値なら空（呼び出し側はその宣言を無視する）
+///
+/// 例: `padding: 1px 2px` → [(PaddingTop, 1px), (PaddingRight, 2px), (PaddingBottom, 1px), (PaddingLeft, 2px)]
+pub fn expand_declaration(declaration: &Declaration) -> Vec<(PropertyId, SpecifiedValue)> {
+    let shorthand = match lookup(&declaration.property) {
+        Some(shorthand) => shorthand,
+        None => return parse_declaration(declaration).into_iter().collect(),
+    };
+    if contains_var(&declaration.values) {
+        return shorthand
+            .longhands
+            .iter()
+            .map(|id| {
+                let value =
+                    SpecifiedValue::PendingShorthand(shorthand.name, declaration.values.clone());
+                (*id, value)
+            })
+            .collect();
+    }
+    expand(shorthand, &declaration.values).unwrap_or_default()
+}
+
+/// ショートハンドの値を展開する（`var()` は置き換え済みであること）
+///
+/// - CSS-wide キーワード（`inherit` など）はすべてのロングハンドに同じものを指定する
+/// - 読めない値なら None
+pub fn expand(
+    shorthand: &ShorthandDefinition,
+    values: &[ComponentValue],
+) -> Option<Vec<(PropertyId, SpecifiedValue)>> {
+    if let [ComponentValue::Ident(keyword)] = values {
+        let keyword = match keyword.as_str() {
+            "inherit" => Some(SpecifiedValue::Inherit),
+            "initial" => Some(SpecifiedValue::Initial),
+            "unset" => Some(SpecifiedValue::Unset),
+            _ => None,
+        };
+        if let Some(keyword) = keyword {
+            return Some(
+                shorthand
+                    .longhands
+                    .iter()
+                    .map(|id| (*id, keyword.clone()))
+                    .collect(),
+            );
+        }
+    }
+
+    let specified = (shorthand.expand)(values)?;
+    Some(
+        shorthand
+            .longhands
+            .iter()
+            .map(|id| {
+                let value = match specified.iter().find(|(longhand, _)| longhand == id) {
+                    Some((_, value)) => value.clone(),
+                    None => (id.definition().initial)(),
+                };
+                (*id, SpecifiedValue::Value(value))
+            })
+            .collect(),
+    )
+}
+
+// 値を空白区切りの成分に分ける（`rgb(0, 0, 0)` や `calc(1px + 2px)` のような関数は 1 つの成分）
+// 括弧の対応が取れていなければ None
+fn components(values: &[ComponentValue]) -> Option<Vec<&[ComponentValue]>> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        let start = i;
+        if matches!(
+            values[i],
+            ComponentValue::Function(_) | ComponentValue::OpenParenthesis
+        ) {
+            let mut depth = 0;
+            loop {
+                match values.get(i)? {
+                    ComponentValue::Function(_) | ComponentValue::OpenParenthesis => depth += 1,
+                    ComponentValue::CloseParenthesis => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+        i += 1;
+        result.push(&values[start..i]);
+    }
+    Some(result)
+}
+
+// 1 つの成分をロングハンド `id` の値として読む
+fn parse_longhand(id: PropertyId, component: &[ComponentValue]) -> Option<PropertyValue> {
+    (id.definition().parse)(component)
+}
+
+// 上・右・下・左の 4 辺（1〜4 個の値）
+// - 1 個: 4 辺とも同じ / 2 個: 上下・左右 / 3 個: 上・左右・下 / 4 個: 上・右・下・左
+// https://www.w3.org/TR/css-box-4/#margin-shorthand
+fn box_sides(values: &[ComponentValue], longhand: fn(Side) -> PropertyId) -> Option<Longhands> {
+    let components = components(values)?;
+    let mut sides = Vec::new();
+    for component in &components {
+        sides.push(parse_longhand(longhand(Side::Top), component)?);
+    }
+    let [top, right, bottom, left] = match sides.as_slice() {
+        [all] => [all, all, all, all],
+        [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+        [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+        [top, right, bottom, left] => [top, right, bottom, left],
+        _ => return None,
+    };
+    Some(vec![
+        (longhand(Side::Top), top.clone()),
+        (longhand(Side::Right), right.clone()),
+        (longhand(Side::Bottom), bottom.clone()),
+        (longhand(Side::Left), left.clone()),
+    ])
+}
+
+// 順不同で、それぞれ高々 1 回ずつ書ける成分（仕様の `a || b || c`）
+// 成分ごとに、まだ決まっていないロングハンドを先頭から試して最初に読めたものにする
+fn any_order(components: &[&[ComponentValue]], longhands: &[PropertyId]) -> Option<Longhands> {
+    if components.is_empty() {
+        return None;
+    }
+    let mut result: Longhands = Vec::new();
+    for component in components {
+        let parsed = longhands
+            .iter()
+            .filter(|id| !result.iter().any(|(set, _)| set == *id))
+            .find_map(|id| parse_longhand(*id, component).map(|value| (*id, value)))?;
+        result.push(parsed);
+    }
+    Some(result)
+}
+
+// border-top など: `<line-width> || <line-style> || <color>`
+// https://www.w3.org/TR/css-backgrounds-3/#border-shorthands
+fn border_side(values: &[ComponentValue], side: Side) -> Option<Longhands> {
+    any_order(
+        &components(values)?,
+        &[
+            PropertyId::border_width(side),
+            PropertyId::border_style(side),
+            PropertyId::border_color(side),
+        ],
+    )
+}
+
+// border: 4 辺すべてに同じ border-top の値
+fn expand_border(values: &[ComponentValue]) -> Option<Longhands> {
+    let top = border_side(values, Side::Top)?;
+    let mut result = Vec::new();
+    for side in Side::ALL {
+        for (id, value) in &top {
+            let id = match id {
+                PropertyId::BorderTopWidth => PropertyId::border_width(side),
+                PropertyId::BorderTopStyle => PropertyId::border_style(side),
+                _ => PropertyId::border_color(side),
+            };
+            result.push((id, value.clone()));
+        }
+    }
+    Some(result)
+}
+
+// background: カンマ区切りの層。background-color は最後の層にだけ書ける
+// - 画像（`none` / `url()` / グラデーション）・位置と大きさ・繰り返し・固定・箱のキーワードは
+//   ロングハンドが無いので、読めるかだけ確かめて捨てる
+// https://www.w3.org/TR/css-backgrounds-3/#background
+fn expand_background(values: &[ComponentValue]) -> Option<Longhands> {
+    let layers: Vec<&[ComponentValue]> = values.split(|v| *v == ComponentValue::Comma).collect();
+    let mut result = Vec::new();
+    for (i, layer) in layers.iter().enumerate() {
+        let is_final = i + 1 == layers.len();
+        let components = components(layer)?;
+        if components.is_empty() {
+            return None;
+        }
+        for component in components {
+            if is_background_layer_part(component) {
+                continue;
+            }
+            if !is_final || !result.is_empty() {
+                return None;
+            }
+            let color = parse_longhand(PropertyId::BackgroundColor, component)?;
+            result.push((PropertyId::BackgroundColor, color));
+        }
+    }
+    Some(result)
+}
+
+// background の層のうち、色以外の部分か
+fn is_background_layer_part(component: &[ComponentValue]) -> bool {
+    match component {
+        [ComponentValue::Url(_)] => true,
+        [ComponentValue::Function(name), ..] => {
+            let name = name.to_ascii_lowercase();
+            name == "url" || name.ends_with("gradient")
+        }
+        [ComponentValue::Ident(keyword)] => matches!(
+            keyword.to_ascii_lowercase().as_str(),
+            "none"
+                | "repeat"
+                | "repeat-x"
+                | "repeat-y"
+                | "no-repeat"
+                | "space"
+                | "round"
+                | "scroll"
+                | "fixed"
+                | "local"
+                | "border-box"
+                | "padding-box"
+                | "content-box"
+                | "left"
+                | "right"
+                | "top"
+                | "bottom"
+                | "center"
+                | "auto"
+                | "cover"
+                | "contain"
+        ),
+        // 位置と大きさ（`10px 50% / cover`）
+        [ComponentValue::Number(..)]
+        | [ComponentValue::Percentage(_)]
+        | [ComponentValue::Dimension(..)]
+        | [ComponentValue::Delim('/')] => true,
+        _ => false,
+    }
+}
+
+// font: `[ <font-style> || <font-variant-css2> || <font-weight> || <font-stretch-css3> ]?
+//        <font-size> [ / <line-height> ]? <font-family>`
+// - font-variant / font-stretch は読めるかだけ確かめて捨てる
+// - line-height を書かなければ normal に戻る
+// - 先頭の `normal` はどの部分にも当てはまる（書かなかったのと同じ）
+// https://www.w3.org/TR/css-fonts-4/#font-prop
+fn expand_font(values: &[ComponentValue]) -> Option<Longhands> {
+    let components = components(values)?;
+    let mut result: Longhands = Vec::new();
+    let mut rest = components.as_slice();
+
+    // font-size より前の部分（最大 4 つ）
+    let mut prefix = 0;
+    while let [component, tail @ ..] = rest {
+        if prefix == 4 {
+            break;
+        }
+        let is_set = |id| result.iter().any(|(set, _)| *set == id);
+        let part = match component {
+            [ComponentValue::Ident(s)] if s == "normal" => None,
+            [ComponentValue::Ident(s)]
+                if matches!(s.as_str(), "small-caps" | "condensed" | "expanded")
+                    || s.ends_with("-condensed")
+                    || s.ends_with("-expanded") =>
+            {
+                None
+            }
+            _ => {
+                let parse = |id| {
+                    (!is_set(id))
+                        .then(|| parse_longhand(id, component))
+                        .flatten()
+                        .map(|value| (id, value))
+                };
+                match parse(PropertyId::FontStyle).or_else(|| parse(PropertyId::FontWeight)) {
+                    Some(part) => Some(part),
+                    None => break,
+                }
+            }
+        };
+        result.extend(part);
+        prefix += 1;
+        rest = tail;
+    }
+
+    let (size, tail) = rest.split_first()?;
+    result.push((
+        PropertyId::FontSize,
+        parse_longhand(PropertyId::FontSize, size)?,
+    ));
+    rest = tail;
+
+    // `/ <line-height>`
+    if let [[ComponentValue::Delim('/')], line_height, tail @ ..] = rest {
+        result.push((
+            PropertyId::LineHeight,
+            parse_longhand(PropertyId::LineHeight, line_height)?,
+        ));
+        rest = tail;
+    }
+
+    let family = rest.concat();
+    result.push((
+        PropertyId::FontFamily,
+        parse_longhand(PropertyId::FontFamily, &family)?,
+    ));
+    Some(result)
+}
+
+// list-style: `<list-style-position> || <list-style-image> || <list-style-type>`
+// - `none` は image と type のうち、ほかの成分で決まらなかった方を none にする
+//   （`list-style: none` は両方、`list-style: none square` は image だけ）
+// https://www.w3.org/TR/css-lists-3/#list-style-property
+fn expand_list_style(values: &[ComponentValue]) -> Option<Longhands> {
+    let components = components(values)?;
+    let none: &[ComponentValue] = &[ComponentValue::Ident(String::from("none"))];
+    let (nones, others): (Vec<&[ComponentValue]>, Vec<&[ComponentValue]>) =
+        components.into_iter().partition(|c| *c == none);
+
+    let mut result = if others.is_empty() {
+        Vec::new()
+    } else {
+        any_order(
+            &others,
+            &[
+                PropertyId::ListStylePosition,
+                PropertyId::ListStyleImage,
+                PropertyId::ListStyleType,
+            ],
+        )?
+    };
+    let unset: Vec<PropertyId> = [PropertyId::ListStyleImage, PropertyId::ListStyleType]
+        .into_iter()
+        .filter(|id| !result.iter().any(|(set, _)| set == id))
+        .collect();
+    // `none` が 1 つなら決まっていない方すべて、2 つなら image と type の両方
+    if nones.len() > unset.len() || (nones.is_empty() && result.is_empty()) {
+        return None;
+    }
+    if !nones.is_empty() {
+        for id in unset {
+            result.push((id, parse_longhand(id, none)?));
+        }
+    }
+    Some(result)
+}
+
+// flex: `none | [ <'flex-grow'> <'flex-shrink'>? || <'flex-basis'> ]`
+// - 省略した flex-grow / flex-shrink は 1、flex-basis は 0%（どれも初期値とは違う）
+// - `none` は `0 0 auto`、`auto` は `1 1 auto`
+// https://www.w3.org/TR/css-flexbox-1/#flex-property
+fn expand_flex(values: &[ComponentValue]) -> Option<Longhands> {
+    let (grow, shrink) = match values {
+        [ComponentValue::Ident(s)] if s == "none" => (0.0, 0.0),
+        [ComponentValue::Ident(s)] if s == "auto" => (1.0, 1.0),
+        _ => return expand_flex_parts(values),
+    };
+    Some(vec![
+        (PropertyId::FlexGrow, PropertyValue::Number(grow)),
+        (PropertyId::FlexShrink, PropertyValue::Number(shrink)),
+        (PropertyId::FlexBasis, PropertyValue::Auto),
+    ])
+}
+
+fn expand_flex_parts(values: &[ComponentValue]) -> Option<Longhands> {
+    let components = components(values)?;
+    let mut factors: Option<(PropertyValue, PropertyValue)> = None;
+    let mut basis = None;
+    let mut rest = components.as_slice();
+    while let [component, tail @ ..] = rest {
+        rest = tail;
+        if factors.is_none() {
+            if let Some(grow) = parse_longhand(PropertyId::FlexGrow, component) {
+                // flex-shrink は flex-grow のすぐ後にだけ書ける
+                let shrink = match rest {
+                    [next, tail @ ..] => match parse_longhand(PropertyId::FlexShrink, next) {
+                        Some(shrink) => {
+                            rest = tail;
+                            shrink
+                        }
+                        None => PropertyValue::Number(1.0),
+                    },
+                    [] => PropertyValue::Number(1.0),
+                };
+                factors = Some((grow, shrink));
+                continue;
+            }
+        }
+        if basis.is_some() {
+            return None;
+        }
+        basis = Some(parse_longhand(PropertyId::FlexBasis, component)?);
+    }
+
+    let (grow, shrink) =
+        factors.unwrap_or((PropertyValue::Number(1.0), PropertyValue::Number(1.0)));
+    let basis = match basis {
+        Some(basis) => basis,
+        None => parse_longhand(PropertyId::FlexBasis, &[ComponentValue::Percentage(0.0)])?,
+    };
+    Some(vec![
+        (PropertyId::FlexGrow, grow),
+        (PropertyId::FlexShrink, shrink),
+        (PropertyId::FlexBasis, basis),
+    ])
+}
+
+// transition / animation: カンマ区切りの層ごとに `a || b || ...` を読み、ロングハンドごとのリストにする
+// - 層に書かれなかった部分は、初期値のリストの項目で埋める（`transition: color 1s, opacity` の delay は 0s, 0s）
+// - リストにならない値（`transition: none`）は、層が 1 つのときだけ書ける
+fn layers(values: &[ComponentValue], longhands: &[PropertyId]) -> Option<Longhands> {
+    let layers = comma_separated(values)?;
+    if let [layer] = layers.as_slice() {
+        return any_order(&components(layer)?, longhands);
+    }
+    let mut lists: Vec<Vec<PropertyValue>> = vec![Vec::new(); longhands.len()];
+    for layer in &layers {
+        let parsed = any_order(&components(layer)?, longhands)?;
+        for (id, list) in longhands.iter().zip(lists.iter_mut()) {
+            let value = match parsed.iter().find(|(set, _)| set == id) {
+                Some((_, value)) => value.clone(),
+                None => (id.definition().initial)(),
+            };
+            match value {
+                PropertyValue::List(items) => list.extend(items),
+                _ => return None,
+            }
+        }
+    }
+    Some(
+        longhands
+            .iter()
+            .zip(lists)
+            .map(|(id, items)| (*id, PropertyValue::List(items)))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::token::CssToken;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::layout::animation::AnimationDirection;
+    use crate::renderer::layout::animation::TimingFunction;
+    use crate::renderer::layout::computed_style::BorderStyle;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::computed_style::FontStyle;
+    use crate::renderer::layout::computed_style::FontWeight;
+    use crate::renderer::layout::computed_style::Length;
+    use crate::renderer::layout::computed_style::LengthUnit;
+    use crate::renderer::layout::computed_style::ListStyleType;
+    use crate::renderer::layout::computed_style::TextDecoration;
+
+    fn expand_str(property: &str, css: &str) -> Vec<(PropertyId, SpecifiedValue)> {
+        let mut declaration = Declaration::new();
+        declaration.set_property(String::from(property));
+        declaration.set_values(
+            CssTokenizer::new(String::from(css))
+                .filter(|t| *t != CssToken::Whitespace)
+                .collect(),
+        );
+        expand_declaration(&declaration)
+    }
+
+    // 展開した結果のうち、ロングハンド `id` の値
+    fn value_of(expanded: &[(PropertyId, SpecifiedValue)], id: PropertyId) -> PropertyValue {
+        match expanded.iter().find(|(longhand, _)| *longhand == id) {
+            Some((_, SpecifiedValue::Value(value))) => value.clone(),
+            v => panic!("unexpected value for {:?}: {:?}", id, v),
+        }
+    }
+
+    fn px(n: f64) -> PropertyValue {
+        PropertyValue::Length(Length::px(n))
+    }
+
+    #[test]
+    fn test_box_sides() {
+        let margin = expand_str("margin", "1px 2px 3px");
+        assert_eq!(value_of(&margin, PropertyId::MarginTop), px(1.0));
+        assert_eq!(value_of(&margin, PropertyId::MarginRight), px(2.0));
+        assert_eq!(value_of(&margin, PropertyId::MarginBottom), px(3.0));
+        assert_eq!(value_of(&margin, PropertyId::MarginLeft), px(2.0));
+        // margin だけは `auto` も書ける
+        let centered = expand_str("margin", "0 auto");
+        assert_eq!(value_of(&centered, PropertyId::MarginTop), px(0.0));
+        assert_eq!(
+            value_of(&centered, PropertyId::MarginRight),
+            PropertyValue::Auto
+        );
+        assert_eq!(
+            value_of(&centered, PropertyId::MarginLeft),
+            PropertyValue::Auto
+        );
+        assert_eq!(expand_str("margin", "auto").len(), 4);
+
+        let padding = expand_str("padding", "calc(1px + 1px) 10%");
+        assert_eq!(padding.len(), 4);
+        assert_eq!(
+            value_of(&padding, PropertyId::PaddingLeft),
+            PropertyValue::Length(Length::new(10.0, LengthUnit::Percent))
+        );
+
+        // 5 個以上・読めない値・負の padding は宣言ごと無視される
+        assert_eq!(expand_str("margin", "1px 2px 3px 4px 5px"), vec![]);
+        assert_eq!(expand_str("margin", "1px red"), vec![]);
+        assert_eq!(expand_str("padding", "1px -2px"), vec![]);
+        assert_eq!(expand_str("padding", "auto"), vec![]);
+    }
+
+    #[test]
+    fn test_border() {
+        // 書かれなかった部分は初期値に戻る（幅は medium = 3px、色は currentColor）
+        let border = expand_str("border", "solid rgb(255, 0, 0)");
+        assert_eq!(border.len(), 12);
+        assert_eq!(value_of(&border, PropertyId::BorderLeftWidth), px(3.0));
+        assert_eq!(
+            value_of(&border, PropertyId::BorderBottomColor),
+            PropertyValue::Color(Color::rgb(255, 0, 0))
+        );
+
+        let top = expand_str("border-top", "2px");
+        assert_eq!(
+            top.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![
+                PropertyId::BorderTopWidth,
+                PropertyId::BorderTopStyle,
+                PropertyId::BorderTopColor
+            ]
+        );
+        assert_eq!(
+            value_of(&top, PropertyId::BorderTopColor),
+            PropertyValue::CurrentColor
+        );
+
+        // 線の種類は <line-style> のキーワードどれでもよい（2 つ書くのは無効）
+        assert_eq!(
+            value_of(
+                &expand_str("border-top", "thick dashed"),
+                PropertyId::BorderTopStyle
+            ),
+            PropertyValue::BorderStyle(BorderStyle::Dashed)
+        );
+        assert_eq!(expand_str("border", "solid dashed"), vec![]);
+        assert_eq!(
+            value_of(
+                &expand_str("border-color", "red blue"),
+                PropertyId::BorderLeftColor
+            ),
+            PropertyValue::Color(Color::rgb(0, 0, 255))
+        );
+    }
+
+    #[test]
+    fn test_background() {
+        let background = expand_str("background", "url(a.png) no-repeat left 10px / cover, #00f");
+        assert_eq!(
+            value_of(&background, PropertyId::BackgroundColor),
+            PropertyValue::Color(Color::rgb(0, 0, 255))
+        );
+        // 色が無ければ初期値
+        assert_eq!(
+            value_of(
+                &expand_str("background", "none"),
+                PropertyId::BackgroundColor
+            ),
+            (PropertyId::BackgroundColor.definition().initial)()
+        );
+        // 色は最後の層にしか書けない
+        assert_eq!(expand_str("background", "red, url(a.png)"), vec![]);
+    }
+
+    #[test]
+    fn test_font() {
+        let font = expand_str("font", "italic bold 12px/1.5 \"Noto Sans\", serif");
+        assert_eq!(
+            value_of(&font, PropertyId::FontStyle),
+            PropertyValue::FontStyle(FontStyle::Italic)
+        );
+        assert_eq!(
+            value_of(&font, PropertyId::FontWeight),
+            PropertyValue::FontWeight(FontWeight::BOLD)
+        );
+        assert_eq!(value_of(&font, PropertyId::FontSize), px(12.0));
+        assert_eq!(
+            value_of(&font, PropertyId::LineHeight),
+            PropertyValue::Number(1.5)
+        );
+        assert_eq!(
+            value_of(&font, PropertyId::FontFamily),
+            PropertyValue::FontFamily(vec![String::from("Noto Sans"), String::from("serif")])
+        );
+
+        // 書かなかった font-style / font-weight は normal に戻る
+        let font = expand_str("font", "small-caps 700 x-large monospace");
+        assert_eq!(
+            value_of(&font, PropertyId::FontWeight),
+            PropertyValue::FontWeight(FontWeight::BOLD)
+        );
+        assert_eq!(
+            value_of(&font, PropertyId::FontStyle),
+            PropertyValue::FontStyle(FontStyle::Normal)
+        );
+        assert_eq!(
+            value_of(&font, PropertyId::LineHeight),
+            PropertyValue::Normal
+        );
+
+        // font-size と font-family は省略できない
+        assert_eq!(expand_str("font", "bold serif"), vec![]);
+        assert_eq!(expand_str("font", "12px"), vec![]);
+    }
+
+    #[test]
+    fn test_list_style() {
+        let list_style = expand_str("list-style", "none");
+        assert_eq!(
+            value_of(&list_style, PropertyId::ListStyleType),
+            PropertyValue::ListStyleType(ListStyleType::None)
+        );
+        assert_eq!(
+            value_of(&list_style, PropertyId::ListStyleImage),
+            PropertyValue::None
+        );
+
+        let list_style = expand_str("list-style", "inside none square");
+        assert_eq!(
+            value_of(&list_style, PropertyId::ListStyleType),
+            PropertyValue::ListStyleType(ListStyleType::Square)
+        );
+        assert_eq!(
+            value_of(&list_style, PropertyId::ListStyleImage),
+            PropertyValue::None
+        );
+
+        let list_style = expand_str("list-style", "url(dot.png)");
+        assert_eq!(
+            value_of(&list_style, PropertyId::ListStyleType),
+            PropertyValue::ListStyleType(ListStyleType::Disc)
+        );
+        assert_eq!(expand_str("list-style", "none none none"), vec![]);
+        assert_eq!(expand_str("list-style", "square none circle"), vec![]);
+    }
+
+    #[test]
+    fn test_flex() {
+        let flex = |css| {
+            let expanded = expand_str("flex", css);
+            [
+                PropertyId::FlexGrow,
+                PropertyId::FlexShrink,
+                PropertyId::FlexBasis,
+            ]
+            .map(|id| value_of(&expanded, id))
+        };
+        let n = PropertyValue::Number;
+        let percent = |v| PropertyValue::Length(Length::new(v, LengthUnit::Percent));
+
+        assert_eq!(flex("none"), [n(0.0), n(0.0), PropertyValue::Auto]);
+        assert_eq!(flex("auto"), [n(1.0), n(1.0), PropertyValue::Auto]);
+        // 省略した部分は初期値ではなく 1 / 1 / 0%
+        assert_eq!(flex("2"), [n(2.0), n(1.0), percent(0.0)]);
+        assert_eq!(flex("2 3"), [n(2.0), n(3.0), percent(0.0)]);
+        assert_eq!(flex("30px"), [n(1.0), n(1.0), px(30.0)]);
+        assert_eq!(flex("30px 2 0"), [n(2.0), n(0.0), px(30.0)]);
+
+        assert_eq!(expand_str("flex", "1 30px 2"), vec![]);
+        assert_eq!(expand_str("flex", "1 2 3 4"), vec![]);
+    }
+
+    #[test]
+    fn test_text_decoration() {
+        let decoration = expand_str("text-decoration", "underline");
+        assert_eq!(
+            value_of(&decoration, PropertyId::TextDecorationLine),
+            PropertyValue::TextDecoration(TextDecoration::Underline)
+        );
+        assert_eq!(
+            value_of(&decoration, PropertyId::TextDecorationColor),
+            PropertyValue::CurrentColor
+        );
+
+        // 上線・打ち消し線も読める（どれか 1 つ）
+        let decoration = expand_str("text-decoration", "line-through red");
+        assert_eq!(
+            value_of(&decoration, PropertyId::TextDecorationLine),
+            PropertyValue::TextDecoration(TextDecoration::LineThrough)
+        );
+        assert_eq!(
+            value_of(
+                &expand_str("text-decoration", "overline"),
+                PropertyId::TextDecorationLine
+            ),
+            PropertyValue::TextDecoration(TextDecoration::Overline)
+        );
+        assert_eq!(expand_str("text-decoration", "blink"), vec![]);
+    }
+
+    #[test]
+    fn test_keywords_and_var() {
+        // CSS-wide キーワードはすべてのロングハンドに同じものを指定する
+        assert_eq!(
+            expand_str("padding", "inherit"),
+            PADDING.map(|id| (id, SpecifiedValue::Inherit)).to_vec()
+        );
+        // var() を含む値は、計算値を決めるときまで展開しない
+        let values: Vec<ComponentValue> = CssTokenizer::new(String::from("var(--m) 0"))
+            .filter(|t| *t != CssToken::Whitespace)
+            .collect();
+        assert_eq!(
+            expand_str("margin", "var(--m) 0"),
+            MARGIN
+                .map(|id| (
+                    id,
+                    SpecifiedValue::PendingShorthand("margin", values.clone())
+                ))
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn test_transition_and_animation() {
+        let time = PropertyValue::Time;
+        let ident = |name: &str| PropertyValue::Ident(String::from(name));
+        let ease = PropertyValue::TimingFunction(TimingFunction::EASE);
+        let linear = PropertyValue::TimingFunction(TimingFunction::Linear);
+
+        // 最初の時間は duration、2 つ目は delay。書かれなかった部分は初期値
+        let transition = expand_str("transition", "color 1s linear 200ms, opacity 2s");
+        assert_eq!(
+            value_of(&transition, PropertyId::TransitionProperty),
+            PropertyValue::List(vec![ident("color"), ident("opacity")])
+        );
+        assert_eq!(
+            value_of(&transition, PropertyId::TransitionDuration),
+            PropertyValue::List(vec![time(1000.0), time(2000.0)])
+        );
+        assert_eq!(
+            value_of(&transition, PropertyId::TransitionTimingFunction),
+            PropertyValue::List(vec![linear, ease.clone()])
+        );
+        assert_eq!(
+            value_of(&transition, PropertyId::TransitionDelay),
+            PropertyValue::List(vec![time(200.0), time(0.0)])
+        );
+        assert_eq!(
+            value_of(
+                &expand_str("transition", "none"),
+                PropertyId::TransitionProperty
+            ),
+            PropertyValue::None
+        );
+        // none は層が 1 つのときだけ
+        assert!(expand_str("transition", "none, color 1s").is_empty());
+
+        // キーワードとして読める識別子は名前にしない
+        let animation = expand_str("animation", "3s infinite alternate both slide");
+        assert_eq!(
+            value_of(&animation, PropertyId::AnimationName),
+            PropertyValue::List(vec![ident("slide")])
+        );
+        assert_eq!(
+            value_of(&animation, PropertyId::AnimationIterationCount),
+            PropertyValue::List(vec![PropertyValue::Number(f64::INFINITY)])
+        );
+        assert_eq!(
+            value_of(&animation, PropertyId::AnimationDirection),
+            PropertyValue::List(vec![PropertyValue::AnimationDirection(
+                AnimationDirection::Alternate
+            )])
+        );
+        assert_eq!(
+            value_of(&animation, PropertyId::AnimationTimingFunction),
+            PropertyValue::List(vec![ease])
+        );
+        assert!(expand_str("animation", "1s 2s 3s slide").is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+//! スタイルの計算をまとめて速くする（ルールの索引・祖先フィルタ・スタイル共有）
+//!
+//! 役割
+//! - レイアウトツリーを 1 回作る間、要素ごとのカスケードに必要なものを持ち回ります。
+//!   - `RuleSet`: ルールを id / クラス / 型名で引ける索引（最初に 1 回だけ作る）
+//!   - `AncestorFilter`: いま計算している要素の祖先の名前（木を下りるときに `enter`、戻るときに `leave`）
+//!   - スタイル共有のキャッシュ: 最近計算した要素のスタイル
+//!
+//! スタイル共有
+//! - 同じ親を持ち、タグと属性（class / id / style も含む）がまったく同じ兄弟は、
+//!   一致するルールも親から継承する値も同じなので、計算済みのスタイルをそのまま使えます。
+//!   例: `<li class=item>` が 100 個並んでいれば、カスケードは最初の 1 個だけで済む。
+//! - ただし `:first-child` / `p + p` / `:hover` のように、兄弟の並びや状態で一致が変わるセレクタに
+//!   どちらかが一致するときは共有しません（`RuleSet::matches_sharing_sensitive_rule`）。
+//! - 共有するのはアニメーションを当てる前のスタイルです（アニメーションは要素ごとに当てる）。
+//!
+//! 言語ブリッジ（TS / Python / Go）
+//! - `StyleResolver<'a>` の `'a` は「借りているシートと状態が生きている間だけ使える」という印です。
+//!   TS なら `{ stylesheets: readonly StyleSheet[], context: MatchingContext, ... }` を持つクラスです。
+//!
+//! 参考: https://chromium.googlesource.com/chromium/src/+/HEAD/third_party/blink/renderer/core/css/resolver/style_resolver.h
+
+use crate::renderer::css::cascade::cascaded_declarations_with;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::rule_set::AncestorFilter;
+use crate::renderer::css::rule_set::RuleSet;
+use crate::renderer::css::selector::MatchingContext;
+use crate::renderer::dom::node::Element;
+use crate::renderer::dom::node::Node;
+use crate::renderer::layout::computed_style::ComputedStyle;
+use crate::renderer::layout::layout_object::LayoutObject;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// スタイル共有のために覚えておく要素の数（Blink と同じ 15 個）
+const STYLE_SHARING_CANDIDATES: usize = 15;
+
+/// スタイル共有の候補（計算済みの要素と、その親とスタイル）
+#[derive(Debug, Clone)]
+struct SharingCandidate {
+    element: Element,
+    parent: Option<Rc<RefCell<LayoutObject>>>,
+    style: ComputedStyle,
+}
+
+/// レイアウトツリーを作る間、スタイルの計算に使うもの一式
+#[derive(Debug)]
+pub struct StyleResolver<'a> {
+    stylesheets: &'a [StyleSheet],
+    context: &'a MatchingContext,
+    rules: RuleSet,
+    ancestors: AncestorFilter,
+    // 新しい順
+    candidates: VecDeque<SharingCandidate>,
+    // スタイルを共有できた要素の数
+    shared_count: usize,
+}
+
+impl<'a> StyleResolver<'a> {
+    pub fn new(stylesheets: &'a [StyleSheet], context: &'a MatchingContext) -> Self {
+        Self {
+            stylesheets,
+            context,
+            rules: RuleSet::new(stylesheets),
+            ancestors: AncestorFilter::new(),
+            candidates: VecDeque::new(),
+            shared_count: 0,
+        }
+    }
+
+    pub fn stylesheets(&self) -> &'a [StyleSheet] {
+        self.stylesheets
+    }
+
+    pub fn shared_count(&self) -> usize {
+        self.shared_count
+    }
+
+    /// `node` の子孫のスタイルを計算する前に呼ぶ（`leave` と対にする）
+    pub fn enter(&mut self, node: &Rc<RefCell<Node>>) {
+        self.ancestors.push(node);
+    }
+
+    pub fn leave(&mut self) {
+        self.ancestors.pop();
+    }
+
+    /// `node` に一致する宣言を、弱いものから強いものの順に並べて返す（`cascade::cascaded_declarations` と同じ）
+    pub fn cascaded_declarations(&self, node: &Rc<RefCell<Node>>) -> Vec<Declaration> {
+        cascaded_declarations_with(
+            node,
+            self.stylesheets,
+            &self.rules,
+            self.context,
+            Some(&self.ancestors),
+        )
+    }
+
+    /// 同じ親・同じタグと属性の兄弟のスタイルが計算済みなら、その複製を返す
+    pub fn shared_style(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        parent: &Option<Rc<RefCell<LayoutObject>>>,
+    ) -> Option<ComputedStyle> {
+        let element = node.borrow().get_element()?;
+        let index = self.candidates.iter().position(|candidate| {
+            candidate.element == element && same_parent(&candidate.parent, parent)
+        })?;
+        if self.is_sharing_sensitive(node) {
+            return None;
+        }
+
+        self.shared_count += 1;
+        let candidate = self.candidates.remove(index)?;
+        let style = candidate.style.clone();
+        self.candidates.push_front(candidate);
+        Some(style)
+    }
+
+    /// 計算したスタイルを、後の兄弟が共有できるように覚えておく
+    ///
+    /// `style` はデフォルティングまで済ませた、アニメーションを当てる前のもの。
+    pub fn remember(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        parent: &Option<Rc<RefCell<LayoutObject>>>,
+        style: &ComputedStyle,
+    ) {
+        let element = match node.borrow().get_element() {
+            Some(element) => element,
+            None => return,
+        };
+        if self.is_sharing_sensitive(node) {
+            return;
+        }
+
+        self.candidates.push_front(SharingCandidate {
+            element,
+            parent: parent.clone(),
+            style: style.clone(),
+        });
+        self.candidates.truncate(STYLE_SHARING_CANDIDATES);
+    }
+
+    fn is_sharing_sensitive(&self, node: &Rc<RefCell<Node>>) -> bool {
+        self.rules
+            .matches_sharing_sensitive_rule(node, self.context, Some(&self.ancestors))
+    }
+}
+
+/// 親のレイアウトオブジェクトが同じもの（どちらも無い場合を含む）か
+fn same_parent(
+    a: &Option<Rc<RefCell<LayoutObject>>>,
+    b: &Option<Rc<RefCell<LayoutObject>>>,
+) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::layout::animation::AnimationTimeline;
+    use crate::renderer::layout::layout_object::create_layout_object;
+    use alloc::string::ToString;
+
+    // `<body>` の直下の子を順にスタイル計算し、（共有できた数, 各子のスタイル）を返す
+    // `hovered` を渡すと、その番号の子にカーソルが乗っている状態で計算する
+    fn style_children(
+        body: &str,
+        css: &str,
+        hovered: Option<usize>,
+    ) -> (usize, Vec<ComputedStyle>) {
+        let html = "<html><body>".to_string() + body + "</body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let body = get_target_element_node(Some(document), ElementKind::Body);
+        let body_node = body.clone().expect("body should exist");
+
+        let mut children = Vec::new();
+        let mut child = body_node.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            children.push(c);
+        }
+
+        let sheets = [CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet()];
+        let mut context = MatchingContext::new();
+        context.set_hovered(hovered.map(|i| children[i].clone()));
+        let mut resolver = StyleResolver::new(&sheets, &context);
+        let mut timeline = AnimationTimeline::new();
+
+        let body_obj = create_layout_object(&body, &None, &mut resolver, &mut timeline);
+        resolver.enter(&body_node);
+        let styles = children
+            .iter()
+            .map(|c| {
+                create_layout_object(&Some(c.clone()), &body_obj, &mut resolver, &mut timeline)
+                    .expect("child should be laid out")
+                    .borrow()
+                    .style()
+            })
+            .collect();
+        (resolver.shared_count(), styles)
+    }
+
+    #[test]
+    fn test_share_style_between_siblings() {
+        // 属性がまったく同じ兄弟だけが共有する（id や class が違えば自分で計算する）
+        let (shared, styles) = style_children(
+            "<p class=note>1</p><p class=note>2</p><p class=other>3</p><p id=a class=note>4</p>",
+            ".note { color: red; } p { color: blue; } .note { font-size: 20px; }",
+            None,
+        );
+        assert_eq!(1, shared);
+        assert_eq!(styles[0].color(), styles[1].color());
+        assert_ne!(styles[1].color(), styles[2].color());
+        assert_eq!(styles[0].font_size(), styles[3].font_size());
+    }
+
+    #[test]
+    fn test_no_sharing_with_sibling_sensitive_rules() {
+        let body = "<p>1</p><p>2</p><p>3</p>";
+
+        // 何も無ければ 2 つ目と 3 つ目が 1 つ目のスタイルを使う
+        assert_eq!(2, style_children(body, "p { color: red; }", None).0);
+        // 1 つ目は :first-child に一致するので候補にならない。3 つ目は 2 つ目と共有する
+        let (shared, styles) = style_children(body, "p:first-child { color: red; }", None);
+        assert_eq!(1, shared);
+        assert_ne!(styles[0].color(), styles[1].color());
+        // p + p は 2 つ目以降に一致するので、どれも共有しない
+        assert_eq!(0, style_children(body, "p + p { color: red; }", None).0);
+    }
+
+    #[test]
+    fn test_no_sharing_with_hovered_element() {
+        // カーソルが乗っている 2 つ目は :hover に一致するので、1 つ目とも 3 つ目とも共有しない
+        let (shared, styles) = style_children(
+            "<p>1</p><p>2</p><p>3</p>",
+            "p:hover { color: red; }",
+            Some(1),
+        );
+        assert_eq!(1, shared);
+        assert_ne!(styles[0].color(), styles[1].color());
+        assert_eq!(styles[0].color(), styles[2].color());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod accessibility;
 pub mod css;
 pub mod dom;
 pub mod html;
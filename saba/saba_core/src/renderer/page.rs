@@ -12,6 +12,43 @@
 //! - `clicked((x,y))` がその入口で、もし `<a href="…">` をクリックしていれば `Some(url)` を返します。
 //! - 座標系は「コンテンツ左上が (0,0)」。ウィンドウのツールバー/余白分は呼び出し側で差し引きます。
 //!
+//! アクセシビリティ
+//! - `accessibility_tree()` でレイアウト結果から役割/名前/状態のツリーを取得できます（`accessibility` モジュール）。
+//! - フォーカス中の要素と訪問済み URL はページが覚えておき、ツリーの focused/visited 状態に反映します。
+//!
+//! ユーザー操作の状態（:hover / :focus / :active）
+//! - UI はマウスが動くたびに `mouse_move`、ボタンを押した/離したときに `mouse_down`/`mouse_up`、
+//!   Tab キーで `focus_next` を呼びます。
+//! - ページはカーソルの下の要素（hovered）、押されている要素（active）、フォーカス中の要素を覚えておき、
+//!   変化したらスタイルを計算し直して（`restyle`）描画命令を作り直します。戻り値 `true` は「再描画が必要」。
+//!
+//! スタイルシート
+//! - ブラウザ組み込みの UA スタイルシート（`css::user_agent`）を `Page::new` で 1 回だけ解釈して持ち、
+//!   文書のシートより前（弱い出どころ）としてカスケードに渡します。
+//!
+//! 外部スタイルシート
+//! - `<style>` に加えて `<link rel="stylesheet" href>` と `@import` を読み込みます。
+//!   URL は文書の URL（`set_url`）を基準に解決し、組み込み側が渡したネットワーク関数（`set_network_callback`）で取得します。
+//! - 取得したシートは文書順に 1 つの `StyleSheet` へまとめます（`@import` の中身は読み込んだシートの前に入る）。
+//!   取得に失敗したシートは読み飛ばし、残りのスタイルでページを表示します。
+//!
+//! スクリプトによる DOM の書き換え
+//! - `el.textContent = ...` や `el.style.color = ...` で DOM が変わったら、スタイルとレイアウトをやり直します。
+//!   読み込み直後のスクリプトはレイアウト前に走るので、そのまま最初のレイアウトに反映されます。
+//! - `getComputedStyle(el)` はページのシートと状態（:hover やビューポート）でレイアウトした結果を返します。
+//! - `document.styleSheets` の `insertRule` / `deleteRule` で書き換えたシートは、ページのシートとして残ります。
+//!
+//! トランジションとアニメーション
+//! - `transition` / `@keyframes` と `animation` の時刻はページの `AnimationTimeline` が持ちます。
+//! - UI は自分の時計の時刻（ミリ秒）でフレームごとに `tick(now)` を呼びます。動いているものがあれば
+//!   その時刻でスタイルを計算し直して描画命令を作り直し、まだ続くなら `true`（次のフレームも呼ぶ）を返します。
+//! - `:hover` などで値が変わったときのトランジションは、最後に `tick` で渡した時刻から始まります。
+//!
+//! テキスト選択
+//! - マウスを押した位置で `start_selection`、ドラッグ中は `extend_selection` を呼びます。
+//!   座標はレイアウトツリーで DOM の境界点（Text ノード + 文字オフセット）に変換され、`Selection` に入ります。
+//! - 選択範囲は描画命令（ハイライトの Rect）にも反映され、`selected_text()` でクリップボード用の文字列を取れます。
+//!
 //! 言語ブリッジ（TS / Python / Go）
 //! - `Rc<RefCell<T>>`/`Weak<T>` は「共有 + 内部可変 / 循環参照回避」。
 //! - `receive_response` は“ページがネットワーク応答を受け取り、DOM/CSSOM→レイアウト→描画命令”へ進める入口メソッド。
@@ -27,34 +64,72 @@
 //! 5) Paint: [Rect(..pの背景..), Text("Hi", ..座標..)] のような DisplayItem 列が得られる
 use crate::browser::Browser;
 use crate::display_item::DisplayItem;
+use crate::error::Error;
 use crate::http::HttpResponse;
+use crate::renderer::accessibility::AccessibilityTree;
 use crate::renderer::css::cssom::CssParser;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::media::MediaEnvironment;
+use crate::renderer::css::selector::MatchingContext;
 use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::css::user_agent::user_agent_stylesheet;
 use crate::renderer::dom::api::get_js_content;
-use crate::renderer::dom::api::get_style_content;
+use crate::renderer::dom::api::get_style_sources;
+use crate::renderer::dom::api::StyleSource;
 use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
 use crate::renderer::dom::node::Window;
+use crate::renderer::dom::range::Selection;
 use crate::renderer::html::parser::HtmlParser;
 use crate::renderer::html::token::HtmlTokenizer;
 use crate::renderer::js::ast::JsParser;
 use crate::renderer::js::runtime::JsRuntime;
 use crate::renderer::js::token::JsLexer;
+use crate::renderer::layout::animation::AnimationTimeline;
 use crate::renderer::layout::layout_view::LayoutView;
+use crate::url::Url;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+/// 組み込み側（UI）が用意するネットワーク関数。URL を受け取り HTTP レスポンスを返す
+pub type NetworkCallback = fn(String) -> Result<HttpResponse, Error>;
+
+// `@import` の入れ子をたどる深さの上限（循環していなくても深すぎる読み込みは打ち切る）
+const MAX_IMPORT_DEPTH: usize = 8;
+
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
+    // 表示中の文書の URL（相対 URL を解決する基準）
+    url: Option<Url>,
+    // 外部リソース（スタイルシート）を取りに行くネットワーク関数
+    network: Option<NetworkCallback>,
     frame: Option<Rc<RefCell<Window>>>,
-    style: Option<StyleSheet>,
+    // カスケードに使うシート。先頭は UA スタイルシート（Page を作るときに 1 回だけ解釈）、
+    // 2 つ目以降が表示中の文書のシート
+    stylesheets: Vec<StyleSheet>,
     layout_view: Option<LayoutView>,
     display_items: Vec<DisplayItem>,
+    // フォーカス中の要素（DOM が作り直されたら自然に無効になるよう Weak で持つ）
+    focused: Weak<RefCell<Node>>,
+    // 訪問済みリンクの URL（:visited やアクセシビリティの visited 状態に使う）
+    visited_urls: Vec<String>,
+    // マウスカーソルの下にある要素（:hover）
+    hovered: Weak<RefCell<Node>>,
+    // マウスボタンで押されている要素（:active）
+    active: Weak<RefCell<Node>>,
+    // ユーザーのテキスト選択
+    selection: Selection,
+    // 表示する環境（ビューポートの大きさ・画面か印刷か・配色。`@media` の判定とレイアウトに使う）
+    media: MediaEnvironment,
+    // トランジション・アニメーションの時刻と、要素ごとの実行中の状態
+    timeline: AnimationTimeline,
 }
 
 impl Page {
@@ -62,10 +137,19 @@ impl Page {
     pub fn new() -> Self {
         Self {
             browser: Weak::new(),
+            url: None,
+            network: None,
             frame: None,
-            style: None,
+            stylesheets: vec![user_agent_stylesheet()],
             layout_view: None,
             display_items: Vec::new(),
+            focused: Weak::new(),
+            visited_urls: Vec::new(),
+            hovered: Weak::new(),
+            active: Weak::new(),
+            selection: Selection::new(),
+            media: MediaEnvironment::default(),
+            timeline: AnimationTimeline::new(),
         }
     }
 
@@ -82,30 +166,288 @@ impl Page {
     /// 実装の概要
     /// - `layout_view.find_node_by_position(position)` で、座標に重なるレイアウトノードを取得。
     /// - 見つかったノードの親が `<a>` 要素なら `href` 属性を返す（簡易版。祖先すべては辿らない）。
-    pub fn clicked(&self, position: (i64, i64)) -> Option<String> {
+    /// - リンクをクリックした場合はそのリンクにフォーカスを移し、URL を訪問済みとして記録します。
+    ///   リンク以外をクリックした場合はフォーカスを外します。
+    pub fn clicked(&mut self, position: (i64, i64)) -> Option<String> {
         let view = match &self.layout_view {
             Some(v) => v,
             None => return None,
         };
 
+        let mut link = None;
         if let Some(n) = view.find_node_by_position(position) {
             if let Some(parent) = n.borrow().parent().upgrade() {
                 if let NodeKind::Element(e) = parent.borrow().node_kind() {
                     if e.kind() == ElementKind::A {
-                        return e.get_attribute("href");
+                        if let Some(href) = e.get_attribute("href") {
+                            link = Some((parent.borrow().node(), href));
+                        }
                     }
                 }
             }
         }
 
+        let (changed, href) = match link {
+            Some((node, href)) => {
+                let visited = self.add_visited(href.clone());
+                (self.set_focus(Some(node)) || visited, Some(href))
+            }
+            None => (self.set_focus(None), None),
+        };
+        if changed {
+            self.restyle();
+        }
+        href
+    }
+
+    // フォーカスを `node` に移す（None でフォーカスを外す）。:focus のためにスタイルを計算し直す
+    pub fn focus(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        if self.set_focus(node) {
+            self.restyle();
+        }
+    }
+
+    // フォーカスを移し、変わったら true
+    fn set_focus(&mut self, node: Option<Rc<RefCell<Node>>>) -> bool {
+        let changed = !same_node(&self.focused.upgrade(), &node);
+        self.focused = match node {
+            Some(n) => Rc::downgrade(&n),
+            None => Weak::new(),
+        };
+        changed
+    }
+
+    /// Tab キー: 文書順で次のリンク（href を持つ `<a>`）にフォーカスを移す
+    ///
+    /// - 最後のリンクの次は先頭に戻ります。リンクが 1 つも無ければ何もしません。
+    /// - フォーカスが変わったら `true`（UI は再描画する）。
+    pub fn focus_next(&mut self) -> bool {
+        let document = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return false,
+        };
+        let mut links = Vec::new();
+        collect_links(&document.borrow().first_child(), &mut links);
+        if links.is_empty() {
+            return false;
+        }
+
+        let next = match self.focused_element() {
+            Some(focused) => match links.iter().position(|l| Rc::ptr_eq(l, &focused)) {
+                Some(i) => (i + 1) % links.len(),
+                None => 0,
+            },
+            None => 0,
+        };
+        let changed = self.set_focus(Some(links[next].clone()));
+        if changed {
+            self.restyle();
+        }
+        changed
+    }
+
+    // 現在フォーカスされている要素を返す
+    pub fn focused_element(&self) -> Option<Rc<RefCell<Node>>> {
+        self.focused.upgrade()
+    }
+
+    // URL を訪問済みとして記録する（重複は記録しない）。:visited のためにスタイルを計算し直す
+    pub fn mark_visited(&mut self, url: String) {
+        if self.add_visited(url) {
+            self.restyle();
+        }
+    }
+
+    // 訪問済みに追加し、新しく追加したら true
+    fn add_visited(&mut self, url: String) -> bool {
+        if self.visited_urls.contains(&url) {
+            return false;
+        }
+        self.visited_urls.push(url);
+        true
+    }
+
+    /// マウスが `position` に動いた（:hover の更新）
+    ///
+    /// - カーソルの下の要素が変わったらスタイルを計算し直し、`true` を返します。
+    pub fn mouse_move(&mut self, position: (i64, i64)) -> bool {
+        let target = self.element_at(position);
+        if same_node(&self.hovered.upgrade(), &target) {
+            return false;
+        }
+        self.hovered = downgrade(&target);
+        self.restyle();
+        true
+    }
+
+    /// マウスボタンが `position` で押された（:active の開始）
+    pub fn mouse_down(&mut self, position: (i64, i64)) -> bool {
+        let target = self.element_at(position);
+        if same_node(&self.active.upgrade(), &target) {
+            return false;
+        }
+        self.active = downgrade(&target);
+        self.restyle();
+        true
+    }
+
+    /// マウスボタンが離された（:active の終了）
+    pub fn mouse_up(&mut self) -> bool {
+        if self.active.upgrade().is_none() {
+            return false;
+        }
+        self.active = Weak::new();
+        self.restyle();
+        true
+    }
+
+    // 座標 `position` にある最も内側の要素（テキストの上ならその親要素）
+    fn element_at(&self, position: (i64, i64)) -> Option<Rc<RefCell<Node>>> {
+        let view = self.layout_view.as_ref()?;
+        let obj = view.find_node_by_position(position)?;
+        let mut node = Some(obj.borrow().node());
+        while let Some(n) = node {
+            if let NodeKind::Element(_) = n.borrow().kind() {
+                return Some(n.clone());
+            }
+            node = n.borrow().parent().upgrade();
+        }
         None
     }
 
+    // セレクタのマッチングに渡す、ページの現在の状態
+    fn matching_context(&self) -> MatchingContext {
+        let mut context = MatchingContext::new();
+        context.set_hovered(self.hovered.upgrade());
+        context.set_active(self.active.upgrade());
+        context.set_focused(self.focused.upgrade());
+        context.set_visited_urls(self.visited_urls.clone());
+        context.set_media(self.media);
+        context
+    }
+
+    /// 表示する環境（ビューポートの大きさなど）を変える
+    ///
+    /// - 変わったら `@media` を判定し直してレイアウトと描画命令を作り直し、`true` を返します
+    ///   （UI はそのとき再描画する）。
+    /// - 例: ウィンドウの大きさが変わった、印刷用に描く、ダークモードに切り替えた
+    pub fn set_media(&mut self, media: MediaEnvironment) -> bool {
+        if self.media == media {
+            return false;
+        }
+        self.media = media;
+        self.restyle();
+        true
+    }
+
+    /// アニメーションの時刻を `now`（ミリ秒。組み込み側の時計の値）に進める
+    ///
+    /// - 実行中のトランジション・アニメーションがあれば、その時刻でスタイル・レイアウト・描画命令を作り直す
+    /// - 戻り値 `true` は「まだ動いているので次のフレームも `tick` が要る」
+    /// - UI（`ui_wasabi` の `WasabiUI`）はイベントループの 1 周ごとに、自分の時計の値で呼ぶ
+    ///
+    /// 例: `p { transition: color 1s linear }` で色が変わった直後に `tick(t + 500.0)` → 色は中間、`true`
+    pub fn tick(&mut self, now: f64) -> bool {
+        self.timeline.set_current_time(now);
+        if self.layout_view.is_some() && self.timeline.is_running() {
+            self.restyle();
+        }
+        self.timeline.is_running()
+    }
+
+    // :hover などの状態が変わったので、レイアウトツリーと描画命令を作り直す
+    fn restyle(&mut self) {
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
+    /// 座標 `position` からテキスト選択を始める（マウスを押したとき）
+    ///
+    /// - 座標に最も近いテキスト位置に選択を潰します。テキストが無ければ選択を解除します。
+    /// - 選択が変わったら描画命令を作り直し、`true` を返します（UI はそのとき再描画する）。
+    pub fn start_selection(&mut self, position: (i64, i64)) -> bool {
+        let caret = match &self.layout_view {
+            Some(view) => view.caret_position_from_point(position),
+            None => None,
+        };
+
+        let before = self.selection.clone();
+        match caret {
+            Some(point) => self.selection.collapse(point.node(), point.offset()),
+            None => self.selection.remove_all_ranges(),
+        }
+        self.repaint_if_selection_changed(before)
+    }
+
+    /// ドラッグ中の座標 `position` まで選択を広げる（anchor は `start_selection` の位置のまま）
+    pub fn extend_selection(&mut self, position: (i64, i64)) -> bool {
+        let caret = match &self.layout_view {
+            Some(view) => view.caret_position_from_point(position),
+            None => None,
+        };
+
+        let before = self.selection.clone();
+        if let Some(point) = caret {
+            self.selection.extend(point.node(), point.offset());
+        }
+        self.repaint_if_selection_changed(before)
+    }
+
+    /// 選択を解除する
+    pub fn clear_selection(&mut self) -> bool {
+        let before = self.selection.clone();
+        self.selection.remove_all_ranges();
+        self.repaint_if_selection_changed(before)
+    }
+
+    // 選択が変わっていればハイライトを描き直す
+    fn repaint_if_selection_changed(&mut self, before: Selection) -> bool {
+        if before == self.selection {
+            return false;
+        }
+        self.paint_tree();
+        true
+    }
+
+    pub fn selection(&self) -> Selection {
+        self.selection.clone()
+    }
+
+    /// 選択中のテキスト（クリップボードへのコピー用）。選択が無ければ空文字列
+    pub fn selected_text(&self) -> String {
+        self.selection.to_string()
+    }
+
+    /// 現在のレイアウト結果からアクセシビリティツリーを作る（レイアウト未生成なら None）
+    ///
+    /// 使い方（テスト）
+    /// - `tree.dump()` をスナップショットとして比較する。
+    /// - `tree.find(Role::Link, "next")` で要素を探し、`center()` を `clicked` に渡す。
+    pub fn accessibility_tree(&self) -> Option<AccessibilityTree> {
+        self.layout_view
+            .as_ref()
+            .map(|view| AccessibilityTree::new(view, self.focused_element(), &self.visited_urls))
+    }
+
     // 所属ブラウザを弱参照でセット（循環参照回避）。
     pub fn set_browser(&mut self, browser: Weak<RefCell<Browser>>) {
         self.browser = browser;
     }
 
+    /// これから読み込む文書の URL を設定する（`receive_response` の前に呼ぶ）
+    ///
+    /// - `<link href="style.css">` などの相対 URL はこの URL を基準に解決します。
+    /// - パースできない URL なら基準なし（外部スタイルシートは読み込まない）になります。
+    pub fn set_url(&mut self, url: String) {
+        self.url = Url::new(url).parse().ok();
+    }
+
+    /// 外部リソースの取得に使うネットワーク関数を設定する
+    /// 例: UI が `handle_url`（アドレスバーの URL を取得する関数）をそのまま渡す
+    pub fn set_network_callback(&mut self, network: NetworkCallback) {
+        self.network = Some(network);
+    }
+
     // ネットワーク応答（HTML）を受け取り、DOM/CSSOM 構築 → JS 実行 → レイアウト → 描画命令 まで進める
     // フロー:
     // - create_frame: HTML→DOM、<style>→CSSOM を作成
@@ -125,6 +467,8 @@ impl Page {
     /// - DOM から `<script>` のテキストを抽出（`get_js_content`）。
     /// - JS を字句解析（`JsLexer`）→ 構文解析（`JsParser`）して AST を作る。
     /// - JS ランタイム（`JsRuntime`）を用意し、AST を評価して副作用（変数/DOM 変更）を反映。
+    /// - スクリプトがシートのルールを書き換えたら（`insertRule` など）、ページのシートをそれに入れ替える。
+    /// - すでにレイアウト済みのページで DOM かシートが変わったら、スタイル計算からやり直す（`restyle`）。
     fn execute_js(&mut self) {
         // 1) DOM ルート（Document）を取得。ページが未構築なら何もしない
         let dom = match &self.frame {
@@ -142,8 +486,20 @@ impl Page {
 
         // 4) ランタイムを用意して AST を実行
         //    補足: DOM 連携（document.getElementById 等）のために DOM 参照を渡します。
+        //    getComputedStyle と document.styleSheets のために、シートとページの状態も渡します。
         let mut runtime = JsRuntime::new(dom);
+        runtime.set_stylesheets(self.stylesheets.clone(), self.matching_context());
         runtime.execute(&ast);
+
+        // 5) insertRule などでシートが変わったら、書き換えたシートに入れ替える
+        if runtime.styles_modified() {
+            self.stylesheets = runtime.stylesheets().to_vec();
+        }
+
+        // 6) DOM（テキストや style 属性）かシートが変わったら、古いレイアウトを作り直す
+        if (runtime.dom_modified() || runtime.styles_modified()) && self.layout_view.is_some() {
+            self.restyle();
+        }
     }
 
     // HTML 文字列から DOM（Window/Document）と CSSOM（StyleSheet）を作る
@@ -152,12 +508,93 @@ impl Page {
         let frame = HtmlParser::new(html_tokenizer).construct_tree();
         let dom = frame.borrow().document();
 
-        let style = get_style_content(dom);
-        let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
+        let cssom = self.load_stylesheets(dom);
 
         self.frame = Some(frame);
-        self.style = Some(cssom);
+        // UA スタイルシートは残し、文書のシートだけ入れ替える
+        self.stylesheets.truncate(1);
+        self.stylesheets.push(cssom);
+        // 前の文書のレイアウトとアニメーションは使わない
+        self.layout_view = None;
+        self.timeline.clear();
+        // 新しい文書なのでフォーカス・ホバーと選択は外す
+        self.focused = Weak::new();
+        self.hovered = Weak::new();
+        self.active = Weak::new();
+        self.selection.remove_all_ranges();
+    }
+
+    /// 文書の `<style>` / `<link rel="stylesheet">` と、その `@import` を文書順に 1 つのシートへまとめる
+    ///
+    /// 例: `<link href=a.css><style>@import "b.css"; p{}</style>`
+    ///     → [a.css のルール, b.css のルール, p{}]（後ろほどカスケードで強い）
+    fn load_stylesheets(&self, dom: Rc<RefCell<Node>>) -> StyleSheet {
+        let mut sheet = StyleSheet::new();
+        for source in get_style_sources(dom) {
+            match source {
+                StyleSource::Inline(css) => {
+                    let mut chain = Vec::new();
+                    self.append_stylesheet(css, self.url.as_ref(), &mut chain, &mut sheet);
+                }
+                StyleSource::External(href) => {
+                    let url = match &self.url {
+                        Some(base) => base.resolve(&href),
+                        None => continue,
+                    };
+                    let mut chain = Vec::new();
+                    self.fetch_stylesheet(url, &mut chain, &mut sheet);
+                }
+            }
+        }
+        sheet
+    }
+
+    // 外部シートを取得して `out` に追加する。取れなければ何もしない（ページの表示は続ける）
+    // `chain` は今たどっている @import の URL の並び。同じ URL が出てきたら循環なので止める
+    fn fetch_stylesheet(&self, url: String, chain: &mut Vec<String>, out: &mut StyleSheet) {
+        if chain.len() >= MAX_IMPORT_DEPTH || chain.contains(&url) {
+            return;
+        }
+        let network = match self.network {
+            Some(f) => f,
+            None => return,
+        };
+        let css = match network(url.clone()) {
+            Ok(response) if (200..300).contains(&response.status_code()) => response.body(),
+            _ => return,
+        };
+        let base = Url::new(url.clone()).parse().ok();
+
+        chain.push(url);
+        self.append_stylesheet(css, base.as_ref(), chain, out);
+        chain.pop();
+    }
+
+    // CSS 文字列を解釈し、@import 先のルール → 自分のルールの順で `out` に追加する（@keyframes も同じ順）
+    // @import の相対 URL は、それを書いたシートの URL（`base`）から解決する
+    // @import にメディアクエリがあれば、読み込んだルールそれぞれの `media` の外側に付ける
+    // （`@import url(print.css) print;` の中身は、`@media print { ... }` に書いたのと同じ扱い）
+    fn append_stylesheet(
+        &self,
+        css: String,
+        base: Option<&Url>,
+        chain: &mut Vec<String>,
+        out: &mut StyleSheet,
+    ) {
+        let sheet = CssParser::new(CssTokenizer::new(css)).parse_stylesheet();
+        if let Some(base) = base {
+            for import in &sheet.imports {
+                let mut imported = StyleSheet::new();
+                self.fetch_stylesheet(base.resolve(&import.url), chain, &mut imported);
+                for rule in &mut imported.rules {
+                    rule.media.insert(0, import.media.clone());
+                }
+                out.rules.extend(imported.rules);
+                out.keyframes.extend(imported.keyframes);
+            }
+        }
+        out.rules.extend(sheet.rules);
+        out.keyframes.extend(sheet.keyframes);
     }
 
     // DOM + CSSOM から LayoutView（レイアウトツリー）を作る
@@ -167,20 +604,17 @@ impl Page {
             None => return,
         };
 
-        let style = match self.style.clone() {
-            Some(style) => style,
-            None => return,
-        };
-
-        let layout_view = LayoutView::new(dom, &style);
+        let context = self.matching_context();
+        let layout_view =
+            LayoutView::new_with_timeline(dom, &self.stylesheets, &context, &mut self.timeline);
 
         self.layout_view = Some(layout_view);
     }
 
-    // レイアウトツリーから DisplayItem を収集（描画命令列）
+    // レイアウトツリーから DisplayItem を収集（描画命令列）。選択中ならハイライトも含める
     fn paint_tree(&mut self) {
         if let Some(layout_view) = &self.layout_view {
-            self.display_items = layout_view.paint();
+            self.display_items = layout_view.paint_with_selection(self.selection.range().as_ref());
         }
     }
 
@@ -194,3 +628,246 @@ impl Page {
         self.display_items = Vec::new();
     }
 }
+
+// 2 つの（あるかもしれない）ノードが同じものか
+fn same_node(a: &Option<Rc<RefCell<Node>>>, b: &Option<Rc<RefCell<Node>>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn downgrade(node: &Option<Rc<RefCell<Node>>>) -> Weak<RefCell<Node>> {
+    match node {
+        Some(n) => Rc::downgrade(n),
+        None => Weak::new(),
+    }
+}
+
+// 文書順（深さ優先）で href を持つ `<a>` を集める
+fn collect_links(node: &Option<Rc<RefCell<Node>>>, out: &mut Vec<Rc<RefCell<Node>>>) {
+    let mut current = node.clone();
+    while let Some(n) = current {
+        if let NodeKind::Element(e) = n.borrow().kind() {
+            if e.kind() == ElementKind::A && e.get_attribute("href").is_some() {
+                out.push(n.clone());
+            }
+        }
+        collect_links(&n.borrow().first_child(), out);
+        current = n.borrow().next_sibling();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::ComponentValue;
+    use crate::renderer::css::media::MediaType;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::layout_object::LayoutObject;
+    use crate::renderer::layout::property::PropertyId;
+    use crate::renderer::layout::property::PropertyValue;
+    use alloc::format;
+
+    // テスト用のネットワーク: 決まった URL にだけ CSS を返し、それ以外は失敗する
+    fn fake_network(url: String) -> Result<HttpResponse, Error> {
+        let body = match url.as_str() {
+            "http://example.com:80/css/a.css" => "@import \"b.css\"; p { color: red; }",
+            "http://example.com:80/css/b.css" => "h1 { color: blue; }",
+            "http://example.com:80/loop.css" => "@import \"loop.css\"; a { color: gray; }",
+            "http://example.com:80/print.css" => "p { color: red; }",
+            "http://example.com:80/gone.css" => {
+                return HttpResponse::new("HTTP/1.1 404 NotFound\n\np { color: black; }".into())
+            }
+            _ => return Err(Error::Network(format!("unreachable: {}", url))),
+        };
+        HttpResponse::new(format!(
+            "HTTP/1.1 200 OK\nContent-Type: text/css\n\n{}",
+            body
+        ))
+    }
+
+    fn html_response(head: &str) -> HttpResponse {
+        let html = format!(
+            "<html><head>{}</head><body><h1>t</h1><p>x</p><a>y</a></body></html>",
+            head
+        );
+        HttpResponse::new(format!("HTTP/1.1 200 OK\n\n{}", html)).expect("valid response")
+    }
+
+    // まとめられたシートの、各ルールの最初の宣言の値（ここでは color）を並べる
+    fn colors_of(page: &Page) -> Vec<ComponentValue> {
+        page.stylesheets
+            .last()
+            .expect("style should be loaded")
+            .rules
+            .iter()
+            .map(|r| r.declarations[0].value.clone())
+            .collect()
+    }
+
+    fn ident(s: &str) -> ComponentValue {
+        ComponentValue::Ident(s.to_string())
+    }
+
+    #[test]
+    fn test_external_stylesheets_in_document_order() {
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.set_network_callback(fake_network);
+        // 読み込めないシート（存在しない / 404）は飛ばし、循環する @import も止まる
+        page.receive_response(html_response(
+            "<link rel=\"stylesheet\" href=\"css/a.css\">\
+             <link rel=\"stylesheet\" href=\"missing.css\">\
+             <link rel=\"stylesheet\" href=\"/gone.css\">\
+             <link rel=\"icon\" href=\"loop.css\">\
+             <style>@import \"loop.css\"; p { color: green; }</style>",
+        ));
+
+        let colors = colors_of(&page);
+        // a.css の @import（b.css）→ a.css → <style> の @import（loop.css）→ <style>
+        assert_eq!(
+            colors,
+            [ident("blue"), ident("red"), ident("gray"), ident("green")].to_vec()
+        );
+        // 失敗したシートがあってもレイアウトまで進む
+        assert!(!page.display_items().is_empty());
+    }
+
+    #[test]
+    fn test_without_network() {
+        // ネットワーク関数が無ければ <link> は読み飛ばし、<style> だけ使う
+        let mut page = Page::new();
+        page.set_url("http://example.com/".to_string());
+        page.receive_response(html_response(
+            "<link rel=\"stylesheet\" href=\"css/a.css\"><style>p { color: green; }</style>",
+        ));
+        let colors = colors_of(&page);
+        assert_eq!(colors, [ident("green")].to_vec());
+    }
+
+    #[test]
+    fn test_script_edits_stylesheet() {
+        // スクリプトが足したルールはページのシートに残り、最初のレイアウトに反映される
+        let mut page = Page::new();
+        page.receive_response(html_response(
+            "<style>p { color: green; }</style>\
+             <script>var s = document.styleSheets.item(0); s.insertRule(\"p { color: red; }\", 1);</script>",
+        ));
+        assert_eq!(colors_of(&page), [ident("green"), ident("red")].to_vec());
+        let view = page.layout_view.as_ref().expect("layout should exist");
+        let p = view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|h1| h1.borrow().next_sibling())
+            .expect("p should exist");
+        assert_eq!(
+            p.borrow().style().color(),
+            crate::renderer::layout::computed_style::Color::rgb(255, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_media_follows_viewport() {
+        // ビューポートが変わると @media を判定し直し、レイアウトもその幅でやり直す
+        let mut page = Page::new();
+        page.receive_response(html_response(
+            "<style>@media (max-width: 400px) { p { display: none; } }</style>",
+        ));
+        let wide_items = page.display_items().len();
+
+        assert!(page.set_media(MediaEnvironment::new(300, 200)));
+        assert!(page.display_items().len() < wide_items);
+        let view = page.layout_view.as_ref().expect("layout should exist");
+        assert_eq!(view.viewport().width(), 300);
+        assert_eq!(
+            view.root()
+                .expect("body should exist")
+                .borrow()
+                .size()
+                .width(),
+            300
+        );
+
+        // 同じ環境なら何もしない
+        assert!(!page.set_media(MediaEnvironment::new(300, 200)));
+    }
+
+    #[test]
+    fn test_import_with_media() {
+        // `@import url(print.css) print;` のルールは、印刷のときだけ効く
+        let mut page = Page::new();
+        page.set_url("http://example.com/index.html".to_string());
+        page.set_network_callback(fake_network);
+        page.receive_response(html_response(
+            "<style>@import url(print.css) print; h1 { color: green; }</style>",
+        ));
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(colors_of(&page), [ident("red"), ident("green")].to_vec());
+        assert_ne!(paragraph(&page).borrow().style().color(), red);
+
+        let mut print = MediaEnvironment::default();
+        print.set_media_type(MediaType::Print);
+        assert!(page.set_media(print));
+        assert_eq!(paragraph(&page).borrow().style().color(), red);
+    }
+
+    // <body> の 2 つ目の子（html_response の <p>）のレイアウトオブジェクト
+    fn paragraph(page: &Page) -> Rc<RefCell<LayoutObject>> {
+        let view = page.layout_view.as_ref().expect("layout should exist");
+        view.root()
+            .and_then(|body| body.borrow().first_child())
+            .and_then(|h1| h1.borrow().next_sibling())
+            .expect("p should exist")
+    }
+
+    #[test]
+    fn test_transition_follows_clock() {
+        let mut page = Page::new();
+        page.receive_response(html_response(
+            "<style>p { transition: color 1s linear; }\
+             @media (max-width: 400px) { p { color: red; } }</style>",
+        ));
+        // 最初のスタイルはトランジションしない
+        assert!(!page.tick(1000.0));
+
+        // 値が変わったら、最後に渡した時刻（1000ms）から始まる
+        page.set_media(MediaEnvironment::new(300, 200));
+        assert_eq!(paragraph(&page).borrow().style().color(), Color::black());
+        assert!(page.tick(1500.0));
+        assert_eq!(
+            paragraph(&page).borrow().style().color(),
+            Color::rgb(128, 0, 0)
+        );
+        assert!(!page.tick(2000.0));
+        assert_eq!(
+            paragraph(&page).borrow().style().color(),
+            Color::rgb(255, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_keyframe_animation_follows_clock() {
+        let mut page = Page::new();
+        page.receive_response(html_response(
+            "<style>@keyframes fade { from { opacity: 0; } to { opacity: 1; } }\
+             p { opacity: 0.5; animation: fade 1s linear 2; }</style>",
+        ));
+        let opacity = |page: &Page| match paragraph(page).borrow().style().get(PropertyId::Opacity)
+        {
+            PropertyValue::Number(n) => *n,
+            v => panic!("unexpected opacity: {:?}", v),
+        };
+        // 読み込んだ時刻（0ms）から始まる
+        assert_eq!(opacity(&page), 0.0);
+        assert!(page.tick(250.0));
+        assert_eq!(opacity(&page), 0.25);
+        // 2 回目の繰り返し
+        assert!(page.tick(1750.0));
+        assert_eq!(opacity(&page), 0.75);
+        // 終わったら（fill-mode: none）元の値に戻り、次のフレームは要らない
+        assert!(!page.tick(2000.0));
+        assert_eq!(opacity(&page), 0.5);
+    }
+}
@@ -15,6 +15,7 @@
 //! - 学習用の最小実装です。`https` や `#fragment`、パーセントエンコードなどは扱いません。
 //! - 本番用途では公式の `url` crate などの利用を検討してください。
 
+use alloc::format;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -146,6 +147,53 @@ impl Url {
 
         Ok(self.clone())
     }
+
+    /// この URL を基準に、相対 URL `reference` を絶対 URL にする（`parse` 済みであること）
+    ///
+    /// 例（基準が `http://example.com:8888/docs/index.html` のとき）
+    /// - `style.css`      → `http://example.com:8888/docs/style.css`（同じディレクトリ）
+    /// - `../a.css`       → `http://example.com:8888/a.css`
+    /// - `/css/a.css`     → `http://example.com:8888/css/a.css`（ホストの直下から）
+    /// - `http://other/a.css` はそのまま
+    ///
+    /// TS の `new URL(reference, base).href` に近いものです（`.`/`..` 以外の正規化はしません）。
+    /// 仕様: https://url.spec.whatwg.org/#concept-basic-url-parser
+    pub fn resolve(&self, reference: &str) -> String {
+        if reference.contains("://") {
+            return reference.to_string();
+        }
+        if let Some(rest) = reference.strip_prefix("//") {
+            return format!("http://{}", rest);
+        }
+
+        let path = if reference.starts_with('/') {
+            reference.to_string()
+        } else {
+            // 基準のパスから最後の `/` より後ろ（ファイル名）を落としてつなげる
+            match self.path.rfind('/') {
+                Some(index) => format!("/{}/{}", &self.path[..index], reference),
+                None => format!("/{}", reference),
+            }
+        };
+
+        // `.` と `..` のセグメントを取り除く
+        let mut segments: Vec<&str> = Vec::new();
+        for segment in path.split('/').skip(1) {
+            match segment {
+                "." => {}
+                ".." => {
+                    segments.pop();
+                }
+                _ => segments.push(segment),
+            }
+        }
+        // `a/.` や `a/..` で終わるときは末尾の `/` を残す
+        if path.ends_with("/.") || path.ends_with("/..") {
+            segments.push("");
+        }
+
+        format!("http://{}:{}/{}", self.host, self.port, segments.join("/"))
+    }
 }
 
 #[cfg(test)]
@@ -237,4 +285,32 @@ mod tests {
         let expected = Err("Only HTTP scheme is supported.".to_string());
         assert_eq!(expected, Url::new(url).parse());
     }
+
+    #[test]
+    fn test_resolve() {
+        let base = Url::new("http://example.com:8888/docs/index.html".to_string())
+            .parse()
+            .expect("base should parse");
+        assert_eq!(
+            base.resolve("style.css"),
+            "http://example.com:8888/docs/style.css"
+        );
+        assert_eq!(
+            base.resolve("./css/a.css"),
+            "http://example.com:8888/docs/css/a.css"
+        );
+        assert_eq!(base.resolve("../a.css"), "http://example.com:8888/a.css");
+        assert_eq!(base.resolve("/a.css"), "http://example.com:8888/a.css");
+        assert_eq!(
+            base.resolve("http://other.com/a.css"),
+            "http://other.com/a.css"
+        );
+        assert_eq!(base.resolve("//other.com/a.css"), "http://other.com/a.css");
+
+        // パスの無い URL（ポート省略時は 80）
+        let base = Url::new("http://example.com".to_string())
+            .parse()
+            .expect("base should parse");
+        assert_eq!(base.resolve("a.css"), "http://example.com:80/a.css");
+    }
 }
@@ -1,4 +1,5 @@
 use crate::alloc::string::ToString;
+use crate::clock::Clock;
 use crate::cursor::Cursor;
 use alloc::format;
 use alloc::rc::Rc;
@@ -13,15 +14,16 @@ use noli::sys::wasabi::Api;
 use noli::window::StringSize;
 use noli::window::Window;
 use saba_core::browser::Browser;
+use saba_core::compositor::Canvas;
 use saba_core::constants::WHITE;
 use saba_core::constants::WINDOW_HEIGHT;
 use saba_core::constants::WINDOW_INIT_X_POS;
 use saba_core::constants::WINDOW_INIT_Y_POS;
 use saba_core::constants::WINDOW_WIDTH;
 use saba_core::constants::*;
-use saba_core::display_item::DisplayItem;
 use saba_core::error::Error;
 use saba_core::http::HttpResponse;
+use saba_core::renderer::layout::computed_style::Color;
 use saba_core::renderer::layout::computed_style::FontSize;
 use saba_core::renderer::layout::computed_style::TextDecoration;
 
@@ -39,6 +41,14 @@ pub struct WasabiUI {
     input_mode: InputMode,
     window: Window,
     cursor: Cursor,
+    // コンテンツ領域でマウスボタンを押したままか（テキスト選択のドラッグ中）
+    is_dragging: bool,
+    // Ctrl+C でコピーした文字列（この UI 内のクリップボード。Ctrl+V でアドレスバーへ貼り付ける）
+    clipboard: String,
+    // トランジション・アニメーションの時刻を進める時計
+    clock: Clock,
+    // 前回の `Page::tick` でまだ動いているものがあったか
+    animating: bool,
 }
 
 impl WasabiUI {
@@ -57,6 +67,10 @@ impl WasabiUI {
             )
             .unwrap(),
             cursor: Cursor::new(),
+            is_dragging: false,
+            clipboard: String::new(),
+            clock: Clock::new(),
+            animating: false,
         }
     }
 
@@ -75,6 +89,13 @@ impl WasabiUI {
     ) -> Result<(), Error> {
         self.setup()?;
 
+        // <link rel="stylesheet"> などの外部リソースも同じ関数で取得する
+        self.browser
+            .borrow()
+            .current_page()
+            .borrow_mut()
+            .set_network_callback(handle_url);
+
         self.run_app(handle_url)?;
 
         Ok(())
@@ -85,6 +106,7 @@ impl WasabiUI {
     /// - マウス入力・キーボード入力をポーリングし、必要に応じて `handle_url` を呼びます。
     /// - 例えば、Enter 押下でアドレスバーの文字列を URL とみなし、`handle_url(url)` で取得した
     ///   `HttpResponse` を Page に渡して再描画する……といった流れを組みます。
+    /// - 1 周ごとに時計の時刻を Page に渡し、トランジション・アニメーションを進めます。
     fn run_app(
         &mut self,
         handle_url: fn(String) -> Result<HttpResponse, Error>,
@@ -94,7 +116,26 @@ impl WasabiUI {
             self.handle_mouse_input(handle_url)?;
             // キーイベント（文字入力/Enter/Esc 等）の処理
             self.handle_key_input(handle_url)?;
+            // トランジション・アニメーションを今の時刻まで進める
+            self.advance_animations()?;
+        }
+    }
+
+    /// トランジション・アニメーションを時計の今の時刻まで進め、動いている間は再描画する
+    ///
+    /// - `Page::tick(now)` は、動いているものがあればその時刻でスタイルと描画命令を作り直し、
+    ///   まだ続くなら `true` を返します。
+    /// - 最後のフレーム（終わりの値で作り直して `false` が返ったとき）も描き直すため、
+    ///   前回 `true` だったときも再描画します。
+    /// - 何も動いていなければ時刻を記録するだけです（`:hover` で始まるトランジションの開始時刻になる）。
+    fn advance_animations(&mut self) -> Result<(), Error> {
+        let now = self.clock.now_ms();
+        let animating = self.browser.borrow().current_page().borrow_mut().tick(now);
+        if animating || self.animating {
+            self.update_ui()?;
         }
+        self.animating = animating;
+        Ok(())
     }
 
     /// マウス入力を処理する（カーソル更新・クリックでのモード切替・リンク遷移）
@@ -106,6 +147,7 @@ impl WasabiUI {
     ///   - ツールバー帯: 入力モードを `Editing` にし、アドレスバーをクリア。
     ///   - コンテンツ領域: レイアウトに「どの要素をクリックしたか」を問い合わせ、
     ///     もしリンク先 URL が得られたら、アドレスバーを更新して `start_navigation(handle_url, url)` を呼ぶ。
+    ///     リンクでなければ、押した位置からテキスト選択を始め、押したまま動かすと選択を広げる（ドラッグ）。
     ///   - それ以外: 入力モードを `Normal` に戻す。
     ///
     /// 引数
@@ -129,6 +171,23 @@ impl WasabiUI {
             // 4) カーソルの自身のシートも更新（内部の変更を反映）
             self.cursor.flush();
 
+            // 5) カーソルの下の要素を :hover としてページに伝える（ボタンを押していなくても毎回）
+            let hover_position = (
+                position.x - WINDOW_INIT_X_POS,
+                position.y - WINDOW_INIT_Y_POS - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
+            );
+            if hover_position.0 >= 0
+                && hover_position.0 <= WINDOW_WIDTH
+                && hover_position.1 >= 0
+                && hover_position.1 <= CONTENT_AREA_HEIGHT
+            {
+                let page = self.browser.borrow().current_page();
+                let changed = page.borrow_mut().mouse_move(hover_position);
+                if changed {
+                    self.redraw_content_area()?;
+                }
+            }
+
             // いずれかのボタンが押されている？ (L/C/R)
             if button.l() || button.c() || button.r() {
                 // ウィンドウ基準の相対座標に変換（ウィンドウの左上を原点とする）
@@ -169,10 +228,24 @@ impl WasabiUI {
                     relative_pos.0,
                     relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT,
                 );
+                let page = self.browser.borrow().current_page();
+
+                // 押したままマウスが動いている（ドラッグ中）なら、選択範囲を広げるだけ
+                if self.is_dragging {
+                    let changed = page.borrow_mut().extend_selection(position_in_content_area);
+                    if changed {
+                        self.redraw_content_area()?;
+                    }
+                    return Ok(());
+                }
+                self.is_dragging = true;
+
+                // 押した要素を :active にする
+                let active_changed = page.borrow_mut().mouse_down(position_in_content_area);
+
                 // 現在のページモデルに「この座標は何をクリックしたか？」を問い合わせる
                 // - `clicked((x,y)) -> Option<String>` を想定。リンクであれば URL を返す。
-                // - `Rc<RefCell<_>>` 越しに可変借用しているのは、ページ内部の状態更新（ハイライト等）に備えるため。
-                let page = self.browser.borrow().current_page();
+                // - `Rc<RefCell<_>>` 越しに可変借用しているのは、ページ内部の状態更新（フォーカス/訪問済み）のため。
                 let next_destination = page.borrow_mut().clicked(position_in_content_area);
 
                 if let Some(url) = next_destination {
@@ -181,6 +254,21 @@ impl WasabiUI {
                     self.update_address_bar()?;
                     // そして実際に遷移を開始（HTTP 取得 → ページ適用 → 再描画）
                     self.start_navigation(handle_url, url)?;
+                    return Ok(());
+                }
+
+                // リンク以外を押した: 押した位置からテキスト選択を始める（前の選択は消える）
+                let changed = page.borrow_mut().start_selection(position_in_content_area);
+                if changed || active_changed {
+                    self.redraw_content_area()?;
+                }
+            } else if self.is_dragging {
+                // ボタンが離された: ドラッグ終了（選択はそのまま残す）。:active も外す
+                self.is_dragging = false;
+                let page = self.browser.borrow().current_page();
+                let changed = page.borrow_mut().mouse_up();
+                if changed {
+                    self.redraw_content_area()?;
                 }
             }
         }
@@ -191,8 +279,10 @@ impl WasabiUI {
     /// キー入力を処理する（アドレスバーへの文字編集とナビゲーション開始）
     ///
     /// 振る舞い
-    /// - `InputMode::Normal` … キー入力は無視（読み捨て）。
-    /// - `InputMode::Editing` … 1 文字ずつ読み、`Backspace/Delete` なら削除、それ以外は追記。
+    /// - `InputMode::Normal` … キー入力は無視（読み捨て）。ただし Ctrl+C は選択テキストのコピー、
+    ///   Tab は次のリンクへのフォーカス移動（:focus）。
+    /// - `InputMode::Editing` … 1 文字ずつ読み、`Backspace/Delete` なら削除、Ctrl+V ならクリップボードの
+    ///   文字列を貼り付け、それ以外は追記。
     ///   Enter 押下で `handle_url` コールバックを使ってナビゲーションを開始します。
     ///   各入力後に `update_address_bar()` で部分再描画します。
     ///
@@ -209,8 +299,20 @@ impl WasabiUI {
     ) -> Result<(), Error> {
         match self.input_mode {
             InputMode::Normal => {
-                // 入力不可モード。イベントキューが溜まらないよう読み捨てだけ行う。
-                let _ = Api::read_key();
+                // 入力不可モード。Ctrl+C（0x03, ETX）は選択中のテキストのコピー、
+                // Tab（0x09）は次のリンクへフォーカスを移す。
+                // それ以外はイベントキューが溜まらないよう読み捨てる。
+                match Api::read_key() {
+                    Some(c) if c == 0x03 as char => self.copy_selection(),
+                    Some(c) if c == 0x09 as char => {
+                        let page = self.browser.borrow().current_page();
+                        let changed = page.borrow_mut().focus_next();
+                        if changed {
+                            self.redraw_content_area()?;
+                        }
+                    }
+                    _ => {}
+                }
             }
             InputMode::Editing => {
                 // 1 フレームに 0/1 文字想定で読み取り（なければ None）
@@ -227,6 +329,10 @@ impl WasabiUI {
                         self.input_url.pop();
                         // バッファの変更をアドレスバーへ反映（部分フラッシュ内蔵）
                         self.update_address_bar()?;
+                    } else if c == 0x16 as char {
+                        // Ctrl+V(0x16, SYN): Ctrl+C でコピーした文字列を末尾に貼り付ける
+                        self.input_url.push_str(&self.clipboard);
+                        self.update_address_bar()?;
                     } else {
                         // 通常の可視文字を末尾に追加
                         self.input_url.push(c);
@@ -264,10 +370,12 @@ impl WasabiUI {
         self.clear_content_area()?;
 
         // 2) URL を処理し、HTTP レスポンスを得る
-        match handle_url(destination) {
+        match handle_url(destination.clone()) {
             Ok(response) => {
                 // 3) 現在のページにレスポンスを適用（ページモデルが再描画用データを持つ想定）
+                //    相対 URL（<link href="style.css"> など）の基準として文書の URL も渡す
                 let page = self.browser.borrow().current_page();
+                page.borrow_mut().set_url(destination);
                 page.borrow_mut().receive_response(response);
             }
             Err(e) => {
@@ -286,14 +394,16 @@ impl WasabiUI {
     /// 仕組み（初心者向け）
     /// - `display_items()` は「何をどこにどう描くか」の並び（Display List）。
     ///   Web ブラウザのレイアウト後の出力に近い概念で、最終的な描画指示です。
-    /// - 各 `DisplayItem` を `draw_string` や `fill_rect` に落として実際のウィンドウへ描きます。
+    /// - 半透明の色や `opacity` は下の色と混ぜる必要があるので、まず `Canvas`（コンテンツ領域と
+    ///   同じ大きさの画素の面）に全部を重ね、できた画素を `fill_rect` でウィンドウへ写します。
+    ///   同じ色が横に続く画素は 1 つの矩形にまとめて塗ります。
+    /// - 文字は `Canvas` が色だけ合成して返すので、面を写した後に `draw_string` で上に描きます。
     /// - 座標は「コンテンツ左上」を基準に計算しつつ、`WINDOW_PADDING` と `TOOLBAR_HEIGHT` を
     ///   加算してウィンドウ内の実座標へずらします（余白とツールバー分のオフセット）。
     /// - 最後に `flush()` でバッファの内容を画面へ反映します（部分でなく全体フラッシュ）。
     ///
     /// TS/Python たとえ
-    /// - `DisplayItem::Text { ... }` は TS の判別可能ユニオンに似ています（`kind === "Text"`）。
-    /// - `match` は `switch` より型安全な分岐。全バリアントを網羅しないとコンパイル警告が出ます。
+    /// - `Canvas` は TS の `<canvas>` に一度描いてから画面に出すオフスクリーン描画に似ています。
     fn update_ui(&mut self) -> Result<(), Error> {
         let display_items = self
             .browser
@@ -302,54 +412,60 @@ impl WasabiUI {
             .borrow()
             .display_items();
 
-        for item in display_items {
-            match item {
-                DisplayItem::Text {
-                    text,
-                    style,
-                    layout_point,
-                } => {
-                    // テキストを描く
-                    // - 色: `style.color()`（CSS 的な色）→ `code_u32()` で 0xRRGGBB へ
-                    // - 座標: レイアウト済みの点 + 余白 + ツールバー高
-                    // - フォント: レンダラーの `FontSize` → `convert_font_size` で描画 API の段階へ
-                    // - 下線: `text_decoration` が Underline のとき true
-                    if self
-                        .window
-                        .draw_string(
-                            style.color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            &text,
-                            convert_font_size(style.font_size()),
-                            style.text_decoration() == TextDecoration::Underline,
-                        )
-                        .is_err()
-                    {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
-                    }
+        // 下地は白（background-color の初期値は transparent なので、背景を塗らない部分は白く見える）
+        let mut canvas = Canvas::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT, Color::white());
+        canvas.paint(&display_items);
+
+        // 面の画素を、行ごとに同じ色の並びをまとめて塗る
+        for y in 0..canvas.height() {
+            let mut x = 0;
+            while x < canvas.width() {
+                let color = canvas.pixel(x, y).expect("pixel should be in the canvas");
+                let mut width = 1;
+                while canvas.pixel(x + width, y) == Some(color) {
+                    width += 1;
                 }
-                DisplayItem::Rect {
-                    style,
-                    layout_point,
-                    layout_size,
-                } => {
-                    // 塗りつぶし矩形を描く（背景など）
-                    // - 色: `background_color`
-                    // - 位置とサイズ: レイアウトの結果 + 余白/ツールバー分のオフセット
-                    if self
-                        .window
-                        .fill_rect(
-                            style.background_color().code_u32(),
-                            layout_point.x() + WINDOW_PADDING,
-                            layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-                            layout_size.width(),
-                            layout_size.height(),
-                        )
-                        .is_err()
-                    {
-                        return Err(Error::InvalidUI("failed to draw a string".to_string()));
-                    }
+                if self
+                    .window
+                    .fill_rect(
+                        color.code_u32(),
+                        x + WINDOW_PADDING,
+                        y + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                        width,
+                        1,
+                    )
+                    .is_err()
+                {
+                    return Err(Error::InvalidUI("failed to draw a rect".to_string()));
+                }
+                x += width;
+            }
+        }
+
+        for text in canvas.texts() {
+            // テキストを描く
+            // - 色: 下の色と合成済みの `text.color()` → `code_u32()` で 0xRRGGBB へ
+            // - 座標: レイアウト済みの点 + 余白 + ツールバー高
+            // - フォント: `font` の大きさ → `convert_font_size` で描画 API の段階へ
+            //   （書体は 1 つだけなので font-family と font-style は使えない）
+            // - 太字: 太字の書体が無いので、1px 右にずらして重ね描きする
+            // - 下線: `text_decoration` が Underline のとき true
+            let font = text.font();
+            let strokes = if font.weight().is_bold() { 2 } else { 1 };
+            for dx in 0..strokes {
+                if self
+                    .window
+                    .draw_string(
+                        text.color().code_u32(),
+                        text.layout_point().x() + WINDOW_PADDING + dx,
+                        text.layout_point().y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                        &text.text(),
+                        convert_font_size(font.size()),
+                        text.style().text_decoration() == TextDecoration::Underline,
+                    )
+                    .is_err()
+                {
+                    return Err(Error::InvalidUI("failed to draw a string".to_string()));
                 }
             }
         }
@@ -536,6 +652,29 @@ impl WasabiUI {
         Ok(())
     }
 
+    /// 選択中のテキストをクリップボードへコピーする
+    ///
+    /// - 選択が無い（空文字列）ときは、前にコピーした内容を残します。
+    /// - コピーした文字列は、アドレスバーの編集中に Ctrl+V で貼り付けられます。
+    fn copy_selection(&mut self) {
+        let text = self
+            .browser
+            .borrow()
+            .current_page()
+            .borrow()
+            .selected_text();
+        if text.is_empty() {
+            return;
+        }
+        self.clipboard = text;
+    }
+
+    // 選択ハイライトの変化を反映するため、コンテンツ領域を消してから描き直す
+    fn redraw_content_area(&mut self) -> Result<(), Error> {
+        self.clear_content_area()?;
+        self.update_ui()
+    }
+
     fn clear_content_area(&mut self) -> Result<(), Error> {
         // コンテンツエリアを白く塗りつぶす
         if self
@@ -563,27 +702,26 @@ impl WasabiUI {
 /// レイアウト層のフォントサイズ（`FontSize`）を、描画ライブラリのサイズ（`StringSize`）へ変換する
 ///
 /// 背景
-/// - `FontSize` はレンダラー側（`saba_core`）の論理サイズ。CSS の `font-size` のような概念。
+/// - `FontSize` はレンダラー側（`saba_core`）の計算値（px）。CSS の `font-size` そのもの。
 /// - `StringSize` は描画 API（`noli::window`）がサポートする実サイズの列挙。
-///   名称と段階が完全一致しないため、最も近い段階へ丸めます。
-///
-/// 変換ルール（現在の対応）
-/// - `Medium`  → `Medium`
-/// - `XLarge`  → `Large`   （名前は異なるが実寸が近い）
-/// - `XXLarge` → `XLarge`
+///   8x16 のビットマップフォントを 1 倍・2 倍・3 倍に拡大したものしか無いので、最も近い段階へ丸めます。
 ///
-/// メモ
-/// - 列挙型（Rust の enum ≈ TS の union 型）なので、将来 `FontSize` に新しい値が増えたら、
-///   ここでどの `StringSize` に落とすか決める必要があります（未対応だとコンパイルエラーで気づけます）。
+/// 変換ルール（16px / 32px / 48px の中間で区切る）
+/// - 24px 未満        → `Medium`（16px）
+/// - 24px 以上 40px 未満 → `Large`（32px）
+/// - 40px 以上        → `XLarge`（48px）
 ///
 /// 使用例
 /// ```rust
-/// let s = convert_font_size(FontSize::XLarge); // => StringSize::Large
+/// let s = convert_font_size(FontSize::new(32.0)); // => StringSize::Large
 /// ```
 fn convert_font_size(size: FontSize) -> StringSize {
-    match size {
-        FontSize::Medium => StringSize::Medium,
-        FontSize::XLarge => StringSize::Large,
-        FontSize::XXLarge => StringSize::XLarge,
+    let px = size.px();
+    if px < 24.0 {
+        StringSize::Medium
+    } else if px < 40.0 {
+        StringSize::Large
+    } else {
+        StringSize::XLarge
     }
 }
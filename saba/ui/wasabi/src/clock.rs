@@ -0,0 +1,48 @@
+//! アニメーション用の時計（起動してからの経過時間をミリ秒で返す）
+//!
+//! 目的
+//! - `Page::tick(now)` に渡す「今の時刻」を作ります。トランジションやアニメーションは
+//!   この時刻を基準に進みます（saba_core 側は時計を持たず、組み込み側から時刻をもらう設計）。
+//!
+//! しくみ
+//! - noli には時刻を返すシステムコールが無いので、CPU のタイムスタンプカウンタ（TSC。
+//!   起動してからのクロック数を数え続けるカウンタ）を `rdtsc` 命令で読みます。
+//! - カウンタの値をミリ秒にするには 1 秒あたりのカウント数が要りますが、それを OS から
+//!   知る方法も無いため、`TSC_TICKS_PER_MS`（2 GHz 相当）で見積もります。
+//!   CPU や QEMU の設定で周波数が違うと、アニメーションが実際より速く/遅く進みます。
+//!
+//! TS/Python にたとえると
+//! - `Clock::now_ms()` は `performance.now()` / `time.monotonic() * 1000` に相当します。
+
+use core::arch::x86_64::_rdtsc;
+
+/// 1 ミリ秒あたりの TSC のカウント数（2 GHz として見積もる）
+const TSC_TICKS_PER_MS: u64 = 2_000_000;
+
+/// 作ったときを 0 とする、ミリ秒単位の時計
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Clock {
+    start: u64,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self { start: read_tsc() }
+    }
+
+    /// 作ってからの経過時間（ミリ秒）
+    pub fn now_ms(&self) -> f64 {
+        read_tsc().wrapping_sub(self.start) as f64 / TSC_TICKS_PER_MS as f64
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_tsc() -> u64 {
+    // SAFETY: rdtsc はすべての x86_64 CPU にある命令で、メモリには触れない
+    unsafe { _rdtsc() }
+}
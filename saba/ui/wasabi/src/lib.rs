@@ -3,4 +3,5 @@
 extern crate alloc;
 
 pub mod app;
+pub mod clock;
 pub mod cursor;